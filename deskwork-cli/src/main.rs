@@ -0,0 +1,217 @@
+//! Deskwork CLI
+//!
+//! Headless front end for the Deskwork agent. Runs a single prompt or an
+//! interactive REPL in the terminal, using the same database, settings,
+//! authentication and skill categories as the desktop app.
+//!
+//! `deskwork-cli mcp-serve` serves the built-in tools to other agents over MCP
+//! instead.
+
+mod mcp_serve;
+mod output;
+mod session;
+
+use std::io::{IsTerminal, Read};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+use crate::mcp_serve::McpServeArgs;
+use crate::output::{OutputFormat, Renderer};
+use crate::session::{Session, TurnOutcome};
+
+/// Exit code when the agent reported an error.
+const EXIT_ERROR: u8 = 1;
+
+/// Exit code when the run was cancelled (mirrors SIGINT convention).
+const EXIT_CANCELLED: u8 = 130;
+
+/// Command-line arguments.
+#[derive(Debug, Parser)]
+#[command(
+    name = "deskwork-cli",
+    version,
+    about = "Run the Deskwork agent from the terminal",
+    args_conflicts_with_subcommands = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Prompt to run. If omitted, reads the prompt from stdin when piped,
+    /// otherwise starts an interactive session.
+    prompt: Vec<String>,
+
+    /// Model to use (defaults to the model in settings).
+    #[arg(short, long)]
+    model: Option<String>,
+
+    /// Output format.
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// Working directory for the agent (defaults to the one in settings).
+    #[arg(short = 'C', long)]
+    cwd: Option<PathBuf>,
+
+    /// Force interactive mode even when a prompt is given.
+    #[arg(short, long)]
+    interactive: bool,
+
+    /// Approve every tool call that would otherwise ask for permission.
+    /// Deny rules still apply.
+    #[arg(short, long)]
+    yes: bool,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Serve the built-in tools (files, search, shell) to other agents over MCP.
+    McpServe(McpServeArgs),
+}
+
+fn main() -> ExitCode {
+    // Logs go to stderr so stdout stays machine-readable.
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn")),
+        )
+        .init();
+
+    let mut cli = Cli::parse();
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(rt) => rt,
+        Err(e) => {
+            eprintln!("error: failed to create tokio runtime: {e}");
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+
+    if let Some(Command::McpServe(args)) = cli.command.take() {
+        let code = runtime.block_on(mcp_serve::run(args));
+        // Don't wait for the blocking read of stdin
+        runtime.shutdown_background();
+        return code;
+    }
+
+    runtime.block_on(run(cli))
+}
+
+async fn run(cli: Cli) -> ExitCode {
+    let mut session = match Session::open(cli.model.clone(), cli.cwd.clone(), cli.yes) {
+        Ok(session) => session,
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+
+    let mut prompt = cli.prompt.join(" ");
+    if prompt.trim().is_empty() && !std::io::stdin().is_terminal() {
+        if let Err(e) = std::io::stdin().read_to_string(&mut prompt) {
+            eprintln!("error: failed to read prompt from stdin: {e}");
+            return ExitCode::from(EXIT_ERROR);
+        }
+    }
+
+    if cli.interactive || prompt.trim().is_empty() {
+        if !prompt.trim().is_empty() {
+            let mut renderer = Renderer::new(cli.output);
+            session.run_turn(prompt.trim(), &mut renderer).await;
+        }
+        repl(&mut session, cli.output).await
+    } else {
+        let mut renderer = Renderer::new(cli.output);
+        match session.run_turn(prompt.trim(), &mut renderer).await {
+            TurnOutcome::Completed => ExitCode::SUCCESS,
+            TurnOutcome::Failed => ExitCode::from(EXIT_ERROR),
+            TurnOutcome::Cancelled => ExitCode::from(EXIT_CANCELLED),
+        }
+    }
+}
+
+/// Interactive read-eval-print loop.
+///
+/// Built-in commands: `/help`, `/clear`, `/compact`, `/exit` (or `/quit`).
+/// Anything else starting with `/` is resolved as a skill category slash command.
+async fn repl(session: &mut Session, format: OutputFormat) -> ExitCode {
+    let interactive = std::io::stdin().is_terminal();
+    if interactive {
+        eprintln!(
+            "Deskwork v{} — model: {}. Type /help for commands, /exit to quit.",
+            deskwork_core::VERSION,
+            session.model_name()
+        );
+    }
+
+    let mut last_outcome = TurnOutcome::Completed;
+
+    loop {
+        if interactive {
+            eprint!("> ");
+        }
+
+        let line = match session.read_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("error: failed to read input: {e}");
+                return ExitCode::from(EXIT_ERROR);
+            }
+        };
+
+        let input = line.trim();
+        if input.is_empty() {
+            continue;
+        }
+
+        match input {
+            "/exit" | "/quit" => break,
+            "/help" => {
+                print_help(session);
+                continue;
+            }
+            "/clear" => {
+                session.clear();
+                eprintln!("Conversation cleared.");
+                continue;
+            }
+            "/compact" => {
+                let mut renderer = Renderer::new(format);
+                last_outcome = session.compact(&mut renderer).await;
+                continue;
+            }
+            _ => {}
+        }
+
+        let mut renderer = Renderer::new(format);
+        last_outcome = session.run_turn(input, &mut renderer).await;
+    }
+
+    match last_outcome {
+        TurnOutcome::Completed => ExitCode::SUCCESS,
+        TurnOutcome::Failed => ExitCode::from(EXIT_ERROR),
+        TurnOutcome::Cancelled => ExitCode::from(EXIT_CANCELLED),
+    }
+}
+
+fn print_help(session: &Session) {
+    eprintln!("Commands:");
+    eprintln!("  /help                 Show this help");
+    eprintln!("  /clear                Start a new conversation");
+    eprintln!("  /compact              Summarize the conversation to free up context");
+    eprintln!("  /exit, /quit          Leave the session");
+
+    let commands = session.slash_commands();
+    if !commands.is_empty() {
+        eprintln!();
+        eprintln!("Skill commands:");
+        for (command, description) in commands {
+            eprintln!("  {:<22}{}", command, description);
+        }
+    }
+}
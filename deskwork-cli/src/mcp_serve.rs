@@ -0,0 +1,83 @@
+//! `mcp-serve`: Deskwork's built-in tools as an MCP server.
+//!
+//! Serves the file and shell tools to other agents over stdio, or over
+//! streamable HTTP on localhost with `--http`. The tools use the workspace,
+//! path policy and permission rules from the settings.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Args;
+use tracing::warn;
+
+use deskwork_core::mcp_serve::{bind_http, MCP_HTTP_PATH};
+use deskwork_core::McpToolServer;
+
+use crate::session::load_settings;
+use crate::EXIT_ERROR;
+
+/// Arguments of `mcp-serve`.
+#[derive(Debug, Args)]
+pub struct McpServeArgs {
+    /// Serve over HTTP on this localhost port instead of stdio (0 picks a
+    /// free port).
+    #[arg(long, value_name = "PORT")]
+    http: Option<u16>,
+
+    /// Workspace root for the tools (defaults to the one in settings).
+    #[arg(short = 'C', long)]
+    cwd: Option<PathBuf>,
+
+    /// Run tool calls that would otherwise ask for permission.
+    /// Deny rules still apply.
+    #[arg(short, long)]
+    yes: bool,
+}
+
+/// Serve until the client disconnects (stdio) or Ctrl-C.
+pub async fn run(args: McpServeArgs) -> ExitCode {
+    let settings = match load_settings(None, args.cwd) {
+        Ok((_, settings)) => settings,
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+
+    let server = McpToolServer::from_settings(&settings).with_approve_all(args.yes);
+    if server.workspace().root().is_none() {
+        warn!("No working directory in settings; tools use the current directory");
+    }
+
+    let served = match args.http {
+        Some(port) => {
+            let listener = match bind_http(port).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    eprintln!("error: failed to listen on localhost:{port}: {e}");
+                    return ExitCode::from(EXIT_ERROR);
+                }
+            };
+            if let Ok(addr) = listener.local_addr() {
+                eprintln!("Serving Deskwork tools at http://{addr}{MCP_HTTP_PATH}");
+            }
+            tokio::select! {
+                served = server.clone().serve_http(listener) => served,
+                _ = tokio::signal::ctrl_c() => Ok(()),
+            }
+        }
+        None => tokio::select! {
+            served = server.clone().serve_stdio() => served,
+            _ = tokio::signal::ctrl_c() => Ok(()),
+        },
+    };
+    server.shutdown();
+
+    match served {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: MCP server failed: {e}");
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
@@ -0,0 +1,332 @@
+//! Rendering of executor events to stdout.
+//!
+//! - `text`: assistant text streamed to stdout; tool activity and errors go to
+//!   stderr so the output can be piped.
+//! - `json`: a single JSON object printed when the turn ends.
+//! - `stream-json`: one JSON object per event, newline-delimited.
+
+use std::io::Write;
+
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+use deskwork_core::{ExecutorEvent, TokenUsage, ToolOutput};
+
+/// Output format selected with `--output`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    StreamJson,
+}
+
+/// A tool call collected for `json` output.
+#[derive(Debug, Default)]
+struct ToolCallRecord {
+    id: Option<String>,
+    name: String,
+    arguments: String,
+    result: Option<ToolOutput>,
+    success: Option<bool>,
+}
+
+/// Renders the events of a single turn.
+pub struct Renderer {
+    format: OutputFormat,
+    text: String,
+    tool_calls: Vec<ToolCallRecord>,
+    /// Whether the last thing printed to stdout ended with a newline.
+    at_line_start: bool,
+}
+
+impl Renderer {
+    pub fn new(format: OutputFormat) -> Self {
+        Self {
+            format,
+            text: String::new(),
+            tool_calls: Vec::new(),
+            at_line_start: true,
+        }
+    }
+
+    /// Render a single executor event.
+    pub fn event(&mut self, event: &ExecutorEvent) {
+        match self.format {
+            OutputFormat::Text => self.render_text(event),
+            OutputFormat::Json => self.collect_json(event),
+            OutputFormat::StreamJson => print_line(&event_to_json(event)),
+        }
+    }
+
+    /// Report an error that happened outside the agent run (auth, bad command).
+    pub fn error(&mut self, message: &str) {
+        self.event(&ExecutorEvent::Error(message.to_string()));
+    }
+
+    fn render_text(&mut self, event: &ExecutorEvent) {
+        match event {
+            ExecutorEvent::TextDelta(text) => {
+                print!("{text}");
+                let _ = std::io::stdout().flush();
+                if !text.is_empty() {
+                    self.at_line_start = text.ends_with('\n');
+                }
+            }
+            ExecutorEvent::ToolCallStart { name, .. } => {
+                self.end_line();
+                eprintln!("[tool] {name}");
+            }
+            ExecutorEvent::ToolResult {
+                name,
+                result,
+                success,
+                ..
+            } => {
+                if !success {
+                    eprintln!("[tool] {name} failed: {result}");
+                }
+            }
+            ExecutorEvent::Compacted {
+                summarized_requests,
+                tokens_before,
+                tokens_after,
+                ..
+            } => {
+                self.end_line();
+                eprintln!(
+                    "[context] compacted {summarized_requests} earlier messages \
+                     (~{tokens_before} -> ~{tokens_after} tokens)"
+                );
+            }
+            ExecutorEvent::Done { .. } => self.end_line(),
+            ExecutorEvent::Error(msg) => {
+                self.end_line();
+                eprintln!("error: {msg}");
+            }
+            ExecutorEvent::Cancelled { .. } => {
+                self.end_line();
+                eprintln!("cancelled");
+            }
+            _ => {}
+        }
+    }
+
+    fn collect_json(&mut self, event: &ExecutorEvent) {
+        match event {
+            ExecutorEvent::TextDelta(text) => self.text.push_str(text),
+            ExecutorEvent::ToolCallStart { id, name } => self.tool_calls.push(ToolCallRecord {
+                id: id.clone(),
+                name: name.clone(),
+                ..Default::default()
+            }),
+            ExecutorEvent::ToolCallDelta { delta, .. } => {
+                if let Some(call) = self.tool_calls.last_mut() {
+                    call.arguments.push_str(delta);
+                }
+            }
+            ExecutorEvent::ToolResult {
+                id,
+                result,
+                success,
+                ..
+            } => {
+                let index = self
+                    .tool_calls
+                    .iter()
+                    .rposition(|c| id.is_some() && c.id == *id)
+                    .or_else(|| self.tool_calls.len().checked_sub(1));
+                if let Some(call) = index.and_then(|i| self.tool_calls.get_mut(i)) {
+                    call.result = Some(result.clone());
+                    call.success = Some(*success);
+                }
+            }
+            ExecutorEvent::Done { usage, .. } => print_line(&json!({
+                "status": "completed",
+                "result": self.text,
+                "tool_calls": self.tool_calls_json(),
+                "usage": usage_json(usage),
+            })),
+            ExecutorEvent::Error(msg) => print_line(&json!({
+                "status": "error",
+                "error": msg,
+                "result": self.text,
+                "tool_calls": self.tool_calls_json(),
+            })),
+            ExecutorEvent::Cancelled { .. } => print_line(&json!({
+                "status": "cancelled",
+                "result": self.text,
+                "tool_calls": self.tool_calls_json(),
+            })),
+            _ => {}
+        }
+    }
+
+    fn tool_calls_json(&self) -> Value {
+        Value::Array(
+            self.tool_calls
+                .iter()
+                .map(|c| {
+                    json!({
+                        "id": c.id,
+                        "name": c.name,
+                        "arguments": serde_json::from_str::<Value>(&c.arguments)
+                            .unwrap_or_else(|_| Value::String(c.arguments.clone())),
+                        "result": c.result,
+                        "success": c.success,
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    fn end_line(&mut self) {
+        if !self.at_line_start {
+            println!();
+            self.at_line_start = true;
+        }
+    }
+}
+
+fn print_line(value: &Value) {
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{value}");
+    let _ = stdout.flush();
+}
+
+/// Convert an executor event to its `stream-json` representation.
+pub fn event_to_json(event: &ExecutorEvent) -> Value {
+    match event {
+        ExecutorEvent::RunStart { run_id } => json!({"type": "run_start", "run_id": run_id}),
+        ExecutorEvent::TextDelta(text) => json!({"type": "text_delta", "text": text}),
+        ExecutorEvent::ThinkingDelta(text) => json!({"type": "thinking_delta", "text": text}),
+        ExecutorEvent::ToolCallStart { id, name } => {
+            json!({"type": "tool_call_start", "id": id, "name": name})
+        }
+        ExecutorEvent::ToolCallDelta { id, delta } => {
+            json!({"type": "tool_call_delta", "id": id, "delta": delta})
+        }
+        ExecutorEvent::ToolCallComplete { id, name } => {
+            json!({"type": "tool_call_complete", "id": id, "name": name})
+        }
+        ExecutorEvent::ApprovalRequested {
+            id,
+            tool_name,
+            arguments,
+            suggested_rule,
+            ..
+        } => json!({
+            "type": "approval_requested",
+            "id": id,
+            "name": tool_name,
+            "arguments": arguments,
+            "suggested_rule": suggested_rule,
+        }),
+        ExecutorEvent::ToolOutputDelta { id, delta } => {
+            json!({"type": "tool_output_delta", "id": id, "delta": delta})
+        }
+        ExecutorEvent::ToolResult {
+            id,
+            name,
+            result,
+            success,
+        } => json!({
+            "type": "tool_result",
+            "id": id,
+            "name": name,
+            "result": result,
+            "success": success,
+        }),
+        ExecutorEvent::Usage(usage) => json!({"type": "usage", "usage": usage_json(usage)}),
+        ExecutorEvent::Compacted {
+            summarized_requests,
+            tokens_before,
+            tokens_after,
+            message_history,
+        } => json!({
+            "type": "compacted",
+            "summarized_messages": summarized_requests,
+            "tokens_before": tokens_before,
+            "tokens_after": tokens_after,
+            "history_length": message_history.len(),
+        }),
+        ExecutorEvent::Done {
+            usage,
+            message_history,
+        } => json!({
+            "type": "done",
+            "usage": usage_json(usage),
+            "history_length": message_history.len(),
+        }),
+        ExecutorEvent::Error(msg) => json!({"type": "error", "message": msg}),
+        ExecutorEvent::Cancelled { message_history } => json!({
+            "type": "cancelled",
+            "history_length": message_history.len(),
+        }),
+    }
+}
+
+fn usage_json(usage: &TokenUsage) -> Value {
+    serde_json::to_value(usage).unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_to_json_text_delta() {
+        let value = event_to_json(&ExecutorEvent::TextDelta("hi".to_string()));
+        assert_eq!(value, json!({"type": "text_delta", "text": "hi"}));
+    }
+
+    #[test]
+    fn test_event_to_json_tool_result() {
+        let value = event_to_json(&ExecutorEvent::ToolResult {
+            id: Some("call_1".to_string()),
+            name: "read_file".to_string(),
+            result: ToolOutput::json(json!({"lines": 2})),
+            success: true,
+        });
+        assert_eq!(value["type"], "tool_result");
+        assert_eq!(value["id"], "call_1");
+        assert_eq!(value["result"]["lines"], 2);
+        assert_eq!(value["success"], true);
+    }
+
+    #[test]
+    fn test_event_to_json_usage() {
+        let value = event_to_json(&ExecutorEvent::Usage(TokenUsage {
+            requests: 1,
+            input_tokens: 12,
+            output_tokens: 3,
+            ..Default::default()
+        }));
+        assert_eq!(value["type"], "usage");
+        assert_eq!(value["usage"]["input_tokens"], 12);
+        assert_eq!(value["usage"]["cache_read_tokens"], 0);
+    }
+
+    #[test]
+    fn test_json_collects_tool_calls() {
+        let mut renderer = Renderer::new(OutputFormat::Json);
+        renderer.collect_json(&ExecutorEvent::ToolCallStart {
+            id: Some("a".to_string()),
+            name: "grep".to_string(),
+        });
+        renderer.collect_json(&ExecutorEvent::ToolCallDelta {
+            id: Some("a".to_string()),
+            delta: r#"{"pattern":"x"}"#.to_string(),
+        });
+        renderer.collect_json(&ExecutorEvent::ToolResult {
+            id: Some("a".to_string()),
+            name: "grep".to_string(),
+            result: ToolOutput::text("none"),
+            success: true,
+        });
+
+        let calls = renderer.tool_calls_json();
+        assert_eq!(calls[0]["name"], "grep");
+        assert_eq!(calls[0]["arguments"]["pattern"], "x");
+        assert_eq!(calls[0]["result"], "none");
+    }
+}
@@ -0,0 +1,459 @@
+//! Agent session state for the CLI.
+//!
+//! Mirrors what `DeskworkApp` does in the GUI: loads settings and skill
+//! categories, resolves slash commands, builds the system prompt, and keeps the
+//! API message history between turns.
+
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use tokio::io::{AsyncBufReadExt, BufReader, Lines, Stdin};
+use tracing::{debug, warn};
+
+use deskwork_core::skills::categories::{build_mcp_map, McpBridgeResult, SkillCategoryRegistry};
+use deskwork_core::skills::category_context::{build_category_context, ContextBudget};
+use deskwork_core::skills::commands::{self as skill_commands, ParsedSlashCommand};
+use deskwork_core::{
+    build_system_prompt, compact_conversation, context_length_for_model, event_channel,
+    find_mcp_prompt, openai_api_key, run_agent, user_mcp_configs, AnthropicAuth, ApprovalDecision,
+    CancellationToken, ClaudeCodeAuth, CompactArgs, Database, EventReceiver, ExecutorEvent,
+    McpPool, McpServerEntry, ModelRegistry, ModelRequest, ModelType, PermissionAction,
+    PermissionRule, ProcessManager, RunAgentArgs, Settings, ShellSession, SkillsContext,
+};
+
+use crate::output::Renderer;
+
+/// Conversation the CLI's background processes are tracked under.
+const CLI_CONVERSATION: &str = "cli";
+
+/// How a single turn ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TurnOutcome {
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// A CLI agent session.
+pub struct Session {
+    db: Database,
+    settings: Settings,
+    category_registry: SkillCategoryRegistry,
+    category_mcp: McpBridgeResult,
+    skills_context: SkillsContext,
+    api_history: Vec<ModelRequest>,
+    /// Shell kept between turns when `persistent_shell` is on.
+    shell_session: ShellSession,
+    /// Background processes; killed on `/clear` and when the CLI exits.
+    processes: ProcessManager,
+    /// MCP connections kept between turns.
+    mcp_pool: McpPool,
+    /// Approve every tool call that would otherwise ask (`--yes`).
+    approve_all: bool,
+    /// Lines of stdin, shared by the REPL and approval prompts so neither
+    /// loses input buffered by the other.
+    input: Lines<BufReader<Stdin>>,
+}
+
+/// Open the default database and load its settings, with `model` and `cwd`
+/// overriding the stored ones.
+pub fn load_settings(
+    model: Option<String>,
+    cwd: Option<PathBuf>,
+) -> anyhow::Result<(Database, Settings)> {
+    let db = Database::open().context("failed to open database")?;
+    db.migrate().context("failed to migrate database")?;
+
+    let mut settings = Settings::load(&db);
+    if let Some(model) = model {
+        settings.model = model;
+    }
+    if let Some(cwd) = cwd {
+        let cwd = cwd
+            .canonicalize()
+            .with_context(|| format!("invalid working directory: {}", cwd.display()))?;
+        settings.working_directory = Some(cwd.to_string_lossy().to_string());
+    }
+    settings.validate();
+    debug!(?settings, "Loaded settings");
+
+    Ok((db, settings))
+}
+
+impl Session {
+    /// Open the default database and load settings and skill categories.
+    ///
+    /// `model` and `cwd` override the stored settings for this session only;
+    /// nothing is written back to the database.
+    pub fn open(
+        model: Option<String>,
+        cwd: Option<PathBuf>,
+        approve_all: bool,
+    ) -> anyhow::Result<Self> {
+        let (db, settings) = load_settings(model, cwd)?;
+
+        if let Err(e) = deskwork_core::extract_skills_if_needed() {
+            warn!("Failed to extract skills bundle: {}", e);
+        }
+        let skills_context = SkillsContext::build();
+
+        let category_registry = SkillCategoryRegistry::load(&settings.plugins_enabled);
+        let category_mcp = build_mcp_map(&category_registry.enabled_categories());
+
+        Ok(Self {
+            db,
+            settings,
+            category_registry,
+            category_mcp,
+            skills_context,
+            api_history: Vec::new(),
+            shell_session: ShellSession::new(),
+            processes: ProcessManager::new(),
+            mcp_pool: McpPool::new(),
+            approve_all,
+            input: BufReader::new(tokio::io::stdin()).lines(),
+        })
+    }
+
+    /// Read the next line of input; `None` at end of input.
+    pub async fn read_line(&mut self) -> std::io::Result<Option<String>> {
+        self.input.next_line().await
+    }
+
+    /// The model used for this session.
+    pub fn model_name(&self) -> &str {
+        &self.settings.model
+    }
+
+    /// Enabled slash commands as `(command, description)` pairs, followed by
+    /// the prompts of the MCP servers connected so far.
+    pub fn slash_commands(&self) -> Vec<(String, String)> {
+        let prompts = self
+            .mcp_pool
+            .prompts()
+            .into_iter()
+            .map(|p| (p.slash_command, p.description.unwrap_or_default()));
+        self.category_registry
+            .all_slash_commands()
+            .into_iter()
+            .map(|c| (c.slash_command.clone(), c.description.clone()))
+            .chain(prompts)
+            .collect()
+    }
+
+    /// Forget the conversation history.
+    pub fn clear(&mut self) {
+        self.api_history.clear();
+        self.shell_session = ShellSession::new();
+        self.processes.kill_conversation(CLI_CONVERSATION);
+    }
+
+    /// Summarize the conversation so far to free up context (`/compact`).
+    pub async fn compact(&mut self, renderer: &mut Renderer) -> TurnOutcome {
+        if self.api_history.is_empty() {
+            eprintln!("Nothing to compact yet.");
+            return TurnOutcome::Completed;
+        }
+
+        let model_config = ModelRegistry::resolve_from_db(&self.db, &self.settings.model);
+        let Some(access_token) = self.access_token(model_config.model_type, renderer).await else {
+            return TurnOutcome::Failed;
+        };
+
+        let (tx, rx) = event_channel();
+        let handle = compact_conversation(CompactArgs {
+            access_token,
+            model_name: self.settings.model.clone(),
+            model_config: Some(model_config),
+            message_history: self.api_history.clone(),
+            event_sender: tx,
+        });
+        self.drive(handle, rx, None, renderer).await
+    }
+
+    /// Run one user turn to completion, rendering events as they arrive.
+    ///
+    /// The first Ctrl-C stops the run cleanly, keeping what it did so far in
+    /// the history; a second one aborts it right away.
+    pub async fn run_turn(&mut self, raw_input: &str, renderer: &mut Renderer) -> TurnOutcome {
+        let agent_input = match self.resolve_input(raw_input).await {
+            Ok(input) => input,
+            Err(msg) => {
+                renderer.error(&msg);
+                return TurnOutcome::Failed;
+            }
+        };
+
+        // The registry entry decides the credential and the backend
+        let model_config = ModelRegistry::resolve_from_db(&self.db, &self.settings.model);
+        let Some(access_token) = self.access_token(model_config.model_type, renderer).await else {
+            return TurnOutcome::Failed;
+        };
+
+        let budget = ContextBudget {
+            max_tokens: self.settings.plugin_context_token_budget as usize,
+        };
+        let category_context = build_category_context(
+            &self.category_registry,
+            &self.category_mcp,
+            budget,
+            &self.settings.category_playbooks,
+        );
+        let skills_prompt = self
+            .skills_context
+            .to_prompt_section(self.settings.working_directory.as_deref());
+        let system_prompt = build_system_prompt(
+            self.settings.extended_thinking,
+            None,
+            Some(category_context.prompt.as_str()),
+            Some(skills_prompt.as_str()),
+        );
+
+        let (tx, rx) = event_channel();
+        let cancel = CancellationToken::new();
+        let handle = run_agent(RunAgentArgs {
+            access_token,
+            model_name: self.settings.model.clone(),
+            model_config: Some(model_config),
+            settings: self.settings.clone(),
+            system_prompt,
+            user_input: agent_input,
+            images: vec![],
+            documents: vec![],
+            message_history: self.api_history.clone(),
+            context_length: context_length_for_model(&self.db, &self.settings.model),
+            plugin_mcp_configs: self.mcp_configs(),
+            mcp_pool: Some(self.mcp_pool.clone()),
+            // The CLI doesn't store conversations, so there is nothing to revert to
+            checkpointer: None,
+            shell_session: self
+                .settings
+                .persistent_shell
+                .then(|| self.shell_session.clone()),
+            processes: Some(self.processes.conversation(CLI_CONVERSATION)),
+            cancel: cancel.clone(),
+            event_sender: tx,
+        });
+        self.drive(handle, rx, Some(cancel), renderer).await
+    }
+
+    /// MCP connectors for a run: the enabled categories' and the user's own.
+    fn mcp_configs(&self) -> HashMap<String, McpServerEntry> {
+        let mut configs = self.category_mcp.configs.clone();
+        configs.extend(user_mcp_configs(&self.db));
+        configs
+    }
+
+    /// Get the credential of the provider `model_type`: a fresh OAuth access
+    /// token, the Anthropic API key, or the (possibly empty) OpenAI API key for
+    /// endpoint models. Failures are reported through `renderer`.
+    async fn access_token(&self, model_type: ModelType, renderer: &mut Renderer) -> Option<String> {
+        match model_type {
+            ModelType::ClaudeCode => {}
+            ModelType::Anthropic => {
+                return match AnthropicAuth::new(&self.db).api_key() {
+                    Ok(key) => Some(key),
+                    Err(e) => {
+                        renderer.error(&format!(
+                            "{e}. Set ANTHROPIC_API_KEY or add a key in the Deskwork desktop app."
+                        ));
+                        None
+                    }
+                };
+            }
+            ModelType::OpenAiCompatible | ModelType::Local => {
+                return Some(openai_api_key(&self.db));
+            }
+        }
+        match ClaudeCodeAuth::new(&self.db).refresh_if_needed().await {
+            Ok(token) => Some(token),
+            Err(e) => {
+                renderer.error(&format!(
+                    "Not authenticated ({e}). Sign in with the Deskwork desktop app first."
+                ));
+                None
+            }
+        }
+    }
+
+    /// Render the events of a run until it ends, answering approval requests.
+    ///
+    /// Ctrl-C cancels `cancel` if given, and aborts the run otherwise or when
+    /// pressed again.
+    async fn drive(
+        &mut self,
+        handle: tokio::task::JoinHandle<()>,
+        mut rx: EventReceiver,
+        mut cancel: Option<CancellationToken>,
+        renderer: &mut Renderer,
+    ) -> TurnOutcome {
+        loop {
+            tokio::select! {
+                event = rx.recv() => {
+                    let Some(event) = event else {
+                        renderer.error("Agent stopped unexpectedly");
+                        return TurnOutcome::Failed;
+                    };
+                    renderer.event(&event);
+                    match event {
+                        ExecutorEvent::Usage(usage) => {
+                            if let Err(e) = self.db.record_usage(
+                                None,
+                                &self.settings.model,
+                                &deskwork_core::usage_day_today(),
+                                &usage,
+                            ) {
+                                warn!("Failed to record token usage: {}", e);
+                            }
+                        }
+                        ExecutorEvent::Compacted { message_history, .. } => {
+                            self.api_history = message_history;
+                        }
+                        ExecutorEvent::Done { message_history, .. } => {
+                            self.api_history = message_history;
+                            return TurnOutcome::Completed;
+                        }
+                        ExecutorEvent::ApprovalRequested {
+                            tool_name,
+                            arguments,
+                            suggested_rule,
+                            responder,
+                            ..
+                        } => {
+                            let decision =
+                                self.decide_approval(&tool_name, &arguments, &suggested_rule).await;
+                            responder.respond(decision);
+                        }
+                        ExecutorEvent::Error(_) => return TurnOutcome::Failed,
+                        ExecutorEvent::Cancelled { message_history } => {
+                            self.api_history = message_history;
+                            return TurnOutcome::Cancelled;
+                        }
+                        _ => {}
+                    }
+                }
+                _ = tokio::signal::ctrl_c() => {
+                    if let Some(cancel) = cancel.take() {
+                        cancel.cancel();
+                        eprintln!("\nStopping... (press Ctrl-C again to abort)");
+                        continue;
+                    }
+                    handle.abort();
+                    renderer.event(&ExecutorEvent::Cancelled {
+                        message_history: Vec::new(),
+                    });
+                    return TurnOutcome::Cancelled;
+                }
+            }
+        }
+    }
+
+    /// Answer an approval request.
+    ///
+    /// With `--yes` every call is approved. On a terminal the user is asked;
+    /// otherwise the call is denied, since nobody can answer.
+    async fn decide_approval(
+        &mut self,
+        tool_name: &str,
+        arguments: &serde_json::Value,
+        suggested_rule: &str,
+    ) -> ApprovalDecision {
+        if self.approve_all {
+            return ApprovalDecision::ApproveOnce;
+        }
+
+        if !std::io::stdin().is_terminal() {
+            return ApprovalDecision::Deny {
+                feedback: Some(
+                    "Tool calls that need approval are denied in non-interactive mode \
+                     (rerun with --yes to allow them)."
+                        .to_string(),
+                ),
+            };
+        }
+
+        eprintln!(
+            "\nApprove {} {}?",
+            tool_name,
+            deskwork_core::config::permissions::primary_argument(arguments)
+        );
+        eprint!("[y]es / [a]lways ({suggested_rule}) / [n]o [feedback]: ");
+
+        let answer = self.read_line().await.ok().flatten().unwrap_or_default();
+
+        let answer = answer.trim();
+        let (choice, feedback) = answer.split_once(' ').unwrap_or((answer, ""));
+        match choice.to_ascii_lowercase().as_str() {
+            "y" | "yes" => ApprovalDecision::ApproveOnce,
+            "a" | "always" => {
+                if !self
+                    .settings
+                    .permission_rules
+                    .iter()
+                    .any(|r| r.pattern == suggested_rule)
+                {
+                    self.settings
+                        .permission_rules
+                        .push(PermissionRule::new(suggested_rule, PermissionAction::Allow));
+                    if let Err(e) = self.save_permission_rules() {
+                        warn!("Failed to save permission rule: {}", e);
+                    }
+                }
+                ApprovalDecision::ApproveAlways
+            }
+            _ => ApprovalDecision::Deny {
+                feedback: (!feedback.trim().is_empty()).then(|| feedback.trim().to_string()),
+            },
+        }
+    }
+
+    /// Persist the session's permission rules without writing back the
+    /// session-only `--model`/`--cwd` overrides.
+    fn save_permission_rules(&self) -> anyhow::Result<()> {
+        let mut stored = Settings::load(&self.db);
+        stored.permission_rules = self.settings.permission_rules.clone();
+        stored.save(&self.db)
+    }
+
+    /// Resolve slash commands into full command prompts, or the messages of
+    /// an MCP prompt.
+    async fn resolve_input(&self, raw_input: &str) -> Result<String, String> {
+        if !raw_input.starts_with('/') {
+            return Ok(raw_input.to_string());
+        }
+
+        let Some(parsed) = skill_commands::parse_slash_command(raw_input) else {
+            return Ok(raw_input.to_string());
+        };
+
+        let Some(command) =
+            skill_commands::get_command_handler(&self.category_registry, &parsed.slash_command)
+        else {
+            return self.resolve_mcp_prompt(&parsed).await;
+        };
+
+        let user_inputs = skill_commands::parse_inline_command_inputs(raw_input);
+        Ok(skill_commands::build_command_prompt(
+            command,
+            &user_inputs,
+            parsed.raw_args.as_deref(),
+        ))
+    }
+
+    /// Fetch the MCP prompt invoked by a slash command, connecting the
+    /// servers first so their prompts are known.
+    async fn resolve_mcp_prompt(&self, parsed: &ParsedSlashCommand) -> Result<String, String> {
+        self.mcp_pool.manager(&self.mcp_configs()).await;
+        let prompts = self.mcp_pool.prompts();
+        let prompt = find_mcp_prompt(&prompts, &parsed.slash_command).ok_or_else(|| {
+            format!(
+                "No enabled command handler found for {}",
+                parsed.slash_command
+            )
+        })?;
+        let invocation = prompt.invocation(parsed.raw_args.as_deref())?;
+        self.mcp_pool.get_prompt(&invocation).await
+    }
+}
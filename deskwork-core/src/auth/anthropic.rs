@@ -0,0 +1,187 @@
+//! Anthropic API key authentication.
+//!
+//! The alternative to Claude Code OAuth: requests are billed to an Anthropic
+//! API key, stored in the `api_keys` table or taken from the
+//! `ANTHROPIC_API_KEY` environment variable. Models fetched with the key are
+//! registered as [`ModelType::Anthropic`] under the `anthropic-` prefix.
+
+use thiserror::Error;
+use tracing::{error, info};
+
+use super::claude::{supports_thinking, ModelsResponse};
+use crate::db::Database;
+use crate::models::{ModelConfig, ModelRegistry, ModelType, ANTHROPIC_MODEL_PREFIX};
+
+/// Name of the key in the `api_keys` table and of the environment variable.
+pub const ANTHROPIC_API_KEY: &str = "ANTHROPIC_API_KEY";
+
+#[derive(Debug, Error)]
+pub enum AnthropicAuthError {
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[error("No Anthropic API key configured")]
+    NoApiKey,
+    #[error("HTTP error: {0}")]
+    Http(String),
+}
+
+/// Anthropic API key manager.
+pub struct AnthropicAuth<'a> {
+    db: &'a Database,
+}
+
+impl<'a> AnthropicAuth<'a> {
+    /// Create a new API key manager.
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Store the API key, replacing any previous one.
+    pub fn save_api_key(&self, api_key: &str) -> Result<(), AnthropicAuthError> {
+        let api_key = api_key.trim();
+        if api_key.is_empty() {
+            return Err(AnthropicAuthError::NoApiKey);
+        }
+        self.db.save_api_key(ANTHROPIC_API_KEY, api_key)?;
+        Ok(())
+    }
+
+    /// The API key: the stored one, or else the `ANTHROPIC_API_KEY`
+    /// environment variable.
+    pub fn api_key(&self) -> Result<String, AnthropicAuthError> {
+        if let Some(key) = self.db.get_api_key(ANTHROPIC_API_KEY)? {
+            return Ok(key);
+        }
+        std::env::var(ANTHROPIC_API_KEY)
+            .ok()
+            .filter(|key| !key.trim().is_empty())
+            .ok_or(AnthropicAuthError::NoApiKey)
+    }
+
+    /// Check if an API key is available.
+    pub fn has_api_key(&self) -> bool {
+        self.api_key().is_ok()
+    }
+
+    /// Check if an API key is stored in the database (the environment
+    /// variable aside).
+    pub fn has_stored_api_key(&self) -> bool {
+        self.db.has_api_key(ANTHROPIC_API_KEY)
+    }
+
+    /// Delete the stored API key.
+    pub fn remove_api_key(&self) -> Result<(), AnthropicAuthError> {
+        self.db.delete_api_key(ANTHROPIC_API_KEY)?;
+        Ok(())
+    }
+}
+
+/// Fetch the Claude models available to an API key.
+pub async fn fetch_anthropic_models(api_key: &str) -> Result<Vec<String>, AnthropicAuthError> {
+    let client = reqwest::Client::new();
+    let response = client
+        .get("https://api.anthropic.com/v1/models")
+        .header("x-api-key", api_key)
+        .header("anthropic-version", "2023-06-01")
+        .timeout(std::time::Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(|e| AnthropicAuthError::Http(e.to_string()))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        error!("Failed to fetch models: {} - {}", status, text);
+        return Err(AnthropicAuthError::Http(format!(
+            "Failed to fetch models: {}",
+            status
+        )));
+    }
+
+    let models_response: ModelsResponse = response
+        .json()
+        .await
+        .map_err(|e| AnthropicAuthError::Http(e.to_string()))?;
+
+    let model_names: Vec<String> = models_response
+        .data
+        .into_iter()
+        .map(|m| m.id)
+        .filter(|name| name.starts_with("claude-"))
+        .collect();
+
+    info!(
+        "Fetched {} Claude models for the API key",
+        model_names.len()
+    );
+    Ok(model_names)
+}
+
+/// Registry name of an API-key model (e.g. "anthropic-claude-sonnet-4-20250514").
+pub fn anthropic_model_name(model_id: &str) -> String {
+    format!("{}{}", ANTHROPIC_MODEL_PREFIX, model_id)
+}
+
+/// Save API-key models to the database.
+pub fn save_anthropic_models_to_db(db: &Database, models: &[String]) -> Result<(), std::io::Error> {
+    for model_id in models {
+        let config = ModelConfig {
+            name: anthropic_model_name(model_id),
+            model_type: ModelType::Anthropic,
+            model_id: Some(model_id.clone()),
+            context_length: 200_000,
+            supports_thinking: supports_thinking(model_id),
+            supports_vision: true,
+            supports_tools: true,
+            description: None,
+            base_url: None,
+        };
+
+        ModelRegistry::add_model_to_db_with_source(db, &config, "api_key")
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+    }
+
+    info!("Saved {} Anthropic API models to database", models.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_at(temp_dir.path().join("test.db")).unwrap();
+        db.migrate().unwrap();
+        (temp_dir, db)
+    }
+
+    #[test]
+    fn test_api_key_roundtrip() {
+        let (_temp, db) = setup_test_db();
+        let auth = AnthropicAuth::new(&db);
+
+        assert!(auth.save_api_key("  ").is_err());
+        auth.save_api_key(" sk-ant-test \n").unwrap();
+        assert!(auth.has_stored_api_key());
+        // The stored key wins over the environment
+        assert_eq!(auth.api_key().unwrap(), "sk-ant-test");
+
+        auth.remove_api_key().unwrap();
+        assert!(!auth.has_stored_api_key());
+    }
+
+    #[test]
+    fn test_save_anthropic_models_to_db() {
+        let (_temp, db) = setup_test_db();
+        save_anthropic_models_to_db(&db, &["claude-sonnet-4-20250514".to_string()]).unwrap();
+
+        let registry = ModelRegistry::load_from_db(&db).unwrap();
+        let model = registry.get("anthropic-claude-sonnet-4-20250514").unwrap();
+        assert_eq!(model.model_type, ModelType::Anthropic);
+        assert_eq!(model.effective_model_id(), "claude-sonnet-4-20250514");
+        assert!(model.supports_thinking);
+        assert!(registry.list_claude_code_models().is_empty());
+    }
+}
@@ -132,13 +132,13 @@ impl<'a> ClaudeCodeAuth<'a> {
 
 /// Model info from Anthropic API
 #[derive(Debug, Deserialize)]
-struct ModelInfo {
-    id: String,
+pub(super) struct ModelInfo {
+    pub(super) id: String,
 }
 
 #[derive(Debug, Deserialize)]
-struct ModelsResponse {
-    data: Vec<ModelInfo>,
+pub(super) struct ModelsResponse {
+    pub(super) data: Vec<ModelInfo>,
 }
 
 /// Fetch available models from Claude API
@@ -253,20 +253,16 @@ pub fn save_claude_models_to_db(db: &Database, models: &[String]) -> Result<(),
         // Create prefixed name like "claude-code-claude-sonnet-4-20250514"
         let prefixed = format!("claude-code-{}", model_name);
 
-        // Determine if it supports thinking (opus and sonnet 4+ do)
-        let supports_thinking = model_name.contains("opus")
-            || (model_name.contains("sonnet")
-                && (model_name.contains("-4") || model_name.contains("4-")));
-
         let config = ModelConfig {
             name: prefixed,
             model_type: ModelType::ClaudeCode,
             model_id: Some(model_name.clone()),
             context_length: 200_000,
-            supports_thinking,
+            supports_thinking: supports_thinking(model_name),
             supports_vision: true,
             supports_tools: true,
             description: None,
+            base_url: None,
         };
 
         ModelRegistry::add_model_to_db(db, &config)
@@ -277,6 +273,12 @@ pub fn save_claude_models_to_db(db: &Database, models: &[String]) -> Result<(),
     Ok(())
 }
 
+/// Whether a Claude model supports extended thinking (opus and sonnet 4+ do).
+pub(super) fn supports_thinking(model_id: &str) -> bool {
+    model_id.contains("opus")
+        || (model_id.contains("sonnet") && (model_id.contains("-4") || model_id.contains("4-")))
+}
+
 /// Run the Claude Code OAuth flow.
 pub async fn run_claude_code_auth(
     db_path: std::path::PathBuf,
@@ -4,11 +4,18 @@
 //! - Token storage in SQLite database
 //! - Claude Code OAuth PKCE flow
 //! - Automatic token refresh
+//! - Anthropic API keys
 //! - Model fetching from API
 
+pub mod anthropic;
 pub mod claude;
 pub mod storage;
 
+pub use anthropic::{
+    anthropic_model_name, fetch_anthropic_models, save_anthropic_models_to_db, AnthropicAuth,
+    AnthropicAuthError, ANTHROPIC_API_KEY,
+};
+
 pub use claude::{
     fetch_claude_models, filter_latest_models, get_claude_code_model, run_claude_code_auth,
     save_claude_models_to_db, ClaudeCodeAuth, ClaudeCodeAuthError,
@@ -0,0 +1,459 @@
+//! File checkpoints for undoing agent edits.
+//!
+//! Before a tool modifies a file for the first time in a run, the file's
+//! current state is snapshotted. Snapshots are grouped by the run that made
+//! them and tagged with the user message that started the turn, so front ends
+//! can revert a single turn or everything since a given message.
+//!
+//! Layout on disk:
+//!
+//! ```text
+//! {data_dir}/deskwork/checkpoints/{conversation_id}/{run_id}/manifest.json
+//! {data_dir}/deskwork/checkpoints/{conversation_id}/{run_id}/files/{index}
+//! ```
+//!
+//! A file that didn't exist before the run is recorded without content and is
+//! deleted on revert; a file the run deleted is restored from its snapshot.
+//! Checkpoints are removed together with their conversation.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, info, warn};
+
+/// Subdirectory under the deskwork data dir where checkpoints are stored.
+const CHECKPOINTS_DIR: &str = "checkpoints";
+
+const MANIFEST_FILE: &str = "manifest.json";
+const FILES_DIR: &str = "files";
+
+/// Serializes manifest updates; tools of one run may execute concurrently.
+static MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+
+/// Errors from the checkpoint store.
+#[derive(Debug, Error)]
+pub enum CheckpointError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid checkpoint manifest: {0}")]
+    Manifest(#[from] serde_json::Error),
+
+    #[error("No checkpoint for run {0}")]
+    NotFound(String),
+
+    #[error("Could not determine data directory")]
+    NoDataDir,
+}
+
+/// The state of one file before a run first modified it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileSnapshot {
+    /// Absolute path of the file.
+    pub path: PathBuf,
+    /// Whether the file existed. If not, reverting deletes it.
+    pub existed: bool,
+}
+
+/// All snapshots taken during one run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunCheckpoint {
+    pub run_id: String,
+    /// ID of the user message that started the turn, if known.
+    pub message_id: Option<i64>,
+    /// Unix timestamp (milliseconds) of the first snapshot.
+    pub created_at: i64,
+    /// Snapshots in the order they were taken. The content of entry `i` is
+    /// stored as `files/{i}`.
+    pub files: Vec<FileSnapshot>,
+}
+
+/// Files changed by a revert.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RevertSummary {
+    /// Runs that were reverted.
+    pub runs: usize,
+    /// Files written back to their earlier content.
+    pub restored: Vec<PathBuf>,
+    /// Files created by the reverted runs and now deleted.
+    pub removed: Vec<PathBuf>,
+}
+
+impl RevertSummary {
+    /// Number of files touched.
+    pub fn file_count(&self) -> usize {
+        self.restored.len() + self.removed.len()
+    }
+
+    fn merge(&mut self, other: RevertSummary) {
+        self.runs += other.runs;
+        self.restored.extend(other.restored);
+        self.removed.extend(other.removed);
+    }
+}
+
+// =============================================================================
+// Store
+// =============================================================================
+
+/// Root of all checkpoints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckpointStore {
+    root: PathBuf,
+}
+
+impl CheckpointStore {
+    /// Create a store rooted at `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The store in the deskwork data directory (next to the database).
+    ///
+    /// e.g. `~/.local/share/deskwork/checkpoints/` on Linux
+    pub fn open_default() -> Result<Self, CheckpointError> {
+        let data_dir = dirs::data_dir()
+            .or_else(|| dirs::home_dir().map(|h| h.join(".local/share")))
+            .ok_or(CheckpointError::NoDataDir)?;
+        Ok(Self::new(data_dir.join("deskwork").join(CHECKPOINTS_DIR)))
+    }
+
+    /// Checkpoints of one conversation.
+    pub fn conversation(&self, conversation_id: &str) -> ConversationCheckpoints {
+        ConversationCheckpoints {
+            dir: self.root.join(conversation_id),
+        }
+    }
+
+    /// Delete all checkpoints of a conversation.
+    pub fn delete_conversation(&self, conversation_id: &str) -> Result<(), CheckpointError> {
+        let dir = self.root.join(conversation_id);
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+            debug!(conversation_id, "Deleted checkpoints");
+        }
+        Ok(())
+    }
+
+    /// Delete checkpoints of conversations not in `live_conversations`.
+    ///
+    /// Returns the number of conversations whose checkpoints were removed.
+    pub fn collect_garbage(
+        &self,
+        live_conversations: &HashSet<String>,
+    ) -> Result<usize, CheckpointError> {
+        let entries = match fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut removed = 0;
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if entry.path().is_dir() && !live_conversations.contains(&name) {
+                fs::remove_dir_all(entry.path())?;
+                removed += 1;
+            }
+        }
+        if removed > 0 {
+            info!(removed, "Removed checkpoints of deleted conversations");
+        }
+        Ok(removed)
+    }
+}
+
+// =============================================================================
+// Conversation Checkpoints
+// =============================================================================
+
+/// Checkpoints of a single conversation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversationCheckpoints {
+    dir: PathBuf,
+}
+
+impl ConversationCheckpoints {
+    /// A handle for the tools of a turn started by user message `message_id`.
+    pub fn checkpointer(&self, message_id: Option<i64>) -> Checkpointer {
+        Checkpointer {
+            dir: self.dir.clone(),
+            message_id,
+        }
+    }
+
+    /// All run checkpoints, oldest first.
+    ///
+    /// Runs with unreadable manifests are skipped.
+    pub fn runs(&self) -> Result<Vec<RunCheckpoint>, CheckpointError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut runs: Vec<RunCheckpoint> = entries
+            .flatten()
+            .filter_map(|entry| match read_manifest(&entry.path()) {
+                Ok(run) => run,
+                Err(e) => {
+                    warn!(path = %entry.path().display(), error = %e, "Skipping checkpoint");
+                    None
+                }
+            })
+            .collect();
+        runs.sort_by_key(|run| run.created_at);
+        Ok(runs)
+    }
+
+    /// IDs of the user messages whose turns have checkpoints.
+    pub fn message_ids(&self) -> Result<HashSet<i64>, CheckpointError> {
+        Ok(self
+            .runs()?
+            .iter()
+            .filter_map(|run| run.message_id)
+            .collect())
+    }
+
+    /// Restore the files changed by one run and drop its checkpoint.
+    pub fn revert_run(&self, run_id: &str) -> Result<RevertSummary, CheckpointError> {
+        let _guard = MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let run_dir = self.dir.join(run_id);
+        let run =
+            read_manifest(&run_dir)?.ok_or_else(|| CheckpointError::NotFound(run_id.into()))?;
+
+        let mut summary = RevertSummary {
+            runs: 1,
+            ..Default::default()
+        };
+        for (index, file) in run.files.iter().enumerate() {
+            if file.existed {
+                if let Some(parent) = file.path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(run_dir.join(FILES_DIR).join(index.to_string()), &file.path)?;
+                summary.restored.push(file.path.clone());
+            } else if file.path.exists() {
+                fs::remove_file(&file.path)?;
+                summary.removed.push(file.path.clone());
+            }
+        }
+
+        fs::remove_dir_all(&run_dir)?;
+        info!(run_id, files = summary.file_count(), "Reverted run");
+        Ok(summary)
+    }
+
+    /// Revert every run of the turn started by user message `message_id`.
+    pub fn revert_turn(&self, message_id: i64) -> Result<RevertSummary, CheckpointError> {
+        self.revert_where(|run| run.message_id == Some(message_id))
+    }
+
+    /// Revert every run since user message `message_id` (inclusive), newest
+    /// first, restoring the files to their state before that message.
+    pub fn revert_since(&self, message_id: i64) -> Result<RevertSummary, CheckpointError> {
+        self.revert_where(|run| run.message_id.is_some_and(|id| id >= message_id))
+    }
+
+    fn revert_where(
+        &self,
+        predicate: impl Fn(&RunCheckpoint) -> bool,
+    ) -> Result<RevertSummary, CheckpointError> {
+        let mut summary = RevertSummary::default();
+        for run in self.runs()?.iter().rev().filter(|run| predicate(run)) {
+            summary.merge(self.revert_run(&run.run_id)?);
+        }
+        Ok(summary)
+    }
+}
+
+// =============================================================================
+// Checkpointer
+// =============================================================================
+
+/// Snapshots files for the tools of one turn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpointer {
+    dir: PathBuf,
+    message_id: Option<i64>,
+}
+
+impl Checkpointer {
+    /// Snapshot `path` unless it was already snapshotted in `run_id`.
+    ///
+    /// Call before modifying or deleting the file. `path` should be absolute.
+    pub fn snapshot(&self, run_id: &str, path: &Path) -> Result<(), CheckpointError> {
+        let _guard = MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let run_dir = self.dir.join(run_id);
+
+        let mut run = read_manifest(&run_dir)?.unwrap_or_else(|| RunCheckpoint {
+            run_id: run_id.to_string(),
+            message_id: self.message_id,
+            created_at: chrono::Utc::now().timestamp_millis(),
+            files: Vec::new(),
+        });
+        if run.files.iter().any(|file| file.path == path) {
+            return Ok(());
+        }
+
+        let files_dir = run_dir.join(FILES_DIR);
+        fs::create_dir_all(&files_dir)?;
+        let existed = path.is_file();
+        if existed {
+            fs::copy(path, files_dir.join(run.files.len().to_string()))?;
+        }
+        run.files.push(FileSnapshot {
+            path: path.to_path_buf(),
+            existed,
+        });
+
+        write_manifest(&run_dir, &run)?;
+        debug!(run_id, path = %path.display(), existed, "Snapshotted file");
+        Ok(())
+    }
+}
+
+fn read_manifest(run_dir: &Path) -> Result<Option<RunCheckpoint>, CheckpointError> {
+    match fs::read_to_string(run_dir.join(MANIFEST_FILE)) {
+        Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Write the manifest atomically so a crash never leaves a truncated one.
+fn write_manifest(run_dir: &Path, run: &RunCheckpoint) -> Result<(), CheckpointError> {
+    let tmp = run_dir.join(format!("{MANIFEST_FILE}.tmp"));
+    fs::write(&tmp, serde_json::to_string_pretty(run)?)?;
+    fs::rename(&tmp, run_dir.join(MANIFEST_FILE))?;
+    Ok(())
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, TempDir, ConversationCheckpoints) {
+        let store_dir = TempDir::new().unwrap();
+        let work_dir = TempDir::new().unwrap();
+        let checkpoints = CheckpointStore::new(store_dir.path()).conversation("conv-1");
+        (store_dir, work_dir, checkpoints)
+    }
+
+    #[test]
+    fn test_revert_restores_modified_file() {
+        let (_store, work, checkpoints) = setup();
+        let file = work.path().join("a.txt");
+        fs::write(&file, "original").unwrap();
+
+        let checkpointer = checkpoints.checkpointer(Some(1));
+        checkpointer.snapshot("run-1", &file).unwrap();
+        fs::write(&file, "first edit").unwrap();
+        // Later writes in the same run keep the first snapshot
+        checkpointer.snapshot("run-1", &file).unwrap();
+        fs::write(&file, "second edit").unwrap();
+
+        let summary = checkpoints.revert_run("run-1").unwrap();
+        assert_eq!(summary.restored, vec![file.clone()]);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "original");
+        assert!(checkpoints.runs().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_revert_deletes_created_and_restores_deleted() {
+        let (_store, work, checkpoints) = setup();
+        let created = work.path().join("sub/new.txt");
+        let deleted = work.path().join("old.txt");
+        fs::write(&deleted, "keep me").unwrap();
+
+        let checkpointer = checkpoints.checkpointer(Some(1));
+        checkpointer.snapshot("run-1", &created).unwrap();
+        fs::create_dir_all(created.parent().unwrap()).unwrap();
+        fs::write(&created, "new").unwrap();
+        checkpointer.snapshot("run-1", &deleted).unwrap();
+        fs::remove_file(&deleted).unwrap();
+
+        let summary = checkpoints.revert_run("run-1").unwrap();
+        assert_eq!(summary.removed, vec![created.clone()]);
+        assert_eq!(summary.restored, vec![deleted.clone()]);
+        assert!(!created.exists());
+        assert_eq!(fs::read_to_string(&deleted).unwrap(), "keep me");
+    }
+
+    #[test]
+    fn test_revert_since_message_reverts_newest_first() {
+        let (_store, work, checkpoints) = setup();
+        let file = work.path().join("a.txt");
+        fs::write(&file, "v1").unwrap();
+
+        checkpoints
+            .checkpointer(Some(1))
+            .snapshot("run-1", &file)
+            .unwrap();
+        fs::write(&file, "v2").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        checkpoints
+            .checkpointer(Some(3))
+            .snapshot("run-2", &file)
+            .unwrap();
+        fs::write(&file, "v3").unwrap();
+
+        assert_eq!(checkpoints.message_ids().unwrap(), HashSet::from([1, 3]));
+
+        // Reverting the last turn goes back to the state after the first one
+        checkpoints.revert_turn(3).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "v2");
+
+        checkpoints
+            .checkpointer(Some(5))
+            .snapshot("run-3", &file)
+            .unwrap();
+        fs::write(&file, "v4").unwrap();
+
+        let summary = checkpoints.revert_since(1).unwrap();
+        assert_eq!(summary.runs, 2);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "v1");
+    }
+
+    #[test]
+    fn test_revert_missing_run() {
+        let (_store, _work, checkpoints) = setup();
+        assert!(matches!(
+            checkpoints.revert_run("nope"),
+            Err(CheckpointError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_delete_and_collect_garbage() {
+        let store_dir = TempDir::new().unwrap();
+        let work = TempDir::new().unwrap();
+        let file = work.path().join("a.txt");
+        let store = CheckpointStore::new(store_dir.path());
+
+        for conversation in ["live", "deleted", "orphan"] {
+            store
+                .conversation(conversation)
+                .checkpointer(None)
+                .snapshot("run", &file)
+                .unwrap();
+        }
+
+        store.delete_conversation("deleted").unwrap();
+        assert!(!store_dir.path().join("deleted").exists());
+
+        let live = HashSet::from(["live".to_string()]);
+        assert_eq!(store.collect_garbage(&live).unwrap(), 1);
+        assert!(store_dir.path().join("live").exists());
+        assert!(!store_dir.path().join("orphan").exists());
+    }
+}
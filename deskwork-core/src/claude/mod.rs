@@ -1,7 +1,11 @@
 //! Claude model integration.
 //!
-//! Thin wrapper around serdes-ai-models for Claude Code OAuth.
+//! Thin wrapper around serdes-ai-models for Claude Code OAuth and Anthropic
+//! API keys.
 
 mod model;
 
-pub use model::{create_model, create_model_with_thinking};
+pub use model::{
+    create_api_key_model, create_model, create_model_with_thinking, sampling_settings,
+    SamplingSettings,
+};
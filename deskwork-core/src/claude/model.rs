@@ -1,14 +1,35 @@
-//! Claude Code OAuth model configuration.
+//! Claude model configuration.
 //!
-//! Thin wrapper around serdes-ai-models to create models with OAuth tokens.
+//! Thin wrapper around serdes-ai-models to create models with Claude Code
+//! OAuth tokens or Anthropic API keys.
 //!
 //! **Important**: Temperature and max_tokens are NOT set on the model.
-//! They are passed via `ModelSettings` at request time through the agent builder.
+//! They are passed via `ModelSettings` at request time through the agent builder
+//! (see [`sampling_settings`]).
 
 use crate::config::Settings;
+use crate::models::{ModelType, ANTHROPIC_MODEL_PREFIX};
+use serdes_ai_models::anthropic::AnthropicModel;
 use serdes_ai_models::claude_code_oauth::ClaudeCodeOAuthModel;
 
-/// Strip the claude-code- prefix from model names.
+/// Temperature of Claude Code OAuth requests (1.0 is required for extended
+/// thinking).
+const CLAUDE_CODE_TEMPERATURE: f64 = 1.0;
+
+/// Output limit of Claude Code OAuth requests (the Claude Code default).
+const CLAUDE_CODE_MAX_TOKENS: u64 = 30000;
+
+/// Output tokens left for the answer beyond the thinking budget.
+const MIN_ANSWER_TOKENS: u64 = 1024;
+
+/// Temperature and output token limit for requests.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplingSettings {
+    pub temperature: f64,
+    pub max_tokens: u64,
+}
+
+/// Strip the claude-code- or anthropic- prefix from model names.
 ///
 /// Model names in the registry are prefixed (e.g., "claude-code-claude-sonnet-4-20250514")
 /// but the API expects just the model ID (e.g., "claude-sonnet-4-20250514").
@@ -16,6 +37,7 @@ fn strip_model_prefix(model_name: &str) -> &str {
     model_name
         .strip_prefix("claude-code-")
         .or_else(|| model_name.strip_prefix("claude_code_"))
+        .or_else(|| model_name.strip_prefix(ANTHROPIC_MODEL_PREFIX))
         .unwrap_or(model_name)
 }
 
@@ -59,6 +81,50 @@ pub fn create_model(
     model
 }
 
+/// Create an Anthropic API model authenticated with an API key.
+///
+/// `model_name` may carry the `anthropic-` registry prefix. Extended thinking
+/// is enabled with `thinking_budget`.
+pub fn create_api_key_model(
+    model_name: &str,
+    api_key: &str,
+    thinking_budget: Option<u64>,
+) -> AnthropicModel {
+    let actual_model_name = strip_model_prefix(model_name);
+    let mut model = AnthropicModel::new(actual_model_name, api_key);
+
+    if let Some(budget) = thinking_budget {
+        model = model.with_thinking(Some(budget));
+    }
+
+    model
+}
+
+/// Temperature and output token limit for requests to a model of
+/// `model_type`.
+///
+/// Claude Code OAuth uses fixed values. API-key and endpoint models use the
+/// user's `temperature` and `max_tokens`, except that extended thinking on
+/// the Anthropic API needs a temperature of 1.0 and room for the thinking
+/// budget.
+pub fn sampling_settings(model_type: ModelType, settings: &Settings) -> SamplingSettings {
+    match model_type {
+        ModelType::ClaudeCode => SamplingSettings {
+            temperature: CLAUDE_CODE_TEMPERATURE,
+            max_tokens: CLAUDE_CODE_MAX_TOKENS,
+        },
+        ModelType::Anthropic if settings.extended_thinking => SamplingSettings {
+            temperature: 1.0,
+            max_tokens: u64::from(settings.max_tokens)
+                .max(u64::from(settings.thinking_budget) + MIN_ANSWER_TOKENS),
+        },
+        ModelType::Anthropic | ModelType::OpenAiCompatible | ModelType::Local => SamplingSettings {
+            temperature: f64::from(settings.temperature),
+            max_tokens: u64::from(settings.max_tokens),
+        },
+    }
+}
+
 /// Create a Claude Code OAuth model with explicit thinking config.
 ///
 /// Useful when you want to override the settings.
@@ -92,6 +158,10 @@ mod tests {
             strip_model_prefix("claude_code_claude-sonnet-4-20250514"),
             "claude-sonnet-4-20250514"
         );
+        assert_eq!(
+            strip_model_prefix("anthropic-claude-sonnet-4-20250514"),
+            "claude-sonnet-4-20250514"
+        );
         assert_eq!(
             strip_model_prefix("claude-sonnet-4-20250514"),
             "claude-sonnet-4-20250514"
@@ -123,6 +193,43 @@ mod tests {
         assert_eq!(model.name(), "claude-sonnet-4-20250514");
     }
 
+    #[test]
+    fn test_create_api_key_model() {
+        let model = create_api_key_model(
+            "anthropic-claude-sonnet-4-20250514",
+            "sk-ant-test",
+            Some(10000),
+        );
+        assert_eq!(model.name(), "claude-sonnet-4-20250514");
+    }
+
+    #[test]
+    fn test_sampling_settings() {
+        let mut settings = Settings::default();
+        settings.temperature = 0.3;
+        settings.max_tokens = 4096;
+        settings.extended_thinking = false;
+
+        // Claude Code ignores the settings
+        let oauth = sampling_settings(ModelType::ClaudeCode, &settings);
+        assert_eq!(oauth.max_tokens, 30000);
+        assert!((oauth.temperature - 1.0).abs() < f64::EPSILON);
+
+        let api = sampling_settings(ModelType::Anthropic, &settings);
+        assert_eq!(api.max_tokens, 4096);
+        assert!((api.temperature - 0.3).abs() < 1e-6);
+        assert_eq!(sampling_settings(ModelType::Local, &settings), api);
+
+        // Thinking forces temperature 1.0 and leaves room for the budget
+        settings.extended_thinking = true;
+        settings.thinking_budget = 10000;
+        let thinking = sampling_settings(ModelType::Anthropic, &settings);
+        assert_eq!(thinking.max_tokens, 11024);
+        assert!((thinking.temperature - 1.0).abs() < f64::EPSILON);
+        // Endpoint models never think
+        assert_eq!(sampling_settings(ModelType::Local, &settings), api);
+    }
+
     #[test]
     fn test_create_model_with_explicit_thinking() {
         let model = create_model_with_thinking(
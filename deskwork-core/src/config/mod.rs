@@ -2,6 +2,8 @@
 //!
 //! Manages application settings stored in SQLite.
 
+pub mod permissions;
 mod settings;
 
+pub use permissions::{PermissionAction, PermissionRule};
 pub use settings::{model_display_name, RenderMode, Settings, Theme, DEFAULT_MODEL};
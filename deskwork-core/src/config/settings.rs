@@ -5,6 +5,13 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::permissions::PermissionRule;
+use crate::models::{
+    ModelRegistry, ModelType, ANTHROPIC_MODEL_PREFIX, LOCAL_MODEL_PREFIX,
+    OPENAI_COMPATIBLE_MODEL_PREFIX,
+};
+use crate::tools::PathPolicy;
+
 // =============================================================================
 // Theme Selection
 // =============================================================================
@@ -44,6 +51,9 @@ impl std::fmt::Display for Theme {
 pub const DEFAULT_MODEL: &str = "claude-code-claude-sonnet-4-20250514";
 pub const DEFAULT_PLUGIN_CONTEXT_TOKEN_BUDGET: u32 = 6000;
 
+/// Default share of the context window at which history is compacted.
+pub const DEFAULT_COMPACTION_THRESHOLD: f32 = 0.8;
+
 /// Default list of enabled skill category IDs.
 ///
 /// We keep this helper because serde's `default = "..."` needs a function.
@@ -59,19 +69,49 @@ fn default_plugin_context_token_budget() -> u32 {
     DEFAULT_PLUGIN_CONTEXT_TOKEN_BUDGET
 }
 
+fn default_permission_rules() -> Vec<PermissionRule> {
+    Vec::new()
+}
+
 fn default_stream_markdown_enabled() -> bool {
     true
 }
 
+fn default_path_policy() -> PathPolicy {
+    PathPolicy::default()
+}
+
+fn default_auto_compact() -> bool {
+    true
+}
+
+fn default_compaction_threshold() -> f32 {
+    DEFAULT_COMPACTION_THRESHOLD
+}
+
 /// Get display name for a model ID.
 ///
 /// Handles prefixed names like "claude-code-claude-sonnet-4-20250514".
+/// Anthropic API-key models get an "(API key)" suffix; models of
+/// OpenAI-compatible and local endpoints keep their ID and get a suffix naming
+/// the endpoint kind.
 /// Examples:
 ///   - `claude-sonnet-4-5-20250929` -> "Claude 4.5 Sonnet"
 ///   - `claude-opus-4-5-20251101` -> "Claude 4.5 Opus"
 ///   - `claude-sonnet-4-20250514` -> "Claude 4 Sonnet"
 ///   - `claude-3-5-sonnet-20241022` -> "Claude 3.5 Sonnet"
 pub fn model_display_name(model_id: &str) -> String {
+    // API-key models are marked, so they can be told from the OAuth ones
+    if let Some(stripped) = model_id.strip_prefix(ANTHROPIC_MODEL_PREFIX) {
+        return format!("{} (API key)", model_display_name(stripped));
+    }
+    if let Some(stripped) = model_id.strip_prefix(OPENAI_COMPATIBLE_MODEL_PREFIX) {
+        return format!("{} (OpenAI-compatible)", stripped);
+    }
+    if let Some(stripped) = model_id.strip_prefix(LOCAL_MODEL_PREFIX) {
+        return format!("{} (local)", stripped);
+    }
+
     // Strip claude-code- prefix if present
     let stripped = model_id.strip_prefix("claude-code-").unwrap_or(model_id);
 
@@ -150,10 +190,11 @@ pub struct Settings {
     #[serde(default)]
     pub available_models: Vec<String>,
 
-    /// Max tokens for response.
+    /// Max tokens for response (Anthropic API-key and endpoint models only).
     pub max_tokens: u32,
 
-    /// Temperature (0.0 - 1.0) - higher = more creative.
+    /// Temperature (0.0 - 1.0) - higher = more creative (Anthropic API-key
+    /// and endpoint models only, and ignored with extended thinking).
     pub temperature: f32,
 
     /// Enable extended thinking mode.
@@ -191,6 +232,28 @@ pub struct Settings {
     /// User-edited playbook content per skill category (maps category_id → markdown content).
     #[serde(default = "default_category_playbooks")]
     pub category_playbooks: HashMap<String, String>,
+
+    /// Tool permission rules (allow/ask/deny per tool and argument pattern).
+    #[serde(default = "default_permission_rules")]
+    pub permission_rules: Vec<PermissionRule>,
+
+    /// Which paths the file tools may read and modify.
+    #[serde(default = "default_path_policy")]
+    pub path_policy: PathPolicy,
+
+    /// Summarize older turns automatically when the prompt nears the model's
+    /// context window.
+    #[serde(default = "default_auto_compact")]
+    pub auto_compact: bool,
+
+    /// Share of the context window (0.5 - 0.95) at which compaction kicks in.
+    #[serde(default = "default_compaction_threshold")]
+    pub compaction_threshold: f32,
+
+    /// Offer the `shell_session` tool, which keeps one shell per conversation
+    /// so `cd`, `export` and `source` persist between commands.
+    #[serde(default)]
+    pub persistent_shell: bool,
 }
 
 impl Default for Settings {
@@ -199,8 +262,8 @@ impl Default for Settings {
             model: DEFAULT_MODEL.to_string(),
             available_models: Vec::new(),
             // NOTE: max_tokens and temperature are NOT used for Claude Code OAuth
-            // They are hardcoded in the executor (30000 tokens, temp 1.0)
-            // Keeping these fields for potential future non-OAuth model support
+            // They are hardcoded there (30000 tokens, temp 1.0); Anthropic API-key
+            // models use them (see `claude::sampling_settings`)
             max_tokens: 30000,
             temperature: 1.0,
             // Extended thinking is always enabled for Claude Code OAuth
@@ -214,6 +277,11 @@ impl Default for Settings {
             plugins_enabled: default_plugins_enabled(),
             plugin_context_token_budget: default_plugin_context_token_budget(),
             category_playbooks: default_category_playbooks(),
+            permission_rules: default_permission_rules(),
+            path_policy: default_path_policy(),
+            auto_compact: default_auto_compact(),
+            compaction_threshold: default_compaction_threshold(),
+            persistent_shell: false,
         }
     }
 }
@@ -253,6 +321,7 @@ impl Settings {
         self.max_tokens = self.max_tokens.clamp(256, 32768);
         self.thinking_budget = self.thinking_budget.clamp(1000, 100000);
         self.plugin_context_token_budget = self.plugin_context_token_budget.clamp(500, 100_000);
+        self.compaction_threshold = self.compaction_threshold.clamp(0.5, 0.95);
 
         // Ensure model is set
         if self.model.is_empty() {
@@ -279,6 +348,26 @@ impl Settings {
             }
         }
     }
+
+    /// Replace the available models of one provider, keeping the others.
+    ///
+    /// Passing no models removes the provider's models, e.g. after signing
+    /// out. Each model's provider is looked up in `registry`.
+    pub fn set_provider_models(
+        &mut self,
+        registry: &ModelRegistry,
+        model_type: ModelType,
+        models: Vec<String>,
+    ) {
+        let mut available: Vec<String> = self
+            .available_models
+            .iter()
+            .filter(|model| registry.resolve(model).model_type != model_type)
+            .cloned()
+            .collect();
+        available.extend(models);
+        self.set_available_models(available);
+    }
 }
 
 // =============================================================================
@@ -375,6 +464,8 @@ mod tests {
         let loaded = Settings::load(&db);
         assert_eq!(loaded.render_mode, RenderMode::Auto);
         assert!(loaded.stream_markdown_enabled);
+        assert!(loaded.auto_compact);
+        assert!((loaded.compaction_threshold - DEFAULT_COMPACTION_THRESHOLD).abs() < f32::EPSILON);
     }
 
     // -------------------------------------------------------------------------
@@ -424,6 +515,19 @@ mod tests {
             "Claude 3 Opus"
         );
 
+        // API-key models
+        assert_eq!(
+            model_display_name("anthropic-claude-sonnet-4-20250514"),
+            "Claude 4 Sonnet (API key)"
+        );
+
+        // Endpoint models keep their ID
+        assert_eq!(model_display_name("local-qwen3:8b"), "qwen3:8b (local)");
+        assert_eq!(
+            model_display_name("openai-gpt-4o"),
+            "gpt-4o (OpenAI-compatible)"
+        );
+
         // Unknown models return as-is
         assert_eq!(model_display_name("unknown-model"), "unknown-model");
     }
@@ -571,6 +675,19 @@ mod tests {
         assert_eq!(settings.plugin_context_token_budget, 100_000);
     }
 
+    #[test]
+    fn test_settings_validate_clamps_compaction_threshold() {
+        let mut settings = Settings::default();
+
+        settings.compaction_threshold = 0.1;
+        settings.validate();
+        assert!((settings.compaction_threshold - 0.5).abs() < f32::EPSILON);
+
+        settings.compaction_threshold = 1.5;
+        settings.validate();
+        assert!((settings.compaction_threshold - 0.95).abs() < f32::EPSILON);
+    }
+
     #[test]
     fn test_settings_plugin_fields_default_on_missing() {
         let (_temp, db) = setup_test_db();
@@ -602,6 +719,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_settings_path_policy_default_on_missing() {
+        let (_temp, db) = setup_test_db();
+
+        // Old configs have no path_policy; the default deny globs must apply
+        let json = r#"{"model":"test","max_tokens":4096,"temperature":0.7,"extended_thinking":false,"thinking_budget":10000,"theme":"Dark","show_thinking":true,"render_mode":"Auto"}"#;
+        db.set_setting("settings", json).unwrap();
+
+        let loaded = Settings::load(&db);
+        assert_eq!(loaded.path_policy, PathPolicy::default());
+        assert!(loaded.path_policy.deny_globs.iter().any(|g| g == "**/.env"));
+    }
+
     #[test]
     fn test_settings_category_playbooks_roundtrip() {
         let (_temp, db) = setup_test_db();
@@ -620,6 +750,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_settings_permission_rules_roundtrip() {
+        use crate::config::permissions::PermissionAction;
+
+        let (_temp, db) = setup_test_db();
+
+        let mut settings = Settings::default();
+        assert!(settings.permission_rules.is_empty());
+        settings.permission_rules.push(PermissionRule::new(
+            "run_shell_command(git *)",
+            PermissionAction::Allow,
+        ));
+        settings.save(&db).unwrap();
+
+        let loaded = Settings::load(&db);
+        assert_eq!(loaded.permission_rules, settings.permission_rules);
+    }
+
     #[test]
     fn test_settings_set_available_models() {
         let mut settings = Settings::default();
@@ -655,6 +803,34 @@ mod tests {
         assert_eq!(settings.model, "claude-3-opus-20240229");
     }
 
+    #[test]
+    fn test_settings_set_provider_models() {
+        let mut settings = Settings::default();
+        settings.model = "claude-code-claude-opus-4-20250514".to_string();
+        settings.set_available_models(vec![
+            "claude-code-claude-opus-4-20250514".to_string(),
+            "anthropic-claude-opus-4-20250514".to_string(),
+        ]);
+
+        let registry = ModelRegistry::new();
+        settings.set_provider_models(
+            &registry,
+            ModelType::Anthropic,
+            vec!["anthropic-claude-sonnet-4-20250514".to_string()],
+        );
+        assert_eq!(
+            settings.available_models,
+            vec![
+                "claude-code-claude-opus-4-20250514".to_string(),
+                "anthropic-claude-sonnet-4-20250514".to_string(),
+            ]
+        );
+
+        // Signing out of Claude Code moves to an API-key model
+        settings.set_provider_models(&registry, ModelType::ClaudeCode, Vec::new());
+        assert_eq!(settings.model, "anthropic-claude-sonnet-4-20250514");
+    }
+
     #[test]
     fn test_settings_serialization() {
         let settings = Settings::default();
@@ -0,0 +1,375 @@
+//! Conversation storage.
+//!
+//! Persists chat sessions so they survive `clear_chat` and restarts:
+//! - `conversations` rows hold the title and the serialized agent history
+//!   (`Vec<ModelRequest>`) needed to continue the conversation with the model.
+//! - `messages` rows hold what the user saw: plain text content plus the
+//!   rendered content blocks as an opaque JSON string owned by the UI.
+
+use rusqlite::{params, OptionalExtension};
+use serdes_ai_core::ModelRequest;
+
+use super::Database;
+
+/// Summary of a stored conversation (for listing).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversationSummary {
+    pub id: String,
+    pub title: Option<String>,
+    /// Unix timestamp (seconds).
+    pub created_at: i64,
+    /// Unix timestamp (seconds). Bumped on every append.
+    pub updated_at: i64,
+    pub message_count: i64,
+}
+
+/// A single stored chat message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredMessage {
+    pub id: i64,
+    /// One of `user`, `assistant`, `system`.
+    pub role: String,
+    /// Plain text content (user input or concatenated assistant text).
+    pub content: String,
+    /// Rendered content blocks as JSON, if the caller stored any.
+    pub blocks: Option<String>,
+    /// Unix timestamp (seconds).
+    pub created_at: i64,
+}
+
+/// A fully loaded conversation.
+#[derive(Debug, Clone)]
+pub struct StoredConversation {
+    pub summary: ConversationSummary,
+    pub messages: Vec<StoredMessage>,
+    /// Agent-level message history, ready to pass back to `run_agent`.
+    pub history: Vec<ModelRequest>,
+}
+
+impl Database {
+    // =========================================================================
+    // Conversation Storage
+    // =========================================================================
+
+    /// Create a new conversation and return its ID.
+    pub fn create_conversation(&self, title: Option<&str>) -> Result<String, rusqlite::Error> {
+        let id = uuid::Uuid::new_v4().to_string();
+        self.conn.execute(
+            "INSERT INTO conversations (id, title) VALUES (?, ?)",
+            params![id, title],
+        )?;
+        Ok(id)
+    }
+
+    /// Append a message to a conversation and bump its `updated_at`.
+    ///
+    /// Returns the new message's row ID.
+    pub fn append_message(
+        &self,
+        conversation_id: &str,
+        role: &str,
+        content: &str,
+        blocks: Option<&str>,
+    ) -> Result<i64, rusqlite::Error> {
+        self.conn.execute(
+            "INSERT INTO messages (conversation_id, role, content, blocks) VALUES (?, ?, ?, ?)",
+            params![conversation_id, role, content, blocks],
+        )?;
+        let id = self.conn.last_insert_rowid();
+        self.touch_conversation(conversation_id)?;
+        Ok(id)
+    }
+
+    /// Replace the serialized agent history of a conversation.
+    pub fn save_conversation_history(
+        &self,
+        conversation_id: &str,
+        history: &[ModelRequest],
+    ) -> anyhow::Result<()> {
+        let json = serde_json::to_string(history)?;
+        self.conn.execute(
+            "UPDATE conversations SET history = ?, updated_at = unixepoch() WHERE id = ?",
+            params![json, conversation_id],
+        )?;
+        Ok(())
+    }
+
+    /// List all conversations, most recently updated first.
+    pub fn list_conversations(&self) -> Result<Vec<ConversationSummary>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.id, c.title, c.created_at, c.updated_at,
+                    (SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id)
+             FROM conversations c
+             ORDER BY c.updated_at DESC, c.rowid DESC",
+        )?;
+        let rows = stmt.query_map([], summary_from_row)?;
+        rows.collect()
+    }
+
+    /// Get a conversation summary by ID.
+    ///
+    /// Returns `None` if the conversation doesn't exist.
+    pub fn get_conversation(
+        &self,
+        conversation_id: &str,
+    ) -> Result<Option<ConversationSummary>, rusqlite::Error> {
+        self.conn
+            .query_row(
+                "SELECT c.id, c.title, c.created_at, c.updated_at,
+                        (SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id)
+                 FROM conversations c WHERE c.id = ?",
+                [conversation_id],
+                summary_from_row,
+            )
+            .optional()
+    }
+
+    /// Load a conversation with all of its messages and agent history.
+    ///
+    /// Returns `None` if the conversation doesn't exist. A history that fails
+    /// to deserialize (e.g. written by an incompatible version) is logged and
+    /// replaced with an empty one so the visible transcript still loads.
+    pub fn load_conversation(
+        &self,
+        conversation_id: &str,
+    ) -> Result<Option<StoredConversation>, rusqlite::Error> {
+        let Some(summary) = self.get_conversation(conversation_id)? else {
+            return Ok(None);
+        };
+
+        let history_json: Option<String> = self.conn.query_row(
+            "SELECT history FROM conversations WHERE id = ?",
+            [conversation_id],
+            |row| row.get(0),
+        )?;
+        let history = match history_json {
+            Some(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                tracing::warn!(
+                    conversation_id,
+                    error = %e,
+                    "Failed to parse stored conversation history, starting fresh"
+                );
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+
+        let mut stmt = self.conn.prepare(
+            "SELECT id, role, content, blocks, created_at FROM messages
+             WHERE conversation_id = ? ORDER BY id",
+        )?;
+        let messages = stmt
+            .query_map([conversation_id], |row| {
+                Ok(StoredMessage {
+                    id: row.get(0)?,
+                    role: row.get(1)?,
+                    content: row.get(2)?,
+                    blocks: row.get(3)?,
+                    created_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some(StoredConversation {
+            summary,
+            messages,
+            history,
+        }))
+    }
+
+    /// Rename a conversation.
+    pub fn rename_conversation(
+        &self,
+        conversation_id: &str,
+        title: &str,
+    ) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "UPDATE conversations SET title = ? WHERE id = ?",
+            [title, conversation_id],
+        )?;
+        Ok(())
+    }
+
+    /// Delete a conversation and (via cascade) all of its messages.
+    ///
+    /// No-op if the conversation doesn't exist.
+    pub fn delete_conversation(&self, conversation_id: &str) -> Result<(), rusqlite::Error> {
+        self.conn
+            .execute("DELETE FROM conversations WHERE id = ?", [conversation_id])?;
+        Ok(())
+    }
+
+    fn touch_conversation(&self, conversation_id: &str) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "UPDATE conversations SET updated_at = unixepoch() WHERE id = ?",
+            [conversation_id],
+        )?;
+        Ok(())
+    }
+}
+
+fn summary_from_row(row: &rusqlite::Row<'_>) -> Result<ConversationSummary, rusqlite::Error> {
+    Ok(ConversationSummary {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        created_at: row.get(2)?,
+        updated_at: row.get(3)?,
+        message_count: row.get(4)?,
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = Database::open_at(db_path).unwrap();
+        db.migrate().unwrap();
+        (temp_dir, db)
+    }
+
+    #[test]
+    fn test_create_and_get_conversation() {
+        let (_temp, db) = setup_test_db();
+
+        let id = db.create_conversation(Some("First chat")).unwrap();
+        let summary = db.get_conversation(&id).unwrap().unwrap();
+
+        assert_eq!(summary.id, id);
+        assert_eq!(summary.title.as_deref(), Some("First chat"));
+        assert_eq!(summary.message_count, 0);
+    }
+
+    #[test]
+    fn test_get_conversation_missing_returns_none() {
+        let (_temp, db) = setup_test_db();
+        assert!(db.get_conversation("nope").unwrap().is_none());
+        assert!(db.load_conversation("nope").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_append_and_load_messages_in_order() {
+        let (_temp, db) = setup_test_db();
+        let id = db.create_conversation(None).unwrap();
+
+        db.append_message(&id, "user", "hello", None).unwrap();
+        db.append_message(&id, "assistant", "hi there", Some(r#"[{"Text":"hi there"}]"#))
+            .unwrap();
+
+        let convo = db.load_conversation(&id).unwrap().unwrap();
+        assert_eq!(convo.summary.message_count, 2);
+        assert_eq!(convo.messages.len(), 2);
+        assert_eq!(convo.messages[0].role, "user");
+        assert_eq!(convo.messages[0].content, "hello");
+        assert!(convo.messages[0].blocks.is_none());
+        assert_eq!(convo.messages[1].role, "assistant");
+        assert_eq!(
+            convo.messages[1].blocks.as_deref(),
+            Some(r#"[{"Text":"hi there"}]"#)
+        );
+        assert!(convo.history.is_empty());
+    }
+
+    #[test]
+    fn test_append_message_rejects_invalid_role() {
+        let (_temp, db) = setup_test_db();
+        let id = db.create_conversation(None).unwrap();
+
+        assert!(db.append_message(&id, "tool", "x", None).is_err());
+    }
+
+    #[test]
+    fn test_append_message_requires_existing_conversation() {
+        let (_temp, db) = setup_test_db();
+        assert!(db.append_message("missing", "user", "x", None).is_err());
+    }
+
+    #[test]
+    fn test_save_and_load_empty_history() {
+        let (_temp, db) = setup_test_db();
+        let id = db.create_conversation(None).unwrap();
+
+        db.save_conversation_history(&id, &[]).unwrap();
+
+        let convo = db.load_conversation(&id).unwrap().unwrap();
+        assert!(convo.history.is_empty());
+    }
+
+    #[test]
+    fn test_corrupt_history_loads_as_empty() {
+        let (_temp, db) = setup_test_db();
+        let id = db.create_conversation(None).unwrap();
+        db.append_message(&id, "user", "hello", None).unwrap();
+        db.conn()
+            .execute(
+                "UPDATE conversations SET history = 'not json' WHERE id = ?",
+                [&id],
+            )
+            .unwrap();
+
+        let convo = db.load_conversation(&id).unwrap().unwrap();
+        assert!(convo.history.is_empty());
+        assert_eq!(convo.messages.len(), 1);
+    }
+
+    #[test]
+    fn test_list_conversations_most_recent_first() {
+        let (_temp, db) = setup_test_db();
+        let older = db.create_conversation(Some("older")).unwrap();
+        let newer = db.create_conversation(Some("newer")).unwrap();
+        db.conn()
+            .execute(
+                "UPDATE conversations SET updated_at = 100 WHERE id = ?",
+                [&older],
+            )
+            .unwrap();
+        db.conn()
+            .execute(
+                "UPDATE conversations SET updated_at = 200 WHERE id = ?",
+                [&newer],
+            )
+            .unwrap();
+
+        let list = db.list_conversations().unwrap();
+        let ids: Vec<_> = list.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec![newer.as_str(), older.as_str()]);
+    }
+
+    #[test]
+    fn test_rename_conversation() {
+        let (_temp, db) = setup_test_db();
+        let id = db.create_conversation(Some("old")).unwrap();
+
+        db.rename_conversation(&id, "new").unwrap();
+
+        let summary = db.get_conversation(&id).unwrap().unwrap();
+        assert_eq!(summary.title.as_deref(), Some("new"));
+    }
+
+    #[test]
+    fn test_delete_conversation_cascades_messages() {
+        let (_temp, db) = setup_test_db();
+        let id = db.create_conversation(None).unwrap();
+        db.append_message(&id, "user", "hello", None).unwrap();
+
+        db.delete_conversation(&id).unwrap();
+
+        assert!(db.get_conversation(&id).unwrap().is_none());
+        let orphaned: i64 = db
+            .conn()
+            .query_row(
+                "SELECT COUNT(*) FROM messages WHERE conversation_id = ?",
+                [&id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(orphaned, 0);
+    }
+}
@@ -0,0 +1,156 @@
+//! User-defined MCP server storage.
+//!
+//! One row per server: its connector entry, stored as the same JSON that
+//! `.mcp.json` files use, and whether it is enabled.
+
+use rusqlite::params;
+
+use super::Database;
+use crate::skills::types::McpServerEntry;
+
+/// A stored MCP server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredMcpServer {
+    pub name: String,
+    pub entry: McpServerEntry,
+    pub enabled: bool,
+}
+
+impl Database {
+    // =========================================================================
+    // MCP Servers
+    // =========================================================================
+
+    /// List the stored MCP servers by name.
+    ///
+    /// Rows whose config no longer parses are skipped.
+    pub fn list_mcp_servers(&self) -> Result<Vec<StoredMcpServer>, rusqlite::Error> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name, config, enabled FROM mcp_servers ORDER BY name")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)? != 0,
+            ))
+        })?;
+
+        let mut servers = Vec::new();
+        for row in rows {
+            let (name, config, enabled) = row?;
+            match serde_json::from_str(&config) {
+                Ok(entry) => servers.push(StoredMcpServer {
+                    name,
+                    entry,
+                    enabled,
+                }),
+                Err(e) => {
+                    tracing::warn!(server = %name, error = %e, "Failed to parse stored MCP server");
+                }
+            }
+        }
+        Ok(servers)
+    }
+
+    /// Store an MCP server, replacing any with the same name.
+    pub fn save_mcp_server(
+        &self,
+        name: &str,
+        entry: &McpServerEntry,
+        enabled: bool,
+    ) -> anyhow::Result<()> {
+        let config = serde_json::to_string(entry)?;
+        self.conn.execute(
+            "INSERT INTO mcp_servers (name, config, enabled, updated_at)
+             VALUES (?, ?, ?, unixepoch())
+             ON CONFLICT(name) DO UPDATE SET config = excluded.config,
+                enabled = excluded.enabled, updated_at = excluded.updated_at",
+            params![name, config, enabled as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Enable or disable a stored MCP server.
+    pub fn set_mcp_server_enabled(&self, name: &str, enabled: bool) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "UPDATE mcp_servers SET enabled = ?, updated_at = unixepoch() WHERE name = ?",
+            params![enabled as i64, name],
+        )?;
+        Ok(())
+    }
+
+    /// Delete a stored MCP server.
+    ///
+    /// No-op if the server doesn't exist.
+    pub fn delete_mcp_server(&self, name: &str) -> Result<(), rusqlite::Error> {
+        self.conn
+            .execute("DELETE FROM mcp_servers WHERE name = ?", [name])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = Database::open_at(db_path).unwrap();
+        db.migrate().unwrap();
+        (temp_dir, db)
+    }
+
+    fn stdio_entry(command: &str) -> McpServerEntry {
+        McpServerEntry {
+            r#type: "stdio".to_string(),
+            url: None,
+            command: Some(command.to_string()),
+            args: Some(vec!["-y".to_string(), "@acme/mcp".to_string()]),
+            env: Some(HashMap::from([(
+                "ACME_TOKEN".to_string(),
+                "${ACME_TOKEN}".to_string(),
+            )])),
+            headers: None,
+        }
+    }
+
+    #[test]
+    fn test_mcp_server_roundtrip() {
+        let (_temp, db) = setup_test_db();
+
+        db.save_mcp_server("acme", &stdio_entry("npx"), true)
+            .unwrap();
+        db.save_mcp_server("zeta", &stdio_entry("uvx"), false)
+            .unwrap();
+
+        let servers = db.list_mcp_servers().unwrap();
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers[0].name, "acme");
+        assert_eq!(servers[0].entry, stdio_entry("npx"));
+        assert!(servers[0].enabled);
+        assert!(!servers[1].enabled);
+    }
+
+    #[test]
+    fn test_mcp_server_update_and_delete() {
+        let (_temp, db) = setup_test_db();
+
+        db.save_mcp_server("acme", &stdio_entry("npx"), true)
+            .unwrap();
+        db.save_mcp_server("acme", &stdio_entry("uvx"), true)
+            .unwrap();
+        db.set_mcp_server_enabled("acme", false).unwrap();
+
+        let servers = db.list_mcp_servers().unwrap();
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].entry.command.as_deref(), Some("uvx"));
+        assert!(!servers[0].enabled);
+
+        db.delete_mcp_server("acme").unwrap();
+        assert!(db.list_mcp_servers().unwrap().is_empty());
+    }
+}
@@ -76,11 +76,64 @@ CREATE TABLE IF NOT EXISTS models (
 );
 "#;
 
+/// SQL for conversation history migration.
+const MIGRATION_004_CONVERSATION_HISTORY: &str = r#"
+-- Serialized agent message history (JSON array of ModelRequest) per conversation
+ALTER TABLE conversations ADD COLUMN history TEXT;
+
+-- Rendered UI blocks (thinking, tool calls, text) per message, as JSON
+ALTER TABLE messages ADD COLUMN blocks TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_conversations_updated ON conversations(updated_at);
+"#;
+
+/// SQL for token usage ledger migration.
+const MIGRATION_005_USAGE: &str = r#"
+-- Token usage ledger (one row per conversation, model and local day)
+CREATE TABLE IF NOT EXISTS usage (
+    conversation_id TEXT NOT NULL DEFAULT '',  -- '' when not tied to a conversation
+    model TEXT NOT NULL,
+    day TEXT NOT NULL,                         -- YYYY-MM-DD (local time)
+    requests INTEGER NOT NULL DEFAULT 0,
+    input_tokens INTEGER NOT NULL DEFAULT 0,
+    output_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_write_tokens INTEGER NOT NULL DEFAULT 0,
+    thinking_tokens INTEGER NOT NULL DEFAULT 0,
+    updated_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    PRIMARY KEY (conversation_id, model, day)
+);
+
+CREATE INDEX IF NOT EXISTS idx_usage_day ON usage(day);
+"#;
+
+/// SQL for model endpoint migration.
+const MIGRATION_006_MODEL_ENDPOINTS: &str = r#"
+-- Base URL of OpenAI-compatible and local models (NULL for Claude models)
+ALTER TABLE models ADD COLUMN base_url TEXT;
+"#;
+
+/// SQL for user MCP servers migration.
+const MIGRATION_007_MCP_SERVERS: &str = r#"
+-- User-defined MCP servers, independent of skill categories
+CREATE TABLE IF NOT EXISTS mcp_servers (
+    name TEXT PRIMARY KEY,
+    config TEXT NOT NULL,  -- JSON McpServerEntry
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    updated_at INTEGER NOT NULL DEFAULT (unixepoch())
+);
+"#;
+
 /// All migrations in order. Each is (name, sql).
 const MIGRATIONS: &[(&str, &str)] = &[
     ("001_initial", MIGRATION_001_INITIAL),
     ("002_oauth_tokens", MIGRATION_002_OAUTH_TOKENS),
     ("003_models", MIGRATION_003_MODELS),
+    ("004_conversation_history", MIGRATION_004_CONVERSATION_HISTORY),
+    ("005_usage", MIGRATION_005_USAGE),
+    ("006_model_endpoints", MIGRATION_006_MODEL_ENDPOINTS),
+    ("007_mcp_servers", MIGRATION_007_MCP_SERVERS),
 ];
 
 /// Run all pending migrations.
@@ -134,7 +187,14 @@ mod tests {
         let count: i32 = conn
             .query_row("SELECT COUNT(*) FROM migrations", [], |row| row.get(0))
             .unwrap();
-        assert_eq!(count, 3); // Three migrations applied
+        assert_eq!(count, 7); // Seven migrations applied
+
+        let model_columns: Vec<String> = {
+            let mut stmt = conn.prepare("PRAGMA table_info(models)").unwrap();
+            let rows = stmt.query_map([], |row| row.get(1)).unwrap();
+            rows.map(|r| r.unwrap()).collect()
+        };
+        assert!(model_columns.contains(&"base_url".to_string()));
     }
 
     #[test]
@@ -159,5 +219,7 @@ mod tests {
         assert!(tables.contains(&"migrations".to_string()));
         assert!(tables.contains(&"oauth_tokens".to_string()));
         assert!(tables.contains(&"models".to_string()));
+        assert!(tables.contains(&"usage".to_string()));
+        assert!(tables.contains(&"mcp_servers".to_string()));
     }
 }
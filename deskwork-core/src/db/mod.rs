@@ -4,8 +4,17 @@
 //! - API keys (encrypted provider credentials)
 //! - Settings (app preferences)
 //! - Conversations and messages (chat history)
+//! - Token usage ledger
+//! - User-defined MCP servers
 
+mod conversations;
+mod mcp_servers;
 mod migrations;
+mod usage;
+
+pub use conversations::{ConversationSummary, StoredConversation, StoredMessage};
+pub use mcp_servers::StoredMcpServer;
+pub use usage::usage_day_today;
 
 use rusqlite::Connection;
 use std::path::PathBuf;
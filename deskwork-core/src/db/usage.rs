@@ -0,0 +1,164 @@
+//! Token usage ledger.
+//!
+//! One row per conversation, model and local day. Rows are additive: each
+//! finished turn adds its counts to the matching row.
+
+use rusqlite::params;
+
+use super::Database;
+use crate::executor::TokenUsage;
+
+/// Today's ledger key (`YYYY-MM-DD`, local time).
+pub fn usage_day_today() -> String {
+    chrono::Local::now().date_naive().format("%Y-%m-%d").to_string()
+}
+
+impl Database {
+    // =========================================================================
+    // Token Usage
+    // =========================================================================
+
+    /// Add `usage` to the ledger row for `(conversation_id, model, day)`.
+    ///
+    /// Usage that isn't tied to a conversation is recorded under an empty ID.
+    pub fn record_usage(
+        &self,
+        conversation_id: Option<&str>,
+        model: &str,
+        day: &str,
+        usage: &TokenUsage,
+    ) -> Result<(), rusqlite::Error> {
+        if usage.is_empty() {
+            return Ok(());
+        }
+
+        self.conn.execute(
+            "INSERT INTO usage (conversation_id, model, day, requests, input_tokens, output_tokens,
+                                cache_read_tokens, cache_write_tokens, thinking_tokens)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(conversation_id, model, day) DO UPDATE SET
+                requests = requests + excluded.requests,
+                input_tokens = input_tokens + excluded.input_tokens,
+                output_tokens = output_tokens + excluded.output_tokens,
+                cache_read_tokens = cache_read_tokens + excluded.cache_read_tokens,
+                cache_write_tokens = cache_write_tokens + excluded.cache_write_tokens,
+                thinking_tokens = thinking_tokens + excluded.thinking_tokens,
+                updated_at = unixepoch()",
+            params![
+                conversation_id.unwrap_or(""),
+                model,
+                day,
+                usage.requests as i64,
+                usage.input_tokens as i64,
+                usage.output_tokens as i64,
+                usage.cache_read_tokens as i64,
+                usage.cache_write_tokens as i64,
+                usage.thinking_tokens as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Total usage across all conversations and models for a day.
+    pub fn usage_for_day(&self, day: &str) -> Result<TokenUsage, rusqlite::Error> {
+        self.sum_usage("WHERE day = ?", day)
+    }
+
+    /// Total usage of a conversation across all days and models.
+    pub fn usage_for_conversation(
+        &self,
+        conversation_id: &str,
+    ) -> Result<TokenUsage, rusqlite::Error> {
+        self.sum_usage("WHERE conversation_id = ?", conversation_id)
+    }
+
+    fn sum_usage(&self, filter: &str, value: &str) -> Result<TokenUsage, rusqlite::Error> {
+        let sql = format!(
+            "SELECT COALESCE(SUM(requests), 0), COALESCE(SUM(input_tokens), 0),
+                    COALESCE(SUM(output_tokens), 0), COALESCE(SUM(cache_read_tokens), 0),
+                    COALESCE(SUM(cache_write_tokens), 0), COALESCE(SUM(thinking_tokens), 0)
+             FROM usage {filter}"
+        );
+        self.conn.query_row(&sql, [value], |row| {
+            Ok(TokenUsage {
+                requests: row.get::<_, i64>(0)? as u64,
+                input_tokens: row.get::<_, i64>(1)? as u64,
+                output_tokens: row.get::<_, i64>(2)? as u64,
+                cache_read_tokens: row.get::<_, i64>(3)? as u64,
+                cache_write_tokens: row.get::<_, i64>(4)? as u64,
+                thinking_tokens: row.get::<_, i64>(5)? as u64,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = Database::open_at(db_path).unwrap();
+        db.migrate().unwrap();
+        (temp_dir, db)
+    }
+
+    fn usage(input: u64, output: u64) -> TokenUsage {
+        TokenUsage {
+            requests: 1,
+            input_tokens: input,
+            output_tokens: output,
+            cache_read_tokens: 10,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_record_usage_accumulates() {
+        let (_temp, db) = setup_test_db();
+
+        db.record_usage(Some("conv"), "claude-sonnet", "2026-01-02", &usage(100, 20))
+            .unwrap();
+        db.record_usage(Some("conv"), "claude-sonnet", "2026-01-02", &usage(50, 5))
+            .unwrap();
+
+        let total = db.usage_for_conversation("conv").unwrap();
+        assert_eq!(total.requests, 2);
+        assert_eq!(total.input_tokens, 150);
+        assert_eq!(total.output_tokens, 25);
+        assert_eq!(total.cache_read_tokens, 20);
+    }
+
+    #[test]
+    fn test_usage_for_day_spans_conversations_and_models() {
+        let (_temp, db) = setup_test_db();
+
+        db.record_usage(Some("a"), "claude-sonnet", "2026-01-02", &usage(100, 20))
+            .unwrap();
+        db.record_usage(Some("b"), "claude-opus", "2026-01-02", &usage(10, 2))
+            .unwrap();
+        db.record_usage(None, "claude-sonnet", "2026-01-02", &usage(1, 1))
+            .unwrap();
+        db.record_usage(Some("a"), "claude-sonnet", "2026-01-03", &usage(7, 7))
+            .unwrap();
+
+        let day = db.usage_for_day("2026-01-02").unwrap();
+        assert_eq!(day.requests, 3);
+        assert_eq!(day.input_tokens, 111);
+
+        let conv = db.usage_for_conversation("a").unwrap();
+        assert_eq!(conv.input_tokens, 107);
+    }
+
+    #[test]
+    fn test_empty_usage() {
+        let (_temp, db) = setup_test_db();
+
+        db.record_usage(Some("a"), "m", "2026-01-02", &TokenUsage::default())
+            .unwrap();
+        assert!(db.usage_for_day("2026-01-02").unwrap().is_empty());
+        assert_eq!(usage_day_today().len(), 10);
+    }
+}
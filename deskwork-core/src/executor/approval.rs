@@ -0,0 +1,243 @@
+//! Tool-call approval.
+//!
+//! Every tool call passes through a [`PermissionGate`] before it runs. The gate
+//! evaluates the permission rules from `Settings`; calls that need approval are
+//! surfaced to the front end as [`ExecutorEvent::ApprovalRequested`] and the
+//! tool waits until the front end answers through the [`ApprovalResponder`].
+
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::oneshot;
+use tracing::{debug, info};
+
+use super::{EventSender, ExecutorEvent};
+use crate::config::permissions::{self, PermissionAction, PermissionRule};
+
+/// The user's answer to an approval request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApprovalDecision {
+    /// Run this call only.
+    ApproveOnce,
+    /// Run this call and allow matching calls (the request's `suggested_rule`)
+    /// for the rest of the run. Front ends persist the rule to `Settings`.
+    ApproveAlways,
+    /// Don't run the call. The feedback is passed back to the model.
+    Deny { feedback: Option<String> },
+}
+
+/// Reply handle carried by [`ExecutorEvent::ApprovalRequested`].
+///
+/// Cloneable so the event stays `Clone`; only the first response is delivered.
+/// Dropping every clone without responding counts as a denial.
+#[derive(Clone)]
+pub struct ApprovalResponder {
+    sender: Arc<Mutex<Option<oneshot::Sender<ApprovalDecision>>>>,
+}
+
+impl ApprovalResponder {
+    /// Create a responder and the receiver the waiting tool call listens on.
+    pub fn channel() -> (Self, oneshot::Receiver<ApprovalDecision>) {
+        let (tx, rx) = oneshot::channel();
+        (
+            Self {
+                sender: Arc::new(Mutex::new(Some(tx))),
+            },
+            rx,
+        )
+    }
+
+    /// Send the decision. Returns `false` if a decision was already sent or
+    /// the run is gone.
+    pub fn respond(&self, decision: ApprovalDecision) -> bool {
+        let sender = self.sender.lock().ok().and_then(|mut guard| guard.take());
+        match sender {
+            Some(tx) => tx.send(decision).is_ok(),
+            None => false,
+        }
+    }
+}
+
+impl std::fmt::Debug for ApprovalResponder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pending = self
+            .sender
+            .lock()
+            .map(|guard| guard.is_some())
+            .unwrap_or(false);
+        f.debug_struct("ApprovalResponder")
+            .field("pending", &pending)
+            .finish()
+    }
+}
+
+/// Applies permission rules to tool calls for a single run.
+pub(crate) struct PermissionGate {
+    rules: Mutex<Vec<PermissionRule>>,
+    event_sender: EventSender,
+}
+
+impl PermissionGate {
+    pub(crate) fn new(rules: Vec<PermissionRule>, event_sender: EventSender) -> Self {
+        Self {
+            rules: Mutex::new(rules),
+            event_sender,
+        }
+    }
+
+    /// Decide whether a tool call may run, asking the user if needed.
+    ///
+    /// Returns `Err` with a message for the model when the call must not run.
+    pub(crate) async fn check(
+        &self,
+        tool_name: &str,
+        call_id: Option<String>,
+        args: &serde_json::Value,
+    ) -> Result<(), String> {
+        let action = {
+            let rules = self.rules.lock().map_err(|_| "Permission rules unavailable")?;
+            permissions::evaluate(&rules, tool_name, args)
+        };
+        debug!(tool = tool_name, ?action, "Permission check");
+
+        match action {
+            PermissionAction::Allow => Ok(()),
+            PermissionAction::Deny => Err(format!(
+                "Permission denied: `{tool_name}` calls matching these arguments are blocked by \
+                 the user's permission rules. Do not retry; choose another approach or ask the user."
+            )),
+            PermissionAction::Ask => self.ask(tool_name, call_id, args).await,
+        }
+    }
+
+    async fn ask(
+        &self,
+        tool_name: &str,
+        call_id: Option<String>,
+        args: &serde_json::Value,
+    ) -> Result<(), String> {
+        let suggested_rule = permissions::suggested_rule(tool_name, args);
+        let (responder, rx) = ApprovalResponder::channel();
+
+        let event = ExecutorEvent::ApprovalRequested {
+            id: call_id,
+            tool_name: tool_name.to_string(),
+            arguments: args.clone(),
+            suggested_rule: suggested_rule.clone(),
+            responder,
+        };
+        if self.event_sender.send(event).is_err() {
+            return Err("Approval could not be requested; the call was not run.".to_string());
+        }
+
+        match rx.await {
+            Ok(ApprovalDecision::ApproveOnce) => Ok(()),
+            Ok(ApprovalDecision::ApproveAlways) => {
+                info!(rule = %suggested_rule, "Tool approved for the rest of the run");
+                if let Ok(mut rules) = self.rules.lock() {
+                    rules.push(PermissionRule::new(suggested_rule, PermissionAction::Allow));
+                }
+                Ok(())
+            }
+            Ok(ApprovalDecision::Deny { feedback }) => {
+                let mut message = format!("The user denied this `{tool_name}` call.");
+                if let Some(feedback) = feedback.filter(|f| !f.trim().is_empty()) {
+                    message.push_str(&format!(" User feedback: {}", feedback.trim()));
+                }
+                Err(message)
+            }
+            Err(_) => Err(format!(
+                "The approval request for `{tool_name}` was dismissed; the call was not run."
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::event_channel;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_allow_runs_without_event() {
+        let (tx, mut rx) = event_channel();
+        let gate = PermissionGate::new(vec![], tx);
+
+        assert!(gate.check("read_file", None, &json!({})).await.is_ok());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_deny_rule_blocks() {
+        let (tx, _rx) = event_channel();
+        let rules = vec![PermissionRule::new("delete_file", PermissionAction::Deny)];
+        let gate = PermissionGate::new(rules, tx);
+
+        let result = gate.check("delete_file", None, &json!({"file_path": "a"})).await;
+        assert!(result.unwrap_err().contains("Permission denied"));
+    }
+
+    #[tokio::test]
+    async fn test_ask_approve_always_adds_rule() {
+        let (tx, mut rx) = event_channel();
+        let gate = Arc::new(PermissionGate::new(vec![], tx));
+        let args = json!({"command": "git status"});
+
+        let responder = tokio::spawn(async move {
+            match rx.recv().await {
+                Some(ExecutorEvent::ApprovalRequested {
+                    suggested_rule,
+                    responder,
+                    ..
+                }) => {
+                    assert_eq!(suggested_rule, "run_shell_command(git *)");
+                    assert!(responder.respond(ApprovalDecision::ApproveAlways));
+                    assert!(!responder.respond(ApprovalDecision::ApproveOnce));
+                }
+                other => panic!("unexpected event: {:?}", other),
+            }
+            rx
+        });
+
+        assert!(gate.check("run_shell_command", None, &args).await.is_ok());
+        let _rx = responder.await.unwrap();
+
+        // Second matching call runs without asking.
+        let args = json!({"command": "git diff"});
+        assert!(gate.check("run_shell_command", None, &args).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_ask_deny_includes_feedback() {
+        let (tx, mut rx) = event_channel();
+        let gate = PermissionGate::new(vec![], tx);
+
+        tokio::spawn(async move {
+            if let Some(ExecutorEvent::ApprovalRequested { responder, .. }) = rx.recv().await {
+                responder.respond(ApprovalDecision::Deny {
+                    feedback: Some("use trash instead".to_string()),
+                });
+            }
+        });
+
+        let err = gate
+            .check("delete_file", None, &json!({"file_path": "a"}))
+            .await
+            .unwrap_err();
+        assert!(err.contains("denied"));
+        assert!(err.contains("use trash instead"));
+    }
+
+    #[tokio::test]
+    async fn test_dropped_responder_counts_as_denial() {
+        let (tx, mut rx) = event_channel();
+        let gate = PermissionGate::new(vec![], tx);
+
+        tokio::spawn(async move {
+            let _ = rx.recv().await;
+        });
+
+        let result = gate.check("delete_file", None, &json!({})).await;
+        assert!(result.unwrap_err().contains("dismissed"));
+    }
+}
@@ -0,0 +1,207 @@
+//! What a cancelled run leaves behind.
+//!
+//! The model only returns the updated message history when a run completes.
+//! When the user stops a run, [`PartialTurn`] rebuilds a history from the
+//! events seen so far: the user's message, followed by a note describing the
+//! partial response and the tool calls that ran. Continuing the conversation
+//! from it, the model knows what already happened (files may have been
+//! edited) without replaying an unfinished response.
+
+use serdes_ai_core::ModelRequest;
+
+use super::ExecutorEvent;
+
+/// Maximum characters of the partial response kept in the note.
+const MAX_PARTIAL_TEXT_CHARS: usize = 4_000;
+
+/// Where a tool call of the cancelled run got to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ToolCallState {
+    /// The model was still writing the arguments.
+    Requested,
+    /// Arguments complete; the tool runs or is about to.
+    Running,
+    Finished {
+        success: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct ToolCallRecord {
+    id: Option<String>,
+    name: String,
+    state: ToolCallState,
+}
+
+/// Events of a run so far, for building the history if it is cancelled.
+#[derive(Debug, Clone)]
+pub(super) struct PartialTurn {
+    /// History the run started from (after any compaction).
+    history: Vec<ModelRequest>,
+    user_input: String,
+    text: String,
+    tool_calls: Vec<ToolCallRecord>,
+}
+
+impl PartialTurn {
+    pub(super) fn new(history: Vec<ModelRequest>, user_input: String) -> Self {
+        Self {
+            history,
+            user_input,
+            text: String::new(),
+            tool_calls: Vec::new(),
+        }
+    }
+
+    /// Take note of an event forwarded to the front end.
+    pub(super) fn record(&mut self, event: &ExecutorEvent) {
+        match event {
+            ExecutorEvent::TextDelta(text) => self.text.push_str(text),
+            ExecutorEvent::ToolCallStart { id, name } => self.tool_calls.push(ToolCallRecord {
+                id: id.clone(),
+                name: name.clone(),
+                state: ToolCallState::Requested,
+            }),
+            ExecutorEvent::ToolCallComplete { id, .. } => {
+                if let Some(call) = self.find(id) {
+                    call.state = ToolCallState::Running;
+                }
+            }
+            ExecutorEvent::ToolResult { id, success, .. } => {
+                if let Some(call) = self.find(id) {
+                    call.state = ToolCallState::Finished { success: *success };
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether a tool is still running and should get the chance to stop
+    /// cleanly (e.g. kill its process) before the run is dropped.
+    pub(super) fn has_running_tools(&self) -> bool {
+        self.tool_calls
+            .iter()
+            .any(|call| call.state == ToolCallState::Running)
+    }
+
+    /// The history to continue the conversation from.
+    ///
+    /// Attachments of the user's message are not included.
+    pub(super) fn history(&self) -> Vec<ModelRequest> {
+        let mut history = self.history.clone();
+        let mut request = ModelRequest::new();
+        request.add_user_prompt(self.user_input.clone());
+        request.add_user_prompt(self.note());
+        history.push(request);
+        history
+    }
+
+    /// Describe the partial response for the model.
+    fn note(&self) -> String {
+        let mut note = String::from("[The user stopped the response to this message.");
+        let text = self.text.trim();
+        if text.is_empty() && self.tool_calls.is_empty() {
+            note.push_str(" Nothing had been done yet.]");
+            return note;
+        }
+
+        if !text.is_empty() {
+            note.push_str(" Your partial response was:\n\n");
+            match text.char_indices().nth(MAX_PARTIAL_TEXT_CHARS) {
+                Some((cut, _)) => {
+                    note.push_str(&text[..cut]);
+                    note.push_str("… [truncated]");
+                }
+                None => note.push_str(text),
+            }
+        }
+        if !self.tool_calls.is_empty() {
+            note.push_str("\n\nTool calls made before stopping:");
+            for call in &self.tool_calls {
+                let state = match call.state {
+                    ToolCallState::Requested => "not run",
+                    ToolCallState::Running => "stopped while running",
+                    ToolCallState::Finished { success: true } => "succeeded",
+                    ToolCallState::Finished { success: false } => "failed or was cancelled",
+                };
+                note.push_str(&format!("\n- {}: {}", call.name, state));
+            }
+        }
+        note.push(']');
+        note
+    }
+
+    fn find(&mut self, id: &Option<String>) -> Option<&mut ToolCallRecord> {
+        // Without IDs, events refer to the latest call
+        match id {
+            Some(_) => self.tool_calls.iter_mut().rev().find(|call| call.id == *id),
+            None => self.tool_calls.last_mut(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::ToolOutput;
+
+    fn tool_event(name: &str, id: &str) -> [ExecutorEvent; 2] {
+        [
+            ExecutorEvent::ToolCallStart {
+                id: Some(id.to_string()),
+                name: name.to_string(),
+            },
+            ExecutorEvent::ToolCallComplete {
+                id: Some(id.to_string()),
+                name: name.to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_note_without_progress() {
+        let partial = PartialTurn::new(Vec::new(), "Fix the bug".to_string());
+        assert_eq!(
+            partial.note(),
+            "[The user stopped the response to this message. Nothing had been done yet.]"
+        );
+        assert!(!partial.has_running_tools());
+    }
+
+    #[test]
+    fn test_note_lists_text_and_tool_calls() {
+        let mut partial = PartialTurn::new(Vec::new(), "Fix the bug".to_string());
+        partial.record(&ExecutorEvent::TextDelta("Let me ".to_string()));
+        partial.record(&ExecutorEvent::TextDelta("look.".to_string()));
+        for event in tool_event("read_file", "a") {
+            partial.record(&event);
+        }
+        partial.record(&ExecutorEvent::ToolResult {
+            id: Some("a".to_string()),
+            name: "read_file".to_string(),
+            result: ToolOutput::text("fn main() {}"),
+            success: true,
+        });
+        for event in tool_event("run_shell_command", "b") {
+            partial.record(&event);
+        }
+        assert!(partial.has_running_tools());
+
+        let note = partial.note();
+        assert!(note.contains("Your partial response was:\n\nLet me look."));
+        assert!(note.contains("- read_file: succeeded"));
+        assert!(note.contains("- run_shell_command: stopped while running"));
+        assert!(note.ends_with(']'));
+    }
+
+    #[test]
+    fn test_history_appends_one_user_message() {
+        let mut previous = ModelRequest::new();
+        previous.add_user_prompt("Hello".to_string());
+        let partial = PartialTurn::new(vec![previous], "Fix the bug".to_string());
+
+        let history = partial.history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].parts.len(), 2);
+    }
+}
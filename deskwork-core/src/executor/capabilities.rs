@@ -0,0 +1,179 @@
+//! Fitting requests to what the model supports.
+//!
+//! Conversations can switch models midway, so the history may hold parts the
+//! current model can't take: thinking blocks signed by another provider, or
+//! images sent to a vision model. [`fit_history`] drops those parts for models
+//! without thinking or vision support. It works on the serialized history, the
+//! same way compaction reads it, and reads each part back as the message type
+//! it was serialized from to tell what it is.
+
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use serdes_ai_core::messages::{ModelResponsePart, UserContentPart};
+use serdes_ai_core::ModelRequest;
+
+use crate::models::ModelConfig;
+
+/// Kinds of parts a model may not support.
+#[derive(Debug, Clone, Copy, Default)]
+struct Unsupported {
+    thinking: bool,
+    attachments: bool,
+}
+
+impl Unsupported {
+    fn matches(self, value: &Value) -> bool {
+        (self.thinking && is_thinking(value)) || (self.attachments && is_attachment(value))
+    }
+}
+
+/// Whether a serialized part is the model's thinking.
+pub(super) fn is_thinking(value: &Value) -> bool {
+    value.is_object()
+        && matches!(
+            ModelResponsePart::deserialize(value),
+            Ok(ModelResponsePart::Thinking { .. })
+        )
+}
+
+/// Whether a serialized part is an image or document the user attached.
+pub(super) fn is_attachment(value: &Value) -> bool {
+    value.is_object()
+        && matches!(
+            UserContentPart::deserialize(value),
+            Ok(UserContentPart::Image { .. } | UserContentPart::Document { .. })
+        )
+}
+
+/// Drop the history parts `model` doesn't support.
+pub(super) fn fit_history(history: Vec<ModelRequest>, model: &ModelConfig) -> Vec<ModelRequest> {
+    let unsupported = Unsupported {
+        thinking: !model.supports_thinking,
+        attachments: !model.supports_vision,
+    };
+    if !unsupported.thinking && !unsupported.attachments {
+        return history;
+    }
+
+    history
+        .into_iter()
+        .map(|request| strip_request(request, unsupported))
+        .collect()
+}
+
+/// Tell the model about attachments left out of the user's message because
+/// it doesn't support them.
+pub(super) fn note_dropped_attachments(user_input: String, dropped: usize) -> String {
+    if dropped == 0 {
+        return user_input;
+    }
+    format!(
+        "{user_input}\n\n[{dropped} attachment(s) left out: this model does not accept images or documents]"
+    )
+}
+
+/// Strip the unsupported parts from one history entry.
+///
+/// Entries without such parts, or that no longer deserialize once stripped,
+/// are kept as they are.
+fn strip_request(request: ModelRequest, unsupported: Unsupported) -> ModelRequest {
+    let Ok(mut value) = serde_json::to_value(&request) else {
+        return request;
+    };
+    let removed = strip_parts(&mut value, unsupported);
+    if removed == 0 {
+        return request;
+    }
+    match serde_json::from_value(value) {
+        Ok(stripped) => {
+            debug!(removed, "Dropped unsupported parts from history");
+            stripped
+        }
+        Err(e) => {
+            warn!(error = %e, "Failed to strip unsupported parts from history");
+            request
+        }
+    }
+}
+
+/// Remove the unsupported array items, at any depth.
+/// Returns the number of items removed.
+fn strip_parts(value: &mut Value, unsupported: Unsupported) -> usize {
+    match value {
+        Value::Array(items) => {
+            let before = items.len();
+            items.retain(|item| !unsupported.matches(item));
+            (before - items.len())
+                + items
+                    .iter_mut()
+                    .map(|item| strip_parts(item, unsupported))
+                    .sum::<usize>()
+        }
+        Value::Object(map) => map.values_mut().map(|v| strip_parts(v, unsupported)).sum(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serdes_ai_core::messages::{ImageMediaType, UserContent};
+
+    fn image_part() -> UserContentPart {
+        UserContentPart::image_binary(vec![0x89, b'P', b'N', b'G'], ImageMediaType::Png)
+    }
+
+    #[test]
+    fn test_is_attachment() {
+        let image = serde_json::to_value(image_part()).unwrap();
+        assert!(is_attachment(&image));
+        assert!(!is_thinking(&image));
+
+        let text = serde_json::to_value(UserContentPart::text("What is this?")).unwrap();
+        assert!(!is_attachment(&text));
+        assert!(!is_attachment(&Value::String("image".to_string())));
+    }
+
+    #[test]
+    fn test_strip_parts() {
+        let content =
+            UserContent::parts(vec![UserContentPart::text("What is this?"), image_part()]);
+        let mut value = serde_json::to_value(&content).unwrap();
+        let text_only = serde_json::to_value(UserContent::parts(vec![UserContentPart::text(
+            "What is this?",
+        )]))
+        .unwrap();
+
+        let thinking = Unsupported {
+            thinking: true,
+            ..Default::default()
+        };
+        assert_eq!(strip_parts(&mut value, thinking), 0);
+        let attachments = Unsupported {
+            attachments: true,
+            ..Default::default()
+        };
+        assert_eq!(strip_parts(&mut value, attachments), 1);
+        assert_eq!(value, text_only);
+    }
+
+    #[test]
+    fn test_note_dropped_attachments() {
+        assert_eq!(note_dropped_attachments("Hi".to_string(), 0), "Hi");
+        let note = note_dropped_attachments("What is this?".to_string(), 2);
+        assert!(note.starts_with("What is this?\n\n[2 attachment(s) left out"));
+    }
+
+    #[test]
+    fn test_fit_history_keeps_plain_requests() {
+        let mut request = ModelRequest::new();
+        request.add_user_prompt("Hello".to_string());
+        let model = ModelConfig::unregistered("local-qwen3:8b");
+
+        let history = fit_history(vec![request], &model);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].parts.len(), 1);
+    }
+}
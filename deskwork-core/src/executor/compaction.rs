@@ -0,0 +1,554 @@
+//! Context compaction.
+//!
+//! Long sessions eventually outgrow the model's context window because
+//! `run_agent` resends the whole message history every turn. Before a run, the
+//! prompt size is estimated against the model's `context_length`; past the
+//! configured threshold, the older turns are summarized by the model into a
+//! single synthetic user message and only the most recent turns are kept
+//! verbatim. Turns are never split, so tool calls stay paired with their
+//! results.
+//!
+//! The same machinery backs the manual `/compact` command through
+//! [`compact_conversation`].
+
+use futures::StreamExt;
+use serde_json::Value;
+use tracing::{debug, info, warn};
+
+use serdes_ai_agent::{agent, AgentStream, AgentStreamEvent, RunOptions};
+use serdes_ai_core::messages::{ModelRequestPart, UserContent};
+use serdes_ai_core::ModelRequest;
+
+use super::capabilities::is_attachment;
+use super::{EventSender, ExecutorEvent, TokenUsage};
+use crate::config::Settings;
+use crate::db::Database;
+use crate::models::{ModelConfig, ModelRegistry, ModelType};
+
+/// Context window assumed for models missing from the registry.
+pub const DEFAULT_CONTEXT_LENGTH: usize = 200_000;
+
+/// Rough characters-per-token ratio used for estimates.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Tokens assumed for each image or document attachment, whatever its size.
+const ATTACHMENT_TOKENS: usize = 1_600;
+
+/// Output tokens reserved for the model's reply when checking the threshold.
+const RESPONSE_RESERVE_TOKENS: usize = 30_000;
+
+/// Share of the context window kept verbatim after automatic compaction.
+const KEEP_RECENT_FRACTION: f32 = 0.2;
+
+/// Longest single text (tool output, file content) copied into the transcript.
+const MAX_TRANSCRIPT_ENTRY_CHARS: usize = 2_000;
+
+/// Prefix of the synthetic message that replaces the summarized turns.
+pub const SUMMARY_PREFIX: &str = "[Summary of the earlier conversation]";
+
+const COMPACTION_SYSTEM_PROMPT: &str = "\
+You compress conversation transcripts between a user and an AI assistant that \
+works on the user's files with tools. Write a concise summary that lets the \
+assistant continue the work without the original transcript. Keep:
+- the user's goals, requirements and preferences;
+- decisions made and why;
+- files created, edited or deleted, with the relevant paths;
+- important tool results, errors and their resolutions;
+- open questions and the next steps that were planned.
+Drop greetings, repetition and raw tool output that is no longer needed. \
+Write in plain prose and short bullet lists. Do not address the user.";
+
+/// Result of a successful compaction.
+#[derive(Debug, Clone)]
+pub struct Compaction {
+    /// The compacted history: the summary message followed by the kept turns.
+    pub history: Vec<ModelRequest>,
+    /// Number of history entries replaced by the summary.
+    pub summarized_requests: usize,
+    /// Estimated history size before compaction.
+    pub tokens_before: usize,
+    /// Estimated history size after compaction.
+    pub tokens_after: usize,
+    /// Usage of the summarization request.
+    pub usage: TokenUsage,
+}
+
+/// Arguments for [`compact_conversation`].
+pub struct CompactArgs {
+    /// Claude Code OAuth access token, or the API key for Anthropic API and
+    /// OpenAI-compatible models.
+    pub access_token: String,
+    pub model_name: String,
+    /// Registry entry of the model; `None` assumes
+    /// [`ModelConfig::unregistered`].
+    pub model_config: Option<ModelConfig>,
+    pub message_history: Vec<ModelRequest>,
+    pub event_sender: EventSender,
+}
+
+/// Look up the context window of a model in the registry.
+pub fn context_length_for_model(db: &Database, model_name: &str) -> usize {
+    ModelRegistry::get_from_db(db, model_name)
+        .ok()
+        .flatten()
+        .map(|config| config.context_length)
+        .filter(|&length| length > 0)
+        .unwrap_or(DEFAULT_CONTEXT_LENGTH)
+}
+
+/// Estimate the token count of a piece of text.
+pub fn estimate_text_tokens(text: &str) -> usize {
+    text.len().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Estimate the token count of a history entry from its serialized form.
+fn estimate_request_tokens(request: &ModelRequest) -> usize {
+    serde_json::to_value(request)
+        .map(|value| estimate_value_tokens(&value))
+        .unwrap_or(0)
+}
+
+/// Estimate the tokens of a serialized message: its texts, tool calls and
+/// tool results, plus a fixed amount per attachment (their base64 data isn't
+/// text the model reads).
+fn estimate_value_tokens(value: &Value) -> usize {
+    let mut texts = Vec::new();
+    collect_texts(value, &mut texts);
+    let text_tokens: usize = texts.iter().map(|text| estimate_text_tokens(text)).sum();
+    text_tokens + count_attachments(value) * ATTACHMENT_TOKENS
+}
+
+fn count_attachments(value: &Value) -> usize {
+    match value {
+        Value::Object(_) if is_attachment(value) => 1,
+        Value::Object(map) => map.values().map(count_attachments).sum(),
+        Value::Array(items) => items.iter().map(count_attachments).sum(),
+        _ => 0,
+    }
+}
+
+/// Estimate the token count of a whole history.
+pub fn estimate_history_tokens(history: &[ModelRequest]) -> usize {
+    history.iter().map(estimate_request_tokens).sum()
+}
+
+/// Whether a prompt of `prompt_tokens` should be compacted before sending.
+pub fn needs_compaction(prompt_tokens: usize, context_length: usize, threshold: f32) -> bool {
+    let limit = (context_length as f32 * threshold) as usize;
+    prompt_tokens + RESPONSE_RESERVE_TOKENS.min(context_length / 4) > limit
+}
+
+/// Whether a history entry starts a new user turn.
+fn starts_user_turn(request: &ModelRequest) -> bool {
+    request
+        .parts
+        .iter()
+        .any(|part| matches!(part, ModelRequestPart::UserPrompt(_)))
+}
+
+/// Find where the kept tail of the history starts.
+///
+/// `entries` holds the estimated size of each history entry and whether it
+/// starts a user turn. The tail always begins at a turn boundary, keeps at
+/// least the last turn, and grows turn by turn while it fits in `keep_tokens`.
+/// Returns 0 when there is nothing older to summarize.
+fn split_index(entries: &[(usize, bool)], keep_tokens: usize) -> usize {
+    let mut split = entries.len();
+    let mut kept = 0usize;
+    let mut turn_tokens = 0usize;
+
+    for (index, &(tokens, starts_turn)) in entries.iter().enumerate().rev() {
+        turn_tokens += tokens;
+        if !starts_turn {
+            continue;
+        }
+        let is_last_turn = split == entries.len();
+        if !is_last_turn && kept + turn_tokens > keep_tokens {
+            break;
+        }
+        kept += turn_tokens;
+        turn_tokens = 0;
+        split = index;
+    }
+
+    if split == entries.len() {
+        0
+    } else {
+        split
+    }
+}
+
+/// Render history entries as a plain-text transcript for the summarizer.
+///
+/// Works on the serialized form so every kind of part (prompts, responses,
+/// tool calls and returns) contributes its text. Long texts are truncated.
+fn transcript(history: &[ModelRequest]) -> String {
+    let mut out = String::new();
+    for (index, request) in history.iter().enumerate() {
+        let Ok(value) = serde_json::to_value(request) else {
+            continue;
+        };
+        let mut texts = Vec::new();
+        collect_texts(&value, &mut texts);
+        if texts.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("--- Message {} ---\n", index + 1));
+        for text in texts {
+            out.push_str(&truncate_chars(&text, MAX_TRANSCRIPT_ENTRY_CHARS));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Collect the human-readable strings of a serialized message.
+///
+/// Keeps `content`, `text`, `args` and `tool_name` fields; skips ids,
+/// signatures, timestamps and attachments.
+fn collect_texts(value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::Object(_) if is_attachment(value) => {}
+        Value::Object(map) => {
+            for (key, value) in map {
+                match (key.as_str(), value) {
+                    ("content" | "text" | "thinking" | "tool_name", Value::String(s)) => {
+                        if !s.trim().is_empty() {
+                            out.push(s.clone());
+                        }
+                    }
+                    ("args" | "arguments", value @ (Value::Object(_) | Value::String(_))) => {
+                        out.push(match value {
+                            Value::String(s) => s.clone(),
+                            other => other.to_string(),
+                        });
+                    }
+                    ("data" | "signature" | "id" | "tool_call_id" | "timestamp", _) => {}
+                    (_, nested) => collect_texts(nested, out),
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_texts(item, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((cut, _)) => format!("{}… [truncated]", &text[..cut]),
+        None => text.to_string(),
+    }
+}
+
+/// Build the synthetic message that stands in for the summarized turns.
+fn summary_request(summary: &str) -> ModelRequest {
+    let mut request = ModelRequest::new();
+    request.add_user_prompt(format!("{SUMMARY_PREFIX}\n\n{}", summary.trim()));
+    request
+}
+
+/// Ask the model for a summary of `transcript`.
+async fn summarize(
+    model: &ModelConfig,
+    access_token: &str,
+    transcript: &str,
+) -> Result<(String, TokenUsage), String> {
+    // Thinking adds latency and cost without improving summaries.
+    let summarizer = match model.model_type {
+        ModelType::ClaudeCode => agent(crate::claude::create_model_with_thinking(
+            &model.name,
+            access_token,
+            None,
+        )),
+        ModelType::Anthropic => agent(crate::claude::create_api_key_model(
+            &model.name,
+            access_token,
+            None,
+        )),
+        ModelType::OpenAiCompatible | ModelType::Local => {
+            agent(crate::openai::create_openai_model(model, access_token))
+        }
+    };
+    let summarizer = summarizer
+        .system_prompt(COMPACTION_SYSTEM_PROMPT)
+        .temperature(1.0)
+        .max_tokens(8000)
+        .build();
+
+    let prompt = format!(
+        "Summarize the following conversation transcript.\n\n<transcript>\n{transcript}</transcript>"
+    );
+    let mut stream = AgentStream::new(
+        &summarizer,
+        UserContent::text(&prompt),
+        (),
+        RunOptions::default(),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut summary = String::new();
+    let mut usage = TokenUsage::default();
+    while let Some(event) = stream.next().await {
+        match event.map_err(|e| e.to_string())? {
+            AgentStreamEvent::TextDelta { text } => summary.push_str(&text),
+            AgentStreamEvent::ResponseComplete { usage: request_usage, .. } => {
+                usage.add(&TokenUsage::from(&request_usage));
+            }
+            AgentStreamEvent::RunComplete { .. } => break,
+            AgentStreamEvent::Error { message } => return Err(message),
+            AgentStreamEvent::Cancelled { .. } => return Err("Summarization cancelled".into()),
+            _ => {}
+        }
+    }
+
+    if summary.trim().is_empty() {
+        return Err("The model returned an empty summary".to_string());
+    }
+    Ok((summary, usage))
+}
+
+/// Summarize all but the most recent turns of `history`.
+///
+/// Keeps as many trailing turns as fit in `keep_tokens` (at least one).
+/// Returns `Ok(None)` when there is nothing older to summarize.
+pub(crate) async fn compact_history(
+    model: &ModelConfig,
+    access_token: &str,
+    history: &[ModelRequest],
+    keep_tokens: usize,
+) -> Result<Option<Compaction>, String> {
+    let entries: Vec<(usize, bool)> = history
+        .iter()
+        .map(|request| (estimate_request_tokens(request), starts_user_turn(request)))
+        .collect();
+    let split = split_index(&entries, keep_tokens);
+    if split == 0 {
+        return Ok(None);
+    }
+
+    let tokens_before: usize = entries.iter().map(|(tokens, _)| tokens).sum();
+    let (summary, usage) =
+        summarize(model, access_token, &transcript(&history[..split])).await?;
+
+    let mut compacted = Vec::with_capacity(history.len() - split + 1);
+    compacted.push(summary_request(&summary));
+    compacted.extend_from_slice(&history[split..]);
+    let tokens_after = estimate_history_tokens(&compacted);
+
+    info!(
+        summarized_requests = split,
+        tokens_before, tokens_after, "Compacted conversation history"
+    );
+
+    Ok(Some(Compaction {
+        history: compacted,
+        summarized_requests: split,
+        tokens_before,
+        tokens_after,
+        usage,
+    }))
+}
+
+/// Compact `history` before a run if the prompt is close to the context limit.
+///
+/// `prompt_tokens` is the estimated size of everything sent besides the
+/// history (system prompt, tool definitions, the new user message). Returns
+/// the compacted history, or `None` to send `history` as it is.
+///
+/// Failures also return `None`; the run may still succeed, and if not the
+/// provider's error is reported as usual.
+pub(crate) async fn auto_compact(
+    model: &ModelConfig,
+    access_token: &str,
+    settings: &Settings,
+    context_length: usize,
+    prompt_tokens: usize,
+    history: &[ModelRequest],
+    sender: &EventSender,
+) -> Option<Vec<ModelRequest>> {
+    let prompt_tokens = prompt_tokens + estimate_history_tokens(history);
+    if !settings.auto_compact
+        || !needs_compaction(prompt_tokens, context_length, settings.compaction_threshold)
+    {
+        return None;
+    }
+
+    debug!(prompt_tokens, context_length, "Prompt near context limit, compacting");
+    let keep_tokens = (context_length as f32 * KEEP_RECENT_FRACTION) as usize;
+    match compact_history(model, access_token, history, keep_tokens).await {
+        Ok(Some(compaction)) => {
+            let _ = sender.send(ExecutorEvent::Usage(compaction.usage));
+            let _ = sender.send(compacted_event(&compaction));
+            Some(compaction.history)
+        }
+        Ok(None) => None,
+        Err(e) => {
+            warn!(error = %e, "Context compaction failed, sending full history");
+            None
+        }
+    }
+}
+
+fn compacted_event(compaction: &Compaction) -> ExecutorEvent {
+    ExecutorEvent::Compacted {
+        summarized_requests: compaction.summarized_requests,
+        tokens_before: compaction.tokens_before,
+        tokens_after: compaction.tokens_after,
+        message_history: compaction.history.clone(),
+    }
+}
+
+/// Compact a conversation on demand (the `/compact` command).
+///
+/// Everything but the last turn is summarized. Emits `Compacted` when
+/// something was summarized, then `Done` with the resulting history (unchanged
+/// if there was nothing to compact), or `Error` if summarization failed.
+pub fn compact_conversation(args: CompactArgs) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let CompactArgs {
+            access_token,
+            model_name,
+            model_config,
+            message_history,
+            event_sender,
+        } = args;
+
+        let model = model_config.unwrap_or_else(|| ModelConfig::unregistered(&model_name));
+        match compact_history(&model, &access_token, &message_history, 0).await {
+            Ok(Some(compaction)) => {
+                let _ = event_sender.send(ExecutorEvent::Usage(compaction.usage));
+                let _ = event_sender.send(compacted_event(&compaction));
+                let _ = event_sender.send(ExecutorEvent::Done {
+                    usage: compaction.usage,
+                    message_history: compaction.history,
+                });
+            }
+            Ok(None) => {
+                let _ = event_sender.send(ExecutorEvent::Done {
+                    usage: TokenUsage::default(),
+                    message_history,
+                });
+            }
+            Err(e) => {
+                let _ = event_sender.send(ExecutorEvent::Error(format!(
+                    "Failed to compact conversation: {e}"
+                )));
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use serdes_ai_core::messages::{ImageMediaType, UserContentPart};
+
+    #[test]
+    fn test_estimate_text_tokens() {
+        assert_eq!(estimate_text_tokens(""), 0);
+        assert_eq!(estimate_text_tokens("abcd"), 1);
+        assert_eq!(estimate_text_tokens("abcde"), 2);
+    }
+
+    #[test]
+    fn test_needs_compaction() {
+        assert!(!needs_compaction(10_000, 200_000, 0.8));
+        assert!(needs_compaction(140_000, 200_000, 0.8));
+        // The response reserve is capped for small context windows.
+        assert!(!needs_compaction(1_000, 8_000, 0.8));
+        assert!(needs_compaction(5_000, 8_000, 0.8));
+    }
+
+    #[test]
+    fn test_split_index_keeps_recent_turns() {
+        // Three turns: [0,1], [2,3], [4]
+        let entries = [(100, true), (50, false), (100, true), (50, false), (30, true)];
+        assert_eq!(split_index(&entries, 0), 4);
+        assert_eq!(split_index(&entries, 200), 2);
+        assert_eq!(split_index(&entries, 10_000), 0);
+    }
+
+    #[test]
+    fn test_split_index_always_keeps_last_turn() {
+        let entries = [(100, true), (5_000, true), (5_000, false)];
+        assert_eq!(split_index(&entries, 10), 1);
+    }
+
+    #[test]
+    fn test_split_index_without_turn_boundaries() {
+        assert_eq!(split_index(&[(10, false), (10, false)], 0), 0);
+        assert_eq!(split_index(&[], 0), 0);
+    }
+
+    #[test]
+    fn test_collect_texts_skips_metadata() {
+        let value = json!({
+            "parts": [
+                {"part_kind": "user-prompt", "content": "Fix the bug", "timestamp": "2026-01-01"},
+                {"part_kind": "tool-call", "tool_name": "read_file",
+                 "args": {"file_path": "src/main.rs"}, "tool_call_id": "call_1"},
+                {"part_kind": "thinking", "thinking": "hmm", "signature": "abc"},
+                {"part_kind": "image", "data": "iVBORw0KGgo"}
+            ]
+        });
+        let mut texts = Vec::new();
+        collect_texts(&value, &mut texts);
+
+        assert!(texts.contains(&"Fix the bug".to_string()));
+        assert!(texts.contains(&"read_file".to_string()));
+        assert!(texts.iter().any(|t| t.contains("src/main.rs")));
+        assert!(texts.contains(&"hmm".to_string()));
+        assert!(!texts.iter().any(|t| t.contains("abc") || t.contains("iVBOR")));
+        assert!(!texts.iter().any(|t| t.contains("call_1") || t.contains("2026")));
+    }
+
+    #[test]
+    fn test_collect_texts_of_real_messages() {
+        let mut request = ModelRequest::new();
+        request.add_user_prompt("Fix the bug".to_string());
+        let mut texts = Vec::new();
+        collect_texts(&serde_json::to_value(&request).unwrap(), &mut texts);
+        assert_eq!(texts, vec!["Fix the bug".to_string()]);
+
+        let content = UserContent::parts(vec![
+            UserContentPart::text("What is this?"),
+            UserContentPart::image_binary(b"iVBORw0KGgo".to_vec(), ImageMediaType::Png),
+        ]);
+        let mut texts = Vec::new();
+        collect_texts(&serde_json::to_value(&content).unwrap(), &mut texts);
+        assert_eq!(texts, vec!["What is this?".to_string()]);
+    }
+
+    #[test]
+    fn test_estimate_counts_attachments_by_item() {
+        let screenshot = vec![0u8; 3_000_000];
+        let content = UserContent::parts(vec![
+            UserContentPart::text("What is this?"),
+            UserContentPart::image_binary(screenshot, ImageMediaType::Png),
+        ]);
+        let value = serde_json::to_value(&content).unwrap();
+        assert_eq!(
+            estimate_value_tokens(&value),
+            estimate_text_tokens("What is this?") + ATTACHMENT_TOKENS
+        );
+
+        let mut request = ModelRequest::new();
+        request.add_user_prompt("Fix the bug".to_string());
+        assert_eq!(
+            estimate_request_tokens(&request),
+            estimate_text_tokens("Fix the bug")
+        );
+    }
+
+    #[test]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("short", 10), "short");
+        assert_eq!(truncate_chars("ééééé", 2), "éé… [truncated]");
+    }
+}
@@ -3,10 +3,31 @@
 //! This module provides the core execution layer that connects Claude to our tools
 //! and streams events back to the GUI.
 
+mod approval;
+mod cancellation;
+mod capabilities;
+mod compaction;
+mod tool_output;
+mod usage;
+
+pub use approval::{ApprovalDecision, ApprovalResponder};
+pub use compaction::{
+    compact_conversation, context_length_for_model, estimate_history_tokens,
+    estimate_text_tokens, CompactArgs, DEFAULT_CONTEXT_LENGTH, SUMMARY_PREFIX,
+};
+pub use tokio_util::sync::CancellationToken;
+pub use tool_output::{ToolOutput, MAX_TOOL_OUTPUT_CHARS};
+pub use usage::{format_token_count, TokenUsage};
+
+use approval::PermissionGate;
+use cancellation::PartialTurn;
+use tool_output::ToolOutputs;
+
 use std::collections::HashMap;
 use std::sync::Arc;
 
 use tokio::sync::mpsc;
+use tokio::time::{timeout_at, Duration, Instant};
 use tracing::{debug, error, info, warn};
 
 use serdes_ai_agent::{agent, AgentStream, AgentStreamEvent, RunOptions, ToolExecutor};
@@ -15,11 +36,16 @@ use serdes_ai_core::ModelRequest;
 use serdes_ai_models::Model;
 use serdes_ai_tools::{RunContext as ToolRunContext, Tool, ToolError, ToolReturn};
 
+use crate::checkpoints::Checkpointer;
 use crate::config::Settings;
+use crate::models::{ModelConfig, ModelType};
+use crate::processes::ConversationProcesses;
 use crate::plugins::mcp_manager::PluginMcpManager;
+use crate::plugins::mcp_pool::McpPool;
+use crate::plugins::mcp_resources::{ListMcpResourcesTool, ReadMcpResourceTool};
 use crate::plugins::mcp_tool::PluginMcpTool;
 use crate::skills::types::McpServerEntry;
-use crate::tools::ToolRegistry;
+use crate::tools::{OutputSink, ShellSession, ToolRegistry, Workspace};
 
 // =============================================================================
 // Events
@@ -49,18 +75,49 @@ pub enum ExecutorEvent {
     /// Tool call completed (arguments fully received).
     ToolCallComplete { id: Option<String>, name: String },
 
+    /// A tool call needs user approval before it runs.
+    ///
+    /// The tool waits until `responder` is answered (or dropped, which denies).
+    ApprovalRequested {
+        id: Option<String>,
+        tool_name: String,
+        arguments: serde_json::Value,
+        /// Rule pattern to persist if the user chooses "approve always".
+        suggested_rule: String,
+        responder: ApprovalResponder,
+    },
+
+    /// Output printed by a running tool (e.g. a shell command), as it arrives.
+    ToolOutputDelta { id: Option<String>, delta: String },
+
     /// Tool execution result.
     ToolResult {
         id: Option<String>,
         name: String,
-        result: String,
+        /// What the tool returned (truncated), or the error message.
+        result: ToolOutput,
         success: bool,
     },
 
+    /// Token usage reported for a single model request.
+    Usage(TokenUsage),
+
+    /// Older turns of the history were replaced by a summary to fit the
+    /// model's context window.
+    Compacted {
+        /// Number of history entries that were summarized.
+        summarized_requests: usize,
+        /// Estimated history tokens before and after compaction.
+        tokens_before: usize,
+        tokens_after: usize,
+        /// The compacted history.
+        message_history: Vec<ModelRequest>,
+    },
+
     /// Generation complete.
     Done {
-        input_tokens: u32,
-        output_tokens: u32,
+        /// Total token usage across all requests of this run.
+        usage: TokenUsage,
         /// Complete message history from this run for conversation continuity.
         message_history: Vec<ModelRequest>,
     },
@@ -69,7 +126,11 @@ pub enum ExecutorEvent {
     Error(String),
 
     /// Run was cancelled.
-    Cancelled,
+    Cancelled {
+        /// History up to the cancelled turn, ending with the user's message
+        /// and a note on what was done before stopping.
+        message_history: Vec<ModelRequest>,
+    },
 }
 
 // =============================================================================
@@ -94,17 +155,38 @@ pub fn event_channel() -> (EventSender, EventReceiver) {
 /// Wrapper that adapts a `serdes_ai_tools::Tool` to the agent's `ToolExecutor` trait.
 ///
 /// This bridges our tool implementations (which use `RunContext<()>`) to the
-/// agent system's generic deps system.
+/// agent system's generic deps system. Every call is checked against the
+/// run's permission rules first, and its output is captured for the GUI.
+/// Once the run is cancelled, calls are refused without running the tool.
 struct ToolWrapper {
     tool: Arc<dyn Tool>,
+    gate: Arc<PermissionGate>,
+    outputs: ToolOutputs,
+    cancel: CancellationToken,
 }
 
 impl ToolWrapper {
-    fn new(tool: Arc<dyn Tool>) -> Self {
-        Self { tool }
+    fn new(
+        tool: Arc<dyn Tool>,
+        gate: Arc<PermissionGate>,
+        outputs: ToolOutputs,
+        cancel: CancellationToken,
+    ) -> Self {
+        Self {
+            tool,
+            gate,
+            outputs,
+            cancel,
+        }
     }
 }
 
+/// Returned for tool calls refused because the run was cancelled.
+const CANCELLED_TOOL_MESSAGE: &str = "Cancelled by the user before the tool ran.";
+
+/// Time running tools get to stop after a cancel before the run is dropped.
+const TOOL_CANCEL_GRACE: Duration = Duration::from_secs(5);
+
 #[async_trait::async_trait]
 impl<Deps: Send + Sync> ToolExecutor<Deps> for ToolWrapper {
     async fn execute(
@@ -112,15 +194,34 @@ impl<Deps: Send + Sync> ToolExecutor<Deps> for ToolWrapper {
         args: serde_json::Value,
         ctx: &serdes_ai_agent::RunContext<Deps>,
     ) -> Result<ToolReturn, ToolError> {
-        // Create a tool context without deps (our tools don't use deps)
+        let tool_name = self.tool.definition().name().to_string();
+        // Don't leave a cancelled run waiting for an approval
+        let allowed = tokio::select! {
+            allowed = self.gate.check(&tool_name, ctx.tool_call_id.clone(), &args) => allowed,
+            _ = self.cancel.cancelled() => Err(CANCELLED_TOOL_MESSAGE.to_string()),
+        };
+        if let Err(reason) = allowed {
+            return Ok(ToolReturn::error(reason));
+        }
+        if self.cancel.is_cancelled() {
+            return Ok(ToolReturn::error(CANCELLED_TOOL_MESSAGE.to_string()));
+        }
+
+        // Tools keep their state in their `Workspace`; the context carries the
+        // run and tool call IDs that checkpoints and streamed output are keyed by
         let tool_ctx = ToolRunContext::minimal(&ctx.model_name)
             .with_run_id(&ctx.run_id)
             .with_tool_context(self.tool.definition().name(), ctx.tool_call_id.clone());
 
         // Call the tool
-        self.tool.call(&tool_ctx, args).await.map_err(|e| {
+        let ret = self.tool.call(&tool_ctx, args).await.map_err(|e| {
             ToolError::execution_failed(format!("{}: {}", self.tool.definition().name(), e))
-        })
+        })?;
+
+        if let (Some(id), Some(output)) = (&ctx.tool_call_id, ToolOutput::from_return(&ret)) {
+            self.outputs.insert(id.clone(), output);
+        }
+        Ok(ret)
     }
 }
 
@@ -143,41 +244,76 @@ pub struct DocumentData {
 
 /// Arguments for [`run_agent`].
 pub struct RunAgentArgs {
+    /// Claude Code OAuth access token, or the API key for Anthropic API and
+    /// OpenAI-compatible models, as picked by `model_config`'s `model_type`.
     pub access_token: String,
     pub model_name: String,
+    /// Registry entry of the model (see [`ModelRegistry::resolve_from_db`]):
+    /// its provider, endpoint and capabilities. `None` assumes
+    /// [`ModelConfig::unregistered`].
+    ///
+    /// [`ModelRegistry::resolve_from_db`]: crate::models::ModelRegistry::resolve_from_db
+    pub model_config: Option<ModelConfig>,
     pub settings: Settings,
     pub system_prompt: String,
     pub user_input: String,
     pub images: Vec<ImageData>,
     pub documents: Vec<DocumentData>,
     pub message_history: Vec<ModelRequest>,
+    /// Context window of the model in tokens (see [`context_length_for_model`]).
+    pub context_length: usize,
     pub plugin_mcp_configs: HashMap<String, McpServerEntry>,
+    /// Connections to the MCP servers kept across runs. `None` connects the
+    /// servers for this run only.
+    pub mcp_pool: Option<McpPool>,
+    /// Snapshots files before the tools modify them, so the turn can be
+    /// reverted. `None` disables checkpoints.
+    pub checkpointer: Option<Checkpointer>,
+    /// Shell kept alive across the turns of a conversation for the
+    /// `shell_session` tool. `None` leaves the tool out.
+    pub shell_session: Option<ShellSession>,
+    /// The conversation's background processes, for the process tools.
+    /// `None` leaves those tools out.
+    pub processes: Option<ConversationProcesses>,
+    /// Cancelling stops running tools (killing their processes) and ends the
+    /// run with [`ExecutorEvent::Cancelled`].
+    pub cancel: CancellationToken,
     pub event_sender: EventSender,
 }
 
 /// Run the agent with streaming output.
 ///
 /// This spawns a background task that streams events back via the channel.
-/// Returns a handle that can be used to abort the generation.
+/// Cancel `args.cancel` to stop the run; aborting the returned handle instead
+/// drops running tools and MCP calls mid-way.
 ///
 /// # Example
 ///
 /// ```ignore
 /// use std::collections::HashMap;
 ///
-/// use deskwork_core::executor::{event_channel, run_agent, ExecutorEvent, RunAgentArgs};
+/// use deskwork_core::executor::{
+///     event_channel, run_agent, CancellationToken, ExecutorEvent, RunAgentArgs,
+/// };
 ///
 /// let (tx, mut rx) = event_channel();
 /// let args = RunAgentArgs {
 ///     access_token,
 ///     model_name: "claude-sonnet-4-20250514".to_string(),
+///     model_config: None,
 ///     settings,
 ///     system_prompt,
 ///     user_input: "Help me write a function".to_string(),
 ///     images: vec![],
 ///     documents: vec![],
 ///     message_history: vec![],
+///     context_length: 200_000,
 ///     plugin_mcp_configs: HashMap::new(),
+///     mcp_pool: None,
+///     checkpointer: None,
+///     shell_session: None,
+///     processes: None,
+///     cancel: CancellationToken::new(),
 ///     event_sender: tx,
 /// };
 ///
@@ -199,62 +335,192 @@ pub fn run_agent(args: RunAgentArgs) -> tokio::task::JoinHandle<()> {
         let RunAgentArgs {
             access_token,
             model_name,
-            settings,
+            model_config,
+            mut settings,
             system_prompt,
             user_input,
             images,
             documents,
             message_history,
+            context_length,
             plugin_mcp_configs,
+            mcp_pool,
+            checkpointer,
+            shell_session,
+            processes,
+            cancel,
             event_sender,
         } = args;
 
         info!("Starting agent execution");
 
-        // Create the model using OAuth token
-        let model = crate::claude::create_model(&model_name, &access_token, &settings);
-        debug!(model = %model.name(), "Created model");
+        let model_config = model_config.unwrap_or_else(|| ModelConfig::unregistered(&model_name));
+        // Models without thinking support get none, whatever the settings say
+        if !model_config.supports_thinking {
+            settings.extended_thinking = false;
+        }
+        let message_history = capabilities::fit_history(message_history, &model_config);
+
+        // Summarize older turns if the history no longer fits comfortably.
+        // Stopping meanwhile keeps the history as it was.
+        let compacted = tokio::select! {
+            compacted = compaction::auto_compact(
+                &model_config,
+                &access_token,
+                &settings,
+                context_length,
+                estimate_text_tokens(&system_prompt) + estimate_text_tokens(&user_input),
+                &message_history,
+                &event_sender,
+            ) => Some(compacted),
+            _ = cancel.cancelled() => None,
+        };
+        let message_history = match compacted {
+            Some(compacted) => compacted.unwrap_or(message_history),
+            None => {
+                info!("Cancelled while compacting");
+                let _ = event_sender.send(ExecutorEvent::Cancelled {
+                    message_history: PartialTurn::new(message_history, user_input).history(),
+                });
+                return;
+            }
+        };
+        let partial = PartialTurn::new(message_history.clone(), user_input.clone());
+
+        // Create the model of the provider the name belongs to
+        let builder = match model_config.model_type {
+            ModelType::ClaudeCode => {
+                let model = crate::claude::create_model(&model_name, &access_token, &settings);
+                debug!(model = %model.name(), "Created Claude Code model");
+                agent(model)
+            }
+            ModelType::Anthropic => {
+                let thinking_budget = settings
+                    .extended_thinking
+                    .then_some(settings.thinking_budget as u64);
+                let model = crate::claude::create_api_key_model(
+                    &model_name,
+                    &access_token,
+                    thinking_budget,
+                );
+                debug!(model = %model.name(), "Created Anthropic API model");
+                agent(model)
+            }
+            ModelType::OpenAiCompatible | ModelType::Local => {
+                let model = crate::openai::create_openai_model(&model_config, &access_token);
+                debug!(
+                    model = %model.name(),
+                    base_url = %crate::openai::base_url(&model_config),
+                    "Created OpenAI-compatible model"
+                );
+                agent(model)
+            }
+        };
 
         // Create tools registry and get tools
-        let registry = ToolRegistry::with_defaults();
+        // Tools work in the workspace from settings, so a folder opened in
+        // the GUI applies from the next run on
+        let mut workspace = Workspace::from_settings(&settings).with_cancel(cancel.clone());
+        if let Some(checkpointer) = checkpointer {
+            workspace = workspace.with_checkpointer(checkpointer);
+        }
+        if let Some(session) = shell_session {
+            workspace = workspace.with_shell_session(session);
+        }
+        if let Some(processes) = processes {
+            workspace = workspace.with_processes(processes);
+        }
+        let output_sender = event_sender.clone();
+        workspace = workspace.with_output_sink(OutputSink::new(move |id, delta| {
+            let _ = output_sender.send(ExecutorEvent::ToolOutputDelta {
+                id: id.map(str::to_string),
+                delta: delta.to_string(),
+            });
+        }));
+        let registry = ToolRegistry::with_workspace(workspace);
+        let gate = Arc::new(PermissionGate::new(
+            settings.permission_rules.clone(),
+            event_sender.clone(),
+        ));
+        let outputs = ToolOutputs::default();
 
-        // Claude Code OAuth uses hardcoded settings (like workitforme)
-        // - Temperature: 1.0 (required for extended thinking)
-        // - Max tokens: 30000 (Claude Code OAuth default)
-        let mut builder = agent(model)
+        // Claude Code OAuth uses hardcoded settings (like workitforme); API-key
+        // models use the user's temperature and max tokens
+        let sampling = crate::claude::sampling_settings(model_config.model_type, &settings);
+        let mut builder = builder
             .system_prompt(&system_prompt)
-            .temperature(1.0)
-            .max_tokens(30000);
+            .temperature(sampling.temperature)
+            .max_tokens(sampling.max_tokens);
+
+        // Models without tool support get a plain chat
+        let tools: &[_] = if model_config.supports_tools {
+            registry.tools()
+        } else {
+            info!(model = %model_name, "Model does not support tools, running without");
+            &[]
+        };
 
         // Add each built-in tool by wrapping it
-        for tool in registry.tools() {
+        for tool in tools {
             let definition = tool.definition();
-            let wrapper = ToolWrapper::new(Arc::clone(tool));
+            let wrapper = ToolWrapper::new(
+                Arc::clone(tool),
+                Arc::clone(&gate),
+                outputs.clone(),
+                cancel.clone(),
+            );
             builder = builder.tool_with_executor(definition, wrapper);
         }
 
         let mut mcp_tool_count = 0usize;
 
-        if !plugin_mcp_configs.is_empty() {
-            let mcp_manager =
-                Arc::new(PluginMcpManager::connect_from_configs(&plugin_mcp_configs).await);
+        if model_config.supports_tools && !plugin_mcp_configs.is_empty() {
+            let mcp_manager = Arc::new(match &mcp_pool {
+                Some(pool) => pool.manager(&plugin_mcp_configs).await,
+                None => PluginMcpManager::connect_from_configs(&plugin_mcp_configs).await,
+            });
 
             for (server, reason) in mcp_manager.unavailable_connectors() {
                 warn!(server = %server, reason = %reason, "MCP connector unavailable");
             }
 
             for meta in mcp_manager.list_all_tools().into_values() {
-                let tool = PluginMcpTool::new(Arc::clone(&mcp_manager), meta);
+                let tool = PluginMcpTool::new(Arc::clone(&mcp_manager), meta, cancel.clone());
                 let definition = tool.definition();
-                let wrapper = ToolWrapper::new(Arc::new(tool));
+                let wrapper = ToolWrapper::new(
+                    Arc::new(tool),
+                    Arc::clone(&gate),
+                    outputs.clone(),
+                    cancel.clone(),
+                );
                 builder = builder.tool_with_executor(definition, wrapper);
                 mcp_tool_count += 1;
             }
+
+            if !mcp_manager.resource_servers().is_empty() {
+                let resource_tools: [Arc<dyn Tool>; 2] = [
+                    Arc::new(ListMcpResourcesTool::new(
+                        Arc::clone(&mcp_manager),
+                        cancel.clone(),
+                    )),
+                    Arc::new(ReadMcpResourceTool::new(
+                        Arc::clone(&mcp_manager),
+                        cancel.clone(),
+                    )),
+                ];
+                for tool in resource_tools {
+                    let definition = tool.definition();
+                    let wrapper =
+                        ToolWrapper::new(tool, Arc::clone(&gate), outputs.clone(), cancel.clone());
+                    builder = builder.tool_with_executor(definition, wrapper);
+                    mcp_tool_count += 1;
+                }
+            }
         }
 
         let agent = builder.build();
         debug!(
-            builtin_tools = registry.len(),
+            builtin_tools = tools.len(),
             mcp_tools = mcp_tool_count,
             "Agent built with tools"
         );
@@ -266,6 +532,19 @@ pub fn run_agent(args: RunAgentArgs) -> tokio::task::JoinHandle<()> {
             RunOptions::default().message_history(message_history)
         };
 
+        // Models without vision get a note in place of the attachments (PDFs
+        // are read visually too)
+        let (images, documents, user_input) = if model_config.supports_vision {
+            (images, documents, user_input)
+        } else {
+            let dropped = images.len() + documents.len();
+            if dropped > 0 {
+                warn!(model = %model_name, dropped, "Model does not support images");
+            }
+            let user_input = capabilities::note_dropped_attachments(user_input, dropped);
+            (Vec::new(), Vec::new(), user_input)
+        };
+
         // Build user content (text + optional images + optional documents)
         let has_attachments = !images.is_empty() || !documents.is_empty();
         let user_content = if !has_attachments {
@@ -297,10 +576,18 @@ pub fn run_agent(args: RunAgentArgs) -> tokio::task::JoinHandle<()> {
             documents.len()
         );
 
+        // Stopped while compacting or connecting to MCP servers
+        if cancel.is_cancelled() {
+            let _ = event_sender.send(ExecutorEvent::Cancelled {
+                message_history: partial.history(),
+            });
+            return;
+        }
+
         // Run with streaming
         match AgentStream::new(&agent, user_content, (), options).await {
             Ok(stream) => {
-                process_stream(stream, event_sender).await;
+                process_stream(stream, event_sender, outputs, cancel, partial).await;
             }
             Err(e) => {
                 error!(error = %e, "Failed to start agent stream");
@@ -311,33 +598,96 @@ pub fn run_agent(args: RunAgentArgs) -> tokio::task::JoinHandle<()> {
 }
 
 /// Process the agent stream and forward events to the GUI.
-async fn process_stream(mut stream: AgentStream, sender: EventSender) {
+///
+/// Tool results carry the output captured by the tool wrappers when there is
+/// one. When `cancel` fires, running tools get [`TOOL_CANCEL_GRACE`] to stop
+/// and report their results; then the stream is dropped and the run ends with
+/// [`ExecutorEvent::Cancelled`] and the history from `partial`.
+async fn process_stream(
+    mut stream: AgentStream,
+    sender: EventSender,
+    outputs: ToolOutputs,
+    cancel: CancellationToken,
+    mut partial: PartialTurn,
+) {
     use futures::StreamExt;
 
-    while let Some(result) = stream.next().await {
+    let mut run_usage = TokenUsage::default();
+    let mut cancel_deadline = None;
+
+    loop {
+        let next = match cancel_deadline {
+            None => tokio::select! {
+                next = stream.next() => next,
+                _ = cancel.cancelled() => {
+                    info!("Cancelling agent run");
+                    cancel_deadline = Some(Instant::now() + TOOL_CANCEL_GRACE);
+                    continue;
+                }
+            },
+            Some(_) if !partial.has_running_tools() => break,
+            Some(deadline) => match timeout_at(deadline, stream.next()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    warn!("Tools still running after cancel, dropping them");
+                    break;
+                }
+            },
+        };
+        let Some(result) = next else {
+            break;
+        };
+
         match result {
             Ok(event) => {
                 let executor_event = convert_event(event);
-                if let Some(ev) = executor_event {
-                    let is_done =
-                        matches!(ev, ExecutorEvent::Done { .. } | ExecutorEvent::Error(_));
+                if let Some(mut ev) = executor_event {
+                    match &mut ev {
+                        ExecutorEvent::Usage(usage) => run_usage.add(usage),
+                        ExecutorEvent::Done { usage, .. } => *usage = run_usage,
+                        ExecutorEvent::Cancelled { message_history } => {
+                            *message_history = partial.history();
+                        }
+                        ExecutorEvent::ToolResult {
+                            id: Some(id),
+                            result,
+                            ..
+                        } => {
+                            if let Some(output) = outputs.take(id) {
+                                *result = output;
+                            }
+                        }
+                        _ => {}
+                    }
+                    partial.record(&ev);
+                    let is_done = matches!(
+                        ev,
+                        ExecutorEvent::Done { .. }
+                            | ExecutorEvent::Error(_)
+                            | ExecutorEvent::Cancelled { .. }
+                    );
                     if sender.send(ev).is_err() {
                         debug!("Event receiver dropped, stopping stream");
-                        break;
+                        return;
                     }
                     if is_done {
-                        break;
+                        return;
                     }
                 }
             }
             Err(e) => {
                 error!(error = %e, "Stream error");
                 let _ = sender.send(ExecutorEvent::Error(e.to_string()));
-                break;
+                return;
             }
         }
     }
 
+    if cancel_deadline.is_some() {
+        let _ = sender.send(ExecutorEvent::Cancelled {
+            message_history: partial.history(),
+        });
+    }
     debug!("Stream processing complete");
 }
 
@@ -380,7 +730,8 @@ fn convert_event(event: AgentStreamEvent) -> Option<ExecutorEvent> {
             success,
             error,
         } => {
-            let result = error.unwrap_or_else(|| "Success".to_string());
+            // The tool's output is filled in by `process_stream` when captured
+            let result = ToolOutput::text(error.unwrap_or_else(|| "Success".to_string()));
             Some(ExecutorEvent::ToolResult {
                 id: tool_call_id,
                 name: tool_name,
@@ -389,19 +740,24 @@ fn convert_event(event: AgentStreamEvent) -> Option<ExecutorEvent> {
             })
         }
 
-        AgentStreamEvent::RunComplete { messages, .. } => {
-            Some(ExecutorEvent::Done {
-                input_tokens: 0,
-                output_tokens: 0,
-                message_history: messages,
-            })
+        AgentStreamEvent::ResponseComplete { usage, .. } => {
+            Some(ExecutorEvent::Usage(TokenUsage::from(&usage)))
         }
 
+        // Usage is filled in by `process_stream` from the per-request totals.
+        AgentStreamEvent::RunComplete { messages, .. } => Some(ExecutorEvent::Done {
+            usage: TokenUsage::default(),
+            message_history: messages,
+        }),
+
         AgentStreamEvent::Error { message } => Some(ExecutorEvent::Error(message)),
 
-        AgentStreamEvent::Cancelled { .. } => Some(ExecutorEvent::Cancelled),
+        // The history is filled in by `process_stream` from the events so far.
+        AgentStreamEvent::Cancelled { .. } => Some(ExecutorEvent::Cancelled {
+            message_history: Vec::new(),
+        }),
 
-        // Ignore other events (RequestStart, ResponseComplete, ContextInfo, etc.)
+        // Ignore other events (RequestStart, ContextInfo, etc.)
         _ => None,
     }
 }
@@ -0,0 +1,192 @@
+//! Tool output forwarded to front ends.
+//!
+//! Tools return their content to the model in full; front ends get a copy
+//! that is truncated to stay cheap to render and store, while JSON results
+//! keep their shape (long strings and arrays are cut, not flattened).
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use serdes_ai_tools::ToolReturn;
+
+/// Maximum characters of text output forwarded to front ends.
+pub const MAX_TOOL_OUTPUT_CHARS: usize = 20_000;
+
+/// Maximum characters of a single JSON string value.
+const MAX_JSON_STRING_CHARS: usize = 2_000;
+
+/// Maximum items kept from a JSON array.
+const MAX_JSON_ARRAY_ITEMS: usize = 100;
+
+/// Output of a tool call.
+///
+/// Serializes untagged, so a plain string (e.g. a result stored before tool
+/// output was forwarded) deserializes as [`ToolOutput::Text`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ToolOutput {
+    Text(String),
+    Json(JsonValue),
+}
+
+impl ToolOutput {
+    /// Text output, truncated to [`MAX_TOOL_OUTPUT_CHARS`].
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text(truncate_text(text.into(), MAX_TOOL_OUTPUT_CHARS))
+    }
+
+    /// JSON output with long strings and arrays truncated.
+    pub fn json(value: JsonValue) -> Self {
+        Self::Json(truncate_json(value))
+    }
+
+    /// The front-end copy of what a tool returned.
+    ///
+    /// Returns `None` for content that is neither text nor JSON (e.g. images).
+    pub fn from_return(ret: &ToolReturn) -> Option<Self> {
+        if let Some(text) = ret.as_text() {
+            return Some(Self::text(text));
+        }
+        ret.as_json().map(|value| Self::json(value.clone()))
+    }
+
+    /// The output as display text (JSON is pretty-printed).
+    pub fn to_display_string(&self) -> String {
+        match self {
+            Self::Text(text) => text.clone(),
+            Self::Json(value) => {
+                serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ToolOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_display_string())
+    }
+}
+
+/// Outputs captured by the tool wrappers of a run, keyed by tool call ID,
+/// until the matching `ToolExecuted` event is forwarded.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ToolOutputs(Arc<Mutex<HashMap<String, ToolOutput>>>);
+
+impl ToolOutputs {
+    pub(crate) fn insert(&self, tool_call_id: String, output: ToolOutput) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(tool_call_id, output);
+    }
+
+    pub(crate) fn take(&self, tool_call_id: &str) -> Option<ToolOutput> {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(tool_call_id)
+    }
+}
+
+fn truncate_text(mut text: String, max_chars: usize) -> String {
+    let total = text.chars().count();
+    if total <= max_chars {
+        return text;
+    }
+    let cut = text
+        .char_indices()
+        .nth(max_chars)
+        .map(|(i, _)| i)
+        .unwrap_or(text.len());
+    text.truncate(cut);
+    text.push_str(&format!(
+        "\n\n[{} more characters truncated]",
+        total - max_chars
+    ));
+    text
+}
+
+fn truncate_json(value: JsonValue) -> JsonValue {
+    match value {
+        JsonValue::String(s) => JsonValue::String(truncate_text(s, MAX_JSON_STRING_CHARS)),
+        JsonValue::Array(items) => {
+            let total = items.len();
+            let mut kept: Vec<JsonValue> = items
+                .into_iter()
+                .take(MAX_JSON_ARRAY_ITEMS)
+                .map(truncate_json)
+                .collect();
+            if total > MAX_JSON_ARRAY_ITEMS {
+                kept.push(JsonValue::String(format!(
+                    "[{} more items truncated]",
+                    total - MAX_JSON_ARRAY_ITEMS
+                )));
+            }
+            JsonValue::Array(kept)
+        }
+        JsonValue::Object(map) => JsonValue::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, truncate_json(value)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_truncated_on_char_boundary() {
+        let output = ToolOutput::text("é".repeat(MAX_TOOL_OUTPUT_CHARS + 5));
+        let ToolOutput::Text(text) = output else {
+            panic!("expected text output");
+        };
+        assert!(text.starts_with(&"é".repeat(MAX_TOOL_OUTPUT_CHARS)));
+        assert!(text.ends_with("[5 more characters truncated]"));
+
+        assert_eq!(ToolOutput::text("short"), ToolOutput::Text("short".into()));
+    }
+
+    #[test]
+    fn test_json_keeps_structure() {
+        let items: Vec<JsonValue> = (0..MAX_JSON_ARRAY_ITEMS + 3).map(JsonValue::from).collect();
+        let output = ToolOutput::json(serde_json::json!({
+            "items": items,
+            "body": "x".repeat(MAX_JSON_STRING_CHARS + 1),
+            "ok": true,
+        }));
+
+        let ToolOutput::Json(value) = output else {
+            panic!("expected JSON output");
+        };
+        let items = value["items"].as_array().unwrap();
+        assert_eq!(items.len(), MAX_JSON_ARRAY_ITEMS + 1);
+        assert_eq!(items.last().unwrap(), "[3 more items truncated]");
+        assert!(value["body"]
+            .as_str()
+            .unwrap()
+            .ends_with("[1 more characters truncated]"));
+        assert_eq!(value["ok"], true);
+    }
+
+    #[test]
+    fn test_plain_string_deserializes_as_text() {
+        let output: ToolOutput = serde_json::from_str("\"Success\"").unwrap();
+        assert_eq!(output, ToolOutput::Text("Success".into()));
+
+        let output: ToolOutput = serde_json::from_str("{\"a\":1}").unwrap();
+        assert_eq!(output, ToolOutput::Json(serde_json::json!({"a": 1})));
+    }
+
+    #[test]
+    fn test_captured_outputs_are_taken_once() {
+        let outputs = ToolOutputs::default();
+        outputs.insert("call_1".into(), ToolOutput::text("hello"));
+        assert_eq!(outputs.take("call_1"), Some(ToolOutput::text("hello")));
+        assert_eq!(outputs.take("call_1"), None);
+    }
+}
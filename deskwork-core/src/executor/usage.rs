@@ -0,0 +1,109 @@
+//! Token usage accounting.
+
+use serde::{Deserialize, Serialize};
+
+/// Token counts for one model request, or a sum of several.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    /// Number of model requests included in these counts.
+    pub requests: u64,
+    /// Uncached input tokens.
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Input tokens served from the prompt cache.
+    pub cache_read_tokens: u64,
+    /// Input tokens written to the prompt cache.
+    pub cache_write_tokens: u64,
+    /// Output tokens spent on extended thinking (already included in
+    /// `output_tokens` when the provider reports them separately).
+    pub thinking_tokens: u64,
+}
+
+impl TokenUsage {
+    /// Add another usage record to this one.
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.requests += other.requests;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_write_tokens += other.cache_write_tokens;
+        self.thinking_tokens += other.thinking_tokens;
+    }
+
+    /// All input tokens, cached or not.
+    pub fn total_input(&self) -> u64 {
+        self.input_tokens + self.cache_read_tokens + self.cache_write_tokens
+    }
+
+    /// All tokens, input and output.
+    pub fn total(&self) -> u64 {
+        self.total_input() + self.output_tokens
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+}
+
+impl From<&serdes_ai_core::RequestUsage> for TokenUsage {
+    fn from(usage: &serdes_ai_core::RequestUsage) -> Self {
+        let detail = |key: &str| usage.details.get(key).copied().unwrap_or(0);
+        Self {
+            requests: 1,
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_read_tokens: usage.cache_read_tokens,
+            cache_write_tokens: usage.cache_write_tokens,
+            thinking_tokens: detail("thinking_tokens").max(detail("reasoning_tokens")),
+        }
+    }
+}
+
+/// Format a token count compactly (`950`, `12.3k`, `1.2M`).
+pub fn format_token_count(tokens: u64) -> String {
+    if tokens >= 1_000_000 {
+        format!("{:.1}M", tokens as f64 / 1_000_000.0)
+    } else if tokens >= 1_000 {
+        format!("{:.1}k", tokens as f64 / 1_000.0)
+    } else {
+        tokens.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_and_totals() {
+        let mut total = TokenUsage::default();
+        total.add(&TokenUsage {
+            requests: 1,
+            input_tokens: 100,
+            output_tokens: 50,
+            cache_read_tokens: 1000,
+            cache_write_tokens: 200,
+            thinking_tokens: 20,
+        });
+        total.add(&TokenUsage {
+            requests: 1,
+            input_tokens: 10,
+            output_tokens: 5,
+            ..Default::default()
+        });
+
+        assert_eq!(total.requests, 2);
+        assert_eq!(total.total_input(), 1310);
+        assert_eq!(total.total(), 1365);
+        assert_eq!(total.thinking_tokens, 20);
+        assert!(!total.is_empty());
+        assert!(TokenUsage::default().is_empty());
+    }
+
+    #[test]
+    fn test_format_token_count() {
+        assert_eq!(format_token_count(950), "950");
+        assert_eq!(format_token_count(12_345), "12.3k");
+        assert_eq!(format_token_count(1_200_000), "1.2M");
+    }
+}
@@ -8,46 +8,83 @@
 //! - Configuration management (settings, preferences)
 //! - Database layer for tokens, settings, and conversations
 //! - Tool implementations (file operations, shell commands, etc.)
+//! - File checkpoints for reverting the agent's edits
+//! - Background processes such as dev servers and file watchers
 //! - Claude model integration via serdes-ai
+//! - OpenAI-compatible and local (Ollama, llama.cpp) model backends
 //! - Agent executor for running Claude with tools
+//! - User-defined MCP servers alongside the skill categories' connectors
+//! - Serving the built-in tools to other agents over MCP
 //! - System prompts for the coding assistant
 //! - External tools management (UV download and installation)
 //! - Python environment management (venv creation, package installation)
 
 pub mod auth;
+pub mod checkpoints;
 pub mod claude;
 pub mod config;
 pub mod db;
 pub mod executor;
 pub mod external_tools;
+pub mod mcp_serve;
+pub mod mcp_servers;
 pub mod models;
+pub mod openai;
 pub mod plugins;
+pub mod processes;
 pub mod prompts;
 pub mod python;
 pub mod skills;
 pub mod tools;
 
 // Re-exports for convenience
-pub use config::{model_display_name, RenderMode, Settings, Theme, DEFAULT_MODEL};
-pub use db::Database;
+pub use config::{
+    model_display_name, PermissionAction, PermissionRule, RenderMode, Settings, Theme,
+    DEFAULT_MODEL,
+};
+pub use db::{usage_day_today, ConversationSummary, Database, StoredConversation, StoredMessage};
 
 // Re-export auth
 pub use auth::{
-    fetch_claude_models, filter_latest_models, get_claude_code_model, has_oauth_tokens,
-    run_claude_code_auth, save_claude_models_to_db, ClaudeCodeAuth, ClaudeCodeAuthError,
-    StoredTokens, TokenStorage, TokenStorageError,
+    anthropic_model_name, fetch_anthropic_models, fetch_claude_models, filter_latest_models,
+    get_claude_code_model, has_oauth_tokens, run_claude_code_auth, save_anthropic_models_to_db,
+    save_claude_models_to_db, AnthropicAuth, AnthropicAuthError, ClaudeCodeAuth,
+    ClaudeCodeAuthError, StoredTokens, TokenStorage, TokenStorageError, ANTHROPIC_API_KEY,
+};
+
+// Re-export checkpoints
+pub use checkpoints::{
+    CheckpointError, CheckpointStore, Checkpointer, ConversationCheckpoints, RevertSummary,
+};
+
+// Re-export background processes
+pub use processes::{
+    ConversationProcesses, ProcessError, ProcessInfo, ProcessManager, ProcessOutput, ProcessStatus,
 };
 
 // Re-export models
-pub use models::{ModelConfig, ModelConfigError, ModelRegistry, ModelType};
+pub use models::{
+    ModelConfig, ModelConfigError, ModelRegistry, ModelType, ANTHROPIC_MODEL_PREFIX,
+    CLAUDE_CODE_MODEL_PREFIX, LOCAL_MODEL_PREFIX, OPENAI_COMPATIBLE_MODEL_PREFIX,
+};
 
 // Re-export Claude integration
-pub use claude::{create_model, create_model_with_thinking};
+pub use claude::{
+    create_api_key_model, create_model, create_model_with_thinking, sampling_settings,
+    SamplingSettings,
+};
+
+// Re-export OpenAI-compatible backends
+pub use openai::{
+    create_openai_model, fetch_openai_models, openai_api_key, save_endpoint_models_to_db,
+    DEFAULT_LOCAL_BASE_URL, DEFAULT_OPENAI_BASE_URL, OPENAI_API_KEY,
+};
 
 // Re-export executor
 pub use executor::{
-    event_channel, run_agent, DocumentData, EventReceiver, EventSender, ExecutorEvent, ImageData,
-    RunAgentArgs,
+    compact_conversation, context_length_for_model, event_channel, format_token_count, run_agent,
+    ApprovalDecision, ApprovalResponder, CancellationToken, CompactArgs, DocumentData,
+    EventReceiver, EventSender, ExecutorEvent, ImageData, RunAgentArgs, TokenUsage, ToolOutput,
 };
 
 // Re-export image types for multimodal requests
@@ -61,13 +98,26 @@ pub use prompts::{build_system_prompt, SYSTEM_PROMPT};
 
 // Re-export tools
 pub use tools::{
-    DeleteFileTool, EditFileTool, FileError, GrepTool, ListFilesTool, ReadFileTool,
-    RunShellCommandTool, ToolRegistry,
+    ApplyPatchTool, DeleteFileTool, EditFileTool, FileError, FindFilesTool, GrepTool, ListFilesTool,
+    ListProcessesTool, PathPolicy, ReadFileTool, ReadProcessOutputTool, ReplaceInFileTool,
+    RunShellCommandTool, ShellSession, ShellSessionTool, StartBackgroundProcessTool,
+    StopProcessTool, ToolRegistry, Workspace,
 };
 
 // Plugin MCP types (still used by executor for MCP server connections)
-pub use plugins::mcp_manager::{NamespacedMcpTool, PluginMcpManager};
+pub use plugins::mcp_manager::{test_mcp_server, NamespacedMcpTool, PluginMcpManager};
 pub use plugins::mcp_tool::PluginMcpTool;
+pub use plugins::mcp_pool::{McpPool, McpServerHealth, McpServerStatus};
+pub use plugins::mcp_prompts::{find_mcp_prompt, McpPromptInvocation, NamespacedMcpPrompt};
+
+// Re-export the MCP server for the built-in tools
+pub use mcp_serve::McpToolServer;
+
+// Re-export user MCP servers
+pub use mcp_servers::{
+    claude_desktop_config_path, import_mcp_config, load_user_mcp_servers, user_mcp_config_path,
+    user_mcp_configs, McpServerSource, UserMcpServer, USER_MCP_NAMESPACE,
+};
 
 // Re-export skills
 pub use skills::{
@@ -87,7 +137,7 @@ pub use skills::categories::{
 pub use skills::category_context::{build_category_context, CategoryContext, ContextBudget};
 pub use skills::commands::{
     build_command_prompt, command_suggestions, command_suggestions_rich, get_command_handler,
-    parse_slash_command, ParsedSlashCommand, SlashCommandSuggestion,
+    parse_inline_command_inputs, parse_slash_command, ParsedSlashCommand, SlashCommandSuggestion,
 };
 pub use skills::types::{
     parse_frontmatter, CommandFile, CommandFrontmatter, McpServerEntry, McpServersFile, SkillFile,
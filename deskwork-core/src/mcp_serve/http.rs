@@ -0,0 +1,308 @@
+//! Streamable HTTP transport for [`McpToolServer`], on hyper's HTTP/1 server.
+//!
+//! Clients POST JSON-RPC messages to `/mcp` and get the reply as a JSON
+//! response, or `202 Accepted` for notifications. The server doesn't open
+//! event streams of its own, so `GET` is answered with `405`.
+//!
+//! The server only listens on loopback addresses and refuses requests whose
+//! `Host` or `Origin` isn't local, so web pages open in the user's browser
+//! can't reach the tools, not even through DNS rebinding.
+
+use std::convert::Infallible;
+use std::io;
+use std::net::Ipv4Addr;
+
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::body::{Body, Bytes, Incoming};
+use hyper::header::{self, HeaderValue};
+use hyper::http::uri::Authority;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde_json::Value as JsonValue;
+use tokio::net::TcpListener;
+use tracing::{debug, info};
+
+use super::{error_response, McpToolServer, PARSE_ERROR};
+
+/// Path of the MCP endpoint.
+pub const MCP_HTTP_PATH: &str = "/mcp";
+
+/// Size limit of a request's line and headers (hyper's read buffer).
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+
+/// Maximum size of a request body.
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Listen on `port` of the IPv4 loopback address (`0` picks a free port).
+pub async fn bind_http(port: u16) -> io::Result<TcpListener> {
+    TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await
+}
+
+impl McpToolServer {
+    /// Serve clients over streamable HTTP at [`MCP_HTTP_PATH`] on `listener`,
+    /// which must be bound to a loopback address (see [`bind_http`]).
+    pub async fn serve_http(self, listener: TcpListener) -> io::Result<()> {
+        let addr = listener.local_addr()?;
+        if !addr.ip().is_loopback() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("MCP HTTP server must listen on localhost, not {addr}"),
+            ));
+        }
+        info!(
+            %addr,
+            root = ?self.workspace.root(),
+            "Serving built-in tools over MCP HTTP"
+        );
+
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let server = self.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |request| {
+                    let server = server.clone();
+                    async move { Ok::<_, Infallible>(server.respond(request).await) }
+                });
+                let connection = http1::Builder::new()
+                    .max_buf_size(MAX_HEADER_BYTES)
+                    .serve_connection(TokioIo::new(stream), service);
+                if let Err(e) = connection.await {
+                    debug!(%peer, error = %e, "MCP HTTP connection closed");
+                }
+            });
+        }
+    }
+
+    async fn respond(&self, request: Request<Incoming>) -> Response<Full<Bytes>> {
+        if request.uri().path() != MCP_HTTP_PATH {
+            return status(StatusCode::NOT_FOUND);
+        }
+        match request.headers().get(header::HOST).map(is_local_host) {
+            None => return status(StatusCode::BAD_REQUEST),
+            Some(false) => return status(StatusCode::FORBIDDEN),
+            Some(true) => {}
+        }
+        if request
+            .headers()
+            .get(header::ORIGIN)
+            .is_some_and(|origin| !is_local_origin(origin))
+        {
+            return status(StatusCode::FORBIDDEN);
+        }
+        if request.method() != Method::POST {
+            let mut response = status(StatusCode::METHOD_NOT_ALLOWED);
+            response
+                .headers_mut()
+                .insert(header::ALLOW, HeaderValue::from_static("POST"));
+            return response;
+        }
+
+        let body = request.into_body();
+        if body.size_hint().lower() > MAX_BODY_BYTES as u64 {
+            return status(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+        let body = match Limited::new(body, MAX_BODY_BYTES).collect().await {
+            Ok(body) => body.to_bytes(),
+            Err(e) if e.is::<LengthLimitError>() => return status(StatusCode::PAYLOAD_TOO_LARGE),
+            Err(_) => return status(StatusCode::BAD_REQUEST),
+        };
+
+        let message = match serde_json::from_slice::<JsonValue>(&body) {
+            Ok(message) => message,
+            Err(e) => {
+                let error =
+                    error_response(JsonValue::Null, PARSE_ERROR, format!("Parse error: {e}"));
+                return json(StatusCode::BAD_REQUEST, &error);
+            }
+        };
+        match self.handle(message).await {
+            Some(reply) => json(StatusCode::OK, &reply),
+            None => status(StatusCode::ACCEPTED),
+        }
+    }
+}
+
+/// An empty response.
+fn status(status: StatusCode) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::default());
+    *response.status_mut() = status;
+    response
+}
+
+/// A JSON response.
+fn json(status: StatusCode, body: &JsonValue) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(body.to_string())));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+/// Whether a host name (without port) names this machine.
+fn is_local_hostname(host: &str) -> bool {
+    matches!(
+        host.to_ascii_lowercase().as_str(),
+        "localhost" | "127.0.0.1" | "[::1]"
+    )
+}
+
+/// Whether a `Host` header names this machine, with or without a port.
+fn is_local_host(host: &HeaderValue) -> bool {
+    host.to_str()
+        .ok()
+        .and_then(|host| host.parse::<Authority>().ok())
+        .is_some_and(|authority| is_local_hostname(authority.host()))
+}
+
+/// Whether an `Origin` header names this machine.
+fn is_local_origin(origin: &HeaderValue) -> bool {
+    origin
+        .to_str()
+        .ok()
+        .and_then(|origin| url::Url::parse(origin).ok())
+        .and_then(|url| url.host_str().map(is_local_hostname))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::Workspace;
+    use serde_json::json;
+    use std::net::SocketAddr;
+    use tempfile::TempDir;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    async fn start_server() -> (TempDir, SocketAddr) {
+        let dir = TempDir::new().unwrap();
+        let server = McpToolServer::new(Workspace::new(dir.path()), Vec::new());
+        let listener = bind_http(0).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(server.serve_http(listener));
+        (dir, addr)
+    }
+
+    /// Send `request` as is and read the response until the server closes.
+    async fn send(addr: SocketAddr, request: &[u8]) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request).await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        String::from_utf8_lossy(&response).to_string()
+    }
+
+    async fn post(addr: SocketAddr, body: &str, headers: &str) -> String {
+        let request = format!(
+            "POST /mcp HTTP/1.1\r\nContent-Type: application/json\r\n{headers}\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        send(addr, request.as_bytes()).await
+    }
+
+    #[test]
+    fn test_is_local_origin() {
+        let origin = HeaderValue::from_static;
+        assert!(is_local_origin(&origin("http://localhost:3000")));
+        assert!(is_local_origin(&origin("http://127.0.0.1")));
+        assert!(is_local_origin(&origin("http://[::1]:8080")));
+        assert!(!is_local_origin(&origin("https://example.com")));
+        assert!(!is_local_origin(&origin("null")));
+    }
+
+    #[test]
+    fn test_is_local_host() {
+        let host = HeaderValue::from_static;
+        assert!(is_local_host(&host("localhost")));
+        assert!(is_local_host(&host("LOCALHOST:3000")));
+        assert!(is_local_host(&host("127.0.0.1:8080")));
+        assert!(is_local_host(&host("[::1]:8080")));
+        assert!(!is_local_host(&host("attacker.example:8080")));
+        assert!(!is_local_host(&host("localhost.attacker.example")));
+        assert!(!is_local_host(&host("")));
+    }
+
+    #[tokio::test]
+    async fn test_serve_http() {
+        let (_dir, addr) = start_server().await;
+        let host = format!("Host: {addr}\r\n");
+
+        let ping = json!({"jsonrpc": "2.0", "id": 7, "method": "ping"}).to_string();
+        let response = post(addr, &ping, &host).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.contains(r#""id":7"#), "{response}");
+
+        let notification =
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}).to_string();
+        let headers = format!("{host}Origin: http://localhost:5173\r\n");
+        let response = post(addr, &notification, &headers).await;
+        assert!(response.starts_with("HTTP/1.1 202"), "{response}");
+
+        let headers = format!("{host}Origin: https://example.com\r\n");
+        let response = post(addr, &ping, &headers).await;
+        assert!(response.starts_with("HTTP/1.1 403"), "{response}");
+    }
+
+    #[tokio::test]
+    async fn test_serve_http_checks_host() {
+        let (_dir, addr) = start_server().await;
+        let ping = json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}).to_string();
+
+        let port = addr.port();
+        let response = post(addr, &ping, &format!("Host: localhost:{port}\r\n")).await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+        // A page on another site whose name resolves to 127.0.0.1
+        let response = post(addr, &ping, &format!("Host: attacker.example:{port}\r\n")).await;
+        assert!(response.starts_with("HTTP/1.1 403"), "{response}");
+
+        let response = post(addr, &ping, "").await;
+        assert!(response.starts_with("HTTP/1.1 400"), "{response}");
+    }
+
+    #[tokio::test]
+    async fn test_serve_http_malformed_requests() {
+        let (_dir, addr) = start_server().await;
+        let host = format!("Host: {addr}\r\n");
+
+        let response = send(addr, b"NOT A REQUEST\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 400"), "{response}");
+
+        let request =
+            format!("POST /mcp HTTP/1.1\r\n{host}Content-Length: ten\r\nConnection: close\r\n\r\n");
+        let response = send(addr, request.as_bytes()).await;
+        assert!(response.starts_with("HTTP/1.1 400"), "{response}");
+
+        let request = format!(
+            "POST /mcp HTTP/1.1\r\n{host}Content-Length: {}\r\nConnection: close\r\n\r\n",
+            MAX_BODY_BYTES + 1
+        );
+        let response = send(addr, request.as_bytes()).await;
+        assert!(response.starts_with("HTTP/1.1 413"), "{response}");
+
+        let request = format!(
+            "POST /mcp HTTP/1.1\r\n{host}X-Padding: {}\r\nConnection: close\r\n\r\n",
+            "a".repeat(2 * MAX_HEADER_BYTES)
+        );
+        let response = send(addr, request.as_bytes()).await;
+        assert!(response.starts_with("HTTP/1.1 431"), "{response}");
+
+        let response = post(addr, "{not json", &host).await;
+        assert!(response.starts_with("HTTP/1.1 400"), "{response}");
+        assert!(response.contains(&PARSE_ERROR.to_string()), "{response}");
+
+        let request = format!("GET /mcp HTTP/1.1\r\n{host}Connection: close\r\n\r\n");
+        let response = send(addr, request.as_bytes()).await;
+        assert!(response.starts_with("HTTP/1.1 405"), "{response}");
+        assert!(response.contains("allow: POST"), "{response}");
+
+        let request = format!("POST /other HTTP/1.1\r\n{host}Connection: close\r\n\r\n");
+        let response = send(addr, request.as_bytes()).await;
+        assert!(response.starts_with("HTTP/1.1 404"), "{response}");
+    }
+}
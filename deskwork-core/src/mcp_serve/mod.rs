@@ -0,0 +1,554 @@
+//! Deskwork's built-in tools served over MCP.
+//!
+//! [`McpToolServer`] offers the tools of a [`ToolRegistry`] (`list_files`,
+//! `read_file`, `edit_file`, `grep`, ...) to other MCP clients, over stdio
+//! (newline-delimited JSON-RPC) or streamable HTTP on localhost (see
+//! [`McpToolServer::serve_http`]).
+//!
+//! The tools work in the workspace from `Settings`, with its path policy, and
+//! calls pass through the user's permission rules: `Deny` rules refuse the
+//! call, and calls that would ask for approval are refused too unless the
+//! server approves all calls, since an MCP client can't show the user an
+//! approval prompt.
+
+mod http;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use serdes_ai_tools::{RunContext, ToolReturn};
+
+use crate::config::permissions::{self, PermissionAction, PermissionRule};
+use crate::config::Settings;
+use crate::processes::ProcessManager;
+use crate::tools::{ShellSession, ToolRegistry, Workspace};
+
+pub use http::{bind_http, MCP_HTTP_PATH};
+
+/// Protocol versions the server speaks, newest first.
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// Server name reported to clients.
+const SERVER_NAME: &str = "deskwork";
+
+/// Conversation the served tools' background processes are tracked under.
+const MCP_CONVERSATION: &str = "mcp";
+
+/// Model name in the tools' run context; MCP clients don't report one.
+const MCP_MODEL_NAME: &str = "mcp-client";
+
+// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// A JSON-RPC message from the client: a request, a notification (no `id`)
+/// or a response to a server request (no `method`).
+#[derive(Debug, Deserialize)]
+struct Message {
+    #[serde(default)]
+    id: Option<JsonValue>,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    params: JsonValue,
+}
+
+#[derive(Debug, Deserialize)]
+struct CallToolParams {
+    name: String,
+    #[serde(default)]
+    arguments: Option<JsonValue>,
+}
+
+/// Serves the built-in tools to MCP clients.
+#[derive(Debug, Clone)]
+pub struct McpToolServer {
+    workspace: Workspace,
+    rules: Vec<PermissionRule>,
+    approve_all: bool,
+    processes: Option<ProcessManager>,
+    /// Cancellation tokens of the running `tools/call` requests, by request ID.
+    in_flight: Arc<Mutex<HashMap<String, CancellationToken>>>,
+}
+
+impl McpToolServer {
+    /// Serve the tools of `workspace`, checking calls against `rules`.
+    pub fn new(workspace: Workspace, rules: Vec<PermissionRule>) -> Self {
+        Self {
+            workspace,
+            rules,
+            approve_all: false,
+            processes: None,
+            in_flight: Arc::default(),
+        }
+    }
+
+    /// Serve the tools the agent gets with `settings`: the same workspace
+    /// root, path policy and permission rules, the persistent shell if
+    /// enabled, and the background process tools.
+    pub fn from_settings(settings: &Settings) -> Self {
+        let processes = ProcessManager::new();
+        let mut workspace = Workspace::from_settings(settings)
+            .with_processes(processes.conversation(MCP_CONVERSATION));
+        if settings.persistent_shell {
+            workspace = workspace.with_shell_session(ShellSession::new());
+        }
+        Self {
+            processes: Some(processes),
+            ..Self::new(workspace, settings.permission_rules.clone())
+        }
+    }
+
+    /// Run calls that the permission rules would ask about. Deny rules still
+    /// apply.
+    pub fn with_approve_all(mut self, approve_all: bool) -> Self {
+        self.approve_all = approve_all;
+        self
+    }
+
+    /// The workspace the tools work in.
+    pub fn workspace(&self) -> &Workspace {
+        &self.workspace
+    }
+
+    /// Serve clients over stdin and stdout until stdin closes.
+    pub async fn serve_stdio(self) -> std::io::Result<()> {
+        self.serve_lines(tokio::io::stdin(), tokio::io::stdout())
+            .await
+    }
+
+    /// Serve newline-delimited JSON-RPC read from `reader`, writing replies to
+    /// `writer`, until `reader` closes.
+    ///
+    /// Requests are handled concurrently so a client can cancel a running
+    /// call; running calls are cancelled when the input closes.
+    pub async fn serve_lines<R, W>(self, reader: R, mut writer: W) -> std::io::Result<()>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        info!(
+            root = ?self.workspace.root(),
+            "Serving built-in tools over MCP stdio"
+        );
+        let (reply_tx, mut reply_rx) = mpsc::unbounded_channel::<JsonValue>();
+        let writer_task = tokio::spawn(async move {
+            while let Some(reply) = reply_rx.recv().await {
+                let mut line = reply.to_string();
+                line.push('\n');
+                writer.write_all(line.as_bytes()).await?;
+                writer.flush().await?;
+            }
+            Ok::<_, std::io::Error>(())
+        });
+
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let message = match serde_json::from_str::<JsonValue>(&line) {
+                Ok(message) => message,
+                Err(e) => {
+                    let _ = reply_tx.send(error_response(
+                        JsonValue::Null,
+                        PARSE_ERROR,
+                        format!("Parse error: {e}"),
+                    ));
+                    continue;
+                }
+            };
+            let server = self.clone();
+            let reply_tx = reply_tx.clone();
+            tokio::spawn(async move {
+                if let Some(reply) = server.handle(message).await {
+                    let _ = reply_tx.send(reply);
+                }
+            });
+        }
+
+        debug!("MCP stdio input closed");
+        self.shutdown();
+        drop(reply_tx);
+        writer_task.await.map_err(std::io::Error::other)?
+    }
+
+    /// Handle one JSON-RPC message and return the reply, if it needs one.
+    pub async fn handle(&self, message: JsonValue) -> Option<JsonValue> {
+        let message: Message = match serde_json::from_value(message) {
+            Ok(message) => message,
+            Err(e) => {
+                return Some(error_response(
+                    JsonValue::Null,
+                    INVALID_REQUEST,
+                    format!("Invalid request: {e}"),
+                ))
+            }
+        };
+        // Responses to server requests; the server sends none
+        let method = message.method?;
+
+        let Some(id) = message.id else {
+            self.handle_notification(&method, &message.params);
+            return None;
+        };
+
+        debug!(%method, %id, "MCP request");
+        let result = match method.as_str() {
+            "initialize" => Ok(initialize_result(&message.params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(self.list_tools()),
+            "tools/call" => self.call_tool(&id, message.params).await,
+            _ => Err((METHOD_NOT_FOUND, format!("Method not found: {method}"))),
+        };
+
+        Some(match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err((code, message)) => error_response(id, code, message),
+        })
+    }
+
+    /// Cancel running calls and stop the background processes they started.
+    pub fn shutdown(&self) {
+        for (_, cancel) in self.lock_in_flight().drain() {
+            cancel.cancel();
+        }
+        if let Some(processes) = &self.processes {
+            processes.kill_conversation(MCP_CONVERSATION);
+        }
+    }
+
+    fn handle_notification(&self, method: &str, params: &JsonValue) {
+        match method {
+            "notifications/cancelled" => {
+                let Some(request_id) = params.get("requestId") else {
+                    return;
+                };
+                if let Some(cancel) = self.lock_in_flight().get(&request_id.to_string()) {
+                    debug!(%request_id, "MCP client cancelled a call");
+                    cancel.cancel();
+                }
+            }
+            _ => debug!(%method, "MCP notification"),
+        }
+    }
+
+    fn registry(&self, cancel: CancellationToken) -> ToolRegistry {
+        ToolRegistry::with_workspace(self.workspace.clone().with_cancel(cancel))
+    }
+
+    fn list_tools(&self) -> JsonValue {
+        let tools: Vec<JsonValue> = self
+            .registry(CancellationToken::new())
+            .tools()
+            .iter()
+            .map(|tool| {
+                let definition = tool.definition();
+                json!({
+                    "name": definition.name(),
+                    "description": definition.description(),
+                    "inputSchema": definition.parameters(),
+                })
+            })
+            .collect();
+        json!({ "tools": tools })
+    }
+
+    async fn call_tool(
+        &self,
+        id: &JsonValue,
+        params: JsonValue,
+    ) -> Result<JsonValue, (i64, String)> {
+        let params: CallToolParams = serde_json::from_value(params)
+            .map_err(|e| (INVALID_PARAMS, format!("Invalid tools/call params: {e}")))?;
+        let args = params.arguments.unwrap_or_else(|| json!({}));
+
+        let cancel = CancellationToken::new();
+        let registry = self.registry(cancel.clone());
+        let Some(tool) = registry.get(&params.name) else {
+            return Err((INVALID_PARAMS, format!("Unknown tool: {}", params.name)));
+        };
+
+        if let Err(reason) = self.check_permission(&params.name, &args) {
+            info!(tool = %params.name, "MCP tool call refused");
+            return Ok(error_result(reason));
+        }
+
+        let key = id.to_string();
+        self.lock_in_flight().insert(key.clone(), cancel);
+        let run_id = format!("mcp-{key}");
+        let ctx = RunContext::minimal(MCP_MODEL_NAME)
+            .with_run_id(&run_id)
+            .with_tool_context(&params.name, Some(key.clone()));
+        let ret = tool.call(&ctx, args).await;
+        self.lock_in_flight().remove(&key);
+
+        Ok(match ret {
+            Ok(ret) => tool_result(&ret),
+            Err(e) => {
+                warn!(tool = %params.name, error = %e, "MCP tool call failed");
+                error_result(format!("{}: {}", params.name, e))
+            }
+        })
+    }
+
+    /// Apply the permission rules; `Err` with the reason for the client when
+    /// the call must not run.
+    fn check_permission(&self, tool_name: &str, args: &JsonValue) -> Result<(), String> {
+        match permissions::evaluate(&self.rules, tool_name, args) {
+            PermissionAction::Allow => Ok(()),
+            PermissionAction::Ask if self.approve_all => Ok(()),
+            PermissionAction::Ask => Err(format!(
+                "Permission denied: `{tool_name}` calls need the user's approval, which can't be \
+                 asked for over MCP. The user can allow them in Deskwork's permission rules."
+            )),
+            PermissionAction::Deny => Err(format!(
+                "Permission denied: `{tool_name}` calls matching these arguments are blocked by \
+                 the user's permission rules."
+            )),
+        }
+    }
+
+    fn lock_in_flight(&self) -> std::sync::MutexGuard<'_, HashMap<String, CancellationToken>> {
+        self.in_flight.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Result of `initialize`, in the client's protocol version if the server
+/// speaks it and the newest one otherwise.
+fn initialize_result(params: &JsonValue) -> JsonValue {
+    let requested = params.get("protocolVersion").and_then(JsonValue::as_str);
+    let version = requested
+        .filter(|version| PROTOCOL_VERSIONS.contains(version))
+        .unwrap_or(PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": { "listChanged": false } },
+        "serverInfo": { "name": SERVER_NAME, "version": crate::VERSION },
+    })
+}
+
+/// MCP content of what a tool returned. JSON is sent as text and, for
+/// objects, as structured content too.
+fn tool_result(ret: &ToolReturn) -> JsonValue {
+    let mut result = if let Some(text) = ret.as_text() {
+        json!({ "content": [{ "type": "text", "text": text }] })
+    } else if let Some(value) = ret.as_json() {
+        let text = serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string());
+        let mut result = json!({ "content": [{ "type": "text", "text": text }] });
+        if value.is_object() {
+            result["structuredContent"] = value.clone();
+        }
+        result
+    } else {
+        json!({ "content": [{
+            "type": "text",
+            "text": "[The tool returned content that can't be sent over MCP]",
+        }] })
+    };
+    result["isError"] = JsonValue::Bool(ret.is_error());
+    result
+}
+
+/// A tool result reporting an error to the client.
+fn error_result(message: String) -> JsonValue {
+    json!({ "content": [{ "type": "text", "text": message }], "isError": true })
+}
+
+fn error_response(id: JsonValue, code: i64, message: String) -> JsonValue {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn request(id: u64, method: &str, params: JsonValue) -> JsonValue {
+        json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+    }
+
+    fn server(dir: &TempDir, rules: Vec<PermissionRule>) -> McpToolServer {
+        McpToolServer::new(Workspace::new(dir.path()), rules)
+    }
+
+    fn text(result: &JsonValue) -> &str {
+        result["content"][0]["text"].as_str().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_initialize_negotiates_version() {
+        let dir = TempDir::new().unwrap();
+        let server = server(&dir, Vec::new());
+
+        let reply = server
+            .handle(request(
+                1,
+                "initialize",
+                json!({"protocolVersion": "2025-03-26"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(reply["id"], 1);
+        assert_eq!(reply["result"]["protocolVersion"], "2025-03-26");
+        assert!(reply["result"]["capabilities"]["tools"].is_object());
+
+        let reply = server
+            .handle(request(
+                2,
+                "initialize",
+                json!({"protocolVersion": "1999-01-01"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(reply["result"]["protocolVersion"], PROTOCOL_VERSIONS[0]);
+    }
+
+    #[tokio::test]
+    async fn test_list_tools_uses_definitions() {
+        let dir = TempDir::new().unwrap();
+        let reply = server(&dir, Vec::new())
+            .handle(request(1, "tools/list", json!({})))
+            .await
+            .unwrap();
+
+        let tools = reply["result"]["tools"].as_array().unwrap();
+        let read_file = tools.iter().find(|t| t["name"] == "read_file").unwrap();
+        assert!(read_file["description"]
+            .as_str()
+            .unwrap()
+            .contains("Read file"));
+        assert!(read_file["inputSchema"]["properties"]["file_path"].is_object());
+        assert!(tools.iter().any(|t| t["name"] == "run_shell_command"));
+    }
+
+    #[tokio::test]
+    async fn test_call_tool_in_workspace() {
+        let dir = TempDir::new().unwrap();
+        let project = dir.path().join("project");
+        fs::create_dir(&project).unwrap();
+        fs::write(project.join("notes.txt"), "hello").unwrap();
+        fs::write(dir.path().join("secret.txt"), "secret").unwrap();
+        let server = McpToolServer::new(Workspace::new(&project), Vec::new());
+
+        let reply = server
+            .handle(request(
+                1,
+                "tools/call",
+                json!({"name": "read_file", "arguments": {"file_path": "notes.txt"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(reply["result"]["isError"], false);
+        assert_eq!(text(&reply["result"]), "hello");
+
+        let reply = server
+            .handle(request(
+                2,
+                "tools/call",
+                json!({"name": "read_file", "arguments": {"file_path": "../secret.txt"}}),
+            ))
+            .await
+            .unwrap();
+        assert!(text(&reply["result"]).contains("Access denied"));
+    }
+
+    #[tokio::test]
+    async fn test_call_tool_applies_permission_rules() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("notes.txt"), "hello").unwrap();
+        let rules = vec![PermissionRule::new("read_file", PermissionAction::Deny)];
+        let server = server(&dir, rules);
+
+        let reply = server
+            .handle(request(
+                1,
+                "tools/call",
+                json!({"name": "read_file", "arguments": {"file_path": "notes.txt"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(reply["result"]["isError"], true);
+        assert!(text(&reply["result"]).contains("blocked"));
+
+        // Calls that would ask need approve-all
+        let args = json!({"name": "delete_file", "arguments": {"file_path": "notes.txt"}});
+        let reply = server
+            .handle(request(2, "tools/call", args.clone()))
+            .await
+            .unwrap();
+        assert_eq!(reply["result"]["isError"], true);
+        assert!(dir.path().join("notes.txt").exists());
+
+        let reply = server
+            .with_approve_all(true)
+            .handle(request(3, "tools/call", args))
+            .await
+            .unwrap();
+        assert_eq!(reply["result"]["isError"], false, "{reply}");
+        assert!(!dir.path().join("notes.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_protocol_errors() {
+        let dir = TempDir::new().unwrap();
+        let server = server(&dir, Vec::new());
+
+        let reply = server
+            .handle(request(1, "resources/list", json!({})))
+            .await
+            .unwrap();
+        assert_eq!(reply["error"]["code"], METHOD_NOT_FOUND);
+
+        let reply = server
+            .handle(request(2, "tools/call", json!({"name": "nope"})))
+            .await
+            .unwrap();
+        assert_eq!(reply["error"]["code"], INVALID_PARAMS);
+
+        let notification = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        assert!(server.handle(notification).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_serve_lines() {
+        let dir = TempDir::new().unwrap();
+        let server = server(&dir, Vec::new());
+
+        let input = format!(
+            "{}\n{}\nnot json\n",
+            request(1, "initialize", json!({})),
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+        );
+        let (output, mut output_reader) = tokio::io::duplex(64 * 1024);
+        server.serve_lines(input.as_bytes(), output).await.unwrap();
+
+        let mut replies = String::new();
+        tokio::io::AsyncReadExt::read_to_string(&mut output_reader, &mut replies)
+            .await
+            .unwrap();
+        let replies: Vec<JsonValue> = replies
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(replies.len(), 2);
+        assert!(replies
+            .iter()
+            .any(|r| r["id"] == 1 && r["result"].is_object()));
+        assert!(replies.iter().any(|r| r["error"]["code"] == PARSE_ERROR));
+    }
+}
@@ -0,0 +1,293 @@
+//! User-defined MCP servers.
+//!
+//! Besides the connectors bundled with skill categories, users can add their
+//! own MCP servers. They come from two places:
+//! - the `mcp_servers` table, edited in the settings UI
+//! - `~/.config/deskwork/mcp.json` (or the platform equivalent), edited by hand
+//!
+//! Both use the `mcpServers` format of `.mcp.json` files, which also reads
+//! Claude Desktop's `claude_desktop_config.json`: a missing `type` is inferred
+//! from `url` or `command`, and `"disabled": true` turns a server off. Servers
+//! in the database win over file servers of the same name.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use tracing::{debug, info, warn};
+
+use crate::db::Database;
+use crate::skills::categories::resolve_entry_placeholders;
+use crate::skills::types::McpServerEntry;
+
+/// Namespace of user servers in the MCP config map passed to `run_agent`,
+/// in place of a category ID (keys are "user:{server_name}").
+pub const USER_MCP_NAMESPACE: &str = "user";
+
+/// Where a user MCP server is defined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum McpServerSource {
+    /// The `mcp_servers` table; editable in the settings UI.
+    Database,
+    /// The user's `mcp.json` file.
+    File,
+}
+
+/// A user-defined MCP server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserMcpServer {
+    pub name: String,
+    pub entry: McpServerEntry,
+    pub enabled: bool,
+    pub source: McpServerSource,
+}
+
+/// Server entry as written in `mcp.json` or Claude Desktop's config.
+#[derive(Debug, Deserialize)]
+struct RawServerEntry {
+    #[serde(rename = "type")]
+    r#type: Option<String>,
+    url: Option<String>,
+    command: Option<String>,
+    args: Option<Vec<String>>,
+    env: Option<HashMap<String, String>>,
+    headers: Option<HashMap<String, String>>,
+    #[serde(default)]
+    disabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawServersFile {
+    #[serde(rename = "mcpServers", default)]
+    mcp_servers: BTreeMap<String, RawServerEntry>,
+}
+
+impl RawServerEntry {
+    fn into_entry(self) -> McpServerEntry {
+        let r#type = self.r#type.unwrap_or_else(|| {
+            if self.url.is_some() && self.command.is_none() {
+                "http".to_string()
+            } else {
+                "stdio".to_string()
+            }
+        });
+        McpServerEntry {
+            r#type,
+            url: self.url,
+            command: self.command,
+            args: self.args,
+            env: self.env,
+            headers: self.headers,
+        }
+    }
+}
+
+/// Path of the user's MCP config file: `{config_dir}/deskwork/mcp.json`.
+pub fn user_mcp_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("deskwork").join("mcp.json"))
+}
+
+/// Path of Claude Desktop's config file, e.g.
+/// `~/Library/Application Support/Claude/claude_desktop_config.json` on macOS.
+pub fn claude_desktop_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("Claude").join("claude_desktop_config.json"))
+}
+
+/// Parse an `mcpServers` config into `(name, entry, enabled)` by name.
+pub fn parse_mcp_config(json: &str) -> Result<Vec<(String, McpServerEntry, bool)>, String> {
+    let file: RawServersFile =
+        serde_json::from_str(json).map_err(|e| format!("Invalid MCP config: {e}"))?;
+    Ok(file
+        .mcp_servers
+        .into_iter()
+        .map(|(name, raw)| {
+            let enabled = !raw.disabled;
+            (name, raw.into_entry(), enabled)
+        })
+        .collect())
+}
+
+/// Read the servers of an `mcpServers` config file.
+///
+/// A missing file has no servers.
+pub fn read_mcp_config_file(path: &Path) -> Result<Vec<(String, McpServerEntry, bool)>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(json) => parse_mcp_config(&json),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to read {}: {e}", path.display())),
+    }
+}
+
+/// All user MCP servers by name, enabled or not.
+///
+/// Problems with the config file are logged and leave out its servers.
+pub fn load_user_mcp_servers(db: &Database) -> Vec<UserMcpServer> {
+    let mut servers: BTreeMap<String, UserMcpServer> = BTreeMap::new();
+
+    if let Some(path) = user_mcp_config_path() {
+        match read_mcp_config_file(&path) {
+            Ok(entries) => {
+                for (name, entry, enabled) in entries {
+                    let server = UserMcpServer {
+                        name: name.clone(),
+                        entry,
+                        enabled,
+                        source: McpServerSource::File,
+                    };
+                    servers.insert(name, server);
+                }
+            }
+            Err(e) => warn!("Ignoring user MCP config: {e}"),
+        }
+    }
+
+    match db.list_mcp_servers() {
+        Ok(stored) => {
+            for server in stored {
+                let server = UserMcpServer {
+                    name: server.name,
+                    entry: server.entry,
+                    enabled: server.enabled,
+                    source: McpServerSource::Database,
+                };
+                servers.insert(server.name.clone(), server);
+            }
+        }
+        Err(e) => warn!("Failed to load MCP servers from database: {e}"),
+    }
+
+    servers.into_values().collect()
+}
+
+/// Copy the servers of a Claude Desktop (or any `mcpServers`) config into the
+/// database, replacing servers of the same name. Returns how many were
+/// imported.
+pub fn import_mcp_config(db: &Database, path: &Path) -> Result<usize, String> {
+    if !path.exists() {
+        return Err(format!("{} not found", path.display()));
+    }
+    let entries = read_mcp_config_file(path)?;
+    for (name, entry, enabled) in &entries {
+        db.save_mcp_server(name, entry, *enabled)
+            .map_err(|e| format!("Failed to save MCP server `{name}`: {e}"))?;
+    }
+    info!(
+        "Imported {} MCP servers from {}",
+        entries.len(),
+        path.display()
+    );
+    Ok(entries.len())
+}
+
+/// Connector configs of the enabled user servers, keyed
+/// "user:{server_name}" like the category connectors of `build_mcp_map`.
+///
+/// Servers with unresolved `${VAR}` placeholders or invalid entries are left
+/// out.
+pub fn user_mcp_configs(db: &Database) -> HashMap<String, McpServerEntry> {
+    let mut configs = HashMap::new();
+    for server in load_user_mcp_servers(db) {
+        if !server.enabled {
+            continue;
+        }
+        match resolve_entry_placeholders(&server.entry) {
+            Ok(resolved) => {
+                configs.insert(format!("{USER_MCP_NAMESPACE}:{}", server.name), resolved);
+            }
+            Err(e) => debug!(server = %server.name, "Skipping user MCP server: {e}"),
+        }
+    }
+    configs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_at(temp_dir.path().join("test.db")).unwrap();
+        db.migrate().unwrap();
+        (temp_dir, db)
+    }
+
+    #[test]
+    fn test_parse_claude_desktop_config() {
+        let json = r#"{
+            "mcpServers": {
+                "filesystem": {
+                    "command": "npx",
+                    "args": ["-y", "@modelcontextprotocol/server-filesystem", "/tmp"]
+                },
+                "remote": {
+                    "url": "https://mcp.example.com/mcp",
+                    "headers": {"Authorization": "Bearer ${REMOTE_TOKEN}"},
+                    "disabled": true
+                }
+            },
+            "globalShortcut": "Ctrl+Space"
+        }"#;
+
+        let servers = parse_mcp_config(json).unwrap();
+        assert_eq!(servers.len(), 2);
+
+        let (name, entry, enabled) = &servers[0];
+        assert_eq!(name, "filesystem");
+        assert_eq!(entry.r#type, "stdio");
+        assert_eq!(entry.command.as_deref(), Some("npx"));
+        assert!(enabled);
+
+        let (name, entry, enabled) = &servers[1];
+        assert_eq!(name, "remote");
+        assert_eq!(entry.r#type, "http");
+        assert_eq!(
+            entry.headers.as_ref().unwrap()["Authorization"],
+            "Bearer ${REMOTE_TOKEN}"
+        );
+        assert!(!enabled);
+    }
+
+    #[test]
+    fn test_parse_mcp_config_invalid() {
+        assert!(parse_mcp_config("not json").is_err());
+        assert!(parse_mcp_config("{}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_import_mcp_config() {
+        let (temp, db) = setup_test_db();
+        let path = temp.path().join("claude_desktop_config.json");
+        std::fs::write(
+            &path,
+            r#"{"mcpServers": {"memory": {"command": "npx", "args": ["-y", "@modelcontextprotocol/server-memory"]}}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(import_mcp_config(&db, &path).unwrap(), 1);
+        let stored = db.list_mcp_servers().unwrap();
+        assert_eq!(stored[0].name, "memory");
+        assert!(stored[0].enabled);
+
+        assert!(import_mcp_config(&db, &temp.path().join("missing.json")).is_err());
+    }
+
+    #[test]
+    fn test_user_mcp_configs_skips_disabled() {
+        let (_temp, db) = setup_test_db();
+        let entry = McpServerEntry {
+            r#type: "http".to_string(),
+            url: Some("http://localhost:3000/mcp".to_string()),
+            command: None,
+            args: None,
+            env: None,
+            headers: None,
+        };
+        db.save_mcp_server("on", &entry, true).unwrap();
+        db.save_mcp_server("off", &entry, false).unwrap();
+
+        let configs = user_mcp_configs(&db);
+        assert!(configs.contains_key("user:on"));
+        assert!(!configs.contains_key("user:off"));
+    }
+}
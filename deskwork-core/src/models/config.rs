@@ -30,6 +30,9 @@ pub struct ModelConfig {
     /// Description of the model
     #[serde(default)]
     pub description: Option<String>,
+    /// Base URL of the model's OpenAI-compatible endpoint (e.g. "http://localhost:11434/v1")
+    #[serde(default)]
+    pub base_url: Option<String>,
 }
 
 fn default_context_length() -> usize {
@@ -51,11 +54,36 @@ impl Default for ModelConfig {
             supports_vision: true,
             supports_tools: true,
             description: None,
+            base_url: None,
         }
     }
 }
 
 impl ModelConfig {
+    /// Configuration assumed for a model missing from the registry.
+    ///
+    /// Claude models get their full capabilities; models of other providers
+    /// are assumed to handle tools but neither images nor thinking.
+    pub fn unregistered(name: &str) -> Self {
+        let model_type = ModelType::from_model_name(name);
+        let is_claude = !model_type.is_openai_compatible();
+        Self {
+            name: name.to_string(),
+            model_type,
+            model_id: Some(
+                name.strip_prefix(model_type.model_prefix())
+                    .unwrap_or(name)
+                    .to_string(),
+            ),
+            context_length: default_context_length(),
+            supports_thinking: is_claude,
+            supports_vision: is_claude,
+            supports_tools: true,
+            description: None,
+            base_url: None,
+        }
+    }
+
     /// Get the effective model ID for API calls.
     pub fn effective_model_id(&self) -> &str {
         self.model_id.as_deref().unwrap_or(&self.name)
@@ -68,9 +96,9 @@ impl ModelConfig {
 
     /// Get a display-friendly name.
     pub fn display_name(&self) -> String {
-        // Strip claude-code- prefix for display
+        // Strip the provider prefix for display
         self.name
-            .strip_prefix("claude-code-")
+            .strip_prefix(self.model_type.model_prefix())
             .unwrap_or(&self.name)
             .to_string()
     }
@@ -112,4 +140,19 @@ mod tests {
         };
         assert_eq!(config.display_name(), "claude-sonnet-4-20250514");
     }
+
+    #[test]
+    fn test_unregistered() {
+        let config = ModelConfig::unregistered("local-qwen3:8b");
+        assert_eq!(config.model_type, ModelType::Local);
+        assert_eq!(config.effective_model_id(), "qwen3:8b");
+        assert!(config.supports_tools);
+        assert!(!config.supports_vision);
+        assert!(!config.supports_thinking);
+
+        let config = ModelConfig::unregistered("claude-code-claude-sonnet-4-20250514");
+        assert_eq!(config.effective_model_id(), "claude-sonnet-4-20250514");
+        assert!(config.supports_thinking);
+        assert!(config.supports_vision);
+    }
 }
@@ -11,4 +11,7 @@ mod types;
 
 pub use config::ModelConfig;
 pub use registry::ModelRegistry;
-pub use types::{ModelConfigError, ModelType};
+pub use types::{
+    ModelConfigError, ModelType, ANTHROPIC_MODEL_PREFIX, CLAUDE_CODE_MODEL_PREFIX,
+    LOCAL_MODEL_PREFIX, OPENAI_COMPATIBLE_MODEL_PREFIX,
+};
@@ -3,7 +3,7 @@
 use std::collections::HashMap;
 
 use rusqlite::params;
-use tracing::debug;
+use tracing::{debug, warn};
 
 use crate::db::Database;
 
@@ -30,7 +30,7 @@ impl ModelRegistry {
             .conn()
             .prepare(
                 "SELECT name, model_type, model_id, context_length, supports_thinking,
-                        supports_vision, supports_tools, description
+                        supports_vision, supports_tools, description, base_url
                  FROM models ORDER BY name",
             )
             .map_err(|e| ModelConfigError::Database(e.to_string()))?;
@@ -48,6 +48,7 @@ impl ModelRegistry {
                     supports_vision: row.get::<_, i64>(5)? != 0,
                     supports_tools: row.get::<_, i64>(6)? != 0,
                     description: row.get(7)?,
+                    base_url: row.get(8)?,
                 })
             })
             .map_err(|e| ModelConfigError::Database(e.to_string()))?;
@@ -91,8 +92,8 @@ impl ModelRegistry {
             .execute(
                 "INSERT OR REPLACE INTO models (name, model_type, model_id, context_length,
                     supports_thinking, supports_vision, supports_tools, description,
-                    source, updated_at)
-                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, unixepoch())",
+                    base_url, source, updated_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, unixepoch())",
                 params![
                     &config.name,
                     config.model_type.to_string(),
@@ -102,6 +103,7 @@ impl ModelRegistry {
                     config.supports_vision as i64,
                     config.supports_tools as i64,
                     &config.description,
+                    &config.base_url,
                     source,
                 ],
             )
@@ -110,6 +112,22 @@ impl ModelRegistry {
         Ok(())
     }
 
+    /// Load a single model from the database.
+    pub fn get_from_db(db: &Database, name: &str) -> Result<Option<ModelConfig>, ModelConfigError> {
+        let mut registry = Self::load_from_db(db)?;
+        Ok(registry.models.remove(name))
+    }
+
+    /// Load a model from the database, or what is assumed for it if it isn't
+    /// there (see [`ModelRegistry::resolve`]).
+    pub fn resolve_from_db(db: &Database, name: &str) -> ModelConfig {
+        let config = Self::get_from_db(db, name).unwrap_or_else(|e| {
+            warn!(model = %name, error = %e, "Failed to load model from database");
+            None
+        });
+        config.unwrap_or_else(|| ModelConfig::unregistered(name))
+    }
+
     /// Remove a model from the database.
     pub fn remove_model_from_db(db: &Database, name: &str) -> Result<(), ModelConfigError> {
         db.conn()
@@ -136,6 +154,16 @@ impl ModelRegistry {
         self.models.get(name)
     }
 
+    /// The configuration of `name`: its entry, or what is assumed for a model
+    /// missing from the registry (see [`ModelConfig::unregistered`]).
+    ///
+    /// Its `model_type` decides the model's credentials and backend.
+    pub fn resolve(&self, name: &str) -> ModelConfig {
+        self.get(name)
+            .cloned()
+            .unwrap_or_else(|| ModelConfig::unregistered(name))
+    }
+
     /// Check if a model exists.
     pub fn contains(&self, name: &str) -> bool {
         self.models.contains_key(name)
@@ -165,10 +193,15 @@ impl ModelRegistry {
 
     /// List Claude Code models (sorted).
     pub fn list_claude_code_models(&self) -> Vec<String> {
+        self.list_models_of_type(ModelType::ClaudeCode)
+    }
+
+    /// List the models of one provider type (sorted).
+    pub fn list_models_of_type(&self, model_type: ModelType) -> Vec<String> {
         let mut models: Vec<String> = self
             .models
             .iter()
-            .filter(|(_, config)| matches!(config.model_type, ModelType::ClaudeCode))
+            .filter(|(_, config)| config.model_type == model_type)
             .map(|(name, _)| name.clone())
             .collect();
         models.sort();
@@ -203,6 +236,7 @@ mod tests {
             supports_vision: true,
             supports_tools: true,
             description: None,
+            base_url: None,
         }
     }
 
@@ -235,6 +269,42 @@ mod tests {
         assert!(model.supports_thinking);
     }
 
+    #[test]
+    fn test_registry_resolve() {
+        let mut registry = ModelRegistry::new();
+        registry.add(ModelConfig {
+            model_type: ModelType::Local,
+            ..ModelConfig::unregistered("openai-qwen3:8b")
+        });
+
+        // The entry's type wins over the name's prefix
+        assert_eq!(
+            registry.resolve("openai-qwen3:8b").model_type,
+            ModelType::Local
+        );
+        assert_eq!(
+            registry
+                .resolve("anthropic-claude-sonnet-4-20250514")
+                .model_type,
+            ModelType::Anthropic
+        );
+    }
+
+    #[test]
+    fn test_registry_resolve_from_db() {
+        let (_temp, db) = setup_test_db();
+        let config = ModelConfig {
+            model_type: ModelType::OpenAiCompatible,
+            ..ModelConfig::unregistered("local-qwen3:8b")
+        };
+        ModelRegistry::add_model_to_db_with_source(&db, &config, "endpoint").unwrap();
+
+        let resolved = ModelRegistry::resolve_from_db(&db, "local-qwen3:8b");
+        assert_eq!(resolved.model_type, ModelType::OpenAiCompatible);
+        let resolved = ModelRegistry::resolve_from_db(&db, "claude-code-claude-opus-4");
+        assert_eq!(resolved.model_type, ModelType::ClaudeCode);
+    }
+
     #[test]
     fn test_registry_list() {
         let mut registry = ModelRegistry::new();
@@ -265,6 +335,31 @@ mod tests {
         assert_eq!(loaded.supports_thinking, config.supports_thinking);
     }
 
+    #[test]
+    fn test_registry_db_roundtrip_endpoint_model() {
+        let (_temp, db) = setup_test_db();
+
+        let config = ModelConfig {
+            base_url: Some("http://localhost:11434/v1".to_string()),
+            supports_vision: false,
+            ..ModelConfig::unregistered("local-qwen3:8b")
+        };
+        ModelRegistry::add_model_to_db_with_source(&db, &config, "endpoint").unwrap();
+
+        let loaded = ModelRegistry::get_from_db(&db, "local-qwen3:8b")
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.model_type, ModelType::Local);
+        assert_eq!(
+            loaded.base_url.as_deref(),
+            Some("http://localhost:11434/v1")
+        );
+        assert!(!loaded.supports_vision);
+        assert!(ModelRegistry::get_from_db(&db, "local-missing")
+            .unwrap()
+            .is_none());
+    }
+
     #[test]
     fn test_registry_remove_from_db() {
         let (_temp, db) = setup_test_db();
@@ -304,4 +399,21 @@ mod tests {
         let models = registry.list_claude_code_models();
         assert_eq!(models.len(), 2);
     }
+
+    #[test]
+    fn test_list_models_of_type() {
+        let mut registry = ModelRegistry::new();
+        registry.add(create_test_model("claude-code-claude-sonnet-4-20250514"));
+        registry.add(ModelConfig {
+            name: "anthropic-claude-sonnet-4-20250514".to_string(),
+            model_type: ModelType::Anthropic,
+            ..create_test_model("claude-sonnet-4-20250514")
+        });
+
+        assert_eq!(
+            registry.list_models_of_type(ModelType::Anthropic),
+            vec!["anthropic-claude-sonnet-4-20250514".to_string()]
+        );
+        assert_eq!(registry.list_claude_code_models().len(), 1);
+    }
 }
@@ -19,6 +19,21 @@ pub enum ModelConfigError {
 #[error("Unknown model type: {0}")]
 pub struct ModelTypeParseError(String);
 
+/// Prefix of registry names for Claude Code OAuth models
+/// (e.g. "claude-code-claude-sonnet-4-20250514").
+pub const CLAUDE_CODE_MODEL_PREFIX: &str = "claude-code-";
+
+/// Prefix of registry names for Anthropic API-key models
+/// (e.g. "anthropic-claude-sonnet-4-20250514").
+pub const ANTHROPIC_MODEL_PREFIX: &str = "anthropic-";
+
+/// Prefix of registry names for models of OpenAI-compatible endpoints
+/// (e.g. "openai-gpt-4o").
+pub const OPENAI_COMPATIBLE_MODEL_PREFIX: &str = "openai-";
+
+/// Prefix of registry names for models of local servers (e.g. "local-qwen3:8b").
+pub const LOCAL_MODEL_PREFIX: &str = "local-";
+
 /// Supported model provider types.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
@@ -26,12 +41,21 @@ pub enum ModelType {
     /// Claude Code OAuth-authenticated.
     #[default]
     ClaudeCode,
+    /// Anthropic API, authenticated with an API key.
+    Anthropic,
+    /// An OpenAI-compatible HTTP endpoint, with an optional API key.
+    OpenAiCompatible,
+    /// A local OpenAI-compatible server such as Ollama or llama.cpp.
+    Local,
 }
 
 impl std::fmt::Display for ModelType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ModelType::ClaudeCode => write!(f, "claude_code"),
+            ModelType::Anthropic => write!(f, "anthropic"),
+            ModelType::OpenAiCompatible => write!(f, "openai_compatible"),
+            ModelType::Local => write!(f, "local"),
         }
     }
 }
@@ -39,11 +63,41 @@ impl std::fmt::Display for ModelType {
 impl ModelType {
     /// Parse from string, defaulting to [`ModelType::ClaudeCode`] for unknown values.
     pub fn parse_lossy(s: &str) -> Self {
-        match s {
-            "claude_code" | "claude-code" => ModelType::ClaudeCode,
-            _ => ModelType::ClaudeCode,
+        s.parse().unwrap_or_default()
+    }
+
+    /// The provider of a registry model name, from its prefix.
+    pub fn from_model_name(model_name: &str) -> Self {
+        [
+            ModelType::Anthropic,
+            ModelType::OpenAiCompatible,
+            ModelType::Local,
+        ]
+        .into_iter()
+        .find(|model_type| model_name.starts_with(model_type.model_prefix()))
+        .unwrap_or(ModelType::ClaudeCode)
+    }
+
+    /// Prefix of this provider's registry model names.
+    pub fn model_prefix(self) -> &'static str {
+        match self {
+            ModelType::ClaudeCode => CLAUDE_CODE_MODEL_PREFIX,
+            ModelType::Anthropic => ANTHROPIC_MODEL_PREFIX,
+            ModelType::OpenAiCompatible => OPENAI_COMPATIBLE_MODEL_PREFIX,
+            ModelType::Local => LOCAL_MODEL_PREFIX,
         }
     }
+
+    /// Registry name of one of this provider's models
+    /// (e.g. "local-qwen3:8b" for the local model "qwen3:8b").
+    pub fn model_name(self, model_id: &str) -> String {
+        format!("{}{}", self.model_prefix(), model_id)
+    }
+
+    /// Whether requests go to an OpenAI-compatible endpoint.
+    pub fn is_openai_compatible(self) -> bool {
+        matches!(self, ModelType::OpenAiCompatible | ModelType::Local)
+    }
 }
 
 impl FromStr for ModelType {
@@ -52,6 +106,9 @@ impl FromStr for ModelType {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "claude_code" | "claude-code" => Ok(ModelType::ClaudeCode),
+            "anthropic" => Ok(ModelType::Anthropic),
+            "openai_compatible" | "openai-compatible" => Ok(ModelType::OpenAiCompatible),
+            "local" => Ok(ModelType::Local),
             _ => Err(ModelTypeParseError(s.to_string())),
         }
     }
@@ -64,15 +121,58 @@ mod tests {
     #[test]
     fn test_model_type_display() {
         assert_eq!(ModelType::ClaudeCode.to_string(), "claude_code");
+        assert_eq!(ModelType::Anthropic.to_string(), "anthropic");
+        assert_eq!(ModelType::OpenAiCompatible.to_string(), "openai_compatible");
+        assert_eq!(ModelType::Local.to_string(), "local");
     }
 
     #[test]
     fn test_model_type_parse_lossy() {
         assert_eq!(ModelType::parse_lossy("claude_code"), ModelType::ClaudeCode);
         assert_eq!(ModelType::parse_lossy("claude-code"), ModelType::ClaudeCode);
+        assert_eq!(ModelType::parse_lossy("anthropic"), ModelType::Anthropic);
+        assert_eq!(
+            ModelType::parse_lossy("openai_compatible"),
+            ModelType::OpenAiCompatible
+        );
+        assert_eq!(ModelType::parse_lossy("local"), ModelType::Local);
         assert_eq!(ModelType::parse_lossy("unknown"), ModelType::ClaudeCode);
     }
 
+    #[test]
+    fn test_model_type_from_model_name() {
+        assert_eq!(
+            ModelType::from_model_name("claude-code-claude-sonnet-4-20250514"),
+            ModelType::ClaudeCode
+        );
+        assert_eq!(
+            ModelType::from_model_name("anthropic-claude-sonnet-4-20250514"),
+            ModelType::Anthropic
+        );
+        assert_eq!(
+            ModelType::from_model_name("openai-gpt-4o"),
+            ModelType::OpenAiCompatible
+        );
+        assert_eq!(
+            ModelType::from_model_name("local-qwen3:8b"),
+            ModelType::Local
+        );
+    }
+
+    #[test]
+    fn test_model_type_model_name() {
+        for model_type in [
+            ModelType::ClaudeCode,
+            ModelType::Anthropic,
+            ModelType::OpenAiCompatible,
+            ModelType::Local,
+        ] {
+            let name = model_type.model_name("some-model");
+            assert_eq!(ModelType::from_model_name(&name), model_type);
+        }
+        assert_eq!(ModelType::Local.model_name("qwen3:8b"), "local-qwen3:8b");
+    }
+
     #[test]
     fn test_model_type_from_str_trait() {
         let parsed: ModelType = "claude_code".parse().unwrap();
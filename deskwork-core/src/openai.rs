@@ -0,0 +1,188 @@
+//! OpenAI-compatible model backends.
+//!
+//! Models of OpenAI-compatible HTTP endpoints ([`ModelType::OpenAiCompatible`])
+//! and of local servers such as Ollama or llama.cpp ([`ModelType::Local`]).
+//! Both speak the OpenAI chat completions API at a configurable base URL,
+//! stored with each model in the registry. Local servers need no API key;
+//! other endpoints use the `OPENAI_API_KEY` from the `api_keys` table or the
+//! environment.
+
+use serde::Deserialize;
+use serdes_ai_models::openai::OpenAIChatModel;
+use tracing::{error, info};
+
+use crate::db::Database;
+use crate::models::{ModelConfig, ModelRegistry, ModelType};
+
+/// Name of the key in the `api_keys` table and of the environment variable.
+pub const OPENAI_API_KEY: &str = "OPENAI_API_KEY";
+
+/// Base URL of the OpenAI API, for endpoint models without one.
+pub const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Base URL of a local Ollama server, for local models without one.
+pub const DEFAULT_LOCAL_BASE_URL: &str = "http://localhost:11434/v1";
+
+/// Context window assumed for newly added endpoint models.
+///
+/// Local servers are often configured with much less than the Claude
+/// models' 200k; the user can adjust it per model.
+pub const DEFAULT_ENDPOINT_CONTEXT_LENGTH: usize = 32_768;
+
+#[derive(Debug, Deserialize)]
+struct ModelInfo {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelsResponse {
+    data: Vec<ModelInfo>,
+}
+
+/// The API key for OpenAI-compatible endpoints: the stored one, or else the
+/// `OPENAI_API_KEY` environment variable. Empty when there is none, which
+/// local servers accept.
+pub fn openai_api_key(db: &Database) -> String {
+    db.get_api_key(OPENAI_API_KEY)
+        .ok()
+        .flatten()
+        .or_else(|| std::env::var(OPENAI_API_KEY).ok())
+        .unwrap_or_default()
+}
+
+/// The base URL of an endpoint model, falling back to the provider default.
+pub fn base_url(config: &ModelConfig) -> &str {
+    match config.base_url.as_deref() {
+        Some(url) if !url.trim().is_empty() => url.trim_end_matches('/'),
+        _ if config.model_type == ModelType::Local => DEFAULT_LOCAL_BASE_URL,
+        _ => DEFAULT_OPENAI_BASE_URL,
+    }
+}
+
+/// Create the model of an OpenAI-compatible or local endpoint.
+///
+/// **Note**: Temperature and max_tokens are passed via the agent builder,
+/// as for the Claude models.
+pub fn create_openai_model(config: &ModelConfig, api_key: &str) -> OpenAIChatModel {
+    OpenAIChatModel::new(config.effective_model_id(), api_key).with_base_url(base_url(config))
+}
+
+/// Fetch the models served at an OpenAI-compatible `base_url`.
+///
+/// Both Ollama and llama.cpp list their models at `/models`, like the
+/// OpenAI API. `api_key` may be empty.
+pub async fn fetch_openai_models(base_url: &str, api_key: &str) -> Result<Vec<String>, String> {
+    let url = format!("{}/models", base_url.trim_end_matches('/'));
+    let mut request = reqwest::Client::new()
+        .get(&url)
+        .timeout(std::time::Duration::from_secs(30));
+    if !api_key.is_empty() {
+        request = request.bearer_auth(api_key);
+    }
+
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        error!("Failed to fetch models from {}: {} - {}", url, status, text);
+        return Err(format!("Failed to fetch models: {}", status));
+    }
+
+    let models_response: ModelsResponse = response.json().await.map_err(|e| e.to_string())?;
+    let mut model_ids: Vec<String> = models_response.data.into_iter().map(|m| m.id).collect();
+    model_ids.sort();
+
+    info!("Fetched {} models from {}", model_ids.len(), url);
+    Ok(model_ids)
+}
+
+/// Register the models of an endpoint.
+///
+/// New models are assumed to handle tools but neither images nor thinking;
+/// models already in the registry keep the capabilities set for them.
+/// Returns the registry names.
+pub fn save_endpoint_models_to_db(
+    db: &Database,
+    model_type: ModelType,
+    base_url: &str,
+    models: &[String],
+) -> Result<Vec<String>, std::io::Error> {
+    let registry =
+        ModelRegistry::load_from_db(db).map_err(|e| std::io::Error::other(e.to_string()))?;
+
+    let mut names = Vec::with_capacity(models.len());
+    for model_id in models {
+        let name = model_type.model_name(model_id);
+        let config = match registry.get(&name) {
+            Some(existing) => ModelConfig {
+                base_url: Some(base_url.to_string()),
+                ..existing.clone()
+            },
+            None => ModelConfig {
+                context_length: DEFAULT_ENDPOINT_CONTEXT_LENGTH,
+                base_url: Some(base_url.to_string()),
+                ..ModelConfig::unregistered(&name)
+            },
+        };
+
+        ModelRegistry::add_model_to_db_with_source(db, &config, "endpoint")
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+        names.push(name);
+    }
+
+    info!("Saved {} {} models to database", models.len(), model_type);
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_at(temp_dir.path().join("test.db")).unwrap();
+        db.migrate().unwrap();
+        (temp_dir, db)
+    }
+
+    #[test]
+    fn test_base_url_defaults() {
+        let mut config = ModelConfig::unregistered("local-qwen3:8b");
+        assert_eq!(base_url(&config), DEFAULT_LOCAL_BASE_URL);
+
+        config.base_url = Some("http://localhost:8080/v1/".to_string());
+        assert_eq!(base_url(&config), "http://localhost:8080/v1");
+
+        let config = ModelConfig::unregistered("openai-gpt-4o");
+        assert_eq!(base_url(&config), DEFAULT_OPENAI_BASE_URL);
+    }
+
+    #[test]
+    fn test_save_endpoint_models_keeps_capabilities() {
+        let (_temp, db) = setup_test_db();
+        let models = vec!["qwen3:8b".to_string()];
+
+        let names =
+            save_endpoint_models_to_db(&db, ModelType::Local, "http://localhost:11434/v1", &models)
+                .unwrap();
+        assert_eq!(names, vec!["local-qwen3:8b".to_string()]);
+
+        let mut config = ModelRegistry::get_from_db(&db, "local-qwen3:8b")
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.context_length, DEFAULT_ENDPOINT_CONTEXT_LENGTH);
+        assert!(!config.supports_vision);
+        config.supports_vision = true;
+        ModelRegistry::add_model_to_db_with_source(&db, &config, "endpoint").unwrap();
+
+        // Loading the models again only moves the endpoint
+        save_endpoint_models_to_db(&db, ModelType::Local, "http://localhost:8080/v1", &models)
+            .unwrap();
+        let config = ModelRegistry::get_from_db(&db, "local-qwen3:8b")
+            .unwrap()
+            .unwrap();
+        assert!(config.supports_vision);
+        assert_eq!(config.base_url.as_deref(), Some("http://localhost:8080/v1"));
+    }
+}
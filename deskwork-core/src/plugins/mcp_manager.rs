@@ -1,17 +1,25 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::future::Future;
 use std::sync::Arc;
 use std::time::Duration;
 
+use serde::Deserialize;
 use serde_json::Value as JsonValue;
 use tokio::time::timeout;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
 
-use serdes_ai_mcp::{CallToolResult, McpClient, McpTool};
+use serdes_ai_mcp::{CallToolResult, HttpTransport, McpClient, McpTool, StdioTransport};
 
+use crate::external_tools::env_overrides;
+use crate::plugins::mcp_pool::McpPool;
+use crate::plugins::mcp_prompts::NamespacedMcpPrompt;
 use crate::plugins::types::McpServerEntry;
+use crate::skills::categories::resolve_entry_placeholders;
 
 const MCP_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
-const MCP_LIST_TOOLS_TIMEOUT: Duration = Duration::from_secs(10);
-const MCP_CALL_TIMEOUT: Duration = Duration::from_secs(30);
+const MCP_LIST_TIMEOUT: Duration = Duration::from_secs(10);
+pub(crate) const MCP_CALL_TIMEOUT: Duration = Duration::from_secs(30);
 
 #[derive(Debug, Clone)]
 pub struct NamespacedMcpTool {
@@ -22,11 +30,47 @@ pub struct NamespacedMcpTool {
     pub input_schema: JsonValue,
 }
 
+impl NamespacedMcpTool {
+    pub fn new(server_name: &str, tool: &McpTool) -> Self {
+        Self {
+            tool_key: mcp_tool_key(server_name, &tool.name),
+            server_name: server_name.to_string(),
+            tool_name: tool.name.clone(),
+            description: tool.description.clone(),
+            input_schema: tool.input_schema.clone(),
+        }
+    }
+}
+
+/// What a connected MCP server offers.
+#[derive(Debug, Clone, Default)]
+pub struct McpServerOfferings {
+    pub tools: Vec<NamespacedMcpTool>,
+    pub prompts: Vec<NamespacedMcpPrompt>,
+    /// Whether the server answers `resources/list`.
+    pub resources: bool,
+}
+
+/// A resource listed by an MCP server.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct McpResourceInfo {
+    pub uri: String,
+    #[serde(default)]
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(alias = "mimeType")]
+    pub mime_type: Option<String>,
+}
+
 #[derive(Default)]
 pub struct PluginMcpManager {
     clients: HashMap<String, Arc<McpClient>>,
     tools: HashMap<String, NamespacedMcpTool>,
     unavailable: HashMap<String, String>,
+    /// Servers that offer resources.
+    resource_servers: BTreeSet<String>,
+    /// Pool the clients were borrowed from, told about broken connections.
+    pool: Option<McpPool>,
 }
 
 impl std::fmt::Debug for PluginMcpManager {
@@ -35,6 +79,8 @@ impl std::fmt::Debug for PluginMcpManager {
             .field("client_count", &self.clients.len())
             .field("tool_count", &self.tools.len())
             .field("unavailable", &self.unavailable)
+            .field("resource_servers", &self.resource_servers)
+            .field("pooled", &self.pool.is_some())
             .finish()
     }
 }
@@ -48,44 +94,42 @@ impl PluginMcpManager {
         let mut manager = Self::new();
 
         for (server_name, entry) in configs {
-            let connect_result = connect_client(entry).await;
-            let client = match connect_result {
-                Ok(client) => Arc::new(client),
-                Err(err) => {
-                    manager.unavailable.insert(server_name.clone(), err);
-                    continue;
+            match connect_server(server_name, entry).await {
+                Ok((client, offerings)) => {
+                    manager.add_server(server_name, Arc::new(client), offerings);
                 }
-            };
+                Err(err) => manager.add_unavailable(server_name, err),
+            }
+        }
 
-            let tools = match timeout(MCP_LIST_TOOLS_TIMEOUT, client.list_tools()).await {
-                Ok(Ok(tools)) => tools,
-                Ok(Err(err)) => {
-                    manager.unavailable.insert(
-                        server_name.clone(),
-                        format!("Failed to list MCP tools: {err}"),
-                    );
-                    continue;
-                }
-                Err(_) => {
-                    manager.unavailable.insert(
-                        server_name.clone(),
-                        format!(
-                            "Timeout listing MCP tools after {}s",
-                            MCP_LIST_TOOLS_TIMEOUT.as_secs()
-                        ),
-                    );
-                    continue;
-                }
-            };
+        manager
+    }
 
-            for tool in tools {
-                manager.register_tool(server_name, &tool);
-            }
+    /// A manager over connections owned by `pool`.
+    pub(crate) fn pooled(pool: McpPool) -> Self {
+        Self {
+            pool: Some(pool),
+            ..Self::default()
+        }
+    }
 
-            manager.clients.insert(server_name.clone(), client);
+    pub(crate) fn add_server(
+        &mut self,
+        server_name: &str,
+        client: Arc<McpClient>,
+        offerings: McpServerOfferings,
+    ) {
+        for tool in offerings.tools {
+            self.tools.insert(tool.tool_key.clone(), tool);
         }
+        if offerings.resources {
+            self.resource_servers.insert(server_name.to_string());
+        }
+        self.clients.insert(server_name.to_string(), client);
+    }
 
-        manager
+    pub(crate) fn add_unavailable(&mut self, server_name: &str, reason: String) {
+        self.unavailable.insert(server_name.to_string(), reason);
     }
 
     pub fn list_all_tools(&self) -> HashMap<String, NamespacedMcpTool> {
@@ -96,11 +140,70 @@ impl PluginMcpManager {
         &self.unavailable
     }
 
+    /// Connected servers that offer resources, by name.
+    pub fn resource_servers(&self) -> Vec<String> {
+        self.resource_servers.iter().cloned().collect()
+    }
+
+    /// List the resources of a server.
+    pub async fn list_resources(
+        &self,
+        server_name: &str,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<McpResourceInfo>, String> {
+        let client = Arc::clone(self.resource_client(server_name)?);
+        let resources = run_request(
+            &format!("Listing MCP resources of `{server_name}`"),
+            async move { client.list_resources().await },
+            cancel,
+        )
+        .await?;
+        Ok(resources
+            .iter()
+            .filter_map(|resource| serde_json::to_value(resource).ok())
+            .filter_map(|resource| serde_json::from_value(resource).ok())
+            .collect())
+    }
+
+    /// Read a resource of a server. Returns the `resources/read` result.
+    pub async fn read_resource(
+        &self,
+        server_name: &str,
+        uri: &str,
+        cancel: &CancellationToken,
+    ) -> Result<JsonValue, String> {
+        let client = Arc::clone(self.resource_client(server_name)?);
+        let request_uri = uri.to_string();
+        let result = run_request(
+            &format!("Reading MCP resource `{uri}` of `{server_name}`"),
+            async move { client.read_resource(&request_uri).await },
+            cancel,
+        )
+        .await?;
+        serde_json::to_value(&result).map_err(|e| format!("Invalid MCP resource `{uri}`: {e}"))
+    }
+
+    fn resource_client(&self, server_name: &str) -> Result<&Arc<McpClient>, String> {
+        if !self.resource_servers.contains(server_name) {
+            if let Some(reason) = self.unavailable.get(server_name) {
+                return Err(format!("MCP server `{server_name}` unavailable: {reason}"));
+            }
+            return Err(format!(
+                "MCP server `{server_name}` has no resources. Servers with resources: {}",
+                self.resource_servers().join(", ")
+            ));
+        }
+        self.clients
+            .get(server_name)
+            .ok_or_else(|| format!("MCP server `{server_name}` is not connected"))
+    }
+
     pub async fn call_tool(
         &self,
         server_name: &str,
         tool_name: &str,
         args: JsonValue,
+        cancel: &CancellationToken,
     ) -> Result<CallToolResult, String> {
         let Some(client) = self.clients.get(server_name) else {
             if let Some(reason) = self.unavailable.get(server_name) {
@@ -109,30 +212,126 @@ impl PluginMcpManager {
             return Err(format!("MCP server `{server_name}` is not connected"));
         };
 
-        match timeout(MCP_CALL_TIMEOUT, client.call_tool(tool_name, args)).await {
-            Ok(Ok(result)) => Ok(result),
-            Ok(Err(err)) => Err(format!(
-                "MCP tool call failed for `{server_name}:{tool_name}`: {err}"
-            )),
-            Err(_) => Err(format!(
+        // The call runs in its own task so that cancelling doesn't drop it
+        // halfway through the protocol exchange. The server isn't notified;
+        // it finishes the call and the result is discarded.
+        let call = tokio::spawn({
+            let client = Arc::clone(client);
+            let tool_name = tool_name.to_string();
+            async move { timeout(MCP_CALL_TIMEOUT, client.call_tool(&tool_name, args)).await }
+        });
+        let result = tokio::select! {
+            result = call => result.map_err(|e| {
+                format!("MCP tool call failed for `{server_name}:{tool_name}`: {e}")
+            })?,
+            _ = cancel.cancelled() => {
+                return Err(format!("MCP tool call `{server_name}:{tool_name}` cancelled"));
+            }
+        };
+
+        let error = match result {
+            Ok(Ok(result)) => return Ok(result),
+            Ok(Err(err)) => format!("MCP tool call failed for `{server_name}:{tool_name}`: {err}"),
+            Err(_) => format!(
                 "Timeout calling MCP tool `{server_name}:{tool_name}` after {}s",
                 MCP_CALL_TIMEOUT.as_secs()
-            )),
+            ),
+        };
+        // The failure may be the tool's or the connection's; the pool finds
+        // out which and reconnects a dead server on a later run.
+        if let Some(pool) = &self.pool {
+            pool.check_connection(server_name, client);
         }
+        Err(error)
     }
+}
 
-    fn register_tool(&mut self, server_name: &str, tool: &McpTool) {
-        let key = mcp_tool_key(server_name, &tool.name);
-        self.tools.insert(
-            key.clone(),
-            NamespacedMcpTool {
-                tool_key: key,
-                server_name: server_name.to_string(),
-                tool_name: tool.name.clone(),
-                description: tool.description.clone(),
-                input_schema: tool.input_schema.clone(),
-            },
-        );
+/// Connect to a single MCP server and list its tools, to test its config.
+///
+/// `${VAR}` placeholders are resolved first. Returns the tool names.
+pub async fn test_mcp_server(entry: &McpServerEntry) -> Result<Vec<String>, String> {
+    let entry = resolve_entry_placeholders(entry)?;
+    let client = connect_client(&entry).await?;
+    let tools = list_tools(&client).await?;
+    Ok(tools.into_iter().map(|tool| tool.name).collect())
+}
+
+/// Connect to an MCP server and list its tools, prompts and resources.
+pub(crate) async fn connect_server(
+    server_name: &str,
+    entry: &McpServerEntry,
+) -> Result<(McpClient, McpServerOfferings), String> {
+    let client = connect_client(entry).await?;
+    let tools = list_tools(&client)
+        .await?
+        .iter()
+        .map(|tool| NamespacedMcpTool::new(server_name, tool))
+        .collect();
+
+    // Prompts and resources are optional; servers without them answer
+    // with an error
+    let prompts = match timeout(MCP_LIST_TIMEOUT, client.list_prompts()).await {
+        Ok(Ok(prompts)) => prompts
+            .iter()
+            .filter_map(|prompt| serde_json::to_value(prompt).ok())
+            .filter_map(|prompt| NamespacedMcpPrompt::from_listed(server_name, prompt))
+            .collect(),
+        Ok(Err(err)) => {
+            debug!(server = %server_name, "No MCP prompts: {err}");
+            Vec::new()
+        }
+        Err(_) => Vec::new(),
+    };
+    let resources = matches!(
+        timeout(MCP_LIST_TIMEOUT, client.list_resources()).await,
+        Ok(Ok(_))
+    );
+
+    let offerings = McpServerOfferings {
+        tools,
+        prompts,
+        resources,
+    };
+    Ok((client, offerings))
+}
+
+pub(crate) async fn list_tools(client: &McpClient) -> Result<Vec<McpTool>, String> {
+    match timeout(MCP_LIST_TIMEOUT, client.list_tools()).await {
+        Ok(Ok(tools)) => Ok(tools),
+        Ok(Err(err)) => Err(format!("Failed to list MCP tools: {err}")),
+        Err(_) => Err(format!(
+            "Timeout listing MCP tools after {}s",
+            MCP_LIST_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+/// Run an MCP request in its own task, with the call timeout.
+///
+/// Like tool calls, a request isn't dropped halfway through when `cancel`
+/// fires; its result is discarded instead.
+async fn run_request<T, E, F>(
+    what: &str,
+    request: F,
+    cancel: &CancellationToken,
+) -> Result<T, String>
+where
+    F: Future<Output = Result<T, E>> + Send + 'static,
+    T: Send + 'static,
+    E: std::fmt::Display + Send + 'static,
+{
+    let task = tokio::spawn(async move { timeout(MCP_CALL_TIMEOUT, request).await });
+    let result = tokio::select! {
+        result = task => result.map_err(|e| format!("{what} failed: {e}"))?,
+        _ = cancel.cancelled() => return Err(format!("{what} cancelled")),
+    };
+    match result {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(err)) => Err(format!("{what} failed: {err}")),
+        Err(_) => Err(format!(
+            "{what} timed out after {}s",
+            MCP_CALL_TIMEOUT.as_secs()
+        )),
     }
 }
 
@@ -163,7 +362,8 @@ async fn connect_client(entry: &McpServerEntry) -> Result<McpClient, String> {
                 .filter(|u| !u.trim().is_empty())
                 .ok_or_else(|| "Missing `url` for MCP connector type `http`".to_string())?;
 
-            let client = McpClient::http(url);
+            let headers = entry.headers.clone().unwrap_or_default();
+            let client = McpClient::new(HttpTransport::new(url).with_headers(headers));
             match timeout(MCP_CONNECT_TIMEOUT, client.initialize()).await {
                 Ok(Ok(_)) => Ok(client),
                 Ok(Err(err)) => Err(format!("Failed to initialize HTTP MCP connector: {err}")),
@@ -183,19 +383,21 @@ async fn connect_client(entry: &McpServerEntry) -> Result<McpClient, String> {
             let arg_storage = entry.args.clone().unwrap_or_default();
             let arg_refs = arg_storage.iter().map(String::as_str).collect::<Vec<_>>();
 
-            let client =
-                match timeout(MCP_CONNECT_TIMEOUT, McpClient::stdio(command, &arg_refs)).await {
-                    Ok(Ok(client)) => client,
-                    Ok(Err(err)) => {
-                        return Err(format!("Failed to spawn stdio MCP connector: {err}"));
-                    }
-                    Err(_) => {
-                        return Err(format!(
-                            "Timeout spawning stdio MCP connector after {}s",
-                            MCP_CONNECT_TIMEOUT.as_secs()
-                        ));
-                    }
-                };
+            let env = connector_env(entry);
+
+            let spawn = StdioTransport::spawn_with_env(command, &arg_refs, env);
+            let client = match timeout(MCP_CONNECT_TIMEOUT, spawn).await {
+                Ok(Ok(transport)) => McpClient::new(transport),
+                Ok(Err(err)) => {
+                    return Err(format!("Failed to spawn stdio MCP connector: {err}"));
+                }
+                Err(_) => {
+                    return Err(format!(
+                        "Timeout spawning stdio MCP connector after {}s",
+                        MCP_CONNECT_TIMEOUT.as_secs()
+                    ));
+                }
+            };
 
             match timeout(MCP_CONNECT_TIMEOUT, client.initialize()).await {
                 Ok(Ok(_)) => Ok(client),
@@ -211,3 +413,54 @@ async fn connect_client(entry: &McpServerEntry) -> Result<McpClient, String> {
         )),
     }
 }
+
+/// Environment variables set for a stdio connector on top of the inherited
+/// ones: the PATH with installed external tools, then the entry's own `env`,
+/// which wins on conflicts.
+fn connector_env(entry: &McpServerEntry) -> HashMap<String, String> {
+    let mut env = env_overrides().unwrap_or_else(|err| {
+        warn!("Failed to add external tools to MCP connector PATH: {err}");
+        HashMap::new()
+    });
+    if let Some(entry_env) = &entry.env {
+        env.extend(entry_env.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stdio_entry(env: Option<HashMap<String, String>>) -> McpServerEntry {
+        McpServerEntry {
+            r#type: "stdio".to_string(),
+            url: None,
+            command: Some("npx".to_string()),
+            args: None,
+            env,
+            headers: None,
+        }
+    }
+
+    #[test]
+    fn test_connector_env_includes_path() {
+        let env = connector_env(&stdio_entry(None));
+        assert!(env.contains_key("PATH"));
+    }
+
+    #[test]
+    fn test_connector_env_entry_wins() {
+        let entry = stdio_entry(Some(HashMap::from([
+            ("API_TOKEN".to_string(), "secret".to_string()),
+            ("PATH".to_string(), "/opt/connector/bin".to_string()),
+        ])));
+
+        let env = connector_env(&entry);
+        assert_eq!(env.get("API_TOKEN").map(String::as_str), Some("secret"));
+        assert_eq!(
+            env.get("PATH").map(String::as_str),
+            Some("/opt/connector/bin")
+        );
+    }
+}
@@ -0,0 +1,484 @@
+//! Long-lived MCP connections shared across agent runs.
+//!
+//! Connecting to an MCP server takes a while, spawning a stdio server most of
+//! all, and a respawned server loses whatever state it kept. The [`McpPool`]
+//! is owned by the app and keeps connections open between runs:
+//! - servers connect lazily, on the first run that uses them
+//! - later runs reuse the client until the server's config changes
+//! - a server that fails to connect, or whose connection breaks, is retried
+//!   on later runs with exponential backoff
+//! - servers that are no longer configured (e.g. their category was disabled)
+//!   are shut down by [`McpPool::sync`]
+//!
+//! Shutting a server down drops its client, which closes the transport and
+//! ends a stdio server once in-flight calls finish. [`McpPool::health`]
+//! reports the state of every server for the settings UI.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serdes_ai_mcp::McpClient;
+use tokio::time::timeout;
+use tracing::{debug, info, warn};
+
+use crate::plugins::mcp_manager::{
+    connect_server, list_tools, McpServerOfferings, PluginMcpManager, MCP_CALL_TIMEOUT,
+};
+use crate::plugins::mcp_prompts::{
+    render_prompt_messages, McpPromptInvocation, NamespacedMcpPrompt,
+};
+use crate::plugins::types::McpServerEntry;
+
+/// Wait before retrying a server after its first failure.
+pub const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Longest wait between retries; the backoff doubles up to this.
+pub const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Connection state of a pooled MCP server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum McpServerStatus {
+    /// Not used by a run yet, or shut down by [`McpPool::reconnect`].
+    NotConnected,
+    Connecting,
+    Connected,
+    /// The last connection attempt failed or the connection broke; see
+    /// [`McpServerHealth::last_error`].
+    Failed,
+}
+
+/// Snapshot of a pooled MCP server's health.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct McpServerHealth {
+    /// Key of the server in the config map, e.g. "user:github".
+    pub name: String,
+    pub status: McpServerStatus,
+    /// Tools listed by the server when it last connected.
+    pub tool_count: usize,
+    /// Prompts listed by the server when it last connected.
+    pub prompt_count: usize,
+    /// Most recent connection error, kept after a successful reconnect.
+    pub last_error: Option<String>,
+    /// Failures since the last successful connection.
+    pub failures: u32,
+    /// When the server connected, while it is connected.
+    pub connected_at: Option<DateTime<Utc>>,
+    /// No connection is attempted before this, after failures.
+    pub retry_at: Option<Instant>,
+}
+
+struct PooledServer {
+    entry: McpServerEntry,
+    client: Option<Arc<McpClient>>,
+    offerings: McpServerOfferings,
+    health: McpServerHealth,
+    /// Held while connecting, so concurrent runs share one attempt.
+    connecting: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl PooledServer {
+    fn new(name: &str, entry: McpServerEntry) -> Self {
+        Self {
+            entry,
+            client: None,
+            offerings: McpServerOfferings::default(),
+            health: McpServerHealth {
+                name: name.to_string(),
+                status: McpServerStatus::NotConnected,
+                tool_count: 0,
+                prompt_count: 0,
+                last_error: None,
+                failures: 0,
+                connected_at: None,
+                retry_at: None,
+            },
+            connecting: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    fn record_failure(&mut self, error: String) {
+        self.client = None;
+        self.offerings = McpServerOfferings::default();
+        self.health.status = McpServerStatus::Failed;
+        self.health.tool_count = 0;
+        self.health.prompt_count = 0;
+        self.health.failures += 1;
+        self.health.connected_at = None;
+        self.health.retry_at = Some(Instant::now() + backoff_delay(self.health.failures));
+        self.health.last_error = Some(error);
+    }
+
+    fn is_current(&self, client: &Arc<McpClient>) -> bool {
+        self.client
+            .as_ref()
+            .is_some_and(|current| Arc::ptr_eq(current, client))
+    }
+}
+
+/// Wait before the next attempt after `failures` consecutive failures.
+fn backoff_delay(failures: u32) -> Duration {
+    let doublings = failures.saturating_sub(1).min(16);
+    INITIAL_BACKOFF
+        .saturating_mul(1 << doublings)
+        .min(MAX_BACKOFF)
+}
+
+/// MCP connections kept open across agent runs.
+///
+/// Cloning shares the same connections. They are closed when the last clone
+/// is dropped.
+#[derive(Clone, Default)]
+pub struct McpPool {
+    servers: Arc<Mutex<HashMap<String, PooledServer>>>,
+}
+
+impl std::fmt::Debug for McpPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("McpPool")
+            .field("servers", &lock(&self.servers).len())
+            .finish()
+    }
+}
+
+impl McpPool {
+    /// Create a pool with no servers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make the pool serve exactly `configs`.
+    ///
+    /// Servers that are gone or whose config changed are shut down; new ones
+    /// are added without connecting.
+    pub fn sync(&self, configs: &HashMap<String, McpServerEntry>) {
+        let removed: Vec<PooledServer> = {
+            let mut servers = lock(&self.servers);
+            let stale: Vec<String> = servers
+                .iter()
+                .filter(|(name, server)| configs.get(*name) != Some(&server.entry))
+                .map(|(name, _)| name.clone())
+                .collect();
+            let removed = stale
+                .iter()
+                .filter_map(|name| servers.remove(name))
+                .collect();
+            for (name, entry) in configs {
+                servers
+                    .entry(name.clone())
+                    .or_insert_with(|| PooledServer::new(name, entry.clone()));
+            }
+            removed
+        };
+
+        // Dropped outside the lock; closing a transport may take a moment
+        for server in removed {
+            if server.client.is_some() {
+                info!(server = %server.health.name, "Shutting down MCP server");
+            }
+        }
+    }
+
+    /// Connections for a run over `configs`, connecting servers as needed.
+    ///
+    /// Servers that fail to connect, or are waiting out their backoff, are
+    /// listed as unavailable in the returned manager.
+    pub async fn manager(&self, configs: &HashMap<String, McpServerEntry>) -> PluginMcpManager {
+        self.sync(configs);
+
+        let names: Vec<&String> = configs.keys().collect();
+        let results =
+            futures::future::join_all(names.iter().map(|name| self.ensure_connected(name))).await;
+
+        let mut manager = PluginMcpManager::pooled(self.clone());
+        for (name, result) in names.into_iter().zip(results) {
+            match result {
+                Ok((client, offerings)) => manager.add_server(name, client, offerings),
+                Err(reason) => manager.add_unavailable(name, reason),
+            }
+        }
+        manager
+    }
+
+    /// Health of every configured server, by name.
+    pub fn health(&self) -> Vec<McpServerHealth> {
+        let mut health: Vec<McpServerHealth> = lock(&self.servers)
+            .values()
+            .map(|server| server.health.clone())
+            .collect();
+        health.sort_by(|a, b| a.name.cmp(&b.name));
+        health
+    }
+
+    /// Prompts of the connected servers, by slash command.
+    ///
+    /// Servers list their prompts when they connect, i.e. on the first run
+    /// that uses them.
+    pub fn prompts(&self) -> Vec<NamespacedMcpPrompt> {
+        let mut prompts: Vec<NamespacedMcpPrompt> = lock(&self.servers)
+            .values()
+            .flat_map(|server| server.offerings.prompts.iter().cloned())
+            .collect();
+        prompts.sort_by(|a, b| a.slash_command.cmp(&b.slash_command));
+        prompts
+    }
+
+    /// Fetch a prompt from its server and render its messages as the text
+    /// of a user message.
+    pub async fn get_prompt(&self, invocation: &McpPromptInvocation) -> Result<String, String> {
+        let McpPromptInvocation {
+            server_name,
+            prompt_name,
+            arguments,
+        } = invocation;
+        let (client, _) = self.ensure_connected(server_name).await?;
+
+        let result = match timeout(
+            MCP_CALL_TIMEOUT,
+            client.get_prompt(prompt_name, arguments.clone()),
+        )
+        .await
+        {
+            Ok(Ok(result)) => result,
+            Ok(Err(err)) => {
+                return Err(format!("Failed to get MCP prompt `{prompt_name}`: {err}"));
+            }
+            Err(_) => {
+                return Err(format!(
+                    "Timeout getting MCP prompt `{prompt_name}` after {}s",
+                    MCP_CALL_TIMEOUT.as_secs()
+                ));
+            }
+        };
+        let result = serde_json::to_value(&result)
+            .map_err(|e| format!("Invalid MCP prompt `{prompt_name}`: {e}"))?;
+
+        let text = render_prompt_messages(&result);
+        if text.trim().is_empty() {
+            return Err(format!("MCP prompt `{prompt_name}` has no text"));
+        }
+        Ok(text)
+    }
+
+    /// Drop a server's connection and backoff, so the next run connects it
+    /// afresh. No-op for unknown servers.
+    pub fn reconnect(&self, name: &str) {
+        let client = {
+            let mut servers = lock(&self.servers);
+            let Some(server) = servers.get_mut(name) else {
+                return;
+            };
+            server.offerings = McpServerOfferings::default();
+            server.health.status = McpServerStatus::NotConnected;
+            server.health.tool_count = 0;
+            server.health.prompt_count = 0;
+            server.health.failures = 0;
+            server.health.connected_at = None;
+            server.health.retry_at = None;
+            server.client.take()
+        };
+        drop(client);
+    }
+
+    /// Check whether `client` still works after a failed call, and mark its
+    /// server failed if not. Runs in the background.
+    pub(crate) fn check_connection(&self, name: &str, client: &Arc<McpClient>) {
+        let pool = self.clone();
+        let name = name.to_string();
+        let client = Arc::clone(client);
+        tokio::spawn(async move {
+            if let Err(err) = list_tools(&client).await {
+                warn!(server = %name, error = %err, "MCP connection lost");
+                pool.report_failure(&name, &client, format!("Connection lost: {err}"));
+            }
+        });
+    }
+
+    /// Mark a server failed if `client` is still its connection; a newer
+    /// connection is left alone.
+    fn report_failure(&self, name: &str, client: &Arc<McpClient>, error: String) {
+        let mut servers = lock(&self.servers);
+        if let Some(server) = servers.get_mut(name) {
+            if server.is_current(client) {
+                server.record_failure(error);
+            }
+        }
+    }
+
+    async fn ensure_connected(
+        &self,
+        name: &str,
+    ) -> Result<(Arc<McpClient>, McpServerOfferings), String> {
+        let connecting = {
+            let servers = lock(&self.servers);
+            let server = servers
+                .get(name)
+                .ok_or_else(|| format!("MCP server `{name}` is not configured"))?;
+            Arc::clone(&server.connecting)
+        };
+        let _connecting = connecting.lock().await;
+
+        let entry = {
+            let mut servers = lock(&self.servers);
+            let server = servers
+                .get_mut(name)
+                .ok_or_else(|| format!("MCP server `{name}` was removed"))?;
+            if let Some(client) = &server.client {
+                return Ok((Arc::clone(client), server.offerings.clone()));
+            }
+            if let Some(retry_at) = server.health.retry_at {
+                let wait = retry_at.saturating_duration_since(Instant::now());
+                if !wait.is_zero() {
+                    let error = server.health.last_error.as_deref().unwrap_or("failed");
+                    return Err(format!("{error} (retrying in {}s)", wait.as_secs().max(1)));
+                }
+            }
+            server.health.status = McpServerStatus::Connecting;
+            server.entry.clone()
+        };
+
+        debug!(server = %name, "Connecting MCP server");
+        let result = connect_server(name, &entry).await;
+
+        let mut servers = lock(&self.servers);
+        let server = servers
+            .get_mut(name)
+            .filter(|server| server.entry == entry)
+            .ok_or_else(|| format!("MCP server `{name}` was reconfigured while connecting"))?;
+        match result {
+            Ok((client, offerings)) => {
+                let client = Arc::new(client);
+                info!(
+                    server = %name,
+                    tools = offerings.tools.len(),
+                    prompts = offerings.prompts.len(),
+                    resources = offerings.resources,
+                    "Connected MCP server"
+                );
+
+                server.client = Some(Arc::clone(&client));
+                server.health.status = McpServerStatus::Connected;
+                server.health.tool_count = offerings.tools.len();
+                server.health.prompt_count = offerings.prompts.len();
+                server.health.failures = 0;
+                server.health.connected_at = Some(Utc::now());
+                server.health.retry_at = None;
+                server.offerings = offerings.clone();
+                Ok((client, offerings))
+            }
+            Err(err) => {
+                warn!(server = %name, error = %err, "Failed to connect MCP server");
+                server.record_failure(err.clone());
+                Err(err)
+            }
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stdio_entry(command: &str) -> McpServerEntry {
+        McpServerEntry {
+            r#type: "stdio".to_string(),
+            url: None,
+            command: Some(command.to_string()),
+            args: None,
+            env: None,
+            headers: None,
+        }
+    }
+
+    fn configs(entries: &[(&str, &str)]) -> HashMap<String, McpServerEntry> {
+        entries
+            .iter()
+            .map(|(name, command)| (name.to_string(), stdio_entry(command)))
+            .collect()
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        assert_eq!(backoff_delay(1), INITIAL_BACKOFF);
+        assert_eq!(backoff_delay(2), INITIAL_BACKOFF * 2);
+        assert_eq!(backoff_delay(3), INITIAL_BACKOFF * 4);
+        assert_eq!(backoff_delay(20), MAX_BACKOFF);
+        assert_eq!(backoff_delay(u32::MAX), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_sync_adds_and_removes_servers() {
+        let pool = McpPool::new();
+        pool.sync(&configs(&[
+            ("docs:search", "search-mcp"),
+            ("user:git", "git-mcp"),
+        ]));
+
+        let health = pool.health();
+        assert_eq!(health.len(), 2);
+        assert_eq!(health[0].name, "docs:search");
+        assert_eq!(health[0].status, McpServerStatus::NotConnected);
+
+        pool.sync(&configs(&[("user:git", "git-mcp")]));
+        let names: Vec<_> = pool.health().into_iter().map(|h| h.name).collect();
+        assert_eq!(names, vec!["user:git"]);
+    }
+
+    #[test]
+    fn test_sync_resets_changed_server() {
+        let pool = McpPool::new();
+        pool.sync(&configs(&[("user:git", "git-mcp")]));
+        lock(&pool.servers)
+            .get_mut("user:git")
+            .unwrap()
+            .record_failure("boom".to_string());
+
+        // Same config keeps the state
+        pool.sync(&configs(&[("user:git", "git-mcp")]));
+        assert_eq!(pool.health()[0].status, McpServerStatus::Failed);
+
+        // A changed config starts over
+        pool.sync(&configs(&[("user:git", "git-mcp-v2")]));
+        let health = &pool.health()[0];
+        assert_eq!(health.status, McpServerStatus::NotConnected);
+        assert_eq!(health.failures, 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_server_waits_for_backoff() {
+        let pool = McpPool::new();
+        let configs = configs(&[("user:missing", "deskwork-no-such-mcp-server")]);
+
+        let manager = pool.manager(&configs).await;
+        assert!(manager
+            .unavailable_connectors()
+            .contains_key("user:missing"));
+
+        let health = &pool.health()[0];
+        assert_eq!(health.status, McpServerStatus::Failed);
+        assert_eq!(health.failures, 1);
+        assert!(health.last_error.is_some());
+        assert!(health.retry_at.is_some());
+
+        // Within the backoff the server isn't retried
+        let manager = pool.manager(&configs).await;
+        let reason = &manager.unavailable_connectors()["user:missing"];
+        assert!(reason.contains("retrying in"), "{reason}");
+        assert_eq!(pool.health()[0].failures, 1);
+
+        pool.reconnect("user:missing");
+        let health = &pool.health()[0];
+        assert_eq!(health.status, McpServerStatus::NotConnected);
+        assert!(health.retry_at.is_none());
+        assert!(health.last_error.is_some());
+    }
+}
@@ -0,0 +1,278 @@
+//! MCP prompts as slash commands.
+//!
+//! Prompt templates listed by a connected MCP server are offered next to the
+//! category commands as `/{server}:{prompt}`, where `{server}` is the server's
+//! own name without its category or `user` namespace (e.g. `/github:review-pr`
+//! for `user:github`). Arguments are given as `name=value` pairs matching the
+//! prompt's declared arguments; text without any pairs goes to the first
+//! argument.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+/// A declared argument of an MCP prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct McpPromptArgument {
+    pub name: String,
+    pub description: Option<String>,
+    pub required: bool,
+}
+
+/// A prompt template offered by an MCP server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamespacedMcpPrompt {
+    /// Slash command that invokes the prompt, e.g. `/github:review-pr`.
+    pub slash_command: String,
+    /// Key of the server in the config map, e.g. "user:github".
+    pub server_name: String,
+    pub prompt_name: String,
+    pub description: Option<String>,
+    pub arguments: Vec<McpPromptArgument>,
+}
+
+/// An MCP prompt to fetch, with its argument values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct McpPromptInvocation {
+    pub server_name: String,
+    pub prompt_name: String,
+    pub arguments: HashMap<String, String>,
+}
+
+/// Prompt as listed by `prompts/list`.
+#[derive(Debug, Deserialize)]
+struct RawPrompt {
+    name: String,
+    description: Option<String>,
+    #[serde(default)]
+    arguments: Option<Vec<RawPromptArgument>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawPromptArgument {
+    name: String,
+    description: Option<String>,
+    required: Option<bool>,
+}
+
+impl NamespacedMcpPrompt {
+    /// Read a prompt listed by `server_name`; `None` if it isn't one.
+    pub(crate) fn from_listed(server_name: &str, prompt: JsonValue) -> Option<Self> {
+        let raw: RawPrompt = serde_json::from_value(prompt).ok()?;
+        let label = server_name.rsplit(':').next().unwrap_or(server_name);
+        Some(Self {
+            slash_command: format!("/{}:{}", command_segment(label), command_segment(&raw.name)),
+            server_name: server_name.to_string(),
+            prompt_name: raw.name,
+            description: raw.description,
+            arguments: raw
+                .arguments
+                .unwrap_or_default()
+                .into_iter()
+                .map(|arg| McpPromptArgument {
+                    name: arg.name,
+                    description: arg.description,
+                    required: arg.required.unwrap_or(false),
+                })
+                .collect(),
+        })
+    }
+
+    /// Usage hint for the arguments, e.g. `repo=<repo> [branch=<branch>]`.
+    pub fn argument_hint(&self) -> Option<String> {
+        if self.arguments.is_empty() {
+            return None;
+        }
+        let hint = self
+            .arguments
+            .iter()
+            .map(|arg| {
+                if arg.required {
+                    format!("{0}=<{0}>", arg.name)
+                } else {
+                    format!("[{0}=<{0}>]", arg.name)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        Some(hint)
+    }
+
+    /// Map the text after the slash command to the prompt's arguments.
+    ///
+    /// `name=value` pairs set declared arguments; without any, the whole text
+    /// goes to the first argument. Fails if a required argument is missing.
+    pub fn invocation(&self, raw_args: Option<&str>) -> Result<McpPromptInvocation, String> {
+        let raw_args = raw_args.map(str::trim).filter(|args| !args.is_empty());
+
+        let mut arguments: HashMap<String, String> = raw_args
+            .into_iter()
+            .flat_map(str::split_whitespace)
+            .filter_map(|item| item.split_once('='))
+            .filter(|(key, _)| self.arguments.iter().any(|arg| arg.name == *key))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        if arguments.is_empty() {
+            if let (Some(text), Some(first)) = (raw_args, self.arguments.first()) {
+                arguments.insert(first.name.clone(), text.to_string());
+            }
+        }
+
+        let missing: Vec<&str> = self
+            .arguments
+            .iter()
+            .filter(|arg| arg.required && !arguments.contains_key(&arg.name))
+            .map(|arg| arg.name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "{} needs {} (usage: {} {})",
+                self.slash_command,
+                missing.join(", "),
+                self.slash_command,
+                self.argument_hint().unwrap_or_default()
+            ));
+        }
+
+        Ok(McpPromptInvocation {
+            server_name: self.server_name.clone(),
+            prompt_name: self.prompt_name.clone(),
+            arguments,
+        })
+    }
+}
+
+/// Find the prompt invoked by `slash_command`.
+pub fn find_mcp_prompt<'a>(
+    prompts: &'a [NamespacedMcpPrompt],
+    slash_command: &str,
+) -> Option<&'a NamespacedMcpPrompt> {
+    prompts
+        .iter()
+        .find(|prompt| prompt.slash_command == slash_command)
+}
+
+/// Text of the messages of a `prompts/get` result, to send as the user's
+/// message. Messages from the assistant role are labelled as such.
+pub(crate) fn render_prompt_messages(result: &JsonValue) -> String {
+    let messages = result
+        .get("messages")
+        .and_then(JsonValue::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    messages
+        .iter()
+        .filter_map(|message| {
+            let text = content_text(message.get("content")?)?;
+            match message.get("role").and_then(JsonValue::as_str) {
+                Some("assistant") => Some(format!("Assistant: {text}")),
+                _ => Some(text),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Text of a prompt message's content: text, or the text of an embedded
+/// resource. Other content (images, audio) is left out.
+fn content_text(content: &JsonValue) -> Option<String> {
+    if let Some(parts) = content.as_array() {
+        let texts: Vec<String> = parts.iter().filter_map(content_text).collect();
+        return (!texts.is_empty()).then(|| texts.join("\n"));
+    }
+    content
+        .get("text")
+        .or_else(|| content.get("resource")?.get("text"))
+        .and_then(JsonValue::as_str)
+        .map(str::to_string)
+}
+
+/// Lowercase a name for use in a slash command, replacing anything but
+/// letters, digits, `-` and `_` with `-`.
+fn command_segment(name: &str) -> String {
+    name.chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' {
+                ch.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>()
+        .trim_matches('-')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn review_prompt() -> NamespacedMcpPrompt {
+        NamespacedMcpPrompt::from_listed(
+            "user:github",
+            json!({
+                "name": "Review PR",
+                "description": "Review a pull request",
+                "arguments": [
+                    {"name": "pr", "description": "PR number", "required": true},
+                    {"name": "focus"}
+                ]
+            }),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_prompt_slash_command() {
+        let prompt = review_prompt();
+        assert_eq!(prompt.slash_command, "/github:review-pr");
+        assert_eq!(prompt.prompt_name, "Review PR");
+        assert_eq!(
+            prompt.argument_hint().as_deref(),
+            Some("pr=<pr> [focus=<focus>]")
+        );
+    }
+
+    #[test]
+    fn test_invocation_maps_pairs_and_free_text() {
+        let prompt = review_prompt();
+
+        let invocation = prompt.invocation(Some("pr=42 focus=tests")).unwrap();
+        assert_eq!(invocation.arguments["pr"], "42");
+        assert_eq!(invocation.arguments["focus"], "tests");
+
+        let invocation = prompt.invocation(Some("42")).unwrap();
+        assert_eq!(invocation.arguments["pr"], "42");
+        assert!(!invocation.arguments.contains_key("focus"));
+    }
+
+    #[test]
+    fn test_invocation_requires_arguments() {
+        let err = review_prompt().invocation(Some("focus=tests")).unwrap_err();
+        assert!(err.contains("needs pr"), "{err}");
+        assert!(review_prompt().invocation(None).is_err());
+    }
+
+    #[test]
+    fn test_render_prompt_messages() {
+        let result = json!({
+            "messages": [
+                {"role": "user", "content": {"type": "text", "text": "Review PR 42"}},
+                {"role": "assistant", "content": {"type": "text", "text": "Which files?"}},
+                {"role": "user", "content": {
+                    "type": "resource",
+                    "resource": {"uri": "file:///diff", "text": "+ added"}
+                }},
+                {"role": "user", "content": {"type": "image", "data": "...", "mimeType": "image/png"}}
+            ]
+        });
+
+        assert_eq!(
+            render_prompt_messages(&result),
+            "Review PR 42\n\nAssistant: Which files?\n\n+ added"
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! Generic tools for the resources of MCP servers.
+//!
+//! Besides tools, MCP servers can offer resources such as files and records.
+//! Rather than one tool per resource, the agent gets `list_mcp_resources` and
+//! `read_mcp_resource`, registered when a connected server offers resources.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
+
+use crate::plugins::mcp_manager::{McpResourceInfo, PluginMcpManager};
+use crate::tools::common::parse_tool_args_lenient;
+
+/// One line per resource, e.g. `- file:///notes.md (notes.md, text/markdown): Team notes`.
+fn describe(resource: &McpResourceInfo) -> String {
+    let mut details = Vec::new();
+    if !resource.name.is_empty() && resource.name != resource.uri {
+        details.push(resource.name.as_str());
+    }
+    if let Some(mime_type) = &resource.mime_type {
+        details.push(mime_type.as_str());
+    }
+
+    let mut line = format!("- {}", resource.uri);
+    if !details.is_empty() {
+        line.push_str(&format!(" ({})", details.join(", ")));
+    }
+    if let Some(description) = resource.description.as_deref().filter(|d| !d.is_empty()) {
+        line.push_str(&format!(": {description}"));
+    }
+    line
+}
+
+/// Text of a `resources/read` result. Binary contents are described, not
+/// included.
+fn format_contents(result: &JsonValue) -> String {
+    let contents = result
+        .get("contents")
+        .and_then(JsonValue::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let parts: Vec<String> = contents
+        .iter()
+        .map(|content| {
+            let uri = content.get("uri").and_then(JsonValue::as_str).unwrap_or("");
+            if let Some(text) = content.get("text").and_then(JsonValue::as_str) {
+                return text.to_string();
+            }
+            let mime_type = content
+                .get("mimeType")
+                .or_else(|| content.get("mime_type"))
+                .and_then(JsonValue::as_str)
+                .unwrap_or("unknown type");
+            let size = content
+                .get("blob")
+                .and_then(JsonValue::as_str)
+                .map_or(0, |blob| blob.len() * 3 / 4);
+            format!("[Binary content {uri} ({mime_type}, about {size} bytes) not shown]")
+        })
+        .collect();
+
+    if parts.is_empty() {
+        "The resource is empty.".to_string()
+    } else {
+        parts.join("\n\n")
+    }
+}
+
+// =============================================================================
+// list_mcp_resources
+// =============================================================================
+
+/// Tool for listing the resources of the connected MCP servers.
+#[derive(Debug, Clone)]
+pub struct ListMcpResourcesTool {
+    manager: Arc<PluginMcpManager>,
+    cancel: CancellationToken,
+}
+
+impl ListMcpResourcesTool {
+    pub fn new(manager: Arc<PluginMcpManager>, cancel: CancellationToken) -> Self {
+        Self { manager, cancel }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListMcpResourcesArgs {
+    server: Option<String>,
+}
+
+#[async_trait]
+impl Tool for ListMcpResourcesTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "list_mcp_resources",
+            format!(
+                "List the resources (files, records, documents) offered by connected MCP \
+                 servers, with their URIs. Read one with read_mcp_resource. Servers with \
+                 resources: {}.",
+                self.manager.resource_servers().join(", ")
+            ),
+        )
+        .with_parameters(
+            SchemaBuilder::new()
+                .string(
+                    "server",
+                    "Only list the resources of this server. Defaults to all servers.",
+                    false,
+                )
+                .build()
+                .expect("schema build failed"),
+        )
+    }
+
+    async fn call(&self, _ctx: &RunContext, args: JsonValue) -> ToolResult {
+        debug!(tool = "list_mcp_resources", ?args, "Tool called");
+
+        let args: ListMcpResourcesArgs = parse_tool_args_lenient(
+            "list_mcp_resources",
+            args.clone(),
+            self.definition().parameters(),
+        )?;
+
+        let servers = match args.server.filter(|s| !s.trim().is_empty()) {
+            Some(server) => vec![server],
+            None => self.manager.resource_servers(),
+        };
+
+        let mut sections = Vec::new();
+        for server in servers {
+            let section = match self.manager.list_resources(&server, &self.cancel).await {
+                Ok(resources) if resources.is_empty() => format!("{server}: no resources"),
+                Ok(resources) => {
+                    let lines: Vec<String> = resources.iter().map(describe).collect();
+                    format!("{server}:\n{}", lines.join("\n"))
+                }
+                Err(error) => format!("{server}: {error}"),
+            };
+            sections.push(section);
+        }
+
+        if sections.is_empty() {
+            return Ok(ToolReturn::text(
+                "No MCP server offers resources.".to_string(),
+            ));
+        }
+        Ok(ToolReturn::text(sections.join("\n\n")))
+    }
+}
+
+// =============================================================================
+// read_mcp_resource
+// =============================================================================
+
+/// Tool for reading a resource of an MCP server.
+#[derive(Debug, Clone)]
+pub struct ReadMcpResourceTool {
+    manager: Arc<PluginMcpManager>,
+    cancel: CancellationToken,
+}
+
+impl ReadMcpResourceTool {
+    pub fn new(manager: Arc<PluginMcpManager>, cancel: CancellationToken) -> Self {
+        Self { manager, cancel }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ReadMcpResourceArgs {
+    server: String,
+    uri: String,
+}
+
+#[async_trait]
+impl Tool for ReadMcpResourceTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "read_mcp_resource",
+            "Read a resource of an MCP server by its URI, as listed by list_mcp_resources.",
+        )
+        .with_parameters(
+            SchemaBuilder::new()
+                .string("server", "The MCP server offering the resource.", true)
+                .string("uri", "The URI of the resource.", true)
+                .build()
+                .expect("schema build failed"),
+        )
+    }
+
+    async fn call(&self, _ctx: &RunContext, args: JsonValue) -> ToolResult {
+        debug!(tool = "read_mcp_resource", ?args, "Tool called");
+
+        let args: ReadMcpResourceArgs = parse_tool_args_lenient(
+            "read_mcp_resource",
+            args.clone(),
+            self.definition().parameters(),
+        )?;
+
+        match self
+            .manager
+            .read_resource(&args.server, &args.uri, &self.cancel)
+            .await
+        {
+            Ok(result) => Ok(ToolReturn::text(format_contents(&result))),
+            Err(error) => Ok(ToolReturn::error(error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_describe_resource() {
+        let resource = McpResourceInfo {
+            uri: "file:///notes.md".to_string(),
+            name: "notes.md".to_string(),
+            description: Some("Team notes".to_string()),
+            mime_type: Some("text/markdown".to_string()),
+        };
+        assert_eq!(
+            describe(&resource),
+            "- file:///notes.md (notes.md, text/markdown): Team notes"
+        );
+    }
+
+    #[test]
+    fn test_format_contents() {
+        let result = json!({
+            "contents": [
+                {"uri": "file:///a.txt", "mimeType": "text/plain", "text": "hello"},
+                {"uri": "file:///b.png", "mimeType": "image/png", "blob": "AAAA"}
+            ]
+        });
+        assert_eq!(
+            format_contents(&result),
+            "hello\n\n[Binary content file:///b.png (image/png, about 3 bytes) not shown]"
+        );
+        assert_eq!(
+            format_contents(&json!({"contents": []})),
+            "The resource is empty."
+        );
+    }
+}
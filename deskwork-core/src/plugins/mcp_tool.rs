@@ -3,6 +3,8 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use serde_json::Value as JsonValue;
 
+use tokio_util::sync::CancellationToken;
+
 use serdes_ai_mcp::ToolResultContent;
 use serdes_ai_tools::{RunContext, Tool, ToolDefinition, ToolError, ToolResult, ToolReturn};
 
@@ -14,10 +16,15 @@ pub struct PluginMcpTool {
     server_name: String,
     tool_name: String,
     definition: ToolDefinition,
+    cancel: CancellationToken,
 }
 
 impl PluginMcpTool {
-    pub fn new(manager: Arc<PluginMcpManager>, meta: NamespacedMcpTool) -> Self {
+    pub fn new(
+        manager: Arc<PluginMcpManager>,
+        meta: NamespacedMcpTool,
+        cancel: CancellationToken,
+    ) -> Self {
         let description = meta.description.clone().unwrap_or_else(|| {
             format!(
                 "MCP tool `{}` exposed by server `{}`",
@@ -33,6 +40,7 @@ impl PluginMcpTool {
             server_name: meta.server_name,
             tool_name: meta.tool_name,
             definition,
+            cancel,
         }
     }
 }
@@ -46,7 +54,7 @@ impl Tool for PluginMcpTool {
     async fn call(&self, _ctx: &RunContext, args: JsonValue) -> ToolResult {
         let result = self
             .manager
-            .call_tool(&self.server_name, &self.tool_name, args)
+            .call_tool(&self.server_name, &self.tool_name, args, &self.cancel)
             .await
             .map_err(ToolError::execution_failed)?;
 
@@ -6,8 +6,18 @@
 //! `skills::categories` system.
 
 pub mod mcp_manager;
+pub mod mcp_pool;
+pub mod mcp_prompts;
+pub mod mcp_resources;
 pub mod mcp_tool;
 pub mod types;
 
-pub use mcp_manager::{NamespacedMcpTool, PluginMcpManager};
+pub use mcp_manager::{
+    test_mcp_server, McpResourceInfo, McpServerOfferings, NamespacedMcpTool, PluginMcpManager,
+};
+pub use mcp_pool::{McpPool, McpServerHealth, McpServerStatus};
+pub use mcp_prompts::{
+    find_mcp_prompt, McpPromptArgument, McpPromptInvocation, NamespacedMcpPrompt,
+};
+pub use mcp_resources::{ListMcpResourcesTool, ReadMcpResourceTool};
 pub use mcp_tool::PluginMcpTool;
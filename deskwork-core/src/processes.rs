@@ -0,0 +1,626 @@
+//! Background processes started by the agent.
+//!
+//! Dev servers, file watchers and other commands that don't finish on their
+//! own run through the [`ProcessManager`] instead of the shell tool, which
+//! would kill them at its timeout. Each process leads its own process group,
+//! so stopping it also stops whatever it spawned. Output from stdout and
+//! stderr is kept in a ring buffer of the last [`MAX_BUFFERED_LINES`] lines.
+//!
+//! Processes belong to the conversation that started them, and the tools only
+//! see those of their own conversation (see [`ConversationProcesses`]).
+//! Whatever is still running is killed when the last handle to the manager is
+//! dropped, i.e. on app exit.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::time::{sleep, Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Output lines kept per process; older lines are dropped.
+pub const MAX_BUFFERED_LINES: usize = 5_000;
+
+/// Longer output lines are cut to this many characters.
+const MAX_LINE_CHARS: usize = 2_000;
+
+/// Time a process gets to exit after SIGTERM before it is killed.
+const STOP_GRACE_PERIOD: Duration = Duration::from_secs(3);
+
+/// Interval for checking whether a stopped process has exited.
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Errors from the process manager.
+#[derive(Debug, Error)]
+pub enum ProcessError {
+    #[error("Failed to start process: {0}")]
+    Spawn(#[from] std::io::Error),
+
+    #[error("Working directory does not exist: {0}")]
+    NoDirectory(String),
+
+    #[error("No background process with ID {0}")]
+    NotFound(u32),
+}
+
+/// Whether a background process is still running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessStatus {
+    Running,
+    /// Exited on its own with this code.
+    Exited(i32),
+    /// Terminated by this signal without being asked to stop.
+    Signaled(i32),
+    /// Stopped through [`ProcessManager::stop`] or a kill.
+    Stopped,
+}
+
+impl ProcessStatus {
+    pub fn is_running(self) -> bool {
+        self == Self::Running
+    }
+
+    fn from_exit(status: std::io::Result<ExitStatus>, stop_requested: bool) -> Self {
+        if stop_requested {
+            return Self::Stopped;
+        }
+        let Ok(status) = status else {
+            return Self::Exited(-1);
+        };
+        if let Some(code) = status.code() {
+            return Self::Exited(code);
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            if let Some(signal) = status.signal() {
+                return Self::Signaled(signal);
+            }
+        }
+        Self::Exited(-1)
+    }
+}
+
+impl std::fmt::Display for ProcessStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Running => write!(f, "running"),
+            Self::Exited(code) => write!(f, "exited with code {}", code),
+            Self::Signaled(signal) => write!(f, "terminated by signal {}", signal),
+            Self::Stopped => write!(f, "stopped"),
+        }
+    }
+}
+
+/// A snapshot of a background process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessInfo {
+    /// ID assigned by the manager, used by the tools.
+    pub id: u32,
+    /// OS process ID (also the process group ID on Unix).
+    pub pid: Option<u32>,
+    pub command: String,
+    pub working_directory: Option<PathBuf>,
+    pub conversation_id: String,
+    pub started_at: DateTime<Utc>,
+    pub status: ProcessStatus,
+    /// Output lines received so far, including any dropped from the buffer.
+    pub total_lines: u64,
+}
+
+/// Output lines read from a process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessOutput {
+    pub info: ProcessInfo,
+    pub lines: Vec<String>,
+    /// Index of the first returned line among all lines the process printed.
+    pub start: u64,
+    /// Requested lines that had already been dropped from the buffer.
+    pub dropped: u64,
+}
+
+impl ProcessOutput {
+    /// Index to pass as `since` to read only newer lines.
+    pub fn next(&self) -> u64 {
+        self.start + self.lines.len() as u64
+    }
+}
+
+/// A process and its buffered output, shared with its reader tasks.
+struct ManagedProcess {
+    info: ProcessInfo,
+    stop_requested: bool,
+    output: VecDeque<String>,
+}
+
+impl ManagedProcess {
+    /// Index of the oldest buffered line.
+    fn first_line(&self) -> u64 {
+        self.info.total_lines - self.output.len() as u64
+    }
+
+    fn push_line(&mut self, line: String) {
+        if self.output.len() == MAX_BUFFERED_LINES {
+            self.output.pop_front();
+        }
+        self.output.push_back(line);
+        self.info.total_lines += 1;
+    }
+}
+
+type SharedProcess = Arc<Mutex<ManagedProcess>>;
+
+#[derive(Default)]
+struct ManagerInner {
+    next_id: AtomicU32,
+    processes: Mutex<BTreeMap<u32, SharedProcess>>,
+}
+
+impl Drop for ManagerInner {
+    fn drop(&mut self) {
+        let processes = self.processes.get_mut().unwrap_or_else(|e| e.into_inner());
+        for process in processes.values() {
+            kill(process);
+        }
+    }
+}
+
+/// Starts, tracks and stops background processes.
+///
+/// Cloning shares the same processes. They are killed when the last clone is
+/// dropped.
+#[derive(Clone, Default)]
+pub struct ProcessManager {
+    inner: Arc<ManagerInner>,
+}
+
+impl std::fmt::Debug for ProcessManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProcessManager")
+            .field("processes", &lock(&self.inner.processes).len())
+            .finish()
+    }
+}
+
+impl PartialEq for ProcessManager {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for ProcessManager {}
+
+impl ProcessManager {
+    /// Create a manager with no processes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Processes of one conversation.
+    pub fn conversation(&self, conversation_id: impl Into<String>) -> ConversationProcesses {
+        ConversationProcesses {
+            manager: self.clone(),
+            conversation_id: conversation_id.into(),
+        }
+    }
+
+    /// Start `command` through the shell in the background.
+    ///
+    /// Must be called from within a Tokio runtime, which reads the output.
+    pub fn start(
+        &self,
+        conversation_id: &str,
+        command: &str,
+        working_directory: Option<&Path>,
+    ) -> Result<ProcessInfo, ProcessError> {
+        let shell = if cfg!(windows) { "cmd" } else { "sh" };
+        let shell_arg = if cfg!(windows) { "/C" } else { "-c" };
+
+        let mut cmd = Command::new(shell);
+        cmd.arg(shell_arg).arg(command);
+
+        if let Some(dir) = working_directory {
+            if !dir.exists() {
+                return Err(ProcessError::NoDirectory(dir.display().to_string()));
+            }
+            cmd.current_dir(dir);
+        }
+
+        cmd.stdin(Stdio::null());
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+        // Lead a new process group, so stopping also reaches child processes
+        #[cfg(unix)]
+        cmd.process_group(0);
+
+        // Set headless environment
+        cmd.env("TERM", "dumb");
+        cmd.env("NO_COLOR", "1");
+        cmd.env("CLICOLOR", "0");
+
+        let mut child = cmd.spawn()?;
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let info = ProcessInfo {
+            id,
+            pid: child.id(),
+            command: command.to_string(),
+            working_directory: working_directory.map(Path::to_path_buf),
+            conversation_id: conversation_id.to_string(),
+            started_at: Utc::now(),
+            status: ProcessStatus::Running,
+            total_lines: 0,
+        };
+        info!(id, pid = ?info.pid, command, "Started background process");
+
+        let process = Arc::new(Mutex::new(ManagedProcess {
+            info: info.clone(),
+            stop_requested: false,
+            output: VecDeque::new(),
+        }));
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(read_output(stdout, Arc::clone(&process)));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(read_output(stderr, Arc::clone(&process)));
+        }
+        let waiter = Arc::clone(&process);
+        tokio::spawn(async move {
+            let status = child.wait().await;
+            let mut process = lock(&waiter);
+            process.info.status = ProcessStatus::from_exit(status, process.stop_requested);
+            debug!(id = process.info.id, status = %process.info.status, "Background process ended");
+        });
+
+        lock(&self.inner.processes).insert(id, process);
+        Ok(info)
+    }
+
+    /// All processes, oldest first.
+    pub fn list(&self) -> Vec<ProcessInfo> {
+        lock(&self.inner.processes)
+            .values()
+            .map(|process| lock(process).info.clone())
+            .collect()
+    }
+
+    /// Number of processes still running.
+    pub fn running_count(&self) -> usize {
+        self.list()
+            .iter()
+            .filter(|info| info.status.is_running())
+            .count()
+    }
+
+    /// Read buffered output of process `id`.
+    ///
+    /// With `since`, returns up to `max_lines` lines starting at that line
+    /// index (see [`ProcessOutput::next`]); without, the last `max_lines`.
+    pub fn read(
+        &self,
+        id: u32,
+        since: Option<u64>,
+        max_lines: usize,
+    ) -> Result<ProcessOutput, ProcessError> {
+        let process = self.get(id)?;
+        let process = lock(&process);
+        let first = process.first_line();
+        let total = process.info.total_lines;
+        let (start, dropped) = match since {
+            Some(since) => (since.clamp(first, total), first.saturating_sub(since)),
+            None => (total.saturating_sub(max_lines as u64).max(first), 0),
+        };
+        let lines = process
+            .output
+            .iter()
+            .skip((start - first) as usize)
+            .take(max_lines)
+            .cloned()
+            .collect();
+        Ok(ProcessOutput {
+            info: process.info.clone(),
+            lines,
+            start,
+            dropped,
+        })
+    }
+
+    /// Stop process `id` and everything it spawned.
+    ///
+    /// Sends SIGTERM to the process group and SIGKILL if it is still running
+    /// after a grace period. Stopping a finished process does nothing.
+    pub async fn stop(&self, id: u32) -> Result<ProcessInfo, ProcessError> {
+        let process = self.get(id)?;
+        let pid = {
+            let mut process = lock(&process);
+            if !process.info.status.is_running() {
+                return Ok(process.info.clone());
+            }
+            process.stop_requested = true;
+            process.info.pid
+        };
+
+        if let Some(pid) = pid {
+            signal_process_group(pid, false);
+            if !wait_for_exit(&process, STOP_GRACE_PERIOD).await {
+                signal_process_group(pid, true);
+                wait_for_exit(&process, STOP_GRACE_PERIOD).await;
+            }
+        }
+        let info = lock(&process).info.clone();
+        info!(id, status = %info.status, "Stopped background process");
+        Ok(info)
+    }
+
+    /// Kill all running processes of a conversation right away.
+    pub fn kill_conversation(&self, conversation_id: &str) {
+        for process in lock(&self.inner.processes).values() {
+            if lock(process).info.conversation_id == conversation_id {
+                kill(process);
+            }
+        }
+    }
+
+    /// Forget a finished process and its output. Running processes are kept.
+    pub fn remove(&self, id: u32) -> bool {
+        let mut processes = lock(&self.inner.processes);
+        let finished = processes
+            .get(&id)
+            .is_some_and(|process| !lock(process).info.status.is_running());
+        finished && processes.remove(&id).is_some()
+    }
+
+    fn get(&self, id: u32) -> Result<SharedProcess, ProcessError> {
+        lock(&self.inner.processes)
+            .get(&id)
+            .cloned()
+            .ok_or(ProcessError::NotFound(id))
+    }
+}
+
+/// The background processes of one conversation.
+///
+/// Processes of other conversations are reported as not found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversationProcesses {
+    manager: ProcessManager,
+    conversation_id: String,
+}
+
+impl ConversationProcesses {
+    pub fn conversation_id(&self) -> &str {
+        &self.conversation_id
+    }
+
+    /// Start `command` in the background. See [`ProcessManager::start`].
+    pub fn start(
+        &self,
+        command: &str,
+        working_directory: Option<&Path>,
+    ) -> Result<ProcessInfo, ProcessError> {
+        self.manager
+            .start(&self.conversation_id, command, working_directory)
+    }
+
+    /// This conversation's processes, oldest first.
+    pub fn list(&self) -> Vec<ProcessInfo> {
+        self.manager
+            .list()
+            .into_iter()
+            .filter(|info| info.conversation_id == self.conversation_id)
+            .collect()
+    }
+
+    /// Read buffered output. See [`ProcessManager::read`].
+    pub fn read(
+        &self,
+        id: u32,
+        since: Option<u64>,
+        max_lines: usize,
+    ) -> Result<ProcessOutput, ProcessError> {
+        self.check_owner(id)?;
+        self.manager.read(id, since, max_lines)
+    }
+
+    /// Stop a process. See [`ProcessManager::stop`].
+    pub async fn stop(&self, id: u32) -> Result<ProcessInfo, ProcessError> {
+        self.check_owner(id)?;
+        self.manager.stop(id).await
+    }
+
+    fn check_owner(&self, id: u32) -> Result<(), ProcessError> {
+        let process = self.manager.get(id)?;
+        if lock(&process).info.conversation_id != self.conversation_id {
+            return Err(ProcessError::NotFound(id));
+        }
+        Ok(())
+    }
+}
+
+/// Send SIGTERM, or SIGKILL if `force`, to the process group led by `pid`.
+///
+/// On Windows, ends the process tree with `taskkill` instead.
+pub(crate) fn signal_process_group(pid: u32, force: bool) {
+    #[cfg(unix)]
+    let result = std::process::Command::new("kill")
+        .args([if force { "-KILL" } else { "-TERM" }, "--"])
+        .arg(format!("-{}", pid))
+        .stderr(Stdio::null())
+        .status();
+    #[cfg(windows)]
+    let result = {
+        let mut cmd = std::process::Command::new("taskkill");
+        cmd.args(["/T", "/PID", &pid.to_string()]);
+        if force {
+            cmd.arg("/F");
+        }
+        cmd.stdout(Stdio::null()).stderr(Stdio::null()).status()
+    };
+    if let Err(e) = result {
+        warn!(pid, error = %e, "Failed to signal process group");
+    }
+}
+
+/// Kill a process group immediately, if it is still running.
+fn kill(process: &SharedProcess) {
+    let mut process = lock(process);
+    if !process.info.status.is_running() {
+        return;
+    }
+    process.stop_requested = true;
+    if let Some(pid) = process.info.pid {
+        signal_process_group(pid, true);
+    }
+}
+
+/// Wait up to `timeout` for a process to exit. Returns whether it did.
+async fn wait_for_exit(process: &SharedProcess, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if !lock(process).info.status.is_running() {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        sleep(STOP_POLL_INTERVAL).await;
+    }
+}
+
+/// Append lines from a pipe to the process's buffer until it closes.
+async fn read_output(pipe: impl AsyncRead + Unpin, process: SharedProcess) {
+    let mut reader = BufReader::new(pipe);
+    let mut buffer = Vec::new();
+    loop {
+        buffer.clear();
+        match reader.read_until(b'\n', &mut buffer).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&buffer);
+                let line = line.trim_end_matches(['\n', '\r']);
+                let line = match line.char_indices().nth(MAX_LINE_CHARS) {
+                    Some((cut, _)) => format!("{}...", &line[..cut]),
+                    None => line.to_string(),
+                };
+                lock(&process).push_line(line);
+            }
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    async fn wait_until(manager: &ProcessManager, id: u32, done: impl Fn(&ProcessOutput) -> bool) {
+        for _ in 0..100 {
+            if done(&manager.read(id, None, 100).unwrap()) {
+                return;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+        panic!("process {} didn't reach the expected state", id);
+    }
+
+    #[tokio::test]
+    async fn test_process_output_and_exit() {
+        let manager = ProcessManager::new();
+        let info = manager
+            .start("chat", "echo one; echo two >&2; exit 3", None)
+            .unwrap();
+        assert_eq!(info.id, 1);
+        assert!(info.status.is_running());
+
+        wait_until(&manager, info.id, |out| !out.info.status.is_running()).await;
+        let output = manager.read(info.id, None, 100).unwrap();
+        assert_eq!(output.info.status, ProcessStatus::Exited(3));
+        let mut lines = output.lines.clone();
+        lines.sort();
+        assert_eq!(lines, vec!["one", "two"]);
+
+        // Reading from the end returns nothing new
+        let newer = manager.read(info.id, Some(output.next()), 100).unwrap();
+        assert!(newer.lines.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ring_buffer_drops_oldest_lines() {
+        let manager = ProcessManager::new();
+        let count = MAX_BUFFERED_LINES + 10;
+        let info = manager
+            .start("chat", &format!("seq 1 {}", count), None)
+            .unwrap();
+
+        wait_until(&manager, info.id, |out| {
+            out.info.total_lines == count as u64 && !out.info.status.is_running()
+        })
+        .await;
+        let output = manager.read(info.id, Some(0), 5).unwrap();
+        assert_eq!(output.dropped, 10);
+        assert_eq!(output.start, 10);
+        assert_eq!(output.lines, vec!["11", "12", "13", "14", "15"]);
+
+        let tail = manager.read(info.id, None, 2).unwrap();
+        assert_eq!(tail.lines, vec![(count - 1).to_string(), count.to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_stop_kills_process_group() {
+        let manager = ProcessManager::new();
+        let dir = tempfile::TempDir::new().unwrap();
+        // The child keeps writing to a file until it is killed
+        let info = manager
+            .start(
+                "chat",
+                "(while true; do date >> out.txt; sleep 0.05; done) & wait",
+                Some(dir.path()),
+            )
+            .unwrap();
+        sleep(Duration::from_millis(200)).await;
+
+        let stopped = manager.stop(info.id).await.unwrap();
+        assert_eq!(stopped.status, ProcessStatus::Stopped);
+
+        sleep(Duration::from_millis(100)).await;
+        let size = std::fs::metadata(dir.path().join("out.txt")).unwrap().len();
+        sleep(Duration::from_millis(200)).await;
+        assert_eq!(
+            std::fs::metadata(dir.path().join("out.txt")).unwrap().len(),
+            size
+        );
+    }
+
+    #[tokio::test]
+    async fn test_conversation_scoping() {
+        let manager = ProcessManager::new();
+        let mine = manager.conversation("a");
+        let theirs = manager.conversation("b");
+
+        let info = mine.start("sleep 5", None).unwrap();
+        assert_eq!(mine.list().len(), 1);
+        assert!(theirs.list().is_empty());
+        assert!(matches!(
+            theirs.read(info.id, None, 10),
+            Err(ProcessError::NotFound(_))
+        ));
+
+        manager.kill_conversation("a");
+        wait_until(&manager, info.id, |out| !out.info.status.is_running()).await;
+        assert_eq!(mine.list()[0].status, ProcessStatus::Stopped);
+        assert!(manager.remove(info.id));
+        assert!(mine.list().is_empty());
+    }
+}
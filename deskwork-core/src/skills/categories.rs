@@ -364,6 +364,11 @@ pub fn resolve_entry_placeholders(entry: &McpServerEntry) -> Result<McpServerEnt
             .map(|(k, v)| (k.clone(), resolve_env_placeholders(v, &mut missing)))
             .collect::<HashMap<_, _>>()
     });
+    let headers = entry.headers.as_ref().map(|map| {
+        map.iter()
+            .map(|(k, v)| (k.clone(), resolve_env_placeholders(v, &mut missing)))
+            .collect::<HashMap<_, _>>()
+    });
 
     if !missing.is_empty() {
         missing.sort();
@@ -380,6 +385,7 @@ pub fn resolve_entry_placeholders(entry: &McpServerEntry) -> Result<McpServerEnt
         command,
         args,
         env,
+        headers,
     };
 
     validate_mcp_entry(&resolved)?;
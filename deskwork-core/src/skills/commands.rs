@@ -1,11 +1,14 @@
 //! Slash command parsing, suggestions, and prompt building.
 //!
 //! Commands use the format `/{category_id}:{command_name}` (e.g., `/legal:review-contract`).
+//! Prompts of connected MCP servers are suggested alongside as
+//! `/{server}:{prompt}` (see [`crate::plugins::mcp_prompts`]).
 
 use std::collections::HashMap;
 
-use crate::skills::types::CommandFile; // reuse existing type for now
+use crate::plugins::mcp_prompts::NamespacedMcpPrompt;
 use crate::skills::categories::SkillCategoryRegistry;
+use crate::skills::types::CommandFile; // reuse existing type for now
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParsedSlashCommand {
@@ -18,9 +21,11 @@ pub struct ParsedSlashCommand {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SlashCommandSuggestion {
     pub slash_command: String,
+    /// Category ID, or the server name for MCP prompts.
     pub category_id: String,
     pub command_name: String,
     pub description: String,
+    pub argument_hint: Option<String>,
 }
 
 /// Parse a slash command from user input.
@@ -54,27 +59,54 @@ pub fn parse_slash_command(input: &str) -> Option<ParsedSlashCommand> {
     })
 }
 
-/// Get command suggestions matching a prefix, using the category registry.
-pub fn command_suggestions(registry: &SkillCategoryRegistry, prefix: &str) -> Vec<String> {
-    command_suggestions_rich(registry, prefix)
+/// Parse `key=value` pairs from the arguments of a slash command input.
+///
+/// Used to fill `{{key}}` placeholders in command templates.
+pub fn parse_inline_command_inputs(input: &str) -> HashMap<String, String> {
+    let Some((_, args)) = input.trim().split_once(' ') else {
+        return HashMap::new();
+    };
+
+    args.split_whitespace()
+        .filter_map(|item| item.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .filter(|(k, _)| !k.is_empty())
+        .collect()
+}
+
+/// Get command suggestions matching a prefix, using the category registry
+/// and the prompts of connected MCP servers.
+pub fn command_suggestions(
+    registry: &SkillCategoryRegistry,
+    mcp_prompts: &[NamespacedMcpPrompt],
+    prefix: &str,
+) -> Vec<String> {
+    command_suggestions_rich(registry, mcp_prompts, prefix)
         .into_iter()
         .map(|s| s.slash_command)
         .collect()
 }
 
-/// Get rich command suggestions with descriptions, using the category registry.
+/// Get rich command suggestions with descriptions, using the category
+/// registry and the prompts of connected MCP servers.
 pub fn command_suggestions_rich(
     registry: &SkillCategoryRegistry,
+    mcp_prompts: &[NamespacedMcpPrompt],
     prefix: &str,
 ) -> Vec<SlashCommandSuggestion> {
     let normalized = prefix.trim().to_ascii_lowercase();
 
-    let mut ranked = registry
+    let category_commands = registry
         .all_slash_commands()
         .into_iter()
-        .filter_map(|command| {
-            let slash = command.slash_command.to_ascii_lowercase();
-            let desc = command.description.to_ascii_lowercase();
+        .map(command_suggestion);
+    let prompt_commands = mcp_prompts.iter().map(prompt_suggestion);
+
+    let mut ranked = category_commands
+        .chain(prompt_commands)
+        .filter_map(|suggestion| {
+            let slash = suggestion.slash_command.to_ascii_lowercase();
+            let desc = suggestion.description.to_ascii_lowercase();
 
             let score = if slash.starts_with(&normalized) {
                 0
@@ -86,15 +118,7 @@ pub fn command_suggestions_rich(
                 return None;
             };
 
-            Some((
-                score,
-                SlashCommandSuggestion {
-                    slash_command: command.slash_command.clone(),
-                    category_id: command.plugin_id.clone(), // plugin_id holds category_id
-                    command_name: command.name.clone(),
-                    description: command.description.clone(),
-                },
-            ))
+            Some((score, suggestion))
         })
         .collect::<Vec<_>>();
 
@@ -111,6 +135,26 @@ pub fn command_suggestions_rich(
         .collect()
 }
 
+fn command_suggestion(command: &CommandFile) -> SlashCommandSuggestion {
+    SlashCommandSuggestion {
+        slash_command: command.slash_command.clone(),
+        category_id: command.plugin_id.clone(), // plugin_id holds category_id
+        command_name: command.name.clone(),
+        description: command.description.clone(),
+        argument_hint: command.argument_hint.clone(),
+    }
+}
+
+fn prompt_suggestion(prompt: &NamespacedMcpPrompt) -> SlashCommandSuggestion {
+    SlashCommandSuggestion {
+        slash_command: prompt.slash_command.clone(),
+        category_id: prompt.server_name.clone(),
+        command_name: prompt.prompt_name.clone(),
+        description: prompt.description.clone().unwrap_or_default(),
+        argument_hint: prompt.argument_hint(),
+    }
+}
+
 /// Build the full prompt that gets sent to the model when a slash command is invoked.
 pub fn build_command_prompt(
     command: &CommandFile,
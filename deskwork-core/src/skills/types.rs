@@ -16,6 +16,8 @@ pub struct McpServerEntry {
     pub command: Option<String>,
     pub args: Option<Vec<String>>,
     pub env: Option<HashMap<String, String>>,
+    /// Headers sent with every request to an `http` connector, e.g. `Authorization`.
+    pub headers: Option<HashMap<String, String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
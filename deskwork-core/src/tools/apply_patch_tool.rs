@@ -0,0 +1,639 @@
+//! ApplyPatch tool implementation.
+//!
+//! Provides a serdesAI-compatible tool for applying a multi-file unified diff
+//! atomically: every hunk is validated against disk before anything is
+//! written, and a failure while writing rolls back the files already changed.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use tracing::{debug, warn};
+
+use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
+
+use super::diff::{self, DiffLine, UnifiedDiff};
+use super::file_ops::TextFile;
+use super::workspace::Workspace;
+
+/// Tool for applying multi-file patches.
+#[derive(Debug, Clone, Default)]
+pub struct ApplyPatchTool {
+    workspace: Workspace,
+}
+
+impl ApplyPatchTool {
+    /// Create the tool for a workspace.
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ApplyPatchArgs {
+    patch: String,
+}
+
+/// What a patch does to one file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+}
+
+/// Per-file line counts for the summary.
+#[derive(Debug)]
+struct FileSummary {
+    kind: ChangeKind,
+    /// Path as written in the patch (`old -> new` for renames).
+    display: String,
+    added: usize,
+    removed: usize,
+}
+
+/// A file as it was before the patch.
+#[derive(Debug)]
+struct Original {
+    bytes: Vec<u8>,
+    /// Decoded text, with the encoding and line endings to write back.
+    text: TextFile,
+    permissions: fs::Permissions,
+}
+
+/// The validated result of a patch: final content of every touched path
+/// (`None` = deleted) and what each was before.
+#[derive(Debug, Default)]
+struct PatchPlan {
+    /// Touched paths in the order they were first seen.
+    order: Vec<PathBuf>,
+    after: HashMap<PathBuf, Option<String>>,
+    before: HashMap<PathBuf, Option<Original>>,
+    /// Rename targets and the path they were renamed from.
+    renamed_from: HashMap<PathBuf, PathBuf>,
+}
+
+impl PatchPlan {
+    /// Current content of `path`: planned by an earlier file diff, or on disk.
+    fn content(&mut self, path: &Path) -> Result<Option<String>, String> {
+        if let Some(content) = self.after.get(path) {
+            return Ok(content.clone());
+        }
+        let original = read_existing(path)?;
+        let content = original
+            .as_ref()
+            .map(|original| original.text.content.clone());
+        self.before.insert(path.to_path_buf(), original);
+        self.order.push(path.to_path_buf());
+        Ok(content)
+    }
+
+    fn set(&mut self, path: &Path, content: Option<String>) {
+        self.after.insert(path.to_path_buf(), content);
+    }
+
+    /// Move `content` from `from` to `to`, which keeps the file's format.
+    fn rename(&mut self, from: &Path, to: &Path, content: String) {
+        self.set(from, None);
+        self.set(to, Some(content));
+        let origin = self.renamed_from.get(from).cloned();
+        self.renamed_from.insert(
+            to.to_path_buf(),
+            origin.unwrap_or_else(|| from.to_path_buf()),
+        );
+    }
+
+    /// The file whose encoding, line endings and permissions `path` keeps.
+    fn original_of(&self, path: &Path) -> Option<&Original> {
+        let source = self.renamed_from.get(path).map_or(path, PathBuf::as_path);
+        self.before.get(source).and_then(Option::as_ref)
+    }
+}
+
+#[async_trait]
+impl Tool for ApplyPatchTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "apply_patch",
+            "Apply a multi-file unified diff (git diff format) atomically. Supports \
+             modifications, new files, deletions and renames. Every hunk is checked against \
+             the files first; if any does not apply, no file is changed.",
+        )
+        .with_parameters(
+            SchemaBuilder::new()
+                .string(
+                    "patch",
+                    "The patch in unified diff format. Each file starts with `diff --git a/... b/...` \
+                     or a `--- a/...` / `+++ b/...` header. Use /dev/null as the old path for new \
+                     files and as the new path for deletions.",
+                    true,
+                )
+                .build()
+                .expect("schema build failed"),
+        )
+    }
+
+    async fn call(&self, ctx: &RunContext, args: JsonValue) -> ToolResult {
+        debug!(tool = "apply_patch", ?args, "Tool called");
+
+        let args: ApplyPatchArgs = super::common::parse_tool_args_lenient(
+            "apply_patch",
+            args.clone(),
+            self.definition().parameters(),
+        )?;
+
+        let diffs = match diff::parse_patch(&args.patch) {
+            Ok(diffs) => diffs,
+            Err(e) => return Ok(ToolReturn::error(format!("Failed to parse patch: {}", e))),
+        };
+
+        // Validate everything before touching the disk
+        let mut plan = PatchPlan::default();
+        let mut summaries = Vec::new();
+        for file_diff in &diffs {
+            match self.plan_file(&mut plan, file_diff) {
+                Ok(summary) => summaries.push(summary),
+                Err(e) => {
+                    return Ok(ToolReturn::error(format!(
+                        "Patch not applied, no files were changed. {}",
+                        e
+                    )))
+                }
+            }
+        }
+
+        for path in &plan.order {
+            self.workspace.checkpoint(&ctx.run_id, path);
+        }
+        if let Err(e) = commit(&plan) {
+            return Ok(ToolReturn::error(format!(
+                "Failed to write patch, no files were changed: {}",
+                e
+            )));
+        }
+
+        let mut output = format!(
+            "Applied patch to {} file{}:",
+            summaries.len(),
+            if summaries.len() == 1 { "" } else { "s" }
+        );
+        for summary in &summaries {
+            let marker = match summary.kind {
+                ChangeKind::Added => "A",
+                ChangeKind::Modified => "M",
+                ChangeKind::Deleted => "D",
+                ChangeKind::Renamed => "R",
+            };
+            output.push_str(&format!(
+                "\n  {} {} (+{} -{})",
+                marker, summary.display, summary.added, summary.removed
+            ));
+        }
+        Ok(ToolReturn::text(output))
+    }
+}
+
+impl ApplyPatchTool {
+    /// Apply one file diff to the plan.
+    fn plan_file(
+        &self,
+        plan: &mut PatchPlan,
+        file_diff: &UnifiedDiff,
+    ) -> Result<FileSummary, String> {
+        let (added, mut removed) = count_lines(file_diff);
+        let resolve = |path: &str| {
+            self.workspace
+                .check_write(path)
+                .map_err(|e| format!("{}: {}", path, e))
+        };
+        let failed = |path: &str, e: diff::DiffError| format!("{}: {}", path, e);
+
+        if file_diff.is_new_file {
+            let path_str = file_diff.new_path.as_deref().unwrap_or_default();
+            let path = resolve(path_str)?;
+            if plan.content(&path)?.is_some() {
+                return Err(format!("{}: file already exists", path_str));
+            }
+            let content = file_diff.apply("").map_err(|e| failed(path_str, e))?;
+            plan.set(&path, Some(content));
+            return Ok(FileSummary {
+                kind: ChangeKind::Added,
+                display: path_str.to_string(),
+                added,
+                removed,
+            });
+        }
+
+        let old_str = file_diff.old_path.as_deref().unwrap_or_default();
+        let old_path = resolve(old_str)?;
+        let original = plan
+            .content(&old_path)?
+            .ok_or_else(|| format!("{}: file not found", old_str))?;
+
+        if file_diff.is_delete {
+            file_diff.apply(&original).map_err(|e| failed(old_str, e))?;
+            if file_diff.hunks.is_empty() {
+                removed = original.lines().count();
+            }
+            plan.set(&old_path, None);
+            return Ok(FileSummary {
+                kind: ChangeKind::Deleted,
+                display: old_str.to_string(),
+                added,
+                removed,
+            });
+        }
+
+        let content = if file_diff.hunks.is_empty() {
+            original
+        } else {
+            file_diff.apply(&original).map_err(|e| failed(old_str, e))?
+        };
+
+        let new_str = file_diff.new_path.as_deref().unwrap_or(old_str);
+        let new_path = resolve(new_str)?;
+        if new_path == old_path {
+            plan.set(&old_path, Some(content));
+            return Ok(FileSummary {
+                kind: ChangeKind::Modified,
+                display: old_str.to_string(),
+                added,
+                removed,
+            });
+        }
+
+        if plan.content(&new_path)?.is_some() {
+            return Err(format!(
+                "cannot rename {} to {}: target already exists",
+                old_str, new_str
+            ));
+        }
+        plan.rename(&old_path, &new_path, content);
+        Ok(FileSummary {
+            kind: ChangeKind::Renamed,
+            display: format!("{} -> {}", old_str, new_str),
+            added,
+            removed,
+        })
+    }
+}
+
+/// Lines added and removed by a file diff's hunks.
+fn count_lines(file_diff: &UnifiedDiff) -> (usize, usize) {
+    file_diff
+        .hunks
+        .iter()
+        .flat_map(|hunk| &hunk.lines)
+        .fold((0, 0), |(added, removed), line| match line {
+            DiffLine::Add(_) => (added + 1, removed),
+            DiffLine::Remove(_) => (added, removed + 1),
+            DiffLine::Context(_) => (added, removed),
+        })
+}
+
+/// Read a file that may not exist.
+fn read_existing(path: &Path) -> Result<Option<Original>, String> {
+    let failed = |e: std::io::Error| format!("{}: {}", path.display(), e);
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(failed(e)),
+    };
+    let permissions = fs::metadata(path).map_err(failed)?.permissions();
+    let text = TextFile::decode(&bytes).ok_or_else(|| {
+        format!(
+            "{}: not a text file in a supported encoding",
+            path.display()
+        )
+    })?;
+    Ok(Some(Original {
+        bytes,
+        text,
+        permissions,
+    }))
+}
+
+/// Write the plan to disk, restoring every touched file on failure.
+///
+/// New content is first written next to its target and then renamed into
+/// place, so an interrupted write never leaves a truncated file. Patched files
+/// keep their encoding, line endings and permissions (renamed files those of
+/// the file they were renamed from).
+fn commit(plan: &PatchPlan) -> Result<(), String> {
+    let mut staged: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let mut created_dirs: Vec<PathBuf> = Vec::new();
+    let cleanup = |staged: &[(PathBuf, PathBuf)], created_dirs: &[PathBuf]| {
+        for (tmp, _) in staged {
+            let _ = fs::remove_file(tmp);
+        }
+        remove_created_dirs(created_dirs);
+    };
+
+    for path in &plan.order {
+        let Some(Some(content)) = plan.after.get(path) else {
+            continue;
+        };
+        let tmp = staging_path(path);
+        let original = plan.original_of(path);
+        let bytes = match original {
+            Some(original) => original.text.encode(content),
+            None => content.as_bytes().to_vec(),
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), |parent| create_dirs(parent, &mut created_dirs))
+            .and_then(|()| fs::write(&tmp, bytes))
+            .and_then(|()| match original {
+                Some(original) => fs::set_permissions(&tmp, original.permissions.clone()),
+                None => Ok(()),
+            });
+        if let Err(e) = result {
+            let _ = fs::remove_file(&tmp);
+            cleanup(&staged, &created_dirs);
+            return Err(format!("{}: {}", path.display(), e));
+        }
+        staged.push((tmp, path.clone()));
+    }
+
+    let mut done = Vec::new();
+    for path in &plan.order {
+        let result = match plan.after.get(path) {
+            Some(Some(_)) => {
+                let (tmp, _) = staged
+                    .iter()
+                    .find(|(_, target)| target == path)
+                    .expect("staged above");
+                fs::rename(tmp, path)
+            }
+            Some(None) if path.exists() => fs::remove_file(path),
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            rollback(plan, &done);
+            cleanup(&staged, &created_dirs);
+            return Err(format!("{}: {}", path.display(), e));
+        }
+        done.push(path.clone());
+    }
+    Ok(())
+}
+
+/// Create `dir` and its missing parents, recording the ones created.
+fn create_dirs(dir: &Path, created: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let missing: Vec<&Path> = dir.ancestors().take_while(|dir| !dir.exists()).collect();
+    fs::create_dir_all(dir)?;
+    // Outermost first, so they can be removed in reverse
+    created.extend(missing.into_iter().rev().map(Path::to_path_buf));
+    Ok(())
+}
+
+/// Remove directories created for the patch, innermost first.
+fn remove_created_dirs(created: &[PathBuf]) {
+    for dir in created.iter().rev() {
+        let _ = fs::remove_dir(dir);
+    }
+}
+
+/// Restore `paths` to what they were before the patch.
+fn rollback(plan: &PatchPlan, paths: &[PathBuf]) {
+    for path in paths {
+        let result = match plan.before.get(path) {
+            Some(Some(original)) => fs::write(path, &original.bytes)
+                .and_then(|()| fs::set_permissions(path, original.permissions.clone())),
+            _ => fs::remove_file(path),
+        };
+        if let Err(e) = result {
+            warn!(path = %path.display(), error = %e, "Failed to roll back patched file");
+        }
+    }
+}
+
+fn staging_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.deskwork-patch", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn apply(dir: &TempDir, patch: &str) -> ToolReturn {
+        let tool = ApplyPatchTool::new(Workspace::new(dir.path()));
+        let ctx = RunContext::minimal("test");
+        tool.call(&ctx, serde_json::json!({ "patch": patch }))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch_multi_file() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("lib.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+        fs::write(dir.path().join("old.txt"), "keep\n").unwrap();
+        fs::write(dir.path().join("gone.txt"), "one\ntwo\n").unwrap();
+
+        let patch = "\
+diff --git a/lib.rs b/lib.rs
+--- a/lib.rs
++++ b/lib.rs
+@@ -1,2 +1,3 @@
+ fn a() {}
+-fn b() {}
++fn b() -> u8 { 1 }
++fn c() {}
+diff --git a/old.txt b/sub/new.txt
+similarity index 100%
+rename from old.txt
+rename to sub/new.txt
+diff --git a/gone.txt b/gone.txt
+deleted file mode 100644
+diff --git a/added.txt b/added.txt
+new file mode 100644
+--- /dev/null
++++ b/added.txt
+@@ -0,0 +1 @@
++hello
+";
+        let ret = apply(&dir, patch).await;
+        assert!(!ret.is_error(), "{:?}", ret.as_text());
+        let text = ret.as_text().unwrap();
+        assert!(text.contains("Applied patch to 4 files"));
+        assert!(text.contains("M lib.rs (+2 -1)"));
+        assert!(text.contains("R old.txt -> sub/new.txt (+0 -0)"));
+        assert!(text.contains("D gone.txt (+0 -2)"));
+        assert!(text.contains("A added.txt (+1 -0)"));
+
+        assert_eq!(
+            fs::read_to_string(dir.path().join("lib.rs")).unwrap(),
+            "fn a() {}\nfn b() -> u8 { 1 }\nfn c() {}\n"
+        );
+        assert!(!dir.path().join("old.txt").exists());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("sub/new.txt")).unwrap(),
+            "keep\n"
+        );
+        assert!(!dir.path().join("gone.txt").exists());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("added.txt")).unwrap(),
+            "hello\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch_is_all_or_nothing() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "a\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "b\n").unwrap();
+
+        // The first file applies, the second doesn't match
+        let patch = "\
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1 @@
+-a
++A
+--- a/b.txt
++++ b/b.txt
+@@ -1 +1 @@
+-x
++X
+";
+        let ret = apply(&dir, patch).await;
+        assert!(ret.is_error());
+        let text = ret.as_text().unwrap();
+        assert!(text.contains("no files were changed"));
+        assert!(text.contains("b.txt"));
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "a\n");
+        assert_eq!(fs::read_to_string(dir.path().join("b.txt")).unwrap(), "b\n");
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch_rejects_existing_new_file() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("exists.txt"), "old\n").unwrap();
+
+        let patch = "--- /dev/null\n+++ b/exists.txt\n@@ -0,0 +1 @@\n+new\n";
+        let ret = apply(&dir, patch).await;
+        assert!(ret.is_error());
+        assert!(ret.as_text().unwrap().contains("already exists"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("exists.txt")).unwrap(),
+            "old\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_apply_patch_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let script = dir.path().join("build.sh");
+        fs::write(&script, "#!/bin/sh\nmake\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let patch = "\
+--- a/build.sh
++++ b/build.sh
+@@ -1,2 +1,2 @@
+ #!/bin/sh
+-make
++make all
+";
+        let ret = apply(&dir, patch).await;
+        assert!(!ret.is_error(), "{:?}", ret.as_text());
+        assert_eq!(
+            fs::read_to_string(&script).unwrap(),
+            "#!/bin/sh\nmake all\n"
+        );
+        let mode = fs::metadata(&script).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch_keeps_line_endings_and_encoding() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("notes.txt");
+        fs::write(&file, b"\xEF\xBB\xBFone\r\ntwo\r\n").unwrap();
+
+        let patch = "--- a/notes.txt\n+++ b/notes.txt\n@@ -1,2 +1,2 @@\n one\n-two\n+2\n";
+        let ret = apply(&dir, patch).await;
+        assert!(!ret.is_error(), "{:?}", ret.as_text());
+        assert_eq!(fs::read(&file).unwrap(), b"\xEF\xBB\xBFone\r\n2\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch_failed_write_removes_created_dirs() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "a\n").unwrap();
+        // The staging file of a.txt can't be written
+        fs::create_dir(dir.path().join(".a.txt.deskwork-patch")).unwrap();
+
+        let patch = "\
+--- /dev/null
++++ b/sub/dir/new.txt
+@@ -0,0 +1 @@
++new
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1 @@
+-a
++A
+";
+        let ret = apply(&dir, patch).await;
+        assert!(ret.is_error());
+        assert!(ret.as_text().unwrap().contains("no files were changed"));
+        assert!(!dir.path().join("sub").exists());
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "a\n");
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch_rejects_mismatched_delete() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("gone.txt"), "one\nchanged\n").unwrap();
+
+        let patch = "--- a/gone.txt\n+++ /dev/null\n@@ -1,2 +0,0 @@\n-one\n-two\n";
+        let ret = apply(&dir, patch).await;
+        assert!(ret.is_error());
+        assert!(ret.as_text().unwrap().contains("gone.txt"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("gone.txt")).unwrap(),
+            "one\nchanged\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch_chains_diffs_of_same_file() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "1\n2\n").unwrap();
+
+        let patch = "\
+--- a/a.txt
++++ b/a.txt
+@@ -1,2 +1,2 @@
+-1
++one
+ 2
+--- a/a.txt
++++ b/a.txt
+@@ -1,2 +1,2 @@
+ one
+-2
++two
+";
+        let ret = apply(&dir, patch).await;
+        assert!(!ret.is_error(), "{:?}", ret.as_text());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "one\ntwo\n"
+        );
+    }
+}
@@ -251,6 +251,38 @@ pub fn parse_tool_args_lenient<T: DeserializeOwned>(
     })
 }
 
+// =============================================================================
+// List Arguments
+// =============================================================================
+
+/// Split a comma-separated list, keeping commas inside `{...}` glob alternatives.
+pub fn split_list(list: Option<&str>) -> Vec<String> {
+    let Some(list) = list else {
+        return Vec::new();
+    };
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    for c in list.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                items.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    items.push(current);
+    items
+        .into_iter()
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
 // =============================================================================
 // Tests
 // =============================================================================
@@ -382,4 +414,17 @@ mod tests {
         assert_eq!(args["recursive"], serde_json::json!(true));
         assert_eq!(args["max_depth"], serde_json::json!(5));
     }
+
+    // -------------------------------------------------------------------------
+    // split_list Tests
+    // -------------------------------------------------------------------------
+
+    #[test]
+    fn test_split_list_keeps_brace_alternatives() {
+        assert_eq!(
+            split_list(Some("*.rs, src/**/*.{ts,tsx},")),
+            vec!["*.rs", "src/**/*.{ts,tsx}"]
+        );
+        assert!(split_list(None).is_empty());
+    }
 }
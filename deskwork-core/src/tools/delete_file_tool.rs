@@ -9,9 +9,20 @@ use tracing::debug;
 
 use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
 
+use super::workspace::Workspace;
+
 /// Tool for deleting files.
 #[derive(Debug, Clone, Default)]
-pub struct DeleteFileTool;
+pub struct DeleteFileTool {
+    workspace: Workspace,
+}
+
+impl DeleteFileTool {
+    /// Create the tool for a workspace.
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
 
 #[derive(Debug, Deserialize)]
 struct DeleteFileArgs {
@@ -33,7 +44,7 @@ impl Tool for DeleteFileTool {
         )
     }
 
-    async fn call(&self, _ctx: &RunContext, args: JsonValue) -> ToolResult {
+    async fn call(&self, ctx: &RunContext, args: JsonValue) -> ToolResult {
         debug!(tool = "delete_file", ?args, "Tool called");
 
         let args: DeleteFileArgs = super::common::parse_tool_args_lenient(
@@ -42,7 +53,10 @@ impl Tool for DeleteFileTool {
             self.definition().parameters(),
         )?;
 
-        let path = std::path::Path::new(&args.file_path);
+        let path = match self.workspace.check_write(&args.file_path) {
+            Ok(path) => path,
+            Err(e) => return Ok(ToolReturn::error(e.to_string())),
+        };
 
         if !path.exists() {
             return Ok(ToolReturn::error(format!(
@@ -58,7 +72,8 @@ impl Tool for DeleteFileTool {
             )));
         }
 
-        match std::fs::remove_file(path) {
+        self.workspace.checkpoint(&ctx.run_id, &path);
+        match std::fs::remove_file(&path) {
             Ok(()) => Ok(ToolReturn::text(format!(
                 "Successfully deleted: {}",
                 args.file_path
@@ -81,7 +96,7 @@ mod tests {
         fs::write(&file_path, "content").unwrap();
         assert!(file_path.exists());
 
-        let tool = DeleteFileTool;
+        let tool = DeleteFileTool::new(Workspace::new(dir.path()));
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -97,16 +112,38 @@ mod tests {
         assert!(!file_path.exists());
     }
 
+    #[tokio::test]
+    async fn test_delete_file_can_be_reverted() {
+        let dir = TempDir::new().unwrap();
+        let store_dir = TempDir::new().unwrap();
+        let file_path = dir.path().join("important.txt");
+        fs::write(&file_path, "content").unwrap();
+
+        let checkpoints =
+            crate::checkpoints::CheckpointStore::new(store_dir.path()).conversation("conv");
+        let workspace =
+            Workspace::new(dir.path()).with_checkpointer(checkpoints.checkpointer(None));
+        let tool = DeleteFileTool::new(workspace);
+        let ctx = RunContext::minimal("test").with_run_id("run-1");
+
+        let result = tool
+            .call(&ctx, serde_json::json!({ "file_path": "important.txt" }))
+            .await;
+        assert!(!result.unwrap().is_error());
+        assert!(!file_path.exists());
+
+        checkpoints.revert_run("run-1").unwrap();
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "content");
+    }
+
     #[tokio::test]
     async fn test_delete_file_not_found() {
-        let tool = DeleteFileTool;
+        let dir = TempDir::new().unwrap();
+        let tool = DeleteFileTool::new(Workspace::new(dir.path()));
         let ctx = RunContext::minimal("test");
 
         let result = tool
-            .call(
-                &ctx,
-                serde_json::json!({ "file_path": "/nonexistent/path/file.txt" }),
-            )
+            .call(&ctx, serde_json::json!({ "file_path": "missing/file.txt" }))
             .await;
 
         assert!(result.is_ok());
@@ -121,7 +158,7 @@ mod tests {
         let subdir = dir.path().join("subdir");
         fs::create_dir(&subdir).unwrap();
 
-        let tool = DeleteFileTool;
+        let tool = DeleteFileTool::new(Workspace::new(dir.path()));
         let ctx = RunContext::minimal("test");
 
         let result = tool
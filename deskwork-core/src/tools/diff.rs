@@ -10,10 +10,19 @@
 //! +added line
 //!  more context
 //! ```
+//!
+//! Hunk bodies are read by the line counts in their headers. Hunks are
+//! verified against the file before they are applied: context and removed
+//! lines must match, searching nearby lines (and ignoring a little context if
+//! needed) when the header's line numbers are off.
 
 use std::str::Lines;
 use thiserror::Error;
 
+/// Maximum number of context lines ignored at each end of a hunk that
+/// doesn't match exactly (GNU patch's default fuzz factor).
+pub const MAX_FUZZ: usize = 2;
+
 /// Diff parsing/application errors.
 #[derive(Debug, Error)]
 pub enum DiffError {
@@ -23,13 +32,28 @@ pub enum DiffError {
     #[error("Hunk header parse error: {0}")]
     HunkParseError(String),
 
-    #[error("Context mismatch at line {line}: expected '{expected}', got '{actual}'")]
+    #[error(
+        "Context mismatch in hunk {hunk} at line {line}: expected '{expected}', got '{actual}'"
+    )]
     ContextMismatch {
+        /// 1-based index of the hunk in the diff.
+        hunk: usize,
+        /// 1-based line in the original file.
         line: usize,
         expected: String,
         actual: String,
     },
 
+    #[error(
+        "Hunk {hunk} is ambiguous: it matches at lines {first} and {second}; \
+         include more context lines"
+    )]
+    AmbiguousHunk {
+        hunk: usize,
+        first: usize,
+        second: usize,
+    },
+
     #[error("Patch application failed: {0}")]
     PatchFailed(String),
 }
@@ -42,6 +66,55 @@ pub struct Hunk {
     pub new_start: usize,
     pub new_count: usize,
     pub lines: Vec<DiffLine>,
+    /// The last old line has no trailing newline (`\ No newline at end of file`).
+    pub old_missing_newline: bool,
+    /// The last new line has no trailing newline.
+    pub new_missing_newline: bool,
+}
+
+impl Hunk {
+    /// Lines the hunk expects in the original (context and removed lines).
+    fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                DiffLine::Context(s) | DiffLine::Remove(s) => Some(s.as_str()),
+                DiffLine::Add(_) => None,
+            })
+            .collect()
+    }
+
+    /// Lines the hunk leaves in the result (context and added lines).
+    fn new_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                DiffLine::Context(s) | DiffLine::Add(s) => Some(s.as_str()),
+                DiffLine::Remove(_) => None,
+            })
+            .collect()
+    }
+
+    /// Number of context lines at the start and at the end of the hunk.
+    fn context_bounds(&self) -> (usize, usize) {
+        let is_context = |line: &&DiffLine| matches!(line, DiffLine::Context(_));
+        let leading = self.lines.iter().take_while(is_context).count();
+        if leading == self.lines.len() {
+            return (leading, 0);
+        }
+        let trailing = self.lines.iter().rev().take_while(is_context).count();
+        (leading, trailing)
+    }
+
+    /// 0-based line where the hunk expects its old lines to start.
+    fn expected_index(&self) -> usize {
+        if self.old_count == 0 {
+            // "-N,0" inserts after line N
+            self.old_start
+        } else {
+            self.old_start.saturating_sub(1)
+        }
+    }
 }
 
 /// A line in a diff hunk.
@@ -96,8 +169,15 @@ impl UnifiedDiff {
             if line.starts_with("@@") {
                 let hunk = parse_hunk(&mut lines)?;
                 hunks.push(hunk);
-            } else {
+            } else if line.trim().is_empty() {
                 lines.next();
+            } else {
+                // Left over when a hunk header counts fewer lines than its body
+                return Err(DiffError::HunkParseError(format!(
+                    "unexpected line after hunk {}: '{}'",
+                    hunks.len(),
+                    line
+                )));
             }
         }
 
@@ -111,37 +191,189 @@ impl UnifiedDiff {
     }
 
     /// Apply this diff to the given content.
+    ///
+    /// Each hunk's context and removed lines must match the original. A hunk
+    /// is searched for near its header position (adjusted by the offset of
+    /// the previous hunk), preferring the closest match. If there is no exact
+    /// match, up to [`MAX_FUZZ`] context lines at either end of the hunk are
+    /// ignored, as GNU patch does. Hunks that don't match, or match equally
+    /// well in two places, are rejected.
+    ///
+    /// The result keeps the original's line endings (`\r\n` if its first line
+    /// ends that way).
     pub fn apply(&self, original: &str) -> Result<String, DiffError> {
         if self.is_new_file {
-            let mut result = String::new();
+            let mut lines = Vec::new();
             for hunk in &self.hunks {
-                for line in &hunk.lines {
-                    if let DiffLine::Add(content) = line {
-                        result.push_str(content);
-                        result.push('\n');
-                    }
-                }
-            }
-            if result.ends_with('\n') && !result.ends_with("\n\n") {
-                result.pop();
+                lines.extend(hunk.lines.iter().filter_map(|line| match line {
+                    DiffLine::Add(content) => Some(content.as_str()),
+                    _ => None,
+                }));
             }
-            return Ok(result);
+            let missing_newline = self.hunks.last().is_some_and(|h| h.new_missing_newline);
+            return Ok(join_lines(&lines, !missing_newline, "\n"));
         }
 
         if self.is_delete {
+            // The removed lines of all hunks, in order, must be the whole file
+            let file: Vec<&str> = original.lines().collect();
+            let mut start = 0;
+            for (index, hunk) in self.hunks.iter().enumerate() {
+                let old_lines = hunk.old_lines();
+                let end = start + old_lines.len();
+                if file.get(start..end) != Some(&old_lines[..])
+                    || (index + 1 == self.hunks.len() && end != file.len())
+                {
+                    return Err(mismatch(&file, &old_lines, index + 1, start));
+                }
+                start = end;
+            }
             return Ok(String::new());
         }
 
-        let mut lines: Vec<String> = original.lines().map(|s| s.to_string()).collect();
+        let file: Vec<&str> = original.lines().collect();
+        // Kept unless a hunk at the end of the file says otherwise
+        let mut trailing_newline = original.is_empty() || original.ends_with('\n');
+        let mut result: Vec<&str> = Vec::with_capacity(file.len());
+        // Next unconsumed line of `file`
+        let mut cursor = 0;
+        // How far the previous hunk was from its header position
+        let mut offset: isize = 0;
+
+        for (index, hunk) in self.hunks.iter().enumerate() {
+            let Placement { pos, lead, trail } =
+                locate_hunk(&file, hunk, index + 1, cursor, offset)?;
+            offset = pos as isize - lead as isize - hunk.expected_index() as isize;
+
+            let old_len = hunk.old_lines().len() - lead - trail;
+            let new_lines = hunk.new_lines();
+            result.extend_from_slice(&file[cursor..pos]);
+            result.extend_from_slice(&new_lines[lead..new_lines.len() - trail]);
+            cursor = pos + old_len;
+
+            // A hunk that ends the file may add or remove the final newline
+            if cursor + trail == file.len() {
+                if hunk.new_missing_newline {
+                    trailing_newline = false;
+                } else if hunk.old_missing_newline {
+                    trailing_newline = true;
+                }
+            }
+        }
+        result.extend_from_slice(&file[cursor..]);
+
+        Ok(join_lines(&result, trailing_newline, line_ending(original)))
+    }
+}
 
-        for hunk in self.hunks.iter().rev() {
-            lines = apply_hunk_to_lines(lines, hunk)?;
+/// Where a hunk applies: its old lines, minus the first `lead` and last
+/// `trail` context lines ignored through fuzz, start at line `pos`.
+struct Placement {
+    pos: usize,
+    lead: usize,
+    trail: usize,
+}
+
+/// Find where `hunk` applies in `file`, at or after line `cursor`.
+fn locate_hunk(
+    file: &[&str],
+    hunk: &Hunk,
+    hunk_number: usize,
+    cursor: usize,
+    offset: isize,
+) -> Result<Placement, DiffError> {
+    let old_lines = hunk.old_lines();
+    let expected = hunk.expected_index() as isize + offset;
+    let (leading, trailing) = hunk.context_bounds();
+
+    let mut tried = Vec::new();
+    for fuzz in 0..=MAX_FUZZ {
+        let (lead, trail) = (fuzz.min(leading), fuzz.min(trailing));
+        if tried.contains(&(lead, trail)) {
+            continue;
         }
+        tried.push((lead, trail));
+        let pattern = &old_lines[lead..old_lines.len() - trail];
 
-        Ok(lines.join("\n"))
+        // A hunk without old lines (pure insertion) can't be verified
+        if pattern.is_empty() {
+            let pos = (expected.max(0) as usize).clamp(cursor, file.len());
+            return Ok(Placement { pos, lead, trail });
+        }
+
+        let Some(last) = file.len().checked_sub(pattern.len()) else {
+            continue;
+        };
+        let mut best: Option<(usize, usize)> = None;
+        let mut tie = None;
+        for pos in (cursor..=last).filter(|&pos| file[pos..pos + pattern.len()] == *pattern) {
+            // Distance of where the full hunk would start from where it should
+            let distance = (pos as isize - lead as isize).abs_diff(expected);
+            match best {
+                Some((_, best_distance)) if distance > best_distance => {}
+                Some((best_pos, best_distance)) if distance == best_distance => {
+                    tie = Some((best_pos - lead, pos - lead));
+                }
+                _ => {
+                    best = Some((pos, distance));
+                    tie = None;
+                }
+            }
+        }
+
+        if let Some((first, second)) = tie {
+            return Err(DiffError::AmbiguousHunk {
+                hunk: hunk_number,
+                first: first + 1,
+                second: second + 1,
+            });
+        }
+        if let Some((pos, _)) = best {
+            return Ok(Placement { pos, lead, trail });
+        }
+    }
+
+    let start = (expected.max(0) as usize).max(cursor);
+    Err(mismatch(file, &old_lines, hunk_number, start))
+}
+
+/// Describe the first line where `file` differs from `old_lines` at `start`.
+fn mismatch(file: &[&str], old_lines: &[&str], hunk_number: usize, start: usize) -> DiffError {
+    let index = (0..old_lines.len())
+        .find(|&i| file.get(start + i) != Some(&old_lines[i]))
+        .unwrap_or(old_lines.len());
+    DiffError::ContextMismatch {
+        hunk: hunk_number,
+        line: start + index + 1,
+        expected: old_lines
+            .get(index)
+            .copied()
+            .unwrap_or("<end of file>")
+            .to_string(),
+        actual: file
+            .get(start + index)
+            .copied()
+            .unwrap_or("<end of file>")
+            .to_string(),
+    }
+}
+
+/// The line terminator of `text`, judged by its first line.
+fn line_ending(text: &str) -> &'static str {
+    match text.find('\n') {
+        Some(i) if text[..i].ends_with('\r') => "\r\n",
+        _ => "\n",
     }
 }
 
+fn join_lines(lines: &[&str], trailing_newline: bool, newline: &str) -> String {
+    let mut content = lines.join(newline);
+    if trailing_newline && !lines.is_empty() {
+        content.push_str(newline);
+    }
+    content
+}
+
 fn parse_file_path(line: &str, prefix: &str) -> String {
     let path = line.strip_prefix(prefix).unwrap_or(line).trim();
     let path = if path.starts_with("a/") || path.starts_with("b/") {
@@ -153,6 +385,10 @@ fn parse_file_path(line: &str, prefix: &str) -> String {
     path.to_string()
 }
 
+/// Parse a hunk: its header and as many body lines as the header counts.
+///
+/// Body lines are told apart by their first character only, so a removed
+/// `-- comment` (`--- comment` in the diff) is not mistaken for a file header.
 fn parse_hunk(lines: &mut std::iter::Peekable<Lines>) -> Result<Hunk, DiffError> {
     let header = lines
         .next()
@@ -160,28 +396,56 @@ fn parse_hunk(lines: &mut std::iter::Peekable<Lines>) -> Result<Hunk, DiffError>
 
     let (old_start, old_count, new_start, new_count) = parse_hunk_header(header)?;
     let mut hunk_lines = Vec::new();
+    let mut old_missing_newline = false;
+    let mut new_missing_newline = false;
+    let count_mismatch = || {
+        DiffError::HunkParseError(format!(
+            "the body of hunk '{}' doesn't match its line counts",
+            header
+        ))
+    };
 
+    let (mut old_left, mut new_left) = (old_count, new_count);
     while let Some(line) = lines.peek() {
-        if line.starts_with("@@") || line.starts_with("---") || line.starts_with("+++") {
+        if line.starts_with('\\') {
+            // "\ No newline at end of file" applies to the line before it
+            match hunk_lines.last() {
+                Some(DiffLine::Context(_)) => {
+                    old_missing_newline = true;
+                    new_missing_newline = true;
+                }
+                Some(DiffLine::Remove(_)) => old_missing_newline = true,
+                Some(DiffLine::Add(_)) => new_missing_newline = true,
+                None => {}
+            }
+            lines.next();
+            continue;
+        }
+        if old_left == 0 && new_left == 0 {
             break;
         }
+        if line.starts_with("@@") {
+            return Err(count_mismatch());
+        }
 
         let line = lines.next().unwrap();
-
-        if line.is_empty() {
-            hunk_lines.push(DiffLine::Context(String::new()));
-        } else if let Some(content) = line.strip_prefix('+') {
+        if let Some(content) = line.strip_prefix('+') {
+            new_left = new_left.checked_sub(1).ok_or_else(count_mismatch)?;
             hunk_lines.push(DiffLine::Add(content.to_string()));
         } else if let Some(content) = line.strip_prefix('-') {
+            old_left = old_left.checked_sub(1).ok_or_else(count_mismatch)?;
             hunk_lines.push(DiffLine::Remove(content.to_string()));
-        } else if let Some(content) = line.strip_prefix(' ') {
-            hunk_lines.push(DiffLine::Context(content.to_string()));
-        } else if line.starts_with('\\') {
-            continue; // "\ No newline at end of file"
         } else {
-            hunk_lines.push(DiffLine::Context(line.to_string()));
+            // Context, possibly with its leading space trimmed away
+            old_left = old_left.checked_sub(1).ok_or_else(count_mismatch)?;
+            new_left = new_left.checked_sub(1).ok_or_else(count_mismatch)?;
+            let content = line.strip_prefix(' ').unwrap_or(line);
+            hunk_lines.push(DiffLine::Context(content.to_string()));
         }
     }
+    if old_left > 0 || new_left > 0 {
+        return Err(count_mismatch());
+    }
 
     Ok(Hunk {
         old_start,
@@ -189,6 +453,8 @@ fn parse_hunk(lines: &mut std::iter::Peekable<Lines>) -> Result<Hunk, DiffError>
         new_start,
         new_count,
         lines: hunk_lines,
+        old_missing_newline,
+        new_missing_newline,
     })
 }
 
@@ -227,37 +493,114 @@ fn parse_range(range: &str) -> Result<(usize, usize), DiffError> {
     Ok((start, count))
 }
 
-fn apply_hunk_to_lines(lines: Vec<String>, hunk: &Hunk) -> Result<Vec<String>, DiffError> {
-    let start_idx = if hunk.old_start == 0 {
-        0
-    } else {
-        hunk.old_start - 1
-    };
-    let mut new_lines = Vec::new();
-
-    // Lines before hunk
-    new_lines.extend(lines.iter().take(start_idx).cloned());
-
-    // Apply hunk
-    for diff_line in &hunk.lines {
-        match diff_line {
-            DiffLine::Context(content) | DiffLine::Add(content) => {
-                new_lines.push(content.clone());
+/// Split a multi-file patch (e.g. `git diff` output) into per-file diffs.
+///
+/// Files start at a `diff --git` line or, in plain unified diffs, at a
+/// `---`/`+++` header pair. Git's extended headers are honored: `rename
+/// from`/`rename to`, `new file mode` and `deleted file mode`, so renames and
+/// empty files need no hunks.
+pub fn parse_patch(patch_text: &str) -> Result<Vec<UnifiedDiff>, DiffError> {
+    let lines: Vec<&str> = patch_text.lines().collect();
+
+    // Split into per-file sections
+    let mut sections: Vec<Vec<&str>> = Vec::new();
+    let mut has_file_header = false;
+    // Old and new lines left in the current hunk body
+    let mut body_left = (0, 0);
+    for (i, line) in lines.iter().enumerate() {
+        if body_left != (0, 0) {
+            body_left = consume_body_line(line, body_left);
+            if let Some(section) = sections.last_mut() {
+                section.push(line);
+            }
+            continue;
+        }
+        if line.starts_with("@@") {
+            if let Ok((_, old_count, _, new_count)) = parse_hunk_header(line) {
+                body_left = (old_count, new_count);
             }
-            DiffLine::Remove(_) => {}
+        }
+
+        let starts_git_file = line.starts_with("diff --git ");
+        let starts_plain_file = line.starts_with("--- ")
+            && lines
+                .get(i + 1)
+                .is_some_and(|next| next.starts_with("+++ "))
+            && (has_file_header || sections.is_empty());
+        if starts_git_file || starts_plain_file {
+            sections.push(Vec::new());
+            has_file_header = !starts_git_file;
+        } else if line.starts_with("--- ") {
+            has_file_header = true;
+        }
+        if let Some(section) = sections.last_mut() {
+            section.push(line);
         }
     }
 
-    // Lines after hunk
-    let skip_count = hunk
-        .lines
+    let diffs = sections
         .iter()
-        .filter(|l| matches!(l, DiffLine::Context(_) | DiffLine::Remove(_)))
-        .count();
+        .map(|section| parse_file_section(section))
+        .collect::<Result<Vec<_>, _>>()?;
+    if diffs.is_empty() {
+        return Err(DiffError::InvalidFormat(
+            "no file diffs found in patch".to_string(),
+        ));
+    }
+    Ok(diffs)
+}
+
+/// Count `line` of a hunk body against the old and new lines left.
+fn consume_body_line(line: &str, (old_left, new_left): (usize, usize)) -> (usize, usize) {
+    if line.starts_with('+') {
+        (old_left, new_left.saturating_sub(1))
+    } else if line.starts_with('-') {
+        (old_left.saturating_sub(1), new_left)
+    } else if line.starts_with('\\') {
+        (old_left, new_left)
+    } else {
+        (old_left.saturating_sub(1), new_left.saturating_sub(1))
+    }
+}
+
+/// Parse the diff of a single file, including git extended headers.
+fn parse_file_section(section: &[&str]) -> Result<UnifiedDiff, DiffError> {
+    let mut diff = UnifiedDiff::parse(&section.join("\n"))?;
+
+    let mut git_paths = None;
+    let mut rename_from = None;
+    let mut rename_to = None;
+    for line in section.iter().take_while(|line| !line.starts_with("@@")) {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            git_paths = paths
+                .rsplit_once(" b/")
+                .map(|(old, new)| (old.trim_start_matches("a/").to_string(), new.to_string()));
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            rename_from = Some(path.trim().to_string());
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            rename_to = Some(path.trim().to_string());
+        } else if line.starts_with("new file mode") {
+            diff.is_new_file = true;
+        } else if line.starts_with("deleted file mode") {
+            diff.is_delete = true;
+        }
+    }
 
-    new_lines.extend(lines.iter().skip(start_idx + skip_count).cloned());
+    let (git_old, git_new) = git_paths.unzip();
+    if !diff.is_new_file {
+        diff.old_path = rename_from.or(diff.old_path).or(git_old);
+    }
+    if !diff.is_delete {
+        diff.new_path = rename_to.or(diff.new_path).or(git_new);
+    }
 
-    Ok(new_lines)
+    if diff.old_path.is_none() && diff.new_path.is_none() {
+        return Err(DiffError::InvalidFormat(format!(
+            "file diff without paths: {}",
+            section.first().unwrap_or(&"")
+        )));
+    }
+    Ok(diff)
 }
 
 /// Apply a unified diff to file content.
@@ -320,7 +663,11 @@ mod tests {
         assert!(parsed.is_new_file);
 
         let result = apply_unified_diff("", diff).unwrap();
-        assert_eq!(result, "line 1\nline 2\nline 3");
+        assert_eq!(result, "line 1\nline 2\nline 3\n");
+
+        let diff =
+            "--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+a\n+b\n\\ No newline at end of file\n";
+        assert_eq!(apply_unified_diff("", diff).unwrap(), "a\nb");
     }
 
     #[test]
@@ -339,6 +686,219 @@ mod tests {
         assert_eq!(result, "");
     }
 
+    #[test]
+    fn test_apply_finds_hunk_at_offset() {
+        let original = "a\nb\nc\nd\ne\nf\n";
+        // Header says line 1, but the context is at line 3
+        let diff = "@@ -1,3 +1,3 @@\n c\n-d\n+D\n e\n";
+        assert_eq!(
+            apply_unified_diff(original, diff).unwrap(),
+            "a\nb\nc\nD\ne\nf\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_offset_carries_to_later_hunks() {
+        let original = "x\nx\n1\n2\n3\n4\n5\n6\n7\n8\n";
+        // Both headers are two lines early
+        let diff = "@@ -1,2 +1,2 @@\n 1\n-2\n+two\n@@ -5,2 +5,2 @@\n 5\n-6\n+six\n";
+        assert_eq!(
+            apply_unified_diff(original, diff).unwrap(),
+            "x\nx\n1\ntwo\n3\n4\n5\nsix\n7\n8\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_rejects_mismatched_context() {
+        let original = "line 1\nline 2\nline 3\n";
+        let diff = "@@ -1,3 +1,3 @@\n line 1\n-line two\n+line 2!\n line 3\n";
+        let err = apply_unified_diff(original, diff).unwrap_err();
+        match err {
+            DiffError::ContextMismatch {
+                hunk,
+                line,
+                expected,
+                actual,
+            } => {
+                assert_eq!((hunk, line), (1, 2));
+                assert_eq!(expected, "line two");
+                assert_eq!(actual, "line 2");
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+        // Nothing is silently spliced in
+        let err = apply_unified_diff("a\n", "@@ -1,2 +1,2 @@\n a\n-b\n+c\n").unwrap_err();
+        assert!(err.to_string().contains("got '<end of file>'"));
+    }
+
+    #[test]
+    fn test_apply_rejects_ambiguous_hunk() {
+        // Line 3 is not "}", and the lines above and below it both are
+        let original = "a\n}\nmid\n}\nb\n";
+        let diff = "@@ -3,1 +3,2 @@\n }\n+baz\n";
+        assert!(matches!(
+            apply_unified_diff(original, diff),
+            Err(DiffError::AmbiguousHunk {
+                hunk: 1,
+                first: 2,
+                second: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn test_apply_with_fuzz_ignores_stale_outer_context() {
+        let original = "alpha\nbeta\ngamma\ndelta\n";
+        let diff = "@@ -1,4 +1,4 @@\n ALPHA\n beta\n-gamma\n+GAMMA\n delta\n";
+        assert_eq!(
+            apply_unified_diff(original, diff).unwrap(),
+            "alpha\nbeta\nGAMMA\ndelta\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_handles_missing_newline_marker() {
+        // Adding a final newline
+        let diff = "@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n";
+        assert_eq!(apply_unified_diff("a\nb", diff).unwrap(), "a\nb\n");
+
+        // Removing it
+        let diff = "@@ -1,2 +1,2 @@\n a\n-b\n+b\n\\ No newline at end of file\n";
+        assert_eq!(apply_unified_diff("a\nb\n", diff).unwrap(), "a\nb");
+
+        // Untouched end keeps the original newline
+        let diff = "@@ -1,2 +1,2 @@\n-a\n+A\n b\n";
+        assert_eq!(apply_unified_diff("a\nb\n", diff).unwrap(), "A\nb\n");
+    }
+
+    #[test]
+    fn test_apply_keeps_crlf_line_endings() {
+        let diff = "--- a/f.txt\n+++ b/f.txt\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n";
+        assert_eq!(
+            apply_unified_diff("a\r\nb\r\nc\r\n", diff).unwrap(),
+            "a\r\nB\r\nc\r\n"
+        );
+        assert_eq!(apply_unified_diff("a\nb\nc", diff).unwrap(), "a\nB\nc");
+    }
+
+    #[test]
+    fn test_hunk_body_lines_look_like_headers() {
+        let diff = "\
+--- a/schema.sql
++++ b/schema.sql
+@@ -1,3 +1,3 @@
+ SELECT 1;
+--- old comment
++++ new comment
+ SELECT 2;
+";
+        let original = "SELECT 1;\n-- old comment\nSELECT 2;\n";
+        assert_eq!(
+            apply_unified_diff(original, diff).unwrap(),
+            "SELECT 1;\n++ new comment\nSELECT 2;\n"
+        );
+
+        let diffs = parse_patch(diff).unwrap();
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].hunks[0].lines.len(), 4);
+    }
+
+    #[test]
+    fn test_hunk_line_counts_must_match_body() {
+        // Header counts more lines than the body has
+        let diff = "--- a/f.txt\n+++ b/f.txt\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n";
+        assert!(matches!(
+            UnifiedDiff::parse(diff),
+            Err(DiffError::HunkParseError(_))
+        ));
+        // ... or fewer
+        let diff = "--- a/f.txt\n+++ b/f.txt\n@@ -1 +1 @@\n-a\n+A\n-b\n+B\n";
+        assert!(matches!(
+            UnifiedDiff::parse(diff),
+            Err(DiffError::HunkParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_delete_file_diff_must_match() {
+        let diff = "--- a/old.txt\n+++ /dev/null\n@@ -1,2 +0,0 @@\n-line 1\n-line 2\n";
+        assert!(matches!(
+            apply_unified_diff("line 1\nline 2\nline 3\n", diff),
+            Err(DiffError::ContextMismatch { line: 3, .. })
+        ));
+        assert!(apply_unified_diff("line 1\nline X\n", diff).is_err());
+
+        // Hunks of a deletion cover the file one after another
+        let diff = "--- a/old.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-a\n@@ -2 +0,0 @@\n-b\n";
+        assert_eq!(apply_unified_diff("a\nb\n", diff).unwrap(), "");
+        assert!(apply_unified_diff("a\nc\n", diff).is_err());
+    }
+
+    #[test]
+    fn test_parse_patch_git_style() {
+        let patch = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1 +1 @@
+-old
++new
+diff --git a/old name.txt b/new name.txt
+similarity index 100%
+rename from old name.txt
+rename to new name.txt
+diff --git a/empty.txt b/empty.txt
+new file mode 100644
+index 0000000..e69de29
+diff --git a/gone.txt b/gone.txt
+deleted file mode 100644
+--- a/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+";
+        let diffs = parse_patch(patch).unwrap();
+        assert_eq!(diffs.len(), 4);
+
+        assert_eq!(diffs[0].old_path.as_deref(), Some("src/lib.rs"));
+        assert_eq!(diffs[0].hunks.len(), 1);
+
+        assert_eq!(diffs[1].old_path.as_deref(), Some("old name.txt"));
+        assert_eq!(diffs[1].new_path.as_deref(), Some("new name.txt"));
+        assert!(diffs[1].hunks.is_empty());
+
+        assert!(diffs[2].is_new_file);
+        assert_eq!(diffs[2].old_path, None);
+        assert_eq!(diffs[2].new_path.as_deref(), Some("empty.txt"));
+
+        assert!(diffs[3].is_delete);
+        assert_eq!(diffs[3].old_path.as_deref(), Some("gone.txt"));
+        assert_eq!(diffs[3].new_path, None);
+    }
+
+    #[test]
+    fn test_parse_patch_plain_multi_file() {
+        let patch = "\
+--- a/one.txt
++++ b/one.txt
+@@ -1 +1 @@
+-1
++one
+--- a/two.txt
++++ b/two.txt
+@@ -1 +1 @@
+-2
++two
+";
+        let diffs = parse_patch(patch).unwrap();
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[1].new_path.as_deref(), Some("two.txt"));
+        assert_eq!(diffs[1].apply("2\n").unwrap(), "two\n");
+
+        assert!(parse_patch("no diff here").is_err());
+    }
+
     #[test]
     fn test_is_unified_diff() {
         assert!(is_unified_diff("--- a/file\n+++ b/file\n@@"));
@@ -9,11 +9,21 @@ use tracing::debug;
 
 use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
 
+use super::workspace::Workspace;
 use super::{diff, file_ops};
 
 /// Tool for creating or editing files.
 #[derive(Debug, Clone, Default)]
-pub struct EditFileTool;
+pub struct EditFileTool {
+    workspace: Workspace,
+}
+
+impl EditFileTool {
+    /// Create the tool for a workspace.
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
 
 #[derive(Debug, Deserialize)]
 struct EditFileArgs {
@@ -56,7 +66,7 @@ impl Tool for EditFileTool {
         )
     }
 
-    async fn call(&self, _ctx: &RunContext, args: JsonValue) -> ToolResult {
+    async fn call(&self, ctx: &RunContext, args: JsonValue) -> ToolResult {
         debug!(tool = "edit_file", ?args, "Tool called");
 
         let args: EditFileArgs = super::common::parse_tool_args_lenient(
@@ -65,10 +75,16 @@ impl Tool for EditFileTool {
             self.definition().parameters(),
         )?;
 
+        let checked_path = match self.workspace.check_write(&args.file_path) {
+            Ok(path) => path,
+            Err(e) => return Ok(ToolReturn::error(e.to_string())),
+        };
+        let path = checked_path.to_string_lossy();
+
         // Determine what content to write
         let final_content = if let Some(diff_text) = &args.diff {
             // Apply diff to existing file
-            let original = match file_ops::read_file(&args.file_path, None, None, None) {
+            let original = match file_ops::read_file(&path, None, None, None) {
                 Ok(result) => result.content,
                 Err(file_ops::FileError::NotFound(_)) => {
                     // For new files via diff, start with empty
@@ -90,7 +106,8 @@ impl Tool for EditFileTool {
         };
 
         // Write the file
-        match file_ops::write_file(&args.file_path, &final_content, args.create_directories) {
+        self.workspace.checkpoint(&ctx.run_id, &checked_path);
+        match file_ops::write_file(&path, &final_content, args.create_directories) {
             Ok(()) => {
                 let line_count = final_content.lines().count();
                 let byte_count = final_content.len();
@@ -120,7 +137,7 @@ mod tests {
         let dir = TempDir::new().unwrap();
         let file_path = dir.path().join("new.txt");
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::new(Workspace::new(dir.path()));
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -144,7 +161,7 @@ mod tests {
         let file_path = dir.path().join("existing.txt");
         fs::write(&file_path, "old content").unwrap();
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::new(Workspace::new(dir.path()));
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -176,7 +193,7 @@ mod tests {
  line 3
 "#;
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::new(Workspace::new(dir.path()));
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -199,7 +216,7 @@ mod tests {
         let dir = TempDir::new().unwrap();
         let file_path = dir.path().join("sub/dir/new.txt");
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::new(Workspace::new(dir.path()));
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -217,9 +234,39 @@ mod tests {
         assert!(file_path.exists());
     }
 
+    #[tokio::test]
+    async fn test_edit_file_denies_read_only_roots() {
+        let workspace = TempDir::new().unwrap();
+        let docs = TempDir::new().unwrap();
+        let file_path = docs.path().join("guide.md");
+        fs::write(&file_path, "original").unwrap();
+
+        let policy = crate::tools::PathPolicy {
+            read_only_roots: vec![docs.path().to_string_lossy().to_string()],
+            ..Default::default()
+        };
+        let tool = EditFileTool::new(Workspace::new(workspace.path()).with_policy(policy));
+        let ctx = RunContext::minimal("test");
+
+        let result = tool
+            .call(
+                &ctx,
+                serde_json::json!({
+                    "file_path": file_path.to_str().unwrap(),
+                    "content": "modified"
+                }),
+            )
+            .await;
+
+        let ret = result.unwrap();
+        assert!(ret.is_error());
+        assert!(ret.as_text().unwrap().contains("read-only"));
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "original");
+    }
+
     #[tokio::test]
     async fn test_edit_file_requires_content_or_diff() {
-        let tool = EditFileTool;
+        let tool = EditFileTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -3,9 +3,16 @@
 //! Provides low-level file system operations with safety limits.
 
 use super::common::should_ignore;
+use chrono::{DateTime, Utc};
+use ignore::overrides::OverrideBuilder;
+use ignore::types::TypesBuilder;
+use ignore::{WalkBuilder, WalkParallel, WalkState};
+use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
 use thiserror::Error;
 
 // =============================================================================
@@ -59,6 +66,27 @@ pub enum FileError {
         total_lines: usize,
         suggested_chunk_size: usize,
     },
+
+    #[error(
+        "Access denied: {path} is outside the workspace. Allowed locations: {}",
+        allowed_roots.join(", ")
+    )]
+    OutsideWorkspace {
+        path: String,
+        allowed_roots: Vec<String>,
+    },
+
+    #[error("Access denied: {0} is in a read-only location")]
+    ReadOnlyPath(String),
+
+    #[error("Access denied: {path} matches the protected pattern '{pattern}'")]
+    DeniedByPattern { path: String, pattern: String },
+
+    #[error("Access denied: {path} resolves to {target}, outside the workspace")]
+    SymlinkEscape { path: String, target: String },
+
+    #[error("Unsupported encoding: {0} is not UTF-8 or UTF-16 text")]
+    UnsupportedEncoding(String),
 }
 
 // =============================================================================
@@ -370,6 +398,111 @@ pub fn read_file(
     })
 }
 
+// =============================================================================
+// Text Files
+// =============================================================================
+
+/// Encoding of a text file, detected from its byte order mark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    Utf8,
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// A text file decoded for in-place editing.
+///
+/// Remembers the encoding and line endings so an edited version can be
+/// written back the same way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextFile {
+    /// Decoded content, without the byte order mark.
+    pub content: String,
+    pub encoding: TextEncoding,
+    /// Whether the file uses `\r\n` line endings (judged by its first line).
+    pub crlf: bool,
+}
+
+impl TextFile {
+    /// Decode file bytes. Returns `None` if they aren't text in a supported encoding.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let encoding = match bytes {
+            [0xEF, 0xBB, 0xBF, ..] => TextEncoding::Utf8Bom,
+            [0xFF, 0xFE, ..] => TextEncoding::Utf16Le,
+            [0xFE, 0xFF, ..] => TextEncoding::Utf16Be,
+            _ => TextEncoding::Utf8,
+        };
+        let content = match encoding {
+            TextEncoding::Utf8 => String::from_utf8(bytes.to_vec()).ok()?,
+            TextEncoding::Utf8Bom => String::from_utf8(bytes[3..].to_vec()).ok()?,
+            TextEncoding::Utf16Le => decode_utf16(&bytes[2..], u16::from_le_bytes)?,
+            TextEncoding::Utf16Be => decode_utf16(&bytes[2..], u16::from_be_bytes)?,
+        };
+        let crlf = content
+            .find('\n')
+            .is_some_and(|i| content[..i].ends_with('\r'));
+        Some(Self {
+            content,
+            encoding,
+            crlf,
+        })
+    }
+
+    /// Encode `content` in the file's encoding, including its byte order mark.
+    pub fn encode(&self, content: &str) -> Vec<u8> {
+        match self.encoding {
+            TextEncoding::Utf8 => content.as_bytes().to_vec(),
+            TextEncoding::Utf8Bom => [b"\xEF\xBB\xBF".as_slice(), content.as_bytes()].concat(),
+            TextEncoding::Utf16Le => [0xFF, 0xFE]
+                .into_iter()
+                .chain(content.encode_utf16().flat_map(u16::to_le_bytes))
+                .collect(),
+            TextEncoding::Utf16Be => [0xFE, 0xFF]
+                .into_iter()
+                .chain(content.encode_utf16().flat_map(u16::to_be_bytes))
+                .collect(),
+        }
+    }
+
+    /// Convert the line endings of `text` to the file's.
+    pub fn with_line_endings(&self, text: &str) -> String {
+        if self.crlf {
+            text.replace("\r\n", "\n").replace('\n', "\r\n")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> Option<String> {
+    let chunks = bytes.chunks_exact(2);
+    if !chunks.remainder().is_empty() {
+        return None;
+    }
+    let units: Vec<u16> = chunks.map(|pair| from_bytes([pair[0], pair[1]])).collect();
+    String::from_utf16(&units).ok()
+}
+
+/// Read a text file for in-place editing.
+pub fn read_text_file(path: &str, max_size: Option<u64>) -> Result<TextFile, FileError> {
+    let file_path = Path::new(path);
+
+    if !file_path.exists() {
+        return Err(FileError::NotFound(path.to_string()));
+    }
+
+    let metadata = fs::metadata(file_path)?;
+    let max = max_size.unwrap_or(10 * 1024 * 1024); // 10MB default
+
+    if metadata.len() > max {
+        return Err(FileError::TooLarge(metadata.len(), max));
+    }
+
+    let bytes = fs::read(file_path)?;
+    TextFile::decode(&bytes).ok_or_else(|| FileError::UnsupportedEncoding(path.to_string()))
+}
+
 // =============================================================================
 // File Writing
 // =============================================================================
@@ -380,7 +513,11 @@ pub fn read_file(
 /// * `path` - Path to the file
 /// * `content` - Content to write
 /// * `create_dirs` - Whether to create parent directories
-pub fn write_file(path: &str, content: &str, create_dirs: bool) -> Result<(), FileError> {
+pub fn write_file(
+    path: &str,
+    content: impl AsRef<[u8]>,
+    create_dirs: bool,
+) -> Result<(), FileError> {
     let file_path = Path::new(path);
 
     if create_dirs {
@@ -413,44 +550,112 @@ pub fn delete_file(path: &str) -> Result<(), FileError> {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GrepMatch {
     pub path: String,
+    /// First line of the match (1-based).
     pub line_number: usize,
+    /// The matching line, or every line of a multiline match joined by `\n`.
     pub content: String,
+    /// Lines before the match.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context_before: Vec<String>,
+    /// Lines after the match.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context_after: Vec<String>,
+}
+
+/// Number of matches in a file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrepFileCount {
+    pub path: String,
+    pub count: usize,
 }
 
 /// Result of a grep search.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GrepResult {
+    /// Matches sorted by path and line. Only filled in [`GrepOutputMode::Content`].
     pub matches: Vec<GrepMatch>,
+    /// Matches in all files searched, including any cut off by the limit.
     pub total_matches: usize,
+    /// Files with matches, sorted by path.
+    pub files: Vec<GrepFileCount>,
+    /// Whether the search stopped at the result limit.
+    pub truncated: bool,
+}
+
+/// What a grep search reports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GrepOutputMode {
+    /// Matching lines, with context if requested.
+    #[default]
+    Content,
+    /// Only the paths of files with matches.
+    FilesWithMatches,
+    /// The number of matches per file.
+    Count,
+}
+
+/// Options for [`grep`].
+#[derive(Debug, Clone, Default)]
+pub struct GrepOptions {
+    /// Maximum matches (or files, outside content mode) to return.
+    pub max_results: Option<usize>,
+    pub case_insensitive: bool,
+    /// Let matches span lines: `.` matches newlines and `^`/`$` match at
+    /// line boundaries.
+    pub multiline: bool,
+    /// Only search files matching one of these globs.
+    pub include: Vec<String>,
+    /// Skip files and directories matching these globs.
+    pub exclude: Vec<String>,
+    /// Only search these file types (ripgrep names, e.g. `rust`, `py`).
+    pub file_types: Vec<String>,
+    pub context_before: usize,
+    pub context_after: usize,
+    /// Search hidden files and directories.
+    pub hidden: bool,
+    /// Don't honor `.gitignore`, `.ignore` and other ignore files.
+    pub no_ignore: bool,
+    pub output_mode: GrepOutputMode,
 }
 
 /// Safety caps for grep.
 const GREP_MAX_MATCHES: usize = 100;
 const GREP_MAX_LINE_LENGTH: usize = 512;
 const GREP_MAX_FILE_SIZE: u64 = 5 * 1024 * 1024; // 5MB
-const GREP_MAX_DEPTH: usize = 10;
+
+/// Bytes checked for NUL when deciding whether a file is binary.
+const GREP_BINARY_CHECK_BYTES: usize = 8 * 1024;
 
 /// Search for a pattern in files.
 ///
+/// Walks `directory` in parallel, honoring `.gitignore`, `.ignore` and
+/// hidden-file rules unless disabled in `options`, and skips binary files.
 /// Uses regex for pattern matching. Falls back to literal search if regex is invalid.
 pub fn grep(
     pattern: &str,
     directory: &str,
-    max_results: Option<usize>,
+    options: &GrepOptions,
 ) -> Result<GrepResult, FileError> {
-    use regex::Regex;
-    #[allow(unused_imports)]
-    use std::io::{BufRead, BufReader};
+    grep_filtered(pattern, directory, options, &|_| true)
+}
 
-    let max_matches = max_results
+/// Like [`grep`], but only searches files for which `filter` returns true.
+///
+/// Used by the grep tool to skip files the path policy doesn't allow reading.
+pub fn grep_filtered(
+    pattern: &str,
+    directory: &str,
+    options: &GrepOptions,
+    filter: &(dyn Fn(&Path) -> bool + Sync),
+) -> Result<GrepResult, FileError> {
+    let limit = options
+        .max_results
         .unwrap_or(GREP_MAX_MATCHES)
         .min(GREP_MAX_MATCHES);
 
     if pattern.is_empty() {
-        return Err(FileError::Io(std::io::Error::new(
-            std::io::ErrorKind::InvalidInput,
-            "pattern must not be empty",
-        )));
+        return Err(invalid_input("pattern must not be empty"));
     }
 
     let path = Path::new(directory);
@@ -458,136 +663,386 @@ pub fn grep(
         return Err(FileError::NotFound(directory.to_string()));
     }
     if !path.is_dir() {
-        return Err(FileError::Io(std::io::Error::new(
-            std::io::ErrorKind::InvalidInput,
-            "not a directory",
-        )));
+        return Err(invalid_input("not a directory"));
+    }
+
+    let re = grep_regex(pattern, options)?;
+    let walker = grep_walker(path, options)?;
+
+    // Matches (content mode) or files found so far, to stop at the limit
+    let found = AtomicUsize::new(0);
+    let stopped = AtomicBool::new(false);
+    let results = Mutex::new(Vec::new());
+    walker.run(|| {
+        Box::new(|entry| {
+            if found.load(Ordering::Relaxed) >= limit {
+                stopped.store(true, Ordering::Relaxed);
+                return WalkState::Quit;
+            }
+            let Ok(entry) = entry else {
+                return WalkState::Continue; // Skip unreadable entries
+            };
+            if !entry.file_type().is_some_and(|t| t.is_file()) || !filter(entry.path()) {
+                return WalkState::Continue;
+            }
+            if let Some(file) = grep_file(&re, path, entry.path(), options) {
+                let added = match options.output_mode {
+                    GrepOutputMode::Content => file.matches.len(),
+                    _ => 1,
+                };
+                found.fetch_add(added, Ordering::Relaxed);
+                results.lock().unwrap_or_else(|e| e.into_inner()).push(file);
+            }
+            WalkState::Continue
+        })
+    });
+
+    let mut results = results.into_inner().unwrap_or_else(|e| e.into_inner());
+    results.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut result = GrepResult {
+        matches: Vec::new(),
+        total_matches: 0,
+        files: Vec::new(),
+        truncated: stopped.into_inner(),
+    };
+    for file in results {
+        result.total_matches += file.count;
+        result.matches.extend(file.matches);
+        result.files.push(GrepFileCount {
+            path: file.path,
+            count: file.count,
+        });
+    }
+    let returned = match options.output_mode {
+        GrepOutputMode::Content => result.matches.len(),
+        _ => result.files.len(),
+    };
+    if returned > limit {
+        result.matches.truncate(limit);
+        if options.output_mode != GrepOutputMode::Content {
+            result.files.truncate(limit);
+        }
+        result.truncated = true;
     }
+    Ok(result)
+}
+
+/// Matches in a single file.
+struct GrepFileResult {
+    path: String,
+    count: usize,
+    matches: Vec<GrepMatch>,
+}
 
+fn invalid_input(message: impl Into<String>) -> FileError {
+    FileError::Io(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        message.into(),
+    ))
+}
+
+fn grep_regex(pattern: &str, options: &GrepOptions) -> Result<Regex, FileError> {
     // Parse flags from pattern (e.g., "--ignore-case pattern")
     let (pattern, case_insensitive) = if let Some(rest) = pattern.strip_prefix("--ignore-case ") {
         (rest.trim(), true)
     } else if let Some(rest) = pattern.strip_prefix("-i ") {
         (rest.trim(), true)
     } else {
-        (pattern, false)
+        (pattern, options.case_insensitive)
+    };
+
+    let build = |pattern: &str| {
+        RegexBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .multi_line(options.multiline)
+            .dot_matches_new_line(options.multiline)
+            .build()
     };
 
     // Try regex, fall back to literal
-    let regex_pattern = if case_insensitive {
-        format!("(?i){}", pattern)
+    build(pattern)
+        .or_else(|_| build(&regex::escape(pattern)))
+        .map_err(|e| invalid_input(format!("invalid pattern: {}", e)))
+}
+
+fn grep_walker(root: &Path, options: &GrepOptions) -> Result<WalkParallel, FileError> {
+    let mut overrides = OverrideBuilder::new(root);
+    let globs = options
+        .include
+        .iter()
+        .cloned()
+        .chain(options.exclude.iter().map(|glob| format!("!{}", glob)));
+    for glob in globs {
+        overrides
+            .add(&glob)
+            .map_err(|e| invalid_input(format!("invalid glob '{}': {}", glob, e)))?;
+    }
+    let overrides = overrides
+        .build()
+        .map_err(|e| invalid_input(format!("invalid glob: {}", e)))?;
+
+    let mut types = TypesBuilder::new();
+    types.add_defaults();
+    for file_type in &options.file_types {
+        types.select(file_type);
+    }
+    let types = types
+        .build()
+        .map_err(|e| invalid_input(format!("invalid file type: {}", e)))?;
+
+    let mut builder = ignore_walker(root, options.hidden, options.no_ignore);
+    builder.overrides(overrides).types(types);
+    Ok(builder.build_parallel())
+}
+
+/// A directory walker honoring `.gitignore`, `.ignore` and hidden-file
+/// rules (unless disabled). `.git` directories are always skipped.
+fn ignore_walker(root: &Path, hidden: bool, no_ignore: bool) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .standard_filters(!no_ignore)
+        .hidden(!hidden)
+        // Honor .gitignore outside of git repositories too
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git");
+    builder
+}
+
+/// Search one file. Returns `None` if it has no matches or can't be searched.
+fn grep_file(
+    re: &Regex,
+    base: &Path,
+    path: &Path,
+    options: &GrepOptions,
+) -> Option<GrepFileResult> {
+    let meta = fs::metadata(path).ok()?;
+    if meta.len() > GREP_MAX_FILE_SIZE {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+    if bytes[..bytes.len().min(GREP_BINARY_CHECK_BYTES)].contains(&0) {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    let lines: Vec<&str> = text.lines().collect();
+
+    // Matched line ranges (0-based, inclusive)
+    let spans: Vec<(usize, usize)> = if options.multiline {
+        multiline_spans(re, &text, lines.len())
     } else {
-        pattern.to_string()
+        lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| re.is_match(line))
+            .map(|(i, _)| (i, i))
+            .collect()
     };
+    if spans.is_empty() {
+        return None;
+    }
 
-    let re = Regex::new(&regex_pattern)
-        .or_else(|_| {
-            let escaped = regex::escape(pattern);
-            if case_insensitive {
-                Regex::new(&format!("(?i){}", escaped))
-            } else {
-                Regex::new(&escaped)
-            }
-        })
-        .map_err(|e| {
-            FileError::Io(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!("invalid pattern: {}", e),
-            ))
-        })?;
-
-    let mut matches = Vec::new();
-    grep_recursive(&re, path, path, &mut matches, max_matches, 0)?;
-
-    Ok(GrepResult {
-        total_matches: matches.len(),
+    let relative = path.strip_prefix(base).unwrap_or(path);
+    let relative = relative.to_string_lossy().to_string();
+    let matches = if options.output_mode == GrepOutputMode::Content {
+        let truncated = |slice: &[&str]| -> Vec<String> {
+            slice.iter().map(|line| truncate_line(line)).collect()
+        };
+        spans
+            .iter()
+            .map(|&(start, end)| GrepMatch {
+                path: relative.clone(),
+                line_number: start + 1,
+                content: truncated(&lines[start..=end]).join("\n"),
+                context_before: truncated(
+                    &lines[start.saturating_sub(options.context_before)..start],
+                ),
+                context_after: truncated(
+                    &lines[end + 1..(end + 1 + options.context_after).min(lines.len())],
+                ),
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    Some(GrepFileResult {
+        path: relative,
+        count: spans.len(),
         matches,
     })
 }
 
-fn grep_recursive(
-    re: &regex::Regex,
-    base: &Path,
-    dir: &Path,
-    matches: &mut Vec<GrepMatch>,
-    max_matches: usize,
-    depth: usize,
-) -> Result<(), FileError> {
-    #[allow(unused_imports)]
-    use std::io::{BufRead, BufReader};
+/// Line ranges covered by the matches of a multiline regex, merging matches
+/// that share a line.
+fn multiline_spans(re: &Regex, text: &str, line_count: usize) -> Vec<(usize, usize)> {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let last_line = line_count.saturating_sub(1);
+    let line_of =
+        |offset: usize| (line_starts.partition_point(|&s| s <= offset) - 1).min(last_line);
+
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    for m in re.find_iter(text) {
+        let start = line_of(m.start());
+        // A match ending with a newline doesn't touch the next line
+        let end = line_of(m.end().saturating_sub(1).max(m.start()));
+        match spans.last_mut() {
+            Some((_, previous_end)) if start <= *previous_end => {
+                *previous_end = (*previous_end).max(end);
+            }
+            _ => spans.push((start, end)),
+        }
+    }
+    spans
+}
 
-    if depth > GREP_MAX_DEPTH || matches.len() >= max_matches {
-        return Ok(());
+fn truncate_line(line: &str) -> String {
+    match line.char_indices().nth(GREP_MAX_LINE_LENGTH) {
+        Some((cut, _)) => format!("{}...", &line[..cut]),
+        None => line.to_string(),
     }
+}
 
-    let entries = match fs::read_dir(dir) {
-        Ok(e) => e,
-        Err(_) => return Ok(()), // Skip unreadable dirs
-    };
+// =============================================================================
+// Find Files
+// =============================================================================
 
-    for entry in entries.flatten() {
-        if matches.len() >= max_matches {
-            break;
-        }
+/// A file found by [`find_files`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FoundFile {
+    /// Path relative to the searched directory.
+    pub path: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+}
 
-        let path = entry.path();
-        let relative = path.strip_prefix(base).unwrap_or(&path);
-        let relative_str = relative.to_string_lossy().to_string();
+/// Result of a find_files search.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindFilesResult {
+    /// One page of matching files, most recently modified first.
+    pub files: Vec<FoundFile>,
+    /// Number of matching files across all pages.
+    pub total: usize,
+    /// Index of the first returned file among all matches.
+    pub offset: usize,
+}
 
-        if should_ignore(&relative_str) {
-            continue;
-        }
+/// Options for [`find_files`].
+#[derive(Debug, Clone, Default)]
+pub struct FindFilesOptions {
+    /// Number of matches to skip, for paging.
+    pub offset: usize,
+    /// Maximum files to return. Defaults to 100.
+    pub limit: Option<usize>,
+    /// Include hidden files and directories.
+    pub hidden: bool,
+    /// Don't honor `.gitignore`, `.ignore` and other ignore files.
+    pub no_ignore: bool,
+}
 
-        if path.is_dir() {
-            grep_recursive(re, base, &path, matches, max_matches, depth + 1)?;
-        } else if path.is_file() {
-            // Check file size
-            if let Ok(meta) = path.metadata() {
-                if meta.len() > GREP_MAX_FILE_SIZE {
-                    continue;
-                }
-            }
+/// Default page size for find_files.
+const FIND_FILES_DEFAULT_LIMIT: usize = 100;
 
-            // Check if text file
-            if !super::common::is_text_file(&relative_str) {
-                continue;
-            }
+/// Hard cap on the page size for find_files.
+const FIND_FILES_MAX_LIMIT: usize = 1_000;
 
-            // Search file
-            let file = match fs::File::open(&path) {
-                Ok(f) => f,
-                Err(_) => continue,
-            };
+/// Find files whose path relative to `directory` matches any of `patterns`.
+///
+/// Patterns use glob syntax: `*` and `?` don't cross `/`, `**` matches any
+/// number of directories (`src/**/*.rs`). Ignore rules are the same as for
+/// [`grep`]. Results are sorted by modification time, newest first.
+pub fn find_files(
+    patterns: &[String],
+    directory: &str,
+    options: &FindFilesOptions,
+) -> Result<FindFilesResult, FileError> {
+    find_files_filtered(patterns, directory, options, &|_| true)
+}
 
-            let reader = BufReader::new(file);
-            for (line_num, line_result) in reader.lines().enumerate() {
-                if matches.len() >= max_matches {
-                    break;
-                }
+/// Like [`find_files`], but only returns files for which `filter` returns true.
+pub fn find_files_filtered(
+    patterns: &[String],
+    directory: &str,
+    options: &FindFilesOptions,
+    filter: &(dyn Fn(&Path) -> bool + Sync),
+) -> Result<FindFilesResult, FileError> {
+    let limit = options
+        .limit
+        .unwrap_or(FIND_FILES_DEFAULT_LIMIT)
+        .clamp(1, FIND_FILES_MAX_LIMIT);
+
+    if patterns.is_empty() {
+        return Err(invalid_input("pattern must not be empty"));
+    }
+    let patterns = patterns
+        .iter()
+        .map(|pattern| {
+            let pattern = pattern.trim_start_matches("./");
+            glob::Pattern::new(pattern)
+                .map_err(|e| invalid_input(format!("invalid glob '{}': {}", pattern, e)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let match_options = glob::MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
 
-                let line = match line_result {
-                    Ok(l) => l,
-                    Err(_) => continue,
-                };
+    let path = Path::new(directory);
+    if !path.exists() {
+        return Err(FileError::NotFound(directory.to_string()));
+    }
+    if !path.is_dir() {
+        return Err(invalid_input("not a directory"));
+    }
 
-                if re.is_match(&line) {
-                    let content = if line.len() > GREP_MAX_LINE_LENGTH {
-                        format!("{}...", &line[..GREP_MAX_LINE_LENGTH])
-                    } else {
-                        line
-                    };
-
-                    matches.push(GrepMatch {
-                        path: relative_str.clone(),
-                        line_number: line_num + 1,
-                        content,
-                    });
+    let found = Mutex::new(Vec::new());
+    ignore_walker(path, options.hidden, options.no_ignore)
+        .build_parallel()
+        .run(|| {
+            Box::new(|entry| {
+                let Ok(entry) = entry else {
+                    return WalkState::Continue; // Skip unreadable entries
+                };
+                if !entry.file_type().is_some_and(|t| t.is_file()) {
+                    return WalkState::Continue;
                 }
-            }
-        }
-    }
+                let relative = entry.path().strip_prefix(path).unwrap_or(entry.path());
+                let matched = patterns
+                    .iter()
+                    .any(|pattern| pattern.matches_path_with(relative, match_options));
+                if !matched || !filter(entry.path()) {
+                    return WalkState::Continue;
+                }
+                let meta = entry.metadata().ok();
+                let file = FoundFile {
+                    path: relative.to_string_lossy().to_string(),
+                    size: meta.as_ref().map_or(0, |m| m.len()),
+                    modified: meta
+                        .and_then(|m| m.modified().ok())
+                        .map(DateTime::<Utc>::from),
+                };
+                found.lock().unwrap_or_else(|e| e.into_inner()).push(file);
+                WalkState::Continue
+            })
+        });
 
-    Ok(())
+    let mut files = found.into_inner().unwrap_or_else(|e| e.into_inner());
+    files.sort_by(|a, b| {
+        b.modified
+            .cmp(&a.modified)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    let total = files.len();
+    let files = files.into_iter().skip(options.offset).take(limit).collect();
+
+    Ok(FindFilesResult {
+        files,
+        total,
+        offset: options.offset,
+    })
 }
 
 // =============================================================================
@@ -766,6 +1221,273 @@ mod tests {
         assert_eq!(content, "Nested!");
     }
 
+    // -------------------------------------------------------------------------
+    // TextFile Tests
+    // -------------------------------------------------------------------------
+
+    #[test]
+    fn test_text_file_round_trips_encodings() {
+        let utf16: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain("hi\r\n".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        let file = TextFile::decode(&utf16).unwrap();
+        assert_eq!(file.encoding, TextEncoding::Utf16Le);
+        assert_eq!(file.content, "hi\r\n");
+        assert!(file.crlf);
+        assert_eq!(file.encode(&file.content), utf16);
+
+        let bom = b"\xEF\xBB\xBFa\nb\n";
+        let file = TextFile::decode(bom).unwrap();
+        assert_eq!(file.encoding, TextEncoding::Utf8Bom);
+        assert_eq!(file.content, "a\nb\n");
+        assert!(!file.crlf);
+        assert_eq!(file.encode(&file.content), bom);
+
+        assert!(TextFile::decode(b"\xFF\x00\x80").is_none());
+    }
+
+    #[test]
+    fn test_text_file_line_endings() {
+        let file = TextFile::decode(b"a\r\nb\n").unwrap();
+        assert_eq!(file.with_line_endings("x\ny\r\nz"), "x\r\ny\r\nz");
+
+        let file = TextFile::decode(b"a\nb\r\n").unwrap();
+        assert_eq!(file.with_line_endings("x\ny"), "x\ny");
+    }
+
+    #[test]
+    fn test_read_text_file_rejects_binary() {
+        let dir = TempDir::new().unwrap();
+        let file_path = dir.path().join("blob.bin");
+        fs::write(&file_path, [0x00, 0xC3, 0x28]).unwrap();
+
+        let result = read_text_file(file_path.to_str().unwrap(), None);
+        assert!(matches!(result, Err(FileError::UnsupportedEncoding(_))));
+    }
+
+    // -------------------------------------------------------------------------
+    // grep Tests
+    // -------------------------------------------------------------------------
+
+    fn grep_paths(dir: &TempDir, pattern: &str, options: &GrepOptions) -> Vec<String> {
+        let result = grep(pattern, dir.path().to_str().unwrap(), options).unwrap();
+        result.files.into_iter().map(|f| f.path).collect()
+    }
+
+    #[test]
+    fn test_grep_honors_ignore_files() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("build")).unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::create_dir_all(dir.path().join(".hidden")).unwrap();
+        fs::write(dir.path().join(".gitignore"), "build/\n").unwrap();
+        fs::write(dir.path().join(".ignore"), "*.log\n").unwrap();
+        fs::write(dir.path().join("build/out.rs"), "needle").unwrap();
+        fs::write(dir.path().join("src/build_info.rs"), "needle").unwrap();
+        fs::write(dir.path().join("debug.log"), "needle").unwrap();
+        fs::write(dir.path().join(".hidden/notes.txt"), "needle").unwrap();
+
+        let paths = grep_paths(&dir, "needle", &GrepOptions::default());
+        assert_eq!(
+            paths,
+            vec![Path::new("src/build_info.rs").to_string_lossy()]
+        );
+
+        let options = GrepOptions {
+            hidden: true,
+            no_ignore: true,
+            ..Default::default()
+        };
+        assert_eq!(grep_paths(&dir, "needle", &options).len(), 4);
+    }
+
+    #[test]
+    fn test_grep_globs_and_types() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("tests")).unwrap();
+        fs::write(dir.path().join("lib.rs"), "needle").unwrap();
+        fs::write(dir.path().join("tests/it.rs"), "needle").unwrap();
+        fs::write(dir.path().join("app.py"), "needle").unwrap();
+        fs::write(dir.path().join("notes.md"), "needle").unwrap();
+
+        let options = GrepOptions {
+            include: vec!["*.rs".to_string(), "*.md".to_string()],
+            exclude: vec!["tests/**".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            grep_paths(&dir, "needle", &options),
+            vec!["lib.rs", "notes.md"]
+        );
+
+        let options = GrepOptions {
+            file_types: vec!["py".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(grep_paths(&dir, "needle", &options), vec!["app.py"]);
+
+        let options = GrepOptions {
+            file_types: vec!["no-such-type".to_string()],
+            ..Default::default()
+        };
+        assert!(grep("needle", dir.path().to_str().unwrap(), &options).is_err());
+    }
+
+    #[test]
+    fn test_grep_context_lines() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "1\n2\nmatch\n4\n5\n6\n").unwrap();
+
+        let options = GrepOptions {
+            context_before: 1,
+            context_after: 2,
+            ..Default::default()
+        };
+        let result = grep("match", dir.path().to_str().unwrap(), &options).unwrap();
+        let m = &result.matches[0];
+        assert_eq!(m.line_number, 3);
+        assert_eq!(m.context_before, vec!["2"]);
+        assert_eq!(m.context_after, vec!["4", "5"]);
+    }
+
+    #[test]
+    fn test_grep_multiline() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("a.rs"),
+            "fn a() {\n    call();\n}\nfn b() {}\n",
+        )
+        .unwrap();
+
+        let options = GrepOptions {
+            multiline: true,
+            ..Default::default()
+        };
+        let result = grep(r"fn a\(\) \{.*?\}", dir.path().to_str().unwrap(), &options).unwrap();
+        assert_eq!(result.total_matches, 1);
+        assert_eq!(result.matches[0].line_number, 1);
+        assert_eq!(result.matches[0].content, "fn a() {\n    call();\n}");
+
+        let result = grep("^fn", dir.path().to_str().unwrap(), &options).unwrap();
+        assert_eq!(result.total_matches, 2);
+    }
+
+    #[test]
+    fn test_grep_count_and_files_modes() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "x\nx\ny\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "x\n").unwrap();
+        fs::write(dir.path().join("c.bin"), b"x\0x").unwrap();
+
+        let options = GrepOptions {
+            output_mode: GrepOutputMode::Count,
+            ..Default::default()
+        };
+        let result = grep("x", dir.path().to_str().unwrap(), &options).unwrap();
+        assert!(result.matches.is_empty());
+        assert_eq!(result.total_matches, 3);
+        let counts: Vec<_> = result
+            .files
+            .iter()
+            .map(|f| (f.path.as_str(), f.count))
+            .collect();
+        assert_eq!(counts, vec![("a.txt", 2), ("b.txt", 1)]);
+    }
+
+    #[test]
+    fn test_grep_limits_results() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "x\n".repeat(10)).unwrap();
+
+        let options = GrepOptions {
+            max_results: Some(3),
+            ..Default::default()
+        };
+        let result = grep("x", dir.path().to_str().unwrap(), &options).unwrap();
+        assert_eq!(result.matches.len(), 3);
+        assert_eq!(result.total_matches, 10);
+        assert!(result.truncated);
+    }
+
+    // -------------------------------------------------------------------------
+    // find_files Tests
+    // -------------------------------------------------------------------------
+
+    fn set_mtime(path: &Path, secs: u64) {
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(time)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_find_files_glob_sorted_by_mtime() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+        fs::write(dir.path().join("src/old.rs"), "old").unwrap();
+        fs::write(dir.path().join("src/nested/new.rs"), "newer").unwrap();
+        fs::write(dir.path().join("src/notes.md"), "md").unwrap();
+        fs::write(dir.path().join("top.rs"), "top").unwrap();
+        set_mtime(&dir.path().join("src/old.rs"), 1_000);
+        set_mtime(&dir.path().join("src/nested/new.rs"), 2_000);
+
+        let patterns = vec!["src/**/*.rs".to_string()];
+        let result = find_files(
+            &patterns,
+            dir.path().to_str().unwrap(),
+            &FindFilesOptions::default(),
+        )
+        .unwrap();
+
+        let paths: Vec<_> = result.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                Path::new("src/nested/new.rs").to_string_lossy(),
+                Path::new("src/old.rs").to_string_lossy(),
+            ]
+        );
+        assert_eq!(result.files[0].size, 5);
+
+        // `*` doesn't cross directories
+        let patterns = vec!["*.rs".to_string()];
+        let result = find_files(
+            &patterns,
+            dir.path().to_str().unwrap(),
+            &FindFilesOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(result.total, 1);
+        assert_eq!(result.files[0].path, "top.rs");
+    }
+
+    #[test]
+    fn test_find_files_honors_ignore_and_pages() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(".gitignore"), "ignored.txt\n").unwrap();
+        fs::write(dir.path().join("ignored.txt"), "").unwrap();
+        for (i, name) in ["a.txt", "b.txt", "c.txt"].iter().enumerate() {
+            fs::write(dir.path().join(name), "").unwrap();
+            set_mtime(&dir.path().join(name), 1_000 + i as u64);
+        }
+
+        let patterns = vec!["*.txt".to_string()];
+        let options = FindFilesOptions {
+            offset: 1,
+            limit: Some(1),
+            ..Default::default()
+        };
+        let result = find_files(&patterns, dir.path().to_str().unwrap(), &options).unwrap();
+        assert_eq!(result.total, 3);
+        assert_eq!(result.offset, 1);
+        assert_eq!(result.files.len(), 1);
+        assert_eq!(result.files[0].path, "b.txt");
+    }
+
     // -------------------------------------------------------------------------
     // delete_file Tests
     // -------------------------------------------------------------------------
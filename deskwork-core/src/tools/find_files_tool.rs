@@ -0,0 +1,241 @@
+//! FindFiles tool implementation.
+//!
+//! Provides a serdesAI-compatible tool for finding files by glob pattern.
+
+use std::path::Path;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use tracing::debug;
+
+use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
+
+use super::file_ops::{self, FindFilesOptions};
+use super::workspace::Workspace;
+
+/// Tool for finding files by glob pattern.
+#[derive(Debug, Clone, Default)]
+pub struct FindFilesTool {
+    workspace: Workspace,
+}
+
+impl FindFilesTool {
+    /// Create the tool for a workspace.
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FindFilesArgs {
+    pattern: String,
+    directory: Option<String>,
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+    #[serde(default)]
+    include_hidden: bool,
+    #[serde(default)]
+    no_ignore: bool,
+}
+
+#[async_trait]
+impl Tool for FindFilesTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "find_files",
+            "Find files by glob pattern, most recently modified first. Honors .gitignore, \
+             .ignore and hidden-file rules. Returns paths with size and modification time; \
+             use offset and limit to page through large results.",
+        )
+        .with_parameters(
+            SchemaBuilder::new()
+                .string(
+                    "pattern",
+                    "Glob pattern relative to the directory, e.g. \"src/**/*.rs\" or \"*.toml\". \
+                     `*` doesn't match across directories; use `**/` for any depth. \
+                     Separate several patterns with commas.",
+                    true,
+                )
+                .string(
+                    "directory",
+                    "Directory to search. Relative paths are resolved against the workspace. \
+                     Defaults to the workspace root.",
+                    false,
+                )
+                .integer(
+                    "offset",
+                    "Number of matching files to skip, for paging. Defaults to 0.",
+                    false,
+                )
+                .integer(
+                    "limit",
+                    "Maximum number of files to return. Defaults to 100, max 1000.",
+                    false,
+                )
+                .boolean(
+                    "include_hidden",
+                    "Also find hidden files and files in hidden directories. Defaults to false.",
+                    false,
+                )
+                .boolean(
+                    "no_ignore",
+                    "Include files excluded by .gitignore and .ignore. Defaults to false.",
+                    false,
+                )
+                .build()
+                .expect("schema build failed"),
+        )
+    }
+
+    async fn call(&self, _ctx: &RunContext, args: JsonValue) -> ToolResult {
+        debug!(tool = "find_files", ?args, "Tool called");
+
+        let args: FindFilesArgs = super::common::parse_tool_args_lenient(
+            "find_files",
+            args.clone(),
+            self.definition().parameters(),
+        )?;
+
+        let directory = args.directory.as_deref().unwrap_or(".");
+        let path = self.workspace.resolve_dir(args.directory.as_deref());
+        let path = match self.workspace.check_read(&path.to_string_lossy()) {
+            Ok(path) => path,
+            Err(e) => return Ok(ToolReturn::error(e.to_string())),
+        };
+
+        // Skip files the policy hides (e.g. `.env`, or symlinks out of the workspace)
+        let readable = |file: &Path| self.workspace.check_read(&file.to_string_lossy()).is_ok();
+
+        let patterns = super::common::split_list(Some(&args.pattern));
+        let options = FindFilesOptions {
+            offset: args.offset,
+            limit: args.limit,
+            hidden: args.include_hidden,
+            no_ignore: args.no_ignore,
+        };
+        match file_ops::find_files_filtered(&patterns, &path.to_string_lossy(), &options, &readable)
+        {
+            Ok(result) => Ok(ToolReturn::text(format_find_files_output(
+                &result,
+                &args.pattern,
+                directory,
+            ))),
+            Err(e) => Ok(ToolReturn::error(format!("Failed to find files: {}", e))),
+        }
+    }
+}
+
+/// Format find_files results, one file per line, with a paging note.
+fn format_find_files_output(
+    result: &file_ops::FindFilesResult,
+    pattern: &str,
+    directory: &str,
+) -> String {
+    if result.total == 0 {
+        return format!("No files matching '{}' in {}", pattern, directory);
+    }
+    if result.files.is_empty() {
+        return format!(
+            "Found {} files matching '{}' in {}, none at offset {}",
+            result.total, pattern, directory, result.offset
+        );
+    }
+
+    let first = result.offset + 1;
+    let last = result.offset + result.files.len();
+    let mut output = format!(
+        "Found {} files matching '{}' in {} (showing {}-{}, newest first):\n",
+        result.total, pattern, directory, first, last
+    );
+    for file in &result.files {
+        let modified = file
+            .modified
+            .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        output.push_str(&format!(
+            "\n{} ({} bytes, modified {})",
+            file.path, file.size, modified
+        ));
+    }
+    if last < result.total {
+        output.push_str(&format!(
+            "\n\n[{} more files. Use offset={} to see the next page]",
+            result.total - last,
+            last
+        ));
+    }
+    output
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_find_files_tool_pages() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        for name in ["a.rs", "b.rs", "c.rs"] {
+            fs::write(dir.path().join("src").join(name), "fn main() {}").unwrap();
+        }
+        fs::write(dir.path().join("README.md"), "readme").unwrap();
+
+        let tool = FindFilesTool::new(Workspace::new(dir.path()));
+        let ctx = RunContext::minimal("test");
+
+        let result = tool
+            .call(
+                &ctx,
+                serde_json::json!({
+                    "pattern": "src/**/*.rs",
+                    "limit": 2
+                }),
+            )
+            .await;
+
+        let text = result.unwrap().as_text().unwrap().to_string();
+        assert!(text.contains("Found 3 files matching 'src/**/*.rs'"));
+        assert!(text.contains("showing 1-2"));
+        assert!(text.contains("(12 bytes, modified "));
+        assert!(text.contains("[1 more files. Use offset=2 to see the next page]"));
+        assert!(!text.contains("README.md"));
+    }
+
+    #[tokio::test]
+    async fn test_find_files_tool_no_matches() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "").unwrap();
+
+        let tool = FindFilesTool::new(Workspace::new(dir.path()));
+        let ctx = RunContext::minimal("test");
+
+        let result = tool
+            .call(&ctx, serde_json::json!({ "pattern": "**/*.rs" }))
+            .await;
+
+        let text = result.unwrap().as_text().unwrap().to_string();
+        assert!(text.contains("No files matching '**/*.rs'"));
+    }
+
+    #[tokio::test]
+    async fn test_find_files_tool_invalid_pattern() {
+        let dir = TempDir::new().unwrap();
+
+        let tool = FindFilesTool::new(Workspace::new(dir.path()));
+        let ctx = RunContext::minimal("test");
+
+        let result = tool
+            .call(&ctx, serde_json::json!({ "pattern": "src/[" }))
+            .await;
+
+        assert!(result.unwrap().is_error());
+    }
+}
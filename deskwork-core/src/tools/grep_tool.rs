@@ -2,6 +2,9 @@
 //!
 //! Provides a serdesAI-compatible tool for searching text patterns across files.
 
+use std::collections::BTreeMap;
+use std::path::Path;
+
 use async_trait::async_trait;
 use serde::Deserialize;
 use serde_json::Value as JsonValue;
@@ -9,17 +12,61 @@ use tracing::debug;
 
 use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
 
-use super::file_ops;
+use super::file_ops::{self, GrepMatch, GrepOptions, GrepOutputMode};
+use super::workspace::Workspace;
 
 /// Tool for searching text patterns across files.
 #[derive(Debug, Clone, Default)]
-pub struct GrepTool;
+pub struct GrepTool {
+    workspace: Workspace,
+}
+
+impl GrepTool {
+    /// Create the tool for a workspace.
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
 
 #[derive(Debug, Deserialize)]
 struct GrepArgs {
     pattern: String,
     directory: Option<String>,
     max_results: Option<usize>,
+    #[serde(default)]
+    case_insensitive: bool,
+    #[serde(default)]
+    multiline: bool,
+    include: Option<String>,
+    exclude: Option<String>,
+    file_type: Option<String>,
+    context: Option<usize>,
+    context_before: Option<usize>,
+    context_after: Option<usize>,
+    #[serde(default)]
+    include_hidden: bool,
+    #[serde(default)]
+    no_ignore: bool,
+    #[serde(default)]
+    output_mode: GrepOutputMode,
+}
+
+impl GrepArgs {
+    fn options(&self) -> GrepOptions {
+        GrepOptions {
+            max_results: self.max_results,
+            case_insensitive: self.case_insensitive,
+            multiline: self.multiline,
+            include: super::common::split_list(self.include.as_deref()),
+            exclude: super::common::split_list(self.exclude.as_deref()),
+            file_types: super::common::split_list(self.file_type.as_deref()),
+            context_before: self.context_before.or(self.context).unwrap_or(0),
+            context_after: self.context_after.or(self.context).unwrap_or(0),
+            hidden: self.include_hidden,
+            no_ignore: self.no_ignore,
+            output_mode: self.output_mode,
+        }
+    }
 }
 
 #[async_trait]
@@ -27,9 +74,11 @@ impl Tool for GrepTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition::new(
             "grep",
-            "Recursively search for text patterns across files. \
-             Searches recognized text file types while limiting results for performance. \
-             Safety: max 100 matches, lines truncated at 512 chars, files over 5MB skipped.",
+            "Recursively search for a regex across files, in parallel. Honors .gitignore, \
+             .ignore and hidden-file rules and skips binary files. Can filter by glob or file \
+             type, show context lines, match across lines, and list only matching files or \
+             match counts. \
+             Safety: max 100 results, lines truncated at 512 chars, files over 5MB skipped.",
         )
         .with_parameters(
             SchemaBuilder::new()
@@ -40,12 +89,73 @@ impl Tool for GrepTool {
                 )
                 .string(
                     "directory",
-                    "Root directory to start the recursive search. Defaults to '.'.",
+                    "Root directory to start the recursive search. Relative paths are \
+                     resolved against the workspace. Defaults to the workspace root.",
                     false,
                 )
                 .integer(
                     "max_results",
-                    "Maximum number of matches to return. Defaults to 100.",
+                    "Maximum number of matches (or files, for files_with_matches and count) \
+                     to return. Defaults to 100.",
+                    false,
+                )
+                .boolean(
+                    "case_insensitive",
+                    "Match case-insensitively. Defaults to false.",
+                    false,
+                )
+                .boolean(
+                    "multiline",
+                    "Let the pattern match across lines: '.' matches newlines and ^/$ match \
+                     at line boundaries. Defaults to false.",
+                    false,
+                )
+                .string(
+                    "include",
+                    "Comma-separated globs; only matching files are searched \
+                     (e.g. \"*.rs,src/**/*.{ts,tsx}\").",
+                    false,
+                )
+                .string(
+                    "exclude",
+                    "Comma-separated globs of files or directories to skip (e.g. \"tests/**\").",
+                    false,
+                )
+                .string(
+                    "file_type",
+                    "Comma-separated file types to search, as in ripgrep's --type \
+                     (e.g. \"rust\", \"py,js\").",
+                    false,
+                )
+                .integer(
+                    "context",
+                    "Lines of context to show before and after each match.",
+                    false,
+                )
+                .integer(
+                    "context_before",
+                    "Lines of context before each match. Overrides context.",
+                    false,
+                )
+                .integer(
+                    "context_after",
+                    "Lines of context after each match. Overrides context.",
+                    false,
+                )
+                .boolean(
+                    "include_hidden",
+                    "Also search hidden files and directories. Defaults to false.",
+                    false,
+                )
+                .boolean(
+                    "no_ignore",
+                    "Search files excluded by .gitignore and .ignore. Defaults to false.",
+                    false,
+                )
+                .string(
+                    "output_mode",
+                    "\"content\" (matching lines, default), \"files_with_matches\" (file paths \
+                     only) or \"count\" (matches per file).",
                     false,
                 )
                 .build()
@@ -63,30 +173,115 @@ impl Tool for GrepTool {
         )?;
 
         let directory = args.directory.as_deref().unwrap_or(".");
+        let path = self.workspace.resolve_dir(args.directory.as_deref());
+        let path = match self.workspace.check_read(&path.to_string_lossy()) {
+            Ok(path) => path,
+            Err(e) => return Ok(ToolReturn::error(e.to_string())),
+        };
 
-        match file_ops::grep(&args.pattern, directory, args.max_results) {
-            Ok(result) => {
-                if result.matches.is_empty() {
-                    return Ok(ToolReturn::text(format!(
-                        "No matches found for pattern '{}' in {}",
-                        args.pattern, directory
-                    )));
-                }
+        // Skip files the policy hides (e.g. `.env`, or symlinks out of the workspace)
+        let readable = |file: &Path| self.workspace.check_read(&file.to_string_lossy()).is_ok();
 
-                let mut output = format!(
-                    "Found {} matches for '{}' in {}:\n",
-                    result.total_matches, args.pattern, directory
-                );
+        let result = match file_ops::grep_filtered(
+            &args.pattern,
+            &path.to_string_lossy(),
+            &args.options(),
+            &readable,
+        ) {
+            Ok(result) => result,
+            Err(e) => return Ok(ToolReturn::error(format!("Grep failed: {}", e))),
+        };
 
-                for m in &result.matches {
-                    output.push_str(&format!("\n{}:{}:{}", m.path, m.line_number, m.content));
+        if result.files.is_empty() {
+            return Ok(ToolReturn::text(format!(
+                "No matches found for pattern '{}' in {}",
+                args.pattern, directory
+            )));
+        }
+
+        let shown = if result.truncated {
+            match args.output_mode {
+                GrepOutputMode::Content => format!(" (showing first {})", result.matches.len()),
+                _ => format!(" (showing first {} files)", result.files.len()),
+            }
+        } else {
+            String::new()
+        };
+
+        let mut output = match args.output_mode {
+            GrepOutputMode::Content => format!(
+                "Found {} matches for '{}' in {}{}:\n",
+                result.total_matches, args.pattern, directory, shown
+            ),
+            _ => format!(
+                "Found {} matches in {} files for '{}' in {}{}:\n",
+                result.total_matches,
+                result.files.len(),
+                args.pattern,
+                directory,
+                shown
+            ),
+        };
+
+        match args.output_mode {
+            GrepOutputMode::Content => output.push_str(&format_matches(&result.matches)),
+            GrepOutputMode::FilesWithMatches => {
+                for file in &result.files {
+                    output.push_str(&format!("\n{}", file.path));
+                }
+            }
+            GrepOutputMode::Count => {
+                for file in &result.files {
+                    output.push_str(&format!("\n{}:{}", file.path, file.count));
                 }
+            }
+        }
+
+        Ok(ToolReturn::text(output))
+    }
+}
+
+/// Format matches like ripgrep: `path:line:text` for matching lines,
+/// `path-line-text` for context, and `--` between non-adjacent groups.
+fn format_matches(matches: &[GrepMatch]) -> String {
+    let mut output = String::new();
+    let mut start = 0;
+    while start < matches.len() {
+        let path = &matches[start].path;
+        let end = matches[start..]
+            .iter()
+            .position(|m| &m.path != path)
+            .map_or(matches.len(), |n| start + n);
 
-                Ok(ToolReturn::text(output))
+        // Line number -> (is a match line, text); match lines win over context
+        let mut lines: BTreeMap<usize, (bool, &str)> = BTreeMap::new();
+        for m in &matches[start..end] {
+            let first_before = m.line_number - m.context_before.len();
+            for (i, line) in m.context_before.iter().enumerate() {
+                lines.entry(first_before + i).or_insert((false, line));
+            }
+            let mut number = m.line_number;
+            for line in m.content.split('\n') {
+                lines.insert(number, (true, line));
+                number += 1;
+            }
+            for (i, line) in m.context_after.iter().enumerate() {
+                lines.entry(number + i).or_insert((false, line));
             }
-            Err(e) => Ok(ToolReturn::error(format!("Grep failed: {}", e))),
         }
+
+        let mut previous: Option<usize> = None;
+        for (number, (is_match, line)) in lines {
+            if previous.is_some_and(|p| number > p + 1) {
+                output.push_str("\n--");
+            }
+            let sep = if is_match { ':' } else { '-' };
+            output.push_str(&format!("\n{}{}{}{}{}", path, sep, number, sep, line));
+            previous = Some(number);
+        }
+        start = end;
     }
+    output
 }
 
 #[cfg(test)]
@@ -101,7 +296,7 @@ mod tests {
         let file_path = dir.path().join("test.txt");
         fs::write(&file_path, "hello world\nfoo bar\nhello again").unwrap();
 
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -122,13 +317,42 @@ mod tests {
         assert!(text.contains("hello"));
     }
 
+    #[tokio::test]
+    async fn test_grep_skips_denied_files() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("secrets")).unwrap();
+        fs::write(dir.path().join("notes.txt"), "token: public").unwrap();
+        fs::write(dir.path().join("secrets/keys.txt"), "token: hunter2").unwrap();
+
+        let policy = crate::tools::PathPolicy {
+            deny_globs: vec!["**/secrets/**".to_string()],
+            ..Default::default()
+        };
+        let tool = GrepTool::new(Workspace::default().with_policy(policy));
+        let ctx = RunContext::minimal("test");
+
+        let result = tool
+            .call(
+                &ctx,
+                serde_json::json!({
+                    "pattern": "token",
+                    "directory": dir.path().to_str().unwrap()
+                }),
+            )
+            .await;
+
+        let text = result.unwrap().as_text().unwrap().to_string();
+        assert!(text.contains("public"));
+        assert!(!text.contains("hunter2"));
+    }
+
     #[tokio::test]
     async fn test_grep_no_matches() {
         let dir = TempDir::new().unwrap();
         let file_path = dir.path().join("test.txt");
         fs::write(&file_path, "hello world").unwrap();
 
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -149,7 +373,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_grep_invalid_directory() {
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -167,13 +391,63 @@ mod tests {
         assert!(ret.is_error());
     }
 
+    #[tokio::test]
+    async fn test_grep_context_and_filters() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.rs"), "1\nfoo\n3\n4\n5\nfoo\n7\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "foo\n").unwrap();
+
+        let tool = GrepTool::new(Workspace::new(dir.path()));
+        let ctx = RunContext::minimal("test");
+
+        let result = tool
+            .call(
+                &ctx,
+                serde_json::json!({
+                    "pattern": "foo",
+                    "include": "*.rs",
+                    "context": 1
+                }),
+            )
+            .await;
+
+        let text = result.unwrap().as_text().unwrap().to_string();
+        assert!(text.contains("Found 2 matches"));
+        assert!(text.contains("a.rs-1-1\na.rs:2:foo\na.rs-3-3\n--\na.rs-5-5\na.rs:6:foo"));
+        assert!(!text.contains("b.txt"));
+    }
+
+    #[tokio::test]
+    async fn test_grep_count_mode() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "foo\nfoo\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "foo\n").unwrap();
+
+        let tool = GrepTool::new(Workspace::new(dir.path()));
+        let ctx = RunContext::minimal("test");
+
+        let result = tool
+            .call(
+                &ctx,
+                serde_json::json!({
+                    "pattern": "foo",
+                    "output_mode": "count"
+                }),
+            )
+            .await;
+
+        let text = result.unwrap().as_text().unwrap().to_string();
+        assert!(text.contains("Found 3 matches in 2 files"));
+        assert!(text.contains("\na.txt:2\nb.txt:1"));
+    }
+
     #[tokio::test]
     async fn test_grep_case_insensitive() {
         let dir = TempDir::new().unwrap();
         let file_path = dir.path().join("test.txt");
         fs::write(&file_path, "Hello World\nHELLO\nhello").unwrap();
 
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
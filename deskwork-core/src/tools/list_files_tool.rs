@@ -10,13 +10,23 @@ use tracing::debug;
 use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
 
 use super::file_ops;
+use super::workspace::Workspace;
 
 /// Maximum characters in list_files output to protect context window.
 const LIST_FILES_MAX_OUTPUT_CHARS: usize = 100_000;
 
 /// Tool for listing files in a directory.
 #[derive(Debug, Clone, Default)]
-pub struct ListFilesTool;
+pub struct ListFilesTool {
+    workspace: Workspace,
+}
+
+impl ListFilesTool {
+    /// Create the tool for a workspace.
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
 
 #[derive(Debug, Deserialize)]
 struct ListFilesArgs {
@@ -39,8 +49,8 @@ impl Tool for ListFilesTool {
             SchemaBuilder::new()
                 .string(
                     "directory",
-                    "Path to the directory to list. Can be relative or absolute. \
-                     Defaults to '.' (current directory).",
+                    "Path to the directory to list. Can be relative (to the workspace) \
+                     or absolute. Defaults to '.' (the workspace root).",
                     false,
                 )
                 .boolean(
@@ -73,11 +83,16 @@ impl Tool for ListFilesTool {
         )?;
 
         let directory = args.directory.as_deref().unwrap_or(".");
+        let path = self.workspace.resolve_dir(args.directory.as_deref());
+        let path = match self.workspace.check_read(&path.to_string_lossy()) {
+            Ok(path) => path,
+            Err(e) => return Ok(ToolReturn::error(e.to_string())),
+        };
         let recursive = args.recursive.unwrap_or(true);
         let max_depth = args.max_depth;
         let max_entries = args.max_entries;
 
-        match file_ops::list_files(directory, recursive, max_depth, max_entries) {
+        match file_ops::list_files(&path.to_string_lossy(), recursive, max_depth, max_entries) {
             Ok(result) => {
                 let mut output = format_list_files_output(&result, directory, recursive);
 
@@ -154,7 +169,7 @@ mod tests {
         let dir = TempDir::new().unwrap();
         fs::write(dir.path().join("test.txt"), "hello").unwrap();
 
-        let tool = ListFilesTool;
+        let tool = ListFilesTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -175,7 +190,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_list_files_tool_not_found() {
-        let tool = ListFilesTool;
+        let tool = ListFilesTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -194,7 +209,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_list_files_tool_default_directory() {
-        let tool = ListFilesTool;
+        let tool = ListFilesTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool.call(&ctx, serde_json::json!({})).await;
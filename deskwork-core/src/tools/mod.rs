@@ -5,28 +5,50 @@
 pub mod common;
 pub mod diff;
 pub mod file_ops;
+pub mod path_policy;
+pub mod shell_session;
+pub mod workspace;
 
 // Tool implementations
+pub mod apply_patch_tool;
 pub mod delete_file_tool;
 pub mod edit_file_tool;
+pub mod find_files_tool;
 pub mod grep_tool;
 pub mod list_files_tool;
+pub mod process_tools;
 pub mod read_file_tool;
+pub mod replace_in_file_tool;
+pub mod shell_session_tool;
 pub mod shell_tool;
 
 // Registry
 pub mod registry;
 
 // Re-exports - file operations
-pub use file_ops::{FileEntry, FileError, GrepMatch, GrepResult, ListFilesResult, ReadFileResult};
+pub use file_ops::{
+    FileEntry, FileError, FindFilesOptions, FindFilesResult, FoundFile, GrepFileCount, GrepMatch,
+    GrepOptions, GrepOutputMode, GrepResult, ListFilesResult, ReadFileResult, TextEncoding,
+    TextFile,
+};
 
 // Re-exports - tools
+pub use apply_patch_tool::ApplyPatchTool;
 pub use delete_file_tool::DeleteFileTool;
 pub use edit_file_tool::EditFileTool;
+pub use find_files_tool::FindFilesTool;
 pub use grep_tool::GrepTool;
 pub use list_files_tool::ListFilesTool;
+pub use process_tools::{
+    ListProcessesTool, ReadProcessOutputTool, StartBackgroundProcessTool, StopProcessTool,
+};
 pub use read_file_tool::ReadFileTool;
+pub use replace_in_file_tool::ReplaceInFileTool;
+pub use shell_session_tool::ShellSessionTool;
 pub use shell_tool::RunShellCommandTool;
 
 // Re-exports - registry
 pub use registry::ToolRegistry;
+pub use path_policy::PathPolicy;
+pub use shell_session::ShellSession;
+pub use workspace::{OutputSink, Workspace};
@@ -0,0 +1,382 @@
+//! Filesystem access policy for the file tools.
+//!
+//! Decides which paths `read_file`, `edit_file`, `replace_in_file`,
+//! `apply_patch`, `delete_file`, `list_files`, `find_files` and `grep` may
+//! touch:
+//!
+//! - **Deny globs** (e.g. `**/.env`, `**/id_rsa`) are never readable or
+//!   writable, wherever they are.
+//! - **Writable roots** may be read and modified. The workspace root is always
+//!   writable.
+//! - **Read-only roots** may be read but not modified.
+//!
+//! Without a workspace root, reads are only checked against the deny globs;
+//! writes are refused unless they fall under a writable root or
+//! `allow_outside_roots` is set. Paths are checked after resolving `..`
+//! components and symlinks, so neither can be used to escape the roots.
+
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::file_ops::FileError;
+
+/// Deny globs used when the user hasn't configured any.
+pub const DEFAULT_DENY_GLOBS: &[&str] = &[
+    "**/.env",
+    "**/.env.local",
+    "**/id_rsa",
+    "**/id_dsa",
+    "**/id_ecdsa",
+    "**/id_ed25519",
+    "**/.ssh/**",
+    "**/.gnupg/**",
+    "**/.aws/credentials",
+    "**/.netrc",
+    "**/.git-credentials",
+];
+
+/// Kind of access a tool needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// User-configurable filesystem access policy (stored in `Settings`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathPolicy {
+    /// Directories outside the workspace the agent may read.
+    pub read_only_roots: Vec<String>,
+    /// Directories outside the workspace the agent may read and modify.
+    pub writable_roots: Vec<String>,
+    /// Glob patterns for paths that are never readable or writable.
+    pub deny_globs: Vec<String>,
+    /// Allow access anywhere outside the roots. Deny globs still apply.
+    pub allow_outside_roots: bool,
+}
+
+impl Default for PathPolicy {
+    fn default() -> Self {
+        Self {
+            read_only_roots: Vec::new(),
+            writable_roots: Vec::new(),
+            deny_globs: DEFAULT_DENY_GLOBS.iter().map(|g| g.to_string()).collect(),
+            allow_outside_roots: false,
+        }
+    }
+}
+
+impl PathPolicy {
+    /// The first deny glob matching `path`, if any.
+    pub fn denied_by(&self, path: &Path) -> Option<&str> {
+        let options = glob::MatchOptions {
+            case_sensitive: !cfg!(windows),
+            require_literal_separator: false,
+            require_literal_leading_dot: false,
+        };
+        let text = path.to_string_lossy().replace('\\', "/");
+
+        self.deny_globs
+            .iter()
+            .filter(|glob| !glob.trim().is_empty())
+            .find(|glob| match glob::Pattern::new(glob.trim()) {
+                Ok(pattern) => pattern.matches_with(&text, options),
+                Err(e) => {
+                    warn!(glob = %glob, error = %e, "Ignoring invalid deny glob");
+                    false
+                }
+            })
+            .map(String::as_str)
+    }
+
+    /// Check `path` (absolute) for `access`.
+    ///
+    /// `workspace_root` is always writable; `implicit_read_roots` are readable
+    /// in addition to the configured roots. Without a workspace root, reads
+    /// are only checked against the deny globs while writes still need a
+    /// writable root. Returns the path to operate on.
+    pub(crate) fn check(
+        &self,
+        path: &Path,
+        access: Access,
+        workspace_root: Option<&Path>,
+        implicit_read_roots: &[PathBuf],
+    ) -> Result<PathBuf, FileError> {
+        let lexical = normalize_lexically(path);
+        let real = resolve_symlinks(&lexical);
+
+        for candidate in [&lexical, &real] {
+            if let Some(pattern) = self.denied_by(candidate) {
+                return Err(FileError::DeniedByPattern {
+                    path: display(path),
+                    pattern: pattern.to_string(),
+                });
+            }
+        }
+
+        if self.allow_outside_roots || (workspace_root.is_none() && access == Access::Read) {
+            return Ok(lexical);
+        }
+
+        let mut writable: Vec<PathBuf> =
+            workspace_root.map(Path::to_path_buf).into_iter().collect();
+        writable.extend(self.writable_roots.iter().map(PathBuf::from));
+        let mut readable: Vec<PathBuf> = self.read_only_roots.iter().map(PathBuf::from).collect();
+        readable.extend(implicit_read_roots.iter().cloned());
+
+        if is_within_any(&real, &writable) {
+            return Ok(lexical);
+        }
+        if is_within_any(&real, &readable) {
+            return match access {
+                Access::Read => Ok(lexical),
+                Access::Write => Err(FileError::ReadOnlyPath(display(path))),
+            };
+        }
+
+        let allowed = writable.iter().chain(readable.iter());
+        let allowed_roots = allowed.map(|root| display(root)).collect();
+        if is_within_any(&lexical, &writable) || is_within_any(&lexical, &readable) {
+            Err(FileError::SymlinkEscape {
+                path: display(path),
+                target: display(&real),
+            })
+        } else {
+            Err(FileError::OutsideWorkspace {
+                path: display(path),
+                allowed_roots,
+            })
+        }
+    }
+}
+
+fn display(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+/// Remove `.` and `..` components without touching the filesystem.
+///
+/// Leading `..` of a relative path are kept (`a/../../b` is `../b`); `..` at
+/// the root stays at the root.
+pub(crate) fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match out.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    out.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => out.push(component),
+            },
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Resolve symlinks in the longest existing prefix of `path`.
+///
+/// The non-existent tail (a file about to be created) is appended as is.
+fn resolve_symlinks(path: &Path) -> PathBuf {
+    let mut existing = path.to_path_buf();
+    let mut tail = Vec::new();
+    loop {
+        if let Ok(real) = existing.canonicalize() {
+            let mut real = real;
+            real.extend(tail.iter().rev());
+            return real;
+        }
+        match (existing.file_name().map(|n| n.to_os_string()), existing.parent()) {
+            (Some(name), Some(parent)) => {
+                tail.push(name);
+                existing = parent.to_path_buf();
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+/// Whether `path` is inside one of `roots` (roots are resolved like paths).
+fn is_within_any(path: &Path, roots: &[PathBuf]) -> bool {
+    roots
+        .iter()
+        .filter(|root| !root.as_os_str().is_empty())
+        .any(|root| {
+            let root = normalize_lexically(root);
+            path.starts_with(&root) || path.starts_with(resolve_symlinks(&root))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn canonical(dir: &TempDir) -> PathBuf {
+        dir.path().canonicalize().unwrap()
+    }
+
+    #[test]
+    fn test_normalize_lexically() {
+        assert_eq!(
+            normalize_lexically(Path::new("/a/b/../c/./d")),
+            PathBuf::from("/a/c/d")
+        );
+        assert_eq!(normalize_lexically(Path::new("/a/../../b")), PathBuf::from("/b"));
+        assert_eq!(
+            normalize_lexically(Path::new("src/../../etc/x")),
+            PathBuf::from("../etc/x")
+        );
+        assert_eq!(
+            normalize_lexically(Path::new("../../src/x")),
+            PathBuf::from("../../src/x")
+        );
+    }
+
+    #[test]
+    fn test_default_deny_globs() {
+        let policy = PathPolicy::default();
+        assert_eq!(
+            policy.denied_by(Path::new("/home/u/project/.env")),
+            Some("**/.env")
+        );
+        assert!(policy.denied_by(Path::new("/home/u/.ssh/config")).is_some());
+        assert!(policy.denied_by(Path::new("/home/u/.ssh/id_rsa")).is_some());
+        assert!(policy.denied_by(Path::new("/home/u/project/.env.example")).is_none());
+        assert!(policy.denied_by(Path::new("/home/u/project/src/env.rs")).is_none());
+    }
+
+    #[test]
+    fn test_workspace_is_writable() {
+        let dir = TempDir::new().unwrap();
+        let root = canonical(&dir);
+        let policy = PathPolicy::default();
+
+        let path = root.join("src/new.rs");
+        assert_eq!(
+            policy.check(&path, Access::Write, Some(&root), &[]).unwrap(),
+            path
+        );
+    }
+
+    #[test]
+    fn test_outside_workspace_denied() {
+        let dir = TempDir::new().unwrap();
+        let root = canonical(&dir).join("project");
+        fs::create_dir(&root).unwrap();
+        let policy = PathPolicy::default();
+
+        let escape = root.join("../other/file.txt");
+        let err = policy
+            .check(&escape, Access::Read, Some(&root), &[])
+            .unwrap_err();
+        assert!(matches!(err, FileError::OutsideWorkspace { .. }));
+    }
+
+    #[test]
+    fn test_no_workspace_reads_only_check_deny_globs() {
+        let policy = PathPolicy::default();
+        assert!(policy
+            .check(Path::new("/etc/hosts"), Access::Read, None, &[])
+            .is_ok());
+        assert!(matches!(
+            policy.check(Path::new("/srv/app/.env"), Access::Read, None, &[]),
+            Err(FileError::DeniedByPattern { .. })
+        ));
+    }
+
+    #[test]
+    fn test_no_workspace_writes_need_writable_root() {
+        let dir = TempDir::new().unwrap();
+        let writable = canonical(&dir);
+        let policy = PathPolicy {
+            writable_roots: vec![writable.to_string_lossy().to_string()],
+            ..Default::default()
+        };
+
+        assert!(policy
+            .check(&writable.join("notes.md"), Access::Write, None, &[])
+            .is_ok());
+        assert!(matches!(
+            policy.check(Path::new("/etc/hosts"), Access::Write, None, &[]),
+            Err(FileError::OutsideWorkspace { .. })
+        ));
+        assert!(matches!(
+            PathPolicy::default().check(Path::new("/tmp/x.txt"), Access::Write, None, &[]),
+            Err(FileError::OutsideWorkspace { .. })
+        ));
+
+        let policy = PathPolicy {
+            allow_outside_roots: true,
+            ..Default::default()
+        };
+        assert!(policy
+            .check(Path::new("/tmp/x.txt"), Access::Write, None, &[])
+            .is_ok());
+    }
+
+    #[test]
+    fn test_read_only_roots() {
+        let workspace = TempDir::new().unwrap();
+        let docs = TempDir::new().unwrap();
+        let root = canonical(&workspace);
+        let policy = PathPolicy {
+            read_only_roots: vec![canonical(&docs).to_string_lossy().to_string()],
+            ..Default::default()
+        };
+
+        let path = canonical(&docs).join("guide.md");
+        assert!(policy.check(&path, Access::Read, Some(&root), &[]).is_ok());
+        assert!(matches!(
+            policy.check(&path, Access::Write, Some(&root), &[]),
+            Err(FileError::ReadOnlyPath(_))
+        ));
+
+        // Implicit read roots behave the same way
+        let policy = PathPolicy::default();
+        let implicit = [canonical(&docs)];
+        assert!(policy.check(&path, Access::Read, Some(&root), &implicit).is_ok());
+        assert!(policy.check(&path, Access::Write, Some(&root), &implicit).is_err());
+    }
+
+    #[test]
+    fn test_allow_outside_roots() {
+        let dir = TempDir::new().unwrap();
+        let root = canonical(&dir);
+        let policy = PathPolicy {
+            allow_outside_roots: true,
+            ..Default::default()
+        };
+        assert!(policy
+            .check(Path::new("/tmp/elsewhere.txt"), Access::Write, Some(&root), &[])
+            .is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_escape_denied() {
+        let workspace = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        let root = canonical(&workspace);
+        fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.join("link")).unwrap();
+
+        let policy = PathPolicy::default();
+        let err = policy
+            .check(&root.join("link/secret.txt"), Access::Read, Some(&root), &[])
+            .unwrap_err();
+        assert!(matches!(err, FileError::SymlinkEscape { .. }));
+
+        // Creating a file through the link is caught as well
+        let err = policy
+            .check(&root.join("link/new.txt"), Access::Write, Some(&root), &[])
+            .unwrap_err();
+        assert!(matches!(err, FileError::SymlinkEscape { .. }));
+    }
+}
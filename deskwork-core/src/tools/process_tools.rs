@@ -0,0 +1,445 @@
+//! Background process tool implementations.
+//!
+//! Provides serdesAI-compatible tools for starting, inspecting and stopping
+//! long-running commands such as dev servers and file watchers. The
+//! processes themselves are managed by [`crate::processes`].
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use tokio::time::{sleep, Duration};
+use tracing::{debug, info};
+
+use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
+
+use super::workspace::Workspace;
+use crate::processes::{ConversationProcesses, ProcessInfo, ProcessOutput};
+
+/// Time to wait after starting a process before reporting its first output.
+const STARTUP_WAIT: Duration = Duration::from_secs(1);
+
+/// Output lines returned when no limit is given.
+const DEFAULT_OUTPUT_LINES: usize = 200;
+
+/// Maximum output lines returned by a single read.
+const MAX_OUTPUT_LINES: usize = 1_000;
+
+/// Look up the workspace's processes, or the error to return without them.
+fn processes(workspace: &Workspace) -> Result<&ConversationProcesses, ToolReturn> {
+    workspace.processes().ok_or_else(|| {
+        ToolReturn::error(
+            "Background processes are not available. Use run_shell_command instead.".to_string(),
+        )
+    })
+}
+
+/// One-line summary of a process, e.g. `Process 1 (running, pid 42): npm run dev`.
+fn describe(info: &ProcessInfo) -> String {
+    match info.pid {
+        Some(pid) => format!(
+            "Process {} ({}, pid {}): {}",
+            info.id, info.status, pid, info.command
+        ),
+        None => format!("Process {} ({}): {}", info.id, info.status, info.command),
+    }
+}
+
+/// Format output lines read from a process.
+fn format_output(output: &ProcessOutput) -> String {
+    let mut text = describe(&output.info);
+    if output.dropped > 0 {
+        text.push_str(&format!(
+            "\n[{} earlier lines were dropped from the buffer]",
+            output.dropped
+        ));
+    }
+    if output.lines.is_empty() {
+        text.push_str("\nNo new output.");
+    } else {
+        text.push_str(&format!(
+            "\nOutput lines {}-{} of {}:\n",
+            output.start + 1,
+            output.next(),
+            output.info.total_lines
+        ));
+        text.push_str(&output.lines.join("\n"));
+    }
+    if output.next() < output.info.total_lines || output.info.status.is_running() {
+        text.push_str(&format!(
+            "\n\nUse since={} to read only newer output.",
+            output.next()
+        ));
+    }
+    text
+}
+
+// =============================================================================
+// start_background_process
+// =============================================================================
+
+/// Tool for starting a command that keeps running in the background.
+///
+/// Only registered when the workspace has
+/// [`ConversationProcesses`].
+#[derive(Debug, Clone, Default)]
+pub struct StartBackgroundProcessTool {
+    workspace: Workspace,
+}
+
+impl StartBackgroundProcessTool {
+    /// Create the tool for a workspace.
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct StartBackgroundProcessArgs {
+    command: String,
+    working_directory: Option<String>,
+}
+
+#[async_trait]
+impl Tool for StartBackgroundProcessTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "start_background_process",
+            "Start a long-running shell command in the background, such as a dev server, \
+             file watcher or test watcher, and return its ID right away along with the \
+             first second of output. The process keeps running without a timeout until \
+             stopped with stop_process.",
+        )
+        .with_parameters(
+            SchemaBuilder::new()
+                .string("command", "The shell command to run.", true)
+                .string(
+                    "working_directory",
+                    "Working directory for the process. Relative paths are resolved \
+                     against the workspace. Defaults to the workspace root.",
+                    false,
+                )
+                .build()
+                .expect("schema build failed"),
+        )
+    }
+
+    async fn call(&self, _ctx: &RunContext, args: JsonValue) -> ToolResult {
+        debug!(tool = "start_background_process", ?args, "Tool called");
+
+        let args: StartBackgroundProcessArgs = super::common::parse_tool_args_lenient(
+            "start_background_process",
+            args.clone(),
+            self.definition().parameters(),
+        )?;
+
+        let processes = match processes(&self.workspace) {
+            Ok(processes) => processes,
+            Err(error) => return Ok(error),
+        };
+        let working_directory = match args.working_directory.as_deref() {
+            Some(dir) => Some(self.workspace.resolve_dir(Some(dir))),
+            None => self.workspace.root().map(|root| root.to_path_buf()),
+        };
+
+        info!(
+            tool = "start_background_process",
+            command = %args.command,
+            working_directory = ?working_directory,
+            "Starting background process"
+        );
+
+        let info = match processes.start(&args.command, working_directory.as_deref()) {
+            Ok(info) => info,
+            Err(e) => return Ok(ToolReturn::error(e.to_string())),
+        };
+
+        // Give it a moment, so immediate failures and startup output show up
+        sleep(STARTUP_WAIT).await;
+        let output = match processes.read(info.id, Some(0), DEFAULT_OUTPUT_LINES) {
+            Ok(output) => output,
+            Err(e) => return Ok(ToolReturn::error(e.to_string())),
+        };
+        let mut text = format!("Started background process {}.\n", info.id);
+        text.push_str(&format_output(&output));
+        if output.info.status.is_running() {
+            text.push_str(&format!(
+                "\nUse read_process_output with process_id={} to check on it.",
+                info.id
+            ));
+        }
+        Ok(ToolReturn::text(text))
+    }
+}
+
+// =============================================================================
+// read_process_output
+// =============================================================================
+
+/// Tool for reading the buffered output of a background process.
+#[derive(Debug, Clone, Default)]
+pub struct ReadProcessOutputTool {
+    workspace: Workspace,
+}
+
+impl ReadProcessOutputTool {
+    /// Create the tool for a workspace.
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ReadProcessOutputArgs {
+    process_id: u32,
+    since: Option<u64>,
+    max_lines: Option<usize>,
+}
+
+#[async_trait]
+impl Tool for ReadProcessOutputTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "read_process_output",
+            "Read the output (stdout and stderr) of a background process and report \
+             whether it is still running. Only the most recent lines are kept.",
+        )
+        .with_parameters(
+            SchemaBuilder::new()
+                .integer("process_id", "ID of the background process.", true)
+                .integer(
+                    "since",
+                    "Return output starting at this line index, as given by the previous \
+                     read. Omit to get the latest lines.",
+                    false,
+                )
+                .integer(
+                    "max_lines",
+                    "Maximum number of lines to return. Defaults to 200, at most 1000.",
+                    false,
+                )
+                .build()
+                .expect("schema build failed"),
+        )
+    }
+
+    async fn call(&self, _ctx: &RunContext, args: JsonValue) -> ToolResult {
+        debug!(tool = "read_process_output", ?args, "Tool called");
+
+        let args: ReadProcessOutputArgs = super::common::parse_tool_args_lenient(
+            "read_process_output",
+            args.clone(),
+            self.definition().parameters(),
+        )?;
+
+        let processes = match processes(&self.workspace) {
+            Ok(processes) => processes,
+            Err(error) => return Ok(error),
+        };
+        let max_lines = args
+            .max_lines
+            .unwrap_or(DEFAULT_OUTPUT_LINES)
+            .clamp(1, MAX_OUTPUT_LINES);
+
+        match processes.read(args.process_id, args.since, max_lines) {
+            Ok(output) => Ok(ToolReturn::text(format_output(&output))),
+            Err(e) => Ok(ToolReturn::error(e.to_string())),
+        }
+    }
+}
+
+// =============================================================================
+// list_processes
+// =============================================================================
+
+/// Tool for listing the conversation's background processes.
+#[derive(Debug, Clone, Default)]
+pub struct ListProcessesTool {
+    workspace: Workspace,
+}
+
+impl ListProcessesTool {
+    /// Create the tool for a workspace.
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
+
+#[async_trait]
+impl Tool for ListProcessesTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "list_processes",
+            "List the background processes started in this conversation, with their \
+             status and start time.",
+        )
+        .with_parameters(SchemaBuilder::new().build().expect("schema build failed"))
+    }
+
+    async fn call(&self, _ctx: &RunContext, args: JsonValue) -> ToolResult {
+        debug!(tool = "list_processes", ?args, "Tool called");
+
+        let processes = match processes(&self.workspace) {
+            Ok(processes) => processes,
+            Err(error) => return Ok(error),
+        };
+
+        let list = processes.list();
+        if list.is_empty() {
+            return Ok(ToolReturn::text("No background processes.".to_string()));
+        }
+        let lines: Vec<String> = list
+            .iter()
+            .map(|info| {
+                format!(
+                    "{} - started {}",
+                    describe(info),
+                    info.started_at.format("%H:%M:%S UTC")
+                )
+            })
+            .collect();
+        Ok(ToolReturn::text(lines.join("\n")))
+    }
+}
+
+// =============================================================================
+// stop_process
+// =============================================================================
+
+/// Tool for stopping a background process and its children.
+#[derive(Debug, Clone, Default)]
+pub struct StopProcessTool {
+    workspace: Workspace,
+}
+
+impl StopProcessTool {
+    /// Create the tool for a workspace.
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct StopProcessArgs {
+    process_id: u32,
+}
+
+#[async_trait]
+impl Tool for StopProcessTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "stop_process",
+            "Stop a background process along with any processes it started. It is asked \
+             to terminate first and killed if it doesn't exit within a few seconds.",
+        )
+        .with_parameters(
+            SchemaBuilder::new()
+                .integer("process_id", "ID of the background process.", true)
+                .build()
+                .expect("schema build failed"),
+        )
+    }
+
+    async fn call(&self, _ctx: &RunContext, args: JsonValue) -> ToolResult {
+        debug!(tool = "stop_process", ?args, "Tool called");
+
+        let args: StopProcessArgs = super::common::parse_tool_args_lenient(
+            "stop_process",
+            args.clone(),
+            self.definition().parameters(),
+        )?;
+
+        let processes = match processes(&self.workspace) {
+            Ok(processes) => processes,
+            Err(error) => return Ok(error),
+        };
+
+        info!(
+            tool = "stop_process",
+            id = args.process_id,
+            "Stopping process"
+        );
+        match processes.stop(args.process_id).await {
+            Ok(info) => Ok(ToolReturn::text(describe(&info))),
+            Err(e) => Ok(ToolReturn::error(e.to_string())),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::processes::ProcessManager;
+
+    #[tokio::test]
+    async fn test_process_tools_lifecycle() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let manager = ProcessManager::new();
+        let workspace = Workspace::new(dir.path()).with_processes(manager.conversation("chat"));
+        let ctx = RunContext::minimal("test");
+
+        let ret = StartBackgroundProcessTool::new(workspace.clone())
+            .call(
+                &ctx,
+                serde_json::json!({ "command": "echo ready; pwd; sleep 30" }),
+            )
+            .await
+            .unwrap();
+        let text = ret.as_text().unwrap();
+        assert!(
+            text.starts_with("Started background process 1."),
+            "{}",
+            text
+        );
+        assert!(text.contains("(running, pid"));
+        assert!(text.contains("ready"));
+
+        let ret = ListProcessesTool::new(workspace.clone())
+            .call(&ctx, serde_json::json!({}))
+            .await
+            .unwrap();
+        assert!(ret.as_text().unwrap().contains("sleep 30"));
+
+        let ret = ReadProcessOutputTool::new(workspace.clone())
+            .call(&ctx, serde_json::json!({ "process_id": 1, "since": 1 }))
+            .await
+            .unwrap();
+        let text = ret.as_text().unwrap();
+        assert!(!text.contains("ready"));
+        assert!(text.contains(&dir.path().display().to_string()));
+        assert!(text.contains("since=2"));
+
+        let ret = StopProcessTool::new(workspace.clone())
+            .call(&ctx, serde_json::json!({ "process_id": 1 }))
+            .await
+            .unwrap();
+        assert!(ret.as_text().unwrap().starts_with("Process 1 (stopped"));
+    }
+
+    #[tokio::test]
+    async fn test_process_tools_only_see_own_conversation() {
+        let manager = ProcessManager::new();
+        let other = manager.conversation("other");
+        let info = other.start("sleep 30", None).unwrap();
+
+        let workspace = Workspace::default().with_processes(manager.conversation("chat"));
+        let ctx = RunContext::minimal("test");
+        let ret = StopProcessTool::new(workspace)
+            .call(&ctx, serde_json::json!({ "process_id": info.id }))
+            .await
+            .unwrap();
+        assert!(ret.is_error());
+        assert!(other.list()[0].status.is_running());
+
+        manager.kill_conversation("other");
+    }
+
+    #[tokio::test]
+    async fn test_process_tools_require_processes() {
+        let ctx = RunContext::minimal("test");
+        let ret = ListProcessesTool::default()
+            .call(&ctx, serde_json::json!({}))
+            .await
+            .unwrap();
+        assert!(ret.is_error());
+    }
+}
@@ -10,10 +10,20 @@ use tracing::debug;
 use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
 
 use super::file_ops::{self, FileError};
+use super::workspace::Workspace;
 
 /// Tool for reading file contents.
 #[derive(Debug, Clone, Default)]
-pub struct ReadFileTool;
+pub struct ReadFileTool {
+    workspace: Workspace,
+}
+
+impl ReadFileTool {
+    /// Create the tool for a workspace.
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
 
 #[derive(Debug, Deserialize)]
 struct ReadFileArgs {
@@ -63,7 +73,16 @@ impl Tool for ReadFileTool {
             self.definition().parameters(),
         )?;
 
-        match file_ops::read_file(&args.file_path, args.start_line, args.num_lines, None) {
+        let path = match self.workspace.check_read(&args.file_path) {
+            Ok(path) => path,
+            Err(e) => return Ok(ToolReturn::error(e.to_string())),
+        };
+        match file_ops::read_file(
+            &path.to_string_lossy(),
+            args.start_line,
+            args.num_lines,
+            None,
+        ) {
             Ok(result) => {
                 let output = format_read_file_output(&result, &args);
                 Ok(ToolReturn::text(output))
@@ -122,7 +141,7 @@ mod tests {
         let file_path = dir.path().join("test.txt");
         fs::write(&file_path, "Hello, World!").unwrap();
 
-        let tool = ReadFileTool;
+        let tool = ReadFileTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -145,7 +164,7 @@ mod tests {
         let file_path = dir.path().join("test.txt");
         fs::write(&file_path, "Line 1\nLine 2\nLine 3\nLine 4\nLine 5").unwrap();
 
-        let tool = ReadFileTool;
+        let tool = ReadFileTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -166,9 +185,47 @@ mod tests {
         assert!(output.contains("lines 2..3 of 5"));
     }
 
+    #[tokio::test]
+    async fn test_read_file_tool_resolves_relative_to_workspace() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "pub fn hello() {}").unwrap();
+
+        let tool = ReadFileTool::new(Workspace::new(dir.path()));
+        let ctx = RunContext::minimal("test");
+
+        let result = tool
+            .call(&ctx, serde_json::json!({ "file_path": "src/lib.rs" }))
+            .await;
+
+        let output = result.unwrap().as_text().unwrap().to_string();
+        assert_eq!(output, "pub fn hello() {}");
+    }
+
+    #[tokio::test]
+    async fn test_read_file_tool_denies_paths_outside_workspace() {
+        let dir = TempDir::new().unwrap();
+        let workspace = dir.path().join("project");
+        fs::create_dir(&workspace).unwrap();
+        fs::write(dir.path().join("secret.txt"), "secret").unwrap();
+        fs::write(workspace.join(".env"), "API_KEY=secret").unwrap();
+
+        let tool = ReadFileTool::new(Workspace::new(&workspace));
+        let ctx = RunContext::minimal("test");
+
+        for file_path in ["../secret.txt", ".env"] {
+            let result = tool
+                .call(&ctx, serde_json::json!({ "file_path": file_path }))
+                .await;
+            let output = result.unwrap().as_text().unwrap().to_string();
+            assert!(output.contains("Access denied"), "{file_path}: {output}");
+            assert!(!output.contains("secret\n") && !output.contains("API_KEY"));
+        }
+    }
+
     #[tokio::test]
     async fn test_read_file_tool_not_found() {
-        let tool = ReadFileTool;
+        let tool = ReadFileTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -192,7 +249,7 @@ mod tests {
         let large_content = "x".repeat(50_000); // ~12,500 tokens
         fs::write(&file_path, &large_content).unwrap();
 
-        let tool = ReadFileTool;
+        let tool = ReadFileTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -216,7 +273,7 @@ mod tests {
         let file_path = dir.path().join("test.txt");
         fs::write(&file_path, "Line 1\nLine 2\nLine 3").unwrap();
 
-        let tool = ReadFileTool;
+        let tool = ReadFileTool::default();
         let ctx = RunContext::minimal("test");
 
         // Test with string numbers (LLM sometimes does this)
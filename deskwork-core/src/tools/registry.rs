@@ -7,20 +7,32 @@ use std::sync::Arc;
 use serdes_ai_tools::Tool;
 
 use super::{
-    delete_file_tool::DeleteFileTool, edit_file_tool::EditFileTool, grep_tool::GrepTool,
-    list_files_tool::ListFilesTool, read_file_tool::ReadFileTool, shell_tool::RunShellCommandTool,
+    apply_patch_tool::ApplyPatchTool, delete_file_tool::DeleteFileTool,
+    edit_file_tool::EditFileTool, find_files_tool::FindFilesTool, grep_tool::GrepTool,
+    list_files_tool::ListFilesTool,
+    process_tools::{
+        ListProcessesTool, ReadProcessOutputTool, StartBackgroundProcessTool, StopProcessTool,
+    },
+    read_file_tool::ReadFileTool,
+    replace_in_file_tool::ReplaceInFileTool,
+    shell_session_tool::ShellSessionTool,
+    shell_tool::RunShellCommandTool,
+    workspace::Workspace,
 };
 
 /// Registry of all available tools.
 #[derive(Clone, Default)]
 pub struct ToolRegistry {
     tools: Vec<Arc<dyn Tool>>,
+    /// Workspace handed to the default tools.
+    workspace: Workspace,
 }
 
 impl std::fmt::Debug for ToolRegistry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("ToolRegistry")
             .field("tools", &self.names())
+            .field("workspace", &self.workspace)
             .finish()
     }
 }
@@ -28,24 +40,57 @@ impl std::fmt::Debug for ToolRegistry {
 impl ToolRegistry {
     /// Create a new empty registry.
     pub fn new() -> Self {
-        Self { tools: Vec::new() }
+        Self::default()
     }
 
     /// Create a registry with all default tools.
+    ///
+    /// Without a workspace root, tools resolve paths against the process's
+    /// current directory.
     pub fn with_defaults() -> Self {
-        let mut registry = Self::new();
+        Self::with_workspace(Workspace::default())
+    }
+
+    /// Create a registry with all default tools working in `workspace`.
+    pub fn with_workspace(workspace: Workspace) -> Self {
+        let mut registry = Self {
+            tools: Vec::new(),
+            workspace,
+        };
         registry.register_defaults();
         registry
     }
 
     /// Register all default tools.
+    ///
+    /// `shell_session` is only included when the workspace has a
+    /// [`ShellSession`](super::ShellSession), and the background process
+    /// tools only when it has processes.
     pub fn register_defaults(&mut self) {
-        self.register(ListFilesTool);
-        self.register(ReadFileTool);
-        self.register(EditFileTool);
-        self.register(DeleteFileTool);
-        self.register(GrepTool);
-        self.register(RunShellCommandTool);
+        let workspace = self.workspace.clone();
+        self.register(ListFilesTool::new(workspace.clone()));
+        self.register(ReadFileTool::new(workspace.clone()));
+        self.register(EditFileTool::new(workspace.clone()));
+        self.register(ReplaceInFileTool::new(workspace.clone()));
+        self.register(ApplyPatchTool::new(workspace.clone()));
+        self.register(DeleteFileTool::new(workspace.clone()));
+        self.register(GrepTool::new(workspace.clone()));
+        self.register(FindFilesTool::new(workspace.clone()));
+        self.register(RunShellCommandTool::new(workspace.clone()));
+        if workspace.shell_session().is_some() {
+            self.register(ShellSessionTool::new(workspace.clone()));
+        }
+        if workspace.processes().is_some() {
+            self.register(StartBackgroundProcessTool::new(workspace.clone()));
+            self.register(ReadProcessOutputTool::new(workspace.clone()));
+            self.register(ListProcessesTool::new(workspace.clone()));
+            self.register(StopProcessTool::new(workspace));
+        }
+    }
+
+    /// The workspace the default tools work in.
+    pub fn workspace(&self) -> &Workspace {
+        &self.workspace
     }
 
     /// Register a custom tool.
@@ -88,6 +133,8 @@ impl ToolRegistry {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::processes::ProcessManager;
+    use crate::tools::ShellSession;
 
     #[test]
     fn test_empty_registry() {
@@ -100,7 +147,7 @@ mod tests {
     fn test_with_defaults() {
         let registry = ToolRegistry::with_defaults();
         assert!(!registry.is_empty());
-        assert_eq!(registry.len(), 6);
+        assert_eq!(registry.len(), 9);
     }
 
     #[test]
@@ -123,15 +170,48 @@ mod tests {
         assert!(names.contains(&"list_files".to_string()));
         assert!(names.contains(&"read_file".to_string()));
         assert!(names.contains(&"edit_file".to_string()));
+        assert!(names.contains(&"replace_in_file".to_string()));
+        assert!(names.contains(&"apply_patch".to_string()));
         assert!(names.contains(&"delete_file".to_string()));
         assert!(names.contains(&"grep".to_string()));
+        assert!(names.contains(&"find_files".to_string()));
         assert!(names.contains(&"run_shell_command".to_string()));
     }
 
+    #[test]
+    fn test_shell_session_tool_needs_session() {
+        let registry = ToolRegistry::with_defaults();
+        assert!(registry.get("shell_session").is_none());
+
+        let workspace = Workspace::default().with_shell_session(ShellSession::new());
+        let registry = ToolRegistry::with_workspace(workspace);
+        assert_eq!(registry.len(), 10);
+        assert!(registry.get("shell_session").is_some());
+    }
+
+    #[test]
+    fn test_process_tools_need_processes() {
+        let registry = ToolRegistry::with_defaults();
+        assert!(registry.get("start_background_process").is_none());
+
+        let manager = ProcessManager::new();
+        let workspace = Workspace::default().with_processes(manager.conversation("chat"));
+        let registry = ToolRegistry::with_workspace(workspace);
+        assert_eq!(registry.len(), 13);
+        for name in [
+            "start_background_process",
+            "read_process_output",
+            "list_processes",
+            "stop_process",
+        ] {
+            assert!(registry.get(name).is_some(), "{} missing", name);
+        }
+    }
+
     #[test]
     fn test_custom_tool_registration() {
         let mut registry = ToolRegistry::new();
-        registry.register(ListFilesTool);
+        registry.register(ListFilesTool::default());
 
         assert_eq!(registry.len(), 1);
         assert!(registry.get("list_files").is_some());
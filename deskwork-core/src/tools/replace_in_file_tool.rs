@@ -0,0 +1,335 @@
+//! ReplaceInFile tool implementation.
+//!
+//! Provides a serdesAI-compatible tool for editing a file by exact string
+//! replacement, which avoids the line-number mistakes of unified diffs.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use tracing::debug;
+
+use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
+
+use super::file_ops::{self, FileError};
+use super::workspace::Workspace;
+
+/// Lines of context shown around each candidate match.
+const CONTEXT_LINES: usize = 2;
+
+/// Maximum candidate matches listed in an error.
+const MAX_REPORTED_MATCHES: usize = 10;
+
+/// Tool for replacing exact strings in a file.
+#[derive(Debug, Clone, Default)]
+pub struct ReplaceInFileTool {
+    workspace: Workspace,
+}
+
+impl ReplaceInFileTool {
+    /// Create the tool for a workspace.
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ReplaceInFileArgs {
+    file_path: String,
+    old_string: String,
+    new_string: String,
+    #[serde(default)]
+    replace_all: bool,
+}
+
+#[async_trait]
+impl Tool for ReplaceInFileTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "replace_in_file",
+            "Replace an exact string in an existing file. old_string must match the file exactly, \
+             including indentation, and must be unique unless replace_all is set. Include enough \
+             surrounding lines to make it unique.",
+        )
+        .with_parameters(
+            SchemaBuilder::new()
+                .string("file_path", "Path to the file to edit.", true)
+                .string("old_string", "The exact text to replace.", true)
+                .string("new_string", "The text to replace it with.", true)
+                .boolean(
+                    "replace_all",
+                    "Replace every occurrence of old_string instead of requiring a unique match. \
+                     Defaults to false.",
+                    false,
+                )
+                .build()
+                .expect("schema build failed"),
+        )
+    }
+
+    async fn call(&self, ctx: &RunContext, args: JsonValue) -> ToolResult {
+        debug!(tool = "replace_in_file", ?args, "Tool called");
+
+        let args: ReplaceInFileArgs = super::common::parse_tool_args_lenient(
+            "replace_in_file",
+            args.clone(),
+            self.definition().parameters(),
+        )?;
+
+        if args.old_string.is_empty() {
+            return Ok(ToolReturn::error(
+                "old_string must not be empty. Use edit_file to create a file.".to_string(),
+            ));
+        }
+        if args.old_string == args.new_string {
+            return Ok(ToolReturn::error(
+                "old_string and new_string are identical".to_string(),
+            ));
+        }
+
+        let checked_path = match self.workspace.check_write(&args.file_path) {
+            Ok(path) => path,
+            Err(e) => return Ok(ToolReturn::error(e.to_string())),
+        };
+        let path = checked_path.to_string_lossy();
+
+        let file = match file_ops::read_text_file(&path, None) {
+            Ok(file) => file,
+            Err(FileError::NotFound(_)) => {
+                return Ok(ToolReturn::error(format!(
+                    "File not found: {}",
+                    args.file_path
+                )))
+            }
+            Err(e) => return Ok(ToolReturn::error(format!("Failed to read file: {}", e))),
+        };
+
+        // Match in the file's own line endings
+        let old_string = file.with_line_endings(&args.old_string);
+        let new_string = file.with_line_endings(&args.new_string);
+
+        let matches: Vec<usize> = file
+            .content
+            .match_indices(&old_string)
+            .map(|(index, _)| index)
+            .collect();
+        let span = old_string.trim_end_matches('\n').matches('\n').count() + 1;
+
+        if matches.is_empty() {
+            let mut message = format!("old_string not found in {}.", args.file_path);
+            let similar = similar_lines(&file.content, &args.old_string);
+            if !similar.is_empty() {
+                message.push_str(" Lines similar to its first line:");
+                message.push_str(&format_candidates(&file.content, &similar, 1));
+            }
+            return Ok(ToolReturn::error(message));
+        }
+
+        if matches.len() > 1 && !args.replace_all {
+            let lines: Vec<usize> = matches
+                .iter()
+                .map(|&index| line_of(&file.content, index))
+                .collect();
+            return Ok(ToolReturn::error(format!(
+                "old_string matches {} locations in {}. Include more surrounding lines to make \
+                 it unique, or set replace_all to replace every occurrence.{}",
+                matches.len(),
+                args.file_path,
+                format_candidates(&file.content, &lines, span)
+            )));
+        }
+
+        let new_content = if args.replace_all {
+            file.content.replace(&old_string, &new_string)
+        } else {
+            file.content.replacen(&old_string, &new_string, 1)
+        };
+
+        self.workspace.checkpoint(&ctx.run_id, &checked_path);
+        match file_ops::write_file(&path, file.encode(&new_content), false) {
+            Ok(()) => Ok(ToolReturn::text(format!(
+                "Replaced {} occurrence{} in {}",
+                matches.len(),
+                if matches.len() == 1 { "" } else { "s" },
+                args.file_path
+            ))),
+            Err(e) => Ok(ToolReturn::error(format!("Failed to write file: {}", e))),
+        }
+    }
+}
+
+/// Zero-based line number of a byte index.
+fn line_of(content: &str, index: usize) -> usize {
+    content[..index].matches('\n').count()
+}
+
+/// Lines equal to the first non-blank line of `needle`, ignoring surrounding
+/// whitespace.
+fn similar_lines(content: &str, needle: &str) -> Vec<usize> {
+    let Some(first) = needle.lines().map(str::trim).find(|line| !line.is_empty()) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.trim() == first)
+        .map(|(number, _)| number)
+        .collect()
+}
+
+/// Show each candidate (starting line, `span` lines long) with surrounding
+/// lines. Candidate lines are marked with `>`.
+fn format_candidates(content: &str, starts: &[usize], span: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut output = String::new();
+    for &start in starts.iter().take(MAX_REPORTED_MATCHES) {
+        let end = (start + span).min(lines.len());
+        output.push_str(&format!("\n\nLine {}:", start + 1));
+        let from = start.saturating_sub(CONTEXT_LINES);
+        let to = (end + CONTEXT_LINES).min(lines.len());
+        for (number, line) in lines.iter().enumerate().take(to).skip(from) {
+            let marker = if (start..end).contains(&number) {
+                '>'
+            } else {
+                ' '
+            };
+            output.push_str(&format!("\n{} {:>5} | {}", marker, number + 1, line));
+        }
+    }
+    if starts.len() > MAX_REPORTED_MATCHES {
+        output.push_str(&format!(
+            "\n\n... and {} more",
+            starts.len() - MAX_REPORTED_MATCHES
+        ));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    async fn replace(dir: &TempDir, args: JsonValue) -> ToolReturn {
+        let tool = ReplaceInFileTool::new(Workspace::new(dir.path()));
+        let ctx = RunContext::minimal("test");
+        tool.call(&ctx, args).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_replace_unique_match() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+
+        let ret = replace(
+            &dir,
+            serde_json::json!({
+                "file_path": "a.rs",
+                "old_string": "fn b() {}",
+                "new_string": "fn b() -> u8 { 1 }"
+            }),
+        )
+        .await;
+
+        assert!(!ret.is_error(), "{:?}", ret.as_text());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.rs")).unwrap(),
+            "fn a() {}\nfn b() -> u8 { 1 }\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replace_rejects_ambiguous_match() {
+        let dir = TempDir::new().unwrap();
+        let content = "let x = 1;\nfoo();\nlet y = 2;\nfoo();\n";
+        fs::write(dir.path().join("a.rs"), content).unwrap();
+
+        let ret = replace(
+            &dir,
+            serde_json::json!({
+                "file_path": "a.rs",
+                "old_string": "foo();",
+                "new_string": "bar();"
+            }),
+        )
+        .await;
+
+        assert!(ret.is_error());
+        let text = ret.as_text().unwrap();
+        assert!(text.contains("matches 2 locations"));
+        assert!(text.contains("Line 2:"));
+        assert!(text.contains("Line 4:"));
+        assert!(text.contains(">     4 | foo();"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.rs")).unwrap(),
+            content
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replace_all() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.rs"), "foo();\nfoo();\n").unwrap();
+
+        let ret = replace(
+            &dir,
+            serde_json::json!({
+                "file_path": "a.rs",
+                "old_string": "foo",
+                "new_string": "bar",
+                "replace_all": true
+            }),
+        )
+        .await;
+
+        assert_eq!(ret.as_text().unwrap(), "Replaced 2 occurrences in a.rs");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.rs")).unwrap(),
+            "bar();\nbar();\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replace_missing_reports_similar_lines() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.rs"), "fn a() {\n    call();\n}\n").unwrap();
+
+        let ret = replace(
+            &dir,
+            serde_json::json!({
+                "file_path": "a.rs",
+                "old_string": "  call();\n  done();",
+                "new_string": "x"
+            }),
+        )
+        .await;
+
+        assert!(ret.is_error());
+        let text = ret.as_text().unwrap();
+        assert!(text.contains("not found"));
+        assert!(text.contains(">     2 |     call();"));
+    }
+
+    #[tokio::test]
+    async fn test_replace_preserves_crlf_and_bom() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("win.txt");
+        fs::write(&path, b"\xEF\xBB\xBFone\r\ntwo\r\nthree\r\n").unwrap();
+
+        let ret = replace(
+            &dir,
+            serde_json::json!({
+                "file_path": "win.txt",
+                "old_string": "one\ntwo",
+                "new_string": "one\n2\n2b"
+            }),
+        )
+        .await;
+
+        assert!(!ret.is_error(), "{:?}", ret.as_text());
+        assert_eq!(
+            fs::read(&path).unwrap(),
+            b"\xEF\xBB\xBFone\r\n2\r\n2b\r\nthree\r\n"
+        );
+    }
+}
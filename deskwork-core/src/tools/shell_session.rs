@@ -0,0 +1,380 @@
+//! Persistent shell sessions.
+//!
+//! A [`ShellSession`] keeps one shell process alive between commands, so
+//! `cd`, `export` and `source venv/bin/activate` carry over from one step to
+//! the next. After each command the shell prints a sentinel line with the
+//! command's exit code to stdout and another to stderr; output up to the
+//! sentinels belongs to the command.
+
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+use tokio::time::{timeout_at, Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+use crate::processes::signal_process_group;
+
+use super::shell_tool::{describe_exit_code, spawn_reader, CapturedOutput, Stream};
+
+/// A long-lived shell shared by the commands of one conversation.
+///
+/// Clones share the same shell. It starts with the first command, in the
+/// directory given to that command, and is killed by [`ShellSession::reset`],
+/// a command timeout or cancellation, or when the last clone is dropped.
+#[derive(Clone, Default)]
+pub struct ShellSession {
+    shell: Arc<Mutex<Option<ShellProcess>>>,
+}
+
+impl std::fmt::Debug for ShellSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ShellSession")
+    }
+}
+
+impl PartialEq for ShellSession {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.shell, &other.shell)
+    }
+}
+
+impl Eq for ShellSession {}
+
+impl ShellSession {
+    /// Create a session. No shell runs until the first command.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `command` in the session's shell, starting one in `start_dir` if
+    /// none is running.
+    ///
+    /// Output lines are passed to `on_output` as they arrive. Returns the
+    /// exit status line followed by the output, in the same format as
+    /// `run_shell_command`. On timeout or when `cancel` is cancelled the
+    /// shell is killed, so the next command starts from a fresh one.
+    pub async fn run(
+        &self,
+        command: &str,
+        start_dir: Option<&Path>,
+        timeout_secs: u64,
+        on_output: &(dyn Fn(&str) + Sync),
+        cancel: &CancellationToken,
+    ) -> Result<String, String> {
+        let mut shell = self.shell.lock().await;
+
+        // The previous command may have ended the shell (e.g. `exit`)
+        let running = match shell.as_mut() {
+            Some(process) => matches!(process.child.try_wait(), Ok(None)),
+            None => false,
+        };
+        if !running {
+            *shell = Some(ShellProcess::spawn(start_dir)?);
+        }
+        let process = shell.as_mut().expect("shell was just started");
+
+        let sentinel = format!("__DESKWORK_DONE_{}__", uuid::Uuid::new_v4().simple());
+        process
+            .stdin
+            .write_all(command_script(command, &sentinel).as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write to shell: {}", e))?;
+        process
+            .stdin
+            .flush()
+            .await
+            .map_err(|e| format!("Failed to write to shell: {}", e))?;
+
+        let deadline = Instant::now() + Duration::from_secs(timeout_secs);
+        let mut output = CapturedOutput::default();
+        let finished = async {
+            let mut exit_code = None;
+            let mut stderr_done = false;
+            while exit_code.is_none() || !stderr_done {
+                let (stream, line) = process.lines.recv().await?;
+                // Output without a trailing newline ends up on the sentinel's line
+                let (text, rest) = match line.find(&sentinel) {
+                    Some(i) => (&line[..i], Some(&line[i + sentinel.len()..])),
+                    None => (line.as_str(), None),
+                };
+                if (rest.is_none() || !text.is_empty()) && output.push(stream, text) {
+                    on_output(&format!("{}\n", text));
+                }
+                match (stream, rest) {
+                    (Stream::Stdout, Some(rest)) => {
+                        exit_code = Some(rest.trim().parse().unwrap_or(-1));
+                    }
+                    (Stream::Stderr, Some(_)) => stderr_done = true,
+                    _ => {}
+                }
+            }
+            exit_code
+        };
+        let result = tokio::select! {
+            result = timeout_at(deadline, finished) => result.map_err(|_| false),
+            _ = cancel.cancelled() => Err(true),
+        };
+
+        match result {
+            Ok(Some(exit_code)) => Ok(format!(
+                "{}\n{}",
+                describe_exit_code(exit_code),
+                output.finish()
+            )),
+            Ok(None) => {
+                // Both pipes closed before the sentinel: the shell exited
+                let status = match process.child.wait().await {
+                    Ok(status) => status.code().unwrap_or(-1),
+                    Err(_) => -1,
+                };
+                *shell = None;
+                Ok(format!(
+                    "Shell exited (exit code: {}). The next command starts a new shell \
+                     session.\n{}",
+                    status,
+                    output.finish()
+                ))
+            }
+            Err(cancelled) => {
+                *shell = None;
+                let (reason, heading) = if cancelled {
+                    (
+                        "Command cancelled by the user".to_string(),
+                        "Output before cancelling",
+                    )
+                } else {
+                    (
+                        format!("Command timed out after {} seconds", timeout_secs),
+                        "Output before timeout",
+                    )
+                };
+                let mut message = format!(
+                    "{}. The shell session was reset, so the working directory and \
+                     environment are back to their defaults.",
+                    reason
+                );
+                let partial = output.finish();
+                if !partial.is_empty() {
+                    message.push_str(&format!("\n\n{}:\n", heading));
+                    message.push_str(&partial);
+                }
+                Err(message)
+            }
+        }
+    }
+
+    /// Kill the shell, if one is running. The next command starts a fresh
+    /// one. Returns whether a shell was running.
+    pub async fn reset(&self) -> bool {
+        self.shell.lock().await.take().is_some()
+    }
+}
+
+/// Shell input that runs `command` and then prints `sentinel` with the exit
+/// code to stdout and on its own to stderr.
+///
+/// The command goes through `eval` so a syntax error can't leave the shell
+/// waiting for more input, and gets its own stdin so it can't read the
+/// lines that follow.
+fn command_script(command: &str, sentinel: &str) -> String {
+    format!(
+        "eval '{}' < /dev/null\n\
+         __deskwork_status=$?\n\
+         printf '%s %s\\n' '{sentinel}' \"$__deskwork_status\"\n\
+         printf '%s\\n' '{sentinel}' >&2\n",
+        command.replace('\'', "'\\''"),
+    )
+}
+
+/// The running shell and the lines it printed.
+struct ShellProcess {
+    child: Child,
+    stdin: ChildStdin,
+    lines: mpsc::UnboundedReceiver<(Stream, String)>,
+    readers: Vec<JoinHandle<()>>,
+}
+
+impl ShellProcess {
+    fn spawn(start_dir: Option<&Path>) -> Result<Self, String> {
+        if cfg!(windows) {
+            return Err("Persistent shell sessions are not supported on Windows".to_string());
+        }
+
+        // Prefer bash, which understands `source`, over a minimal sh
+        let mut child = match spawn_shell("bash", &["--noprofile", "--norc"], start_dir) {
+            Err(e) if e.kind() == ErrorKind::NotFound => spawn_shell("sh", &[], start_dir),
+            result => result,
+        }
+        .map_err(|e| format!("Failed to start shell: {}", e))?;
+        debug!(pid = ?child.id(), "Started shell session");
+
+        let stdin = child.stdin.take().ok_or("Shell has no stdin")?;
+        let (sender, lines) = mpsc::unbounded_channel();
+        let mut readers = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            readers.push(spawn_reader(stdout, Stream::Stdout, sender.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            readers.push(spawn_reader(stderr, Stream::Stderr, sender));
+        }
+
+        Ok(Self {
+            child,
+            stdin,
+            lines,
+            readers,
+        })
+    }
+}
+
+impl Drop for ShellProcess {
+    fn drop(&mut self) {
+        // The shell leads its own process group, so this also stops the
+        // command it is running
+        if let Some(pid) = self.child.id() {
+            signal_process_group(pid, true);
+        }
+        let _ = self.child.start_kill();
+        for reader in &self.readers {
+            reader.abort();
+        }
+    }
+}
+
+fn spawn_shell(program: &str, args: &[&str], start_dir: Option<&Path>) -> std::io::Result<Child> {
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+
+    if let Some(dir) = start_dir {
+        cmd.current_dir(dir);
+    }
+
+    cmd.stdin(Stdio::piped());
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::piped());
+    cmd.kill_on_drop(true);
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    // Set headless environment
+    cmd.env("TERM", "dumb");
+    cmd.env("NO_COLOR", "1");
+    cmd.env("CLICOLOR", "0");
+
+    cmd.spawn()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    async fn run(session: &ShellSession, command: &str) -> Result<String, String> {
+        let cancel = CancellationToken::new();
+        session.run(command, None, 10, &|_| {}, &cancel).await
+    }
+
+    #[tokio::test]
+    async fn test_session_keeps_cwd_and_env() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        let session = ShellSession::new();
+
+        session
+            .run(
+                "cd sub && export GREETING=hi",
+                Some(dir.path()),
+                10,
+                &|_| {},
+                &CancellationToken::new(),
+            )
+            .await
+            .unwrap();
+        let output = run(&session, "pwd; echo \"$GREETING\"").await.unwrap();
+        assert!(output.starts_with("Command completed successfully"));
+        assert!(output.contains("sub\nhi"), "{}", output);
+
+        assert!(session.reset().await);
+        let output = run(&session, "echo \"[$GREETING]\"").await.unwrap();
+        assert!(output.contains("[]"));
+    }
+
+    #[tokio::test]
+    async fn test_session_reports_exit_code_and_partial_lines() {
+        let session = ShellSession::new();
+
+        let output = run(&session, "printf 'no newline'; echo oops >&2; false")
+            .await
+            .unwrap();
+        assert!(output.starts_with("Command failed (exit code: 1)"));
+        assert!(output.contains("--- stdout ---\nno newline"));
+        assert!(output.contains("--- stderr ---\noops"));
+
+        // Quotes and syntax errors don't break the session
+        run(&session, "echo 'unterminated").await.unwrap();
+        let output = run(&session, "echo \"it's fine\"").await.unwrap();
+        assert!(output.contains("it's fine"));
+    }
+
+    #[tokio::test]
+    async fn test_session_restarts_after_exit() {
+        let session = ShellSession::new();
+
+        run(&session, "export KEPT=1").await.unwrap();
+        let output = run(&session, "exit 3").await.unwrap();
+        assert!(output.starts_with("Shell exited (exit code: 3)"));
+
+        let output = run(&session, "echo \"[$KEPT]\"").await.unwrap();
+        assert!(output.contains("[]"));
+    }
+
+    #[tokio::test]
+    async fn test_session_timeout_resets_shell() {
+        let session = ShellSession::new();
+
+        run(&session, "export KEPT=1").await.unwrap();
+        let error = session
+            .run(
+                "echo started; sleep 5",
+                None,
+                1,
+                &|_| {},
+                &CancellationToken::new(),
+            )
+            .await
+            .unwrap_err();
+        assert!(error.contains("timed out after 1 seconds"));
+        assert!(error.contains("started"));
+
+        let output = run(&session, "echo \"[$KEPT]\"").await.unwrap();
+        assert!(output.contains("[]"));
+    }
+
+    #[tokio::test]
+    async fn test_session_cancel_resets_shell() {
+        let session = ShellSession::new();
+        let cancel = CancellationToken::new();
+
+        run(&session, "export KEPT=1").await.unwrap();
+        let canceller = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            canceller.cancel();
+        });
+        let error = session
+            .run("echo started; sleep 30", None, 60, &|_| {}, &cancel)
+            .await
+            .unwrap_err();
+        assert!(error.contains("cancelled by the user"));
+        assert!(error.contains("started"));
+
+        let output = run(&session, "echo \"[$KEPT]\"").await.unwrap();
+        assert!(output.contains("[]"));
+    }
+}
@@ -0,0 +1,183 @@
+//! ShellSession tool implementation.
+//!
+//! Provides a serdesAI-compatible tool for running commands in a persistent
+//! shell, so the working directory and environment carry over between calls.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use tracing::{debug, info};
+
+use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
+
+use super::workspace::Workspace;
+
+/// Default timeout for session commands (60 seconds).
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// Tool for running commands in the conversation's persistent shell.
+///
+/// Only registered when the workspace has a
+/// [`ShellSession`](super::shell_session::ShellSession).
+#[derive(Debug, Clone, Default)]
+pub struct ShellSessionTool {
+    workspace: Workspace,
+}
+
+impl ShellSessionTool {
+    /// Create the tool for a workspace.
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ShellSessionArgs {
+    command: Option<String>,
+    timeout_seconds: Option<u64>,
+    #[serde(default)]
+    reset: bool,
+}
+
+#[async_trait]
+impl Tool for ShellSessionTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "shell_session",
+            "Run a shell command in a persistent shell. The working directory, environment \
+             variables and activated virtualenvs carry over to later calls, so `cd`, `export` \
+             and `source` work across steps. The shell starts in the workspace root.",
+        )
+        .with_parameters(
+            SchemaBuilder::new()
+                .string(
+                    "command",
+                    "The shell command to execute. May be omitted when resetting.",
+                    false,
+                )
+                .integer(
+                    "timeout_seconds",
+                    "Maximum time to wait for command completion. Defaults to 60 seconds. \
+                     A command that times out is killed along with the shell.",
+                    false,
+                )
+                .boolean(
+                    "reset",
+                    "Start a fresh shell in the workspace root before running the command, \
+                     discarding the current directory and environment. Defaults to false.",
+                    false,
+                )
+                .build()
+                .expect("schema build failed"),
+        )
+    }
+
+    async fn call(&self, ctx: &RunContext, args: JsonValue) -> ToolResult {
+        debug!(tool = "shell_session", ?args, "Tool called");
+
+        let args: ShellSessionArgs = super::common::parse_tool_args_lenient(
+            "shell_session",
+            args.clone(),
+            self.definition().parameters(),
+        )?;
+
+        let Some(session) = self.workspace.shell_session() else {
+            return Ok(ToolReturn::error(
+                "No shell session is available. Use run_shell_command instead.".to_string(),
+            ));
+        };
+
+        let was_running = args.reset && session.reset().await;
+        let Some(command) = args.command.filter(|c| !c.trim().is_empty()) else {
+            return Ok(if !args.reset {
+                ToolReturn::error("Provide a command, or set reset to true.".to_string())
+            } else if was_running {
+                ToolReturn::text("Shell session reset.".to_string())
+            } else {
+                ToolReturn::text(
+                    "No shell was running; the next command starts a new one.".to_string(),
+                )
+            });
+        };
+
+        let timeout_secs = args.timeout_seconds.unwrap_or(DEFAULT_TIMEOUT_SECS);
+        info!(
+            tool = "shell_session",
+            command = %command,
+            timeout = timeout_secs,
+            "Executing command in session"
+        );
+
+        // Stream lines to the GUI while the command runs
+        let on_output = |chunk: &str| {
+            self.workspace
+                .emit_output(ctx.tool_call_id.as_deref(), chunk)
+        };
+
+        match session
+            .run(
+                &command,
+                self.workspace.root(),
+                timeout_secs,
+                &on_output,
+                self.workspace.cancel_token(),
+            )
+            .await
+        {
+            Ok(result) => Ok(ToolReturn::text(result)),
+            Err(e) => Ok(ToolReturn::error(format!(
+                "Command execution failed: {}",
+                e
+            ))),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::tools::ShellSession;
+
+    #[tokio::test]
+    async fn test_shell_session_keeps_state_between_calls() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub/inner.txt"), "").unwrap();
+
+        let workspace = Workspace::new(dir.path()).with_shell_session(ShellSession::new());
+        let tool = ShellSessionTool::new(workspace);
+        let ctx = RunContext::minimal("test");
+
+        let ret = tool
+            .call(&ctx, serde_json::json!({ "command": "cd sub" }))
+            .await
+            .unwrap();
+        assert!(!ret.is_error(), "{:?}", ret.as_text());
+
+        let ret = tool
+            .call(&ctx, serde_json::json!({ "command": "ls" }))
+            .await
+            .unwrap();
+        assert!(ret.as_text().unwrap().contains("inner.txt"));
+
+        // A reset starts over in the workspace root
+        let ret = tool
+            .call(&ctx, serde_json::json!({ "command": "ls", "reset": true }))
+            .await
+            .unwrap();
+        let text = ret.as_text().unwrap();
+        assert!(text.contains("sub") && !text.contains("inner.txt"));
+    }
+
+    #[tokio::test]
+    async fn test_shell_session_requires_session() {
+        let tool = ShellSessionTool::default();
+        let ctx = RunContext::minimal("test");
+
+        let ret = tool
+            .call(&ctx, serde_json::json!({ "command": "pwd" }))
+            .await
+            .unwrap();
+        assert!(ret.is_error());
+    }
+}
@@ -2,27 +2,49 @@
 //!
 //! Provides a serdesAI-compatible tool for executing shell commands.
 
-use std::process::Stdio;
+use std::process::{ExitStatus, Stdio};
 
 use async_trait::async_trait;
 use serde::Deserialize;
 use serde_json::Value as JsonValue;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::Command;
-use tokio::time::{timeout, Duration};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep_until, timeout_at, Duration, Instant};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info};
 
 use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
 
+use crate::processes::signal_process_group;
+
+use super::workspace::Workspace;
+
 /// Tool for executing shell commands.
+///
+/// Commands run in the workspace root unless a working directory is given.
 #[derive(Debug, Clone, Default)]
-pub struct RunShellCommandTool;
+pub struct RunShellCommandTool {
+    workspace: Workspace,
+}
+
+impl RunShellCommandTool {
+    /// Create the tool for a workspace.
+    pub fn new(workspace: Workspace) -> Self {
+        Self { workspace }
+    }
+}
 
 /// Default timeout for shell commands (60 seconds).
 const DEFAULT_TIMEOUT_SECS: u64 = 60;
 
 /// Maximum characters in shell output.
-const MAX_OUTPUT_CHARS: usize = 50_000;
+pub(super) const MAX_OUTPUT_CHARS: usize = 50_000;
+
+/// How long output is still read after the command exits. Anything it left
+/// in the background may keep the pipes open much longer.
+const PIPE_DRAIN_GRACE: Duration = Duration::from_millis(500);
 
 #[derive(Debug, Deserialize)]
 struct RunShellCommandArgs {
@@ -44,7 +66,8 @@ impl Tool for RunShellCommandTool {
                 .string("command", "The shell command to execute.", true)
                 .string(
                     "working_directory",
-                    "Working directory for command execution. Defaults to current directory.",
+                    "Working directory for command execution. Relative paths are resolved \
+                     against the workspace. Defaults to the workspace root.",
                     false,
                 )
                 .integer(
@@ -57,7 +80,7 @@ impl Tool for RunShellCommandTool {
         )
     }
 
-    async fn call(&self, _ctx: &RunContext, args: JsonValue) -> ToolResult {
+    async fn call(&self, ctx: &RunContext, args: JsonValue) -> ToolResult {
         debug!(tool = "run_shell_command", ?args, "Tool called");
 
         let args: RunShellCommandArgs = super::common::parse_tool_args_lenient(
@@ -67,19 +90,31 @@ impl Tool for RunShellCommandTool {
         )?;
 
         let timeout_secs = args.timeout_seconds.unwrap_or(DEFAULT_TIMEOUT_SECS);
+        let working_directory = match args.working_directory.as_deref() {
+            Some(dir) => Some(self.workspace.resolve_dir(Some(dir))),
+            None => self.workspace.root().map(|root| root.to_path_buf()),
+        };
 
         info!(
             tool = "run_shell_command",
             command = %args.command,
-            working_directory = ?args.working_directory,
+            working_directory = ?working_directory,
             timeout = timeout_secs,
             "Executing command"
         );
 
+        // Stream lines to the GUI while the command runs
+        let on_output = |chunk: &str| {
+            self.workspace
+                .emit_output(ctx.tool_call_id.as_deref(), chunk)
+        };
+
         match execute_command(
             &args.command,
-            args.working_directory.as_deref(),
+            working_directory.as_deref(),
             timeout_secs,
+            &on_output,
+            self.workspace.cancel_token(),
         )
         .await
         {
@@ -92,10 +127,73 @@ impl Tool for RunShellCommandTool {
     }
 }
 
+/// Which pipe a line of output came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Stream {
+    Stdout,
+    Stderr,
+}
+
+impl Stream {
+    fn marker(self) -> &'static str {
+        match self {
+            Stream::Stdout => "\n--- stdout ---\n",
+            Stream::Stderr => "\n--- stderr ---\n",
+        }
+    }
+}
+
+/// Output collected from both pipes in the order it arrived.
+///
+/// A `--- stdout ---` / `--- stderr ---` marker starts each run of lines
+/// from the same pipe.
+#[derive(Debug, Default)]
+pub(super) struct CapturedOutput {
+    text: String,
+    current: Option<Stream>,
+    truncated: bool,
+}
+
+impl CapturedOutput {
+    /// Append a line. Returns false once the output limit is reached.
+    pub(super) fn push(&mut self, stream: Stream, line: &str) -> bool {
+        if self.truncated {
+            return false;
+        }
+        if self.current != Some(stream) {
+            self.text.push_str(stream.marker());
+            self.current = Some(stream);
+        } else {
+            self.text.push('\n');
+        }
+        let remaining = MAX_OUTPUT_CHARS.saturating_sub(self.text.len());
+        if line.len() <= remaining {
+            self.text.push_str(line);
+            return true;
+        }
+        let mut cut = remaining;
+        while !line.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        self.text.push_str(&line[..cut]);
+        self.truncated = true;
+        false
+    }
+
+    pub(super) fn finish(mut self) -> String {
+        if self.truncated {
+            self.text.push_str("\n\n[OUTPUT TRUNCATED]");
+        }
+        self.text
+    }
+}
+
 async fn execute_command(
     command: &str,
-    working_directory: Option<&str>,
+    working_directory: Option<&std::path::Path>,
     timeout_secs: u64,
+    on_output: &(dyn Fn(&str) + Sync),
+    cancel: &CancellationToken,
 ) -> Result<String, String> {
     let shell = if cfg!(windows) { "cmd" } else { "sh" };
     let shell_arg = if cfg!(windows) { "/C" } else { "-c" };
@@ -104,15 +202,22 @@ async fn execute_command(
     cmd.arg(shell_arg).arg(command);
 
     if let Some(dir) = working_directory {
-        let path = std::path::Path::new(dir);
-        if !path.exists() {
-            return Err(format!("Working directory does not exist: {}", dir));
+        if !dir.exists() {
+            return Err(format!(
+                "Working directory does not exist: {}",
+                dir.display()
+            ));
         }
         cmd.current_dir(dir);
     }
 
+    cmd.stdin(Stdio::null());
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::piped());
+    cmd.kill_on_drop(true);
+    // Lead a process group, so stopping the command also stops its children
+    #[cfg(unix)]
+    cmd.process_group(0);
 
     // Set headless environment
     cmd.env("TERM", "dumb");
@@ -120,87 +225,220 @@ async fn execute_command(
     cmd.env("CLICOLOR", "0");
 
     let mut child = cmd.spawn().map_err(|e| format!("Failed to spawn: {}", e))?;
+    let mut group = ProcessGroupGuard(child.id());
 
-    let stdout = child.stdout.take();
-    let stderr = child.stderr.take();
-
-    // Collect output with timeout
-    let output_future = async {
-        let mut stdout_lines = Vec::new();
-        let mut stderr_lines = Vec::new();
-
-        if let Some(stdout) = stdout {
-            let reader = BufReader::new(stdout);
-            let mut lines = reader.lines();
-            while let Ok(Some(line)) = lines.next_line().await {
-                stdout_lines.push(line);
+    // Read both pipes at once, so a command filling one of them can't block
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut readers = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        readers.push(spawn_reader(stdout, Stream::Stdout, sender.clone()));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        readers.push(spawn_reader(stderr, Stream::Stderr, sender.clone()));
+    }
+    drop(sender);
+
+    let deadline = Instant::now() + Duration::from_secs(timeout_secs);
+    let mut output = CapturedOutput::default();
+    let finished = async {
+        let mut exit_status = None;
+        let mut drain_deadline = None;
+        loop {
+            tokio::select! {
+                next = receiver.recv() => match next {
+                    // Past the output limit, keep draining the pipes but drop the lines
+                    Some((stream, line)) => {
+                        if output.push(stream, &line) {
+                            on_output(&format!("{}\n", line));
+                        }
+                    }
+                    None => break,
+                },
+                status = child.wait(), if exit_status.is_none() => {
+                    exit_status = Some(status);
+                    drain_deadline = Some(Instant::now() + PIPE_DRAIN_GRACE);
+                }
+                _ = sleep_until(drain_deadline.unwrap_or(deadline)), if drain_deadline.is_some() => {
+                    debug!("Pipes still open after the command exited, not waiting for them");
+                    break;
+                }
             }
         }
-
-        if let Some(stderr) = stderr {
-            let reader = BufReader::new(stderr);
-            let mut lines = reader.lines();
-            while let Ok(Some(line)) = lines.next_line().await {
-                stderr_lines.push(line);
-            }
+        match exit_status {
+            Some(status) => status,
+            None => child.wait().await,
         }
-
-        let exit_status = child.wait().await;
-        (stdout_lines, stderr_lines, exit_status)
     };
-
-    let result = timeout(Duration::from_secs(timeout_secs), output_future).await;
+    let result = tokio::select! {
+        result = timeout_at(deadline, finished) => result.map_err(|_| false),
+        _ = cancel.cancelled() => Err(true),
+    };
 
     match result {
-        Ok((stdout_lines, stderr_lines, exit_status)) => {
-            let exit_code = exit_status.map(|s| s.code().unwrap_or(-1)).unwrap_or(-1);
-
-            let success = exit_code == 0;
-
-            let mut output = String::new();
-
-            if success {
-                output.push_str(&format!(
-                    "Command completed successfully (exit code: {})\n",
-                    exit_code
-                ));
+        Ok(exit_status) => {
+            // Leave anything the command put in the background running
+            group.0 = None;
+            let exit_status = exit_status.map_err(|e| format!("Failed to wait: {}", e))?;
+            Ok(format!(
+                "{}\n{}",
+                describe_exit(exit_status),
+                output.finish()
+            ))
+        }
+        Err(cancelled) => {
+            // Kill the process group and report what it printed so far
+            drop(group);
+            let _ = child.kill().await;
+            for reader in readers {
+                reader.abort();
+            }
+            let (mut message, heading) = if cancelled {
+                (
+                    "Command cancelled by the user".to_string(),
+                    "Output before cancelling",
+                )
             } else {
-                output.push_str(&format!("Command failed (exit code: {})\n", exit_code));
+                (
+                    format!("Command timed out after {} seconds", timeout_secs),
+                    "Output before timeout",
+                )
+            };
+            let partial = output.finish();
+            if !partial.is_empty() {
+                message.push_str(&format!("\n\n{}:\n", heading));
+                message.push_str(&partial);
             }
+            Err(message)
+        }
+    }
+}
 
-            if !stdout_lines.is_empty() {
-                output.push_str("\n--- stdout ---\n");
-                output.push_str(&stdout_lines.join("\n"));
-            }
+/// Kills the process group led by a command when dropped, including when the
+/// tool call itself is dropped mid-command.
+struct ProcessGroupGuard(Option<u32>);
 
-            if !stderr_lines.is_empty() {
-                output.push_str("\n--- stderr ---\n");
-                output.push_str(&stderr_lines.join("\n"));
-            }
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        if let Some(pid) = self.0 {
+            signal_process_group(pid, true);
+        }
+    }
+}
 
-            // Truncate if needed
-            if output.len() > MAX_OUTPUT_CHARS {
-                output.truncate(MAX_OUTPUT_CHARS);
-                output.push_str("\n\n[OUTPUT TRUNCATED]");
+/// Forward lines from a pipe until it closes. Invalid UTF-8 is replaced
+/// rather than ending the stream, and lines are cut to [`MAX_OUTPUT_CHARS`].
+///
+/// Once the receiver is gone the rest is read and dropped, so a process left
+/// in the background isn't killed by a closed pipe when it writes.
+pub(super) fn spawn_reader(
+    pipe: impl AsyncRead + Unpin + Send + 'static,
+    stream: Stream,
+    sender: mpsc::UnboundedSender<(Stream, String)>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(pipe);
+        let mut buffer = Vec::new();
+        loop {
+            buffer.clear();
+            match read_line_capped(&mut reader, &mut buffer, MAX_OUTPUT_CHARS).await {
+                Ok(0) | Err(_) => break,
+                Ok(_) if sender.is_closed() => {}
+                Ok(_) => {
+                    let line = String::from_utf8_lossy(&buffer);
+                    let line = line.trim_end_matches(['\n', '\r']).to_string();
+                    let _ = sender.send((stream, line));
+                }
             }
+        }
+    })
+}
 
-            Ok(output)
+/// Read a line into `buffer`, keeping at most `max` bytes of it; the rest of
+/// a longer line is read and dropped. Returns the number of bytes read, 0 at
+/// the end of the input.
+async fn read_line_capped(
+    reader: &mut (impl AsyncBufRead + Unpin),
+    buffer: &mut Vec<u8>,
+    max: usize,
+) -> std::io::Result<usize> {
+    let mut read = 0;
+    loop {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            return Ok(read);
         }
-        Err(_) => {
-            // Timeout - try to kill process
-            let _ = child.kill().await;
-            Err(format!("Command timed out after {} seconds", timeout_secs))
+        let (chunk, line_end) = match available.iter().position(|&b| b == b'\n') {
+            Some(i) => (&available[..=i], true),
+            None => (available, false),
+        };
+        let keep = max.saturating_sub(buffer.len()).min(chunk.len());
+        buffer.extend_from_slice(&chunk[..keep]);
+        let used = chunk.len();
+        reader.consume(used);
+        read += used;
+        if line_end {
+            return Ok(read);
+        }
+    }
+}
+
+/// Status line for how the command ended.
+fn describe_exit(status: ExitStatus) -> String {
+    if let Some(code) = status.code() {
+        return describe_exit_code(code);
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return match signal_name(signal) {
+                Some(name) => format!("Command terminated by signal {} ({})", signal, name),
+                None => format!("Command terminated by signal {}", signal),
+            };
         }
     }
+
+    format!("Command failed ({})", status)
+}
+
+/// Status line for a command that exited with `code`.
+pub(super) fn describe_exit_code(code: i32) -> String {
+    if code == 0 {
+        format!("Command completed successfully (exit code: {})", code)
+    } else {
+        format!("Command failed (exit code: {})", code)
+    }
+}
+
+/// Names of the common signals, which have the same numbers on Linux and
+/// macOS.
+#[cfg(unix)]
+fn signal_name(signal: i32) -> Option<&'static str> {
+    Some(match signal {
+        1 => "SIGHUP",
+        2 => "SIGINT",
+        3 => "SIGQUIT",
+        4 => "SIGILL",
+        6 => "SIGABRT",
+        8 => "SIGFPE",
+        9 => "SIGKILL",
+        11 => "SIGSEGV",
+        13 => "SIGPIPE",
+        14 => "SIGALRM",
+        15 => "SIGTERM",
+        _ => return None,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tools::OutputSink;
 
     #[tokio::test]
     async fn test_shell_echo() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -217,7 +455,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_shell_exit_code() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -233,7 +471,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_shell_invalid_working_directory() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -251,9 +489,37 @@ mod tests {
         assert!(ret.is_error());
     }
 
+    #[tokio::test]
+    async fn test_shell_defaults_to_workspace_root() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("marker.txt"), "").unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub/inner.txt"), "").unwrap();
+
+        let tool = RunShellCommandTool::new(Workspace::new(dir.path()));
+        let ctx = RunContext::minimal("test");
+
+        let command = if cfg!(windows) { "dir /b" } else { "ls" };
+        let ret = tool
+            .call(&ctx, serde_json::json!({ "command": command }))
+            .await
+            .unwrap();
+        assert!(ret.as_text().unwrap().contains("marker.txt"));
+
+        // Relative working directories resolve against the workspace too
+        let ret = tool
+            .call(
+                &ctx,
+                serde_json::json!({ "command": command, "working_directory": "sub" }),
+            )
+            .await
+            .unwrap();
+        assert!(ret.as_text().unwrap().contains("inner.txt"));
+    }
+
     #[tokio::test]
     async fn test_shell_captures_stderr() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -266,4 +532,162 @@ mod tests {
         assert!(text.contains("stderr"));
         assert!(text.contains("error"));
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shell_streams_interleaved_output() {
+        let chunks = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink_chunks = chunks.clone();
+        let workspace = Workspace::default().with_output_sink(OutputSink::new(move |_, chunk| {
+            sink_chunks.lock().unwrap().push(chunk.to_string())
+        }));
+        let tool = RunShellCommandTool::new(workspace);
+        let ctx = RunContext::minimal("test");
+
+        let command = "echo one; sleep 0.2; echo two >&2; sleep 0.2; echo three";
+        let ret = tool
+            .call(&ctx, serde_json::json!({ "command": command }))
+            .await
+            .unwrap();
+
+        assert_eq!(*chunks.lock().unwrap(), vec!["one\n", "two\n", "three\n"]);
+        assert!(ret
+            .as_text()
+            .unwrap()
+            .ends_with("\n--- stdout ---\none\n--- stderr ---\ntwo\n--- stdout ---\nthree"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shell_does_not_block_on_full_stderr() {
+        let tool = RunShellCommandTool::default();
+        let ctx = RunContext::minimal("test");
+
+        // More than a pipe buffer on stderr while stdout stays open
+        let command = "yes | head -n 100000 >&2; echo done";
+        let ret = tool
+            .call(
+                &ctx,
+                serde_json::json!({ "command": command, "timeout_seconds": 10 }),
+            )
+            .await
+            .unwrap();
+
+        let text = ret.as_text().unwrap();
+        assert!(!ret.is_error(), "{}", text);
+        assert!(text.starts_with("Command completed successfully"));
+        assert!(text.ends_with("[OUTPUT TRUNCATED]"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shell_reports_signal() {
+        let tool = RunShellCommandTool::default();
+        let ctx = RunContext::minimal("test");
+
+        let ret = tool
+            .call(&ctx, serde_json::json!({ "command": "kill -9 $$" }))
+            .await
+            .unwrap();
+
+        assert!(ret
+            .as_text()
+            .unwrap()
+            .contains("Command terminated by signal 9 (SIGKILL)"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shell_timeout_keeps_partial_output() {
+        let tool = RunShellCommandTool::default();
+        let ctx = RunContext::minimal("test");
+
+        let ret = tool
+            .call(
+                &ctx,
+                serde_json::json!({ "command": "echo started; sleep 5", "timeout_seconds": 1 }),
+            )
+            .await
+            .unwrap();
+
+        assert!(ret.is_error());
+        let text = ret.as_text().unwrap();
+        assert!(text.contains("timed out after 1 seconds"));
+        assert!(text.contains("started"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shell_cancel_stops_command() {
+        let cancel = CancellationToken::new();
+        let tool = RunShellCommandTool::new(Workspace::default().with_cancel(cancel.clone()));
+        let ctx = RunContext::minimal("test");
+
+        let started = Instant::now();
+        let canceller = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            cancel.cancel();
+        });
+        let ret = tool
+            .call(
+                &ctx,
+                serde_json::json!({ "command": "echo started; sleep 30" }),
+            )
+            .await
+            .unwrap();
+        canceller.await.unwrap();
+
+        assert!(ret.is_error());
+        let text = ret.as_text().unwrap();
+        assert!(text.contains("cancelled by the user"));
+        assert!(text.contains("started"));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shell_returns_when_background_child_holds_pipes() {
+        let tool = RunShellCommandTool::default();
+        let ctx = RunContext::minimal("test");
+
+        let ret = tool
+            .call(
+                &ctx,
+                serde_json::json!({ "command": "sleep 5 & echo done", "timeout_seconds": 2 }),
+            )
+            .await
+            .unwrap();
+
+        assert!(!ret.is_error(), "{:?}", ret.as_text());
+        let text = ret.as_text().unwrap();
+        assert!(text.contains("successfully"));
+        assert!(text.contains("done"));
+    }
+
+    #[tokio::test]
+    async fn test_read_line_capped() {
+        let input = [vec![b'x'; 100_000], b"\nshort\n".to_vec()].concat();
+        let mut reader = input.as_slice();
+        let mut buffer = Vec::new();
+
+        let read = read_line_capped(&mut reader, &mut buffer, 1_000)
+            .await
+            .unwrap();
+        assert_eq!(read, 100_001);
+        assert_eq!(buffer.len(), 1_000);
+
+        buffer.clear();
+        read_line_capped(&mut reader, &mut buffer, 1_000)
+            .await
+            .unwrap();
+        assert_eq!(buffer, b"short\n");
+
+        buffer.clear();
+        assert_eq!(
+            read_line_capped(&mut reader, &mut buffer, 1_000)
+                .await
+                .unwrap(),
+            0
+        );
+    }
 }
@@ -0,0 +1,279 @@
+//! Workspace context shared by the built-in tools.
+//!
+//! A [`Workspace`] is the directory the agent works in together with
+//! everything the tools need while a run is going on.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+use super::file_ops::FileError;
+use super::path_policy::{Access, PathPolicy};
+use super::shell_session::ShellSession;
+use crate::checkpoints::Checkpointer;
+use crate::config::Settings;
+use crate::processes::ConversationProcesses;
+
+/// Receives tool output while the tool is still running, as
+/// `(tool call ID, chunk)`.
+#[derive(Clone)]
+pub struct OutputSink(Arc<dyn Fn(Option<&str>, &str) + Send + Sync>);
+
+impl OutputSink {
+    /// Create a sink that passes each chunk to `f`.
+    pub fn new(f: impl Fn(Option<&str>, &str) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    /// Forward a chunk of output from tool call `tool_call_id`.
+    pub fn send(&self, tool_call_id: Option<&str>, chunk: &str) {
+        (self.0)(tool_call_id, chunk)
+    }
+}
+
+impl std::fmt::Debug for OutputSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("OutputSink")
+    }
+}
+
+/// The directory the agent works in.
+#[derive(Debug, Clone, Default)]
+pub struct Workspace {
+    /// Relative paths resolve against it and the shell runs there. Without
+    /// one (no folder open), paths resolve against the current directory.
+    root: Option<PathBuf>,
+    /// Enforced by [`Workspace::check_read`] and [`Workspace::check_write`].
+    policy: PathPolicy,
+    /// Snapshots files before the tools modify them.
+    checkpointer: Option<Checkpointer>,
+    /// Receives output of long-running tools as it is produced.
+    output_sink: Option<OutputSink>,
+    /// The conversation's persistent shell, if enabled.
+    shell_session: Option<ShellSession>,
+    /// The conversation's background processes.
+    processes: Option<ConversationProcesses>,
+    /// The run's cancellation; long-running tools watch it to stop early.
+    cancel: CancellationToken,
+}
+
+impl Workspace {
+    /// Create a workspace rooted at `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: Some(root.into()),
+            policy: PathPolicy::default(),
+            checkpointer: None,
+            output_sink: None,
+            shell_session: None,
+            processes: None,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// Use `policy` for file access checks.
+    pub fn with_policy(mut self, policy: PathPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Snapshot files through `checkpointer` before the tools modify them.
+    pub fn with_checkpointer(mut self, checkpointer: Checkpointer) -> Self {
+        self.checkpointer = Some(checkpointer);
+        self
+    }
+
+    /// Stream output of running tools to `sink`.
+    pub fn with_output_sink(mut self, sink: OutputSink) -> Self {
+        self.output_sink = Some(sink);
+        self
+    }
+
+    /// Run `shell_session` commands in `session`.
+    pub fn with_shell_session(mut self, session: ShellSession) -> Self {
+        self.shell_session = Some(session);
+        self
+    }
+
+    /// Start and track background processes in `processes`.
+    pub fn with_processes(mut self, processes: ConversationProcesses) -> Self {
+        self.processes = Some(processes);
+        self
+    }
+
+    /// Stop long-running tools when `cancel` is cancelled.
+    pub fn with_cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// The workspace configured in settings (none if no folder is open).
+    pub fn from_settings(settings: &Settings) -> Self {
+        let root = settings
+            .working_directory
+            .as_deref()
+            .filter(|dir| !dir.trim().is_empty());
+        let workspace = match root {
+            Some(root) => Self::new(root),
+            None => Self::default(),
+        };
+        workspace.with_policy(settings.path_policy.clone())
+    }
+
+    /// The workspace root, if one is set.
+    pub fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+
+    /// The persistent shell, if one is attached.
+    pub fn shell_session(&self) -> Option<&ShellSession> {
+        self.shell_session.as_ref()
+    }
+
+    /// The conversation's background processes, if process tools are enabled.
+    pub fn processes(&self) -> Option<&ConversationProcesses> {
+        self.processes.as_ref()
+    }
+
+    /// Cancelled when the run using this workspace is cancelled.
+    pub fn cancel_token(&self) -> &CancellationToken {
+        &self.cancel
+    }
+
+    /// The file access policy.
+    pub fn policy(&self) -> &PathPolicy {
+        &self.policy
+    }
+
+    /// Resolve a tool-supplied path.
+    ///
+    /// Absolute paths are returned unchanged; relative paths are joined onto
+    /// the workspace root.
+    pub fn resolve(&self, path: &str) -> PathBuf {
+        let path = Path::new(path);
+        match &self.root {
+            Some(root) if path.is_relative() => root.join(path),
+            _ => path.to_path_buf(),
+        }
+    }
+
+    /// Resolve an optional directory argument, defaulting to the root.
+    pub fn resolve_dir(&self, dir: Option<&str>) -> PathBuf {
+        match dir {
+            Some(dir) if !dir.trim().is_empty() => self.resolve(dir),
+            _ => self.resolve("."),
+        }
+    }
+
+    /// Resolve `path` and check that the policy allows reading it.
+    pub fn check_read(&self, path: &str) -> Result<PathBuf, FileError> {
+        self.check(path, Access::Read)
+    }
+
+    /// Resolve `path` and check that the policy allows modifying it.
+    pub fn check_write(&self, path: &str) -> Result<PathBuf, FileError> {
+        self.check(path, Access::Write)
+    }
+
+    /// Snapshot `path` before run `run_id` modifies or deletes it.
+    ///
+    /// No-op without a checkpointer. Failures are logged and don't block the
+    /// edit.
+    pub fn checkpoint(&self, run_id: &str, path: &Path) {
+        if let Some(checkpointer) = &self.checkpointer {
+            if let Err(e) = checkpointer.snapshot(run_id, path) {
+                warn!(path = %path.display(), error = %e, "Failed to snapshot file");
+            }
+        }
+    }
+
+    /// Forward a chunk of output from a running tool call. No-op without a
+    /// sink.
+    pub fn emit_output(&self, tool_call_id: Option<&str>, chunk: &str) {
+        if let Some(sink) = &self.output_sink {
+            sink.send(tool_call_id, chunk);
+        }
+    }
+
+    fn check(&self, path: &str, access: Access) -> Result<PathBuf, FileError> {
+        let resolved = self.resolve(path);
+        // Relative paths without a root resolve against the current directory.
+        let absolute = if resolved.is_relative() {
+            std::env::current_dir()?.join(&resolved)
+        } else {
+            resolved
+        };
+        // Skills and their virtual environments live in Deskwork's temp dir.
+        let implicit_read_roots = [crate::external_tools::paths::get_deskwork_temp_dir()];
+        self.policy
+            .check(&absolute, access, self.root(), &implicit_read_roots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_relative_against_root() {
+        let workspace = Workspace::new("/work/project");
+        assert_eq!(
+            workspace.resolve("src/main.rs"),
+            PathBuf::from("/work/project/src/main.rs")
+        );
+        assert_eq!(workspace.resolve_dir(None), PathBuf::from("/work/project/."));
+    }
+
+    #[test]
+    fn test_resolve_absolute_unchanged() {
+        let workspace = Workspace::new("/work/project");
+        assert_eq!(workspace.resolve("/etc/hosts"), PathBuf::from("/etc/hosts"));
+    }
+
+    #[test]
+    fn test_no_root_keeps_relative_paths() {
+        let workspace = Workspace::default();
+        assert_eq!(workspace.resolve("a.txt"), PathBuf::from("a.txt"));
+        assert_eq!(workspace.resolve_dir(Some("")), PathBuf::from("."));
+        assert!(workspace.root().is_none());
+    }
+
+    #[test]
+    fn test_check_resolves_relative_paths() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let workspace = Workspace::new(&root);
+
+        assert_eq!(
+            workspace.check_write("src/lib.rs").unwrap(),
+            root.join("src/lib.rs")
+        );
+        assert!(matches!(
+            workspace.check_read("../outside.txt"),
+            Err(FileError::OutsideWorkspace { .. })
+        ));
+        assert!(matches!(
+            workspace.check_read(".env"),
+            Err(FileError::DeniedByPattern { .. })
+        ));
+    }
+
+    #[test]
+    fn test_from_settings() {
+        let mut settings = Settings::default();
+        let workspace = Workspace::from_settings(&settings);
+        assert_eq!(workspace.root(), None);
+        assert_eq!(workspace.policy(), &settings.path_policy);
+
+        settings.working_directory = Some("   ".to_string());
+        assert_eq!(Workspace::from_settings(&settings).root(), None);
+
+        settings.working_directory = Some("/work/project".to_string());
+        assert_eq!(
+            Workspace::from_settings(&settings).root(),
+            Some(Path::new("/work/project"))
+        );
+    }
+}
@@ -1,16 +1,23 @@
 //! Main application state and update loop.
 
 use eframe::egui;
+use serde::{Deserialize, Serialize};
 use tokio::runtime::Runtime;
 use tracing::{debug, error, info, warn};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
 use deskwork_core::{
-    build_system_prompt, event_channel, run_agent, ClaudeCodeAuth, Database, DocumentData,
-    DocumentMediaType, EventReceiver, ExecutorEvent, ImageData, ImageMediaType, ModelRequest,
-    RunAgentArgs, Settings,
+    build_system_prompt, claude_desktop_config_path, compact_conversation,
+    context_length_for_model, event_channel, find_mcp_prompt, format_token_count,
+    import_mcp_config, load_user_mcp_servers, run_agent, test_mcp_server, user_mcp_configs,
+    AnthropicAuth, ApprovalDecision, ApprovalResponder, CancellationToken, CheckpointError,
+    CheckpointStore, Checkpointer, ClaudeCodeAuth, CompactArgs, ConversationCheckpoints,
+    ConversationSummary, Database, DocumentData, DocumentMediaType, EventReceiver, ExecutorEvent,
+    ImageData, ImageMediaType, McpPool, McpServerEntry, ModelConfig, ModelRegistry, ModelRequest,
+    ModelType, PermissionAction, PermissionRule, ProcessManager, RevertSummary, RunAgentArgs,
+    Settings, ShellSession, StoredMessage, TokenUsage, ToolOutput, UserMcpServer,
 };
 use deskwork_core::skills::categories::{build_mcp_map, McpBridgeResult, SkillCategoryRegistry};
 use deskwork_core::skills::category_context::{build_category_context, ContextBudget};
@@ -24,8 +31,11 @@ use crate::ui::attachments::{self, PendingDocument, PendingImage};
 // =============================================================================
 
 /// A message in the chat.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
+    /// Row ID of the stored message, once persisted.
+    #[serde(skip)]
+    pub id: Option<i64>,
     pub role: MessageRole,
     /// User message text content (for User role).
     pub content: String,
@@ -39,6 +49,7 @@ pub struct Message {
 impl Message {
     pub fn user(content: String) -> Self {
         Self {
+            id: None,
             role: MessageRole::User,
             content,
             blocks: Vec::new(),
@@ -48,32 +59,84 @@ impl Message {
 
     pub fn assistant() -> Self {
         Self {
+            id: None,
             role: MessageRole::Assistant,
             content: String::new(),
             blocks: Vec::new(),
             timestamp: chrono::Utc::now(),
         }
     }
+
+    /// Rebuild a chat message from its stored form.
+    ///
+    /// Assistant blocks that fail to parse fall back to a single text block
+    /// with the stored plain-text content.
+    pub fn from_stored(stored: &StoredMessage) -> Self {
+        let timestamp =
+            chrono::DateTime::from_timestamp(stored.created_at, 0).unwrap_or_else(chrono::Utc::now);
+        match stored.role.as_str() {
+            "assistant" => {
+                let blocks = stored
+                    .blocks
+                    .as_deref()
+                    .and_then(|json| serde_json::from_str::<Vec<ContentBlock>>(json).ok())
+                    .unwrap_or_else(|| vec![ContentBlock::Text(stored.content.clone())]);
+                Self {
+                    id: Some(stored.id),
+                    role: MessageRole::Assistant,
+                    content: stored.content.clone(),
+                    blocks,
+                    timestamp,
+                }
+            }
+            _ => Self {
+                id: Some(stored.id),
+                role: MessageRole::User,
+                content: stored.content.clone(),
+                blocks: Vec::new(),
+                timestamp,
+            },
+        }
+    }
+
+    /// Plain-text content for storage: the user input, or the concatenated
+    /// text blocks of an assistant response.
+    fn plain_text(&self) -> String {
+        match self.role {
+            MessageRole::User => self.content.clone(),
+            MessageRole::Assistant => self
+                .blocks
+                .iter()
+                .filter_map(|b| match b {
+                    ContentBlock::Text(t) => Some(t.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        }
+    }
 }
 
 /// Message sender.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum MessageRole {
     User,
     Assistant,
 }
 
 /// A tool call within a message.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolCall {
-    #[allow(dead_code)]
     pub id: Option<String>,
     pub name: String,
     pub arguments: String,
-    pub result: Option<String>,
+    pub result: Option<ToolOutput>,
     pub success: bool,
     #[allow(dead_code)]
     pub collapsed: bool,
+    /// Output streamed while the tool runs; replaced by `result` when done.
+    #[serde(skip)]
+    pub live_output: String,
 }
 
 impl ToolCall {
@@ -85,12 +148,13 @@ impl ToolCall {
             result: None,
             success: true,
             collapsed: true,
+            live_output: String::new(),
         }
     }
 }
 
 /// A content block within an assistant message, preserving chronological order.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ContentBlock {
     /// Extended thinking / reasoning from Claude.
     Thinking(String),
@@ -100,6 +164,26 @@ pub enum ContentBlock {
     Text(String),
 }
 
+/// The tool call with `id` in `blocks`, or the last one if the call has no
+/// ID.
+fn tool_call_mut<'a>(
+    blocks: &'a mut [ContentBlock],
+    id: &Option<String>,
+) -> Option<&'a mut ToolCall> {
+    let position = blocks
+        .iter()
+        .rposition(|b| matches!(b, ContentBlock::ToolUse(tc) if id.is_some() && tc.id == *id))
+        .or_else(|| {
+            blocks
+                .iter()
+                .rposition(|b| matches!(b, ContentBlock::ToolUse(_)))
+        });
+    match position.and_then(|i| blocks.get_mut(i)) {
+        Some(ContentBlock::ToolUse(tc)) => Some(tc),
+        _ => None,
+    }
+}
+
 // =============================================================================
 // Authentication State
 // =============================================================================
@@ -146,6 +230,130 @@ pub struct PlaybookEditorState {
     pub default_template: String,
 }
 
+/// State for the MCP server editor overlay.
+#[derive(Debug, Clone, Default)]
+pub struct McpServerEditorState {
+    /// Name of the stored server being edited (None when adding one).
+    pub original_name: Option<String>,
+    pub name: String,
+    /// Connector type: `stdio` or `http`.
+    pub kind: String,
+    pub command: String,
+    /// Arguments, one per line.
+    pub args: String,
+    pub url: String,
+    /// Environment variables, one `KEY=value` per line.
+    pub env: String,
+    /// HTTP headers, one `Name: value` per line.
+    pub headers: String,
+    pub enabled: bool,
+    /// Problem with the form, shown above the buttons.
+    pub error: Option<String>,
+}
+
+impl McpServerEditorState {
+    /// Editor for a new stdio server.
+    pub fn new_server() -> Self {
+        Self {
+            kind: "stdio".to_string(),
+            enabled: true,
+            ..Self::default()
+        }
+    }
+
+    /// Editor for an existing server.
+    pub fn from_server(server: &UserMcpServer) -> Self {
+        let entry = &server.entry;
+        Self {
+            original_name: Some(server.name.clone()),
+            name: server.name.clone(),
+            kind: entry.r#type.to_ascii_lowercase(),
+            command: entry.command.clone().unwrap_or_default(),
+            args: entry.args.clone().unwrap_or_default().join("\n"),
+            url: entry.url.clone().unwrap_or_default(),
+            env: format_pairs(entry.env.as_ref(), "="),
+            headers: format_pairs(entry.headers.as_ref(), ": "),
+            enabled: server.enabled,
+            error: None,
+        }
+    }
+
+    /// The connector entry described by the form.
+    pub fn to_entry(&self) -> Result<McpServerEntry, String> {
+        let non_empty = |text: &str| {
+            let text = text.trim();
+            (!text.is_empty()).then(|| text.to_string())
+        };
+
+        if self.kind == "http" {
+            return Ok(McpServerEntry {
+                r#type: self.kind.clone(),
+                url: non_empty(&self.url),
+                command: None,
+                args: None,
+                env: None,
+                headers: parse_pairs(&self.headers, ':', "header")?,
+            });
+        }
+
+        let args: Vec<String> = self
+            .args
+            .lines()
+            .map(str::trim)
+            .filter(|arg| !arg.is_empty())
+            .map(str::to_string)
+            .collect();
+        Ok(McpServerEntry {
+            r#type: self.kind.clone(),
+            url: None,
+            command: non_empty(&self.command),
+            args: (!args.is_empty()).then_some(args),
+            env: parse_pairs(&self.env, '=', "environment")?,
+            headers: None,
+        })
+    }
+}
+
+/// One `{key}{sep}{value}` line per pair, sorted.
+fn format_pairs(pairs: Option<&HashMap<String, String>>, sep: &str) -> String {
+    let mut lines: Vec<String> = pairs
+        .into_iter()
+        .flatten()
+        .map(|(key, value)| format!("{key}{sep}{value}"))
+        .collect();
+    lines.sort();
+    lines.join("\n")
+}
+
+/// Parse lines of `{key}{sep}{value}`; `None` if there are none.
+fn parse_pairs(
+    text: &str,
+    sep: char,
+    what: &str,
+) -> Result<Option<HashMap<String, String>>, String> {
+    let mut pairs = HashMap::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let (key, value) = line
+            .split_once(sep)
+            .ok_or_else(|| format!("Invalid {what} line `{line}`"))?;
+        pairs.insert(key.trim().to_string(), value.trim().to_string());
+    }
+    Ok((!pairs.is_empty()).then_some(pairs))
+}
+
+/// A tool call waiting for the user's approval.
+#[derive(Debug, Clone)]
+pub struct PendingApproval {
+    pub tool_name: String,
+    /// Pretty-printed tool arguments.
+    pub arguments: String,
+    /// Rule added to settings when the user chooses "Always allow".
+    pub suggested_rule: String,
+    /// Feedback text typed in the dialog (sent on deny).
+    pub feedback: String,
+    responder: ApprovalResponder,
+}
+
 /// Tracks the kind of block currently being streamed, so we know when to start a new block.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum StreamingBlockKind {
@@ -178,6 +386,18 @@ pub struct DeskworkApp {
     /// Skills context for system prompt injection.
     pub skills_context: Option<deskwork_core::SkillsContext>,
 
+    /// User-defined MCP servers (database and `mcp.json`).
+    pub mcp_servers: Vec<UserMcpServer>,
+
+    /// MCP server editor state (Some when editor is open).
+    pub editing_mcp_server: Option<McpServerEditorState>,
+
+    /// Server being test-connected, by name.
+    pub testing_mcp_server: Option<String>,
+
+    /// Result of the last test connection: server name and its tool names.
+    pub mcp_test_result: Option<(String, Result<Vec<String>, String>)>,
+
     // -------------------------------------------------------------------------
     // Authentication
     // -------------------------------------------------------------------------
@@ -190,6 +410,28 @@ pub struct DeskworkApp {
     /// Whether we're currently fetching models.
     pub fetching_models: bool,
 
+    /// Whether an Anthropic API key is available (stored or from the environment).
+    pub has_api_key: bool,
+
+    /// API key being typed in the settings panel.
+    pub api_key_input: String,
+
+    /// Whether we're currently fetching the API key's models.
+    pub fetching_api_models: bool,
+
+    /// Kind of endpoint being set up in the settings panel
+    /// (`Local` or `OpenAiCompatible`).
+    pub endpoint_kind: ModelType,
+
+    /// Base URL of the endpoint being set up in the settings panel.
+    pub endpoint_url_input: String,
+
+    /// OpenAI API key being typed in the settings panel.
+    pub openai_key_input: String,
+
+    /// Whether we're currently fetching an endpoint's models.
+    pub fetching_endpoint_models: bool,
+
     // -------------------------------------------------------------------------
     // Chat State
     // -------------------------------------------------------------------------
@@ -201,6 +443,35 @@ pub struct DeskworkApp {
     /// thinking signatures, and tool call IDs.
     pub api_history: Vec<ModelRequest>,
 
+    /// ID of the stored conversation backing the current chat.
+    /// `None` until the first message of a new chat is persisted.
+    pub conversation_id: Option<String>,
+
+    /// Number of entries in `messages` already written to the database.
+    persisted_messages: usize,
+
+    /// File checkpoints of agent edits (`None` if the data dir is unavailable).
+    checkpoints: Option<CheckpointStore>,
+
+    /// IDs of user messages in the current chat whose turns can be reverted.
+    pub checkpointed_messages: HashSet<i64>,
+
+    /// Persistent shell of the current chat, if `persistent_shell` is on.
+    /// Dropping it kills the shell.
+    shell_session: Option<ShellSession>,
+
+    /// Background processes started by the agent, across all chats.
+    /// Dropping it kills those still running.
+    pub processes: ProcessManager,
+
+    /// MCP server connections shared by the runs of all chats.
+    /// Dropping it closes them.
+    pub mcp_pool: McpPool,
+
+    /// Whether the MCP servers were connected for their prompts since they
+    /// last changed.
+    mcp_prompts_requested: bool,
+
     /// Current input text.
     pub input: String,
 
@@ -222,6 +493,25 @@ pub struct DeskworkApp {
     /// Handle to the generation task.
     pub generation_handle: Option<tokio::task::JoinHandle<()>>,
 
+    /// Cancels the running agent, letting it stop tools and keep what it did.
+    /// Taken by the first press of Stop; a second press aborts the task.
+    cancel_token: Option<CancellationToken>,
+
+    /// Tool calls waiting for approval, oldest first.
+    pub pending_approvals: Vec<PendingApproval>,
+
+    // -------------------------------------------------------------------------
+    // Token Usage
+    // -------------------------------------------------------------------------
+    /// Usage reported so far by the run in progress (not yet recorded).
+    turn_usage: TokenUsage,
+
+    /// Usage since the app was started.
+    pub session_usage: TokenUsage,
+
+    /// Usage recorded today across all conversations (from the ledger).
+    pub day_usage: TokenUsage,
+
     // -------------------------------------------------------------------------
     // UI State
     // -------------------------------------------------------------------------
@@ -234,6 +524,18 @@ pub struct DeskworkApp {
     /// Whether to show the command bar above the input area.
     pub show_command_bar: bool,
 
+    /// Show the conversation history window.
+    pub show_history: bool,
+
+    /// Show the background processes window.
+    pub show_processes: bool,
+
+    /// Cached conversation list for the history window.
+    pub conversations: Vec<ConversationSummary>,
+
+    /// Conversation being renamed in the history window: (id, new title).
+    pub renaming_conversation: Option<(String, String)>,
+
     /// Current working directory.
     pub working_dir: Option<std::path::PathBuf>,
 
@@ -249,6 +551,16 @@ pub struct DeskworkApp {
     /// Pending models result receiver.
     models_result_rx: Option<tokio::sync::oneshot::Receiver<Result<Vec<String>, String>>>,
 
+    /// Pending API key models result receiver.
+    api_models_result_rx: Option<tokio::sync::oneshot::Receiver<Result<Vec<String>, String>>>,
+
+    /// Pending endpoint models result receiver, with the endpoint kind.
+    endpoint_models_result_rx:
+        Option<tokio::sync::oneshot::Receiver<(ModelType, Result<Vec<String>, String>)>>,
+
+    /// Pending MCP server test result receiver.
+    mcp_test_rx: Option<tokio::sync::oneshot::Receiver<Result<Vec<String>, String>>>,
+
     /// Pending folder selection result receiver.
     folder_result_rx: Option<tokio::sync::oneshot::Receiver<Option<std::path::PathBuf>>>,
 
@@ -286,16 +598,35 @@ pub struct DeskworkApp {
 
 
 
-fn parse_inline_command_inputs(input: &str) -> HashMap<String, String> {
-    let Some((_, args)) = input.trim().split_once(' ') else {
-        return HashMap::new();
-    };
+/// Derive a conversation title from the first user message.
+fn conversation_title(content: &str) -> String {
+    const MAX_TITLE_CHARS: usize = 60;
+    let first_line = content.lines().next().unwrap_or("").trim();
+    if first_line.chars().count() > MAX_TITLE_CHARS {
+        let truncated: String = first_line.chars().take(MAX_TITLE_CHARS).collect();
+        format!("{}...", truncated.trim_end())
+    } else {
+        first_line.to_string()
+    }
+}
 
-    args.split_whitespace()
-        .filter_map(|item| item.split_once('='))
-        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
-        .filter(|(k, _)| !k.is_empty())
-        .collect()
+/// Kind and base URL of an endpoint models were loaded from before, or a
+/// local Ollama server if there is none.
+fn last_endpoint(db: &Database) -> (ModelType, String) {
+    ModelRegistry::load_from_db(db)
+        .ok()
+        .and_then(|registry| {
+            registry
+                .all()
+                .filter(|config| config.model_type.is_openai_compatible())
+                .find_map(|config| Some((config.model_type, config.base_url.clone()?)))
+        })
+        .unwrap_or_else(|| {
+            (
+                ModelType::Local,
+                deskwork_core::DEFAULT_LOCAL_BASE_URL.to_string(),
+            )
+        })
 }
 
 
@@ -337,6 +668,7 @@ impl DeskworkApp {
 
         // Build skills context (best-effort, don't block on failures)
         let skills_context = Some(deskwork_core::SkillsContext::build());
+        let mcp_servers = load_user_mcp_servers(&db);
 
         // Initialize skill category registry
         let category_registry = SkillCategoryRegistry::load(&settings.plugins_enabled);
@@ -355,9 +687,39 @@ impl DeskworkApp {
             }
         };
 
+        let has_api_key = AnthropicAuth::new(&db).has_api_key();
+
+        // Prefill the endpoint form with an endpoint used before
+        let (endpoint_kind, endpoint_url_input) = last_endpoint(&db);
+
         // Get available models from settings
         let available_models = settings.available_models.clone();
 
+        // Open the checkpoint store and drop checkpoints of deleted conversations
+        let checkpoints = match CheckpointStore::open_default() {
+            Ok(store) => {
+                match db.list_conversations() {
+                    Ok(list) => {
+                        let live: HashSet<String> = list.into_iter().map(|c| c.id).collect();
+                        if let Err(e) = store.collect_garbage(&live) {
+                            warn!("Failed to collect old checkpoints: {}", e);
+                        }
+                    }
+                    Err(e) => warn!("Failed to list conversations for checkpoint cleanup: {}", e),
+                }
+                Some(store)
+            }
+            Err(e) => {
+                error!("Failed to open checkpoint store: {}", e);
+                None
+            }
+        };
+
+        // Today's token usage so far (from earlier sessions)
+        let day_usage = db
+            .usage_for_day(&deskwork_core::usage_day_today())
+            .unwrap_or_default();
+
         // Apply theme
         let visuals = match settings.theme {
             deskwork_core::Theme::Dark => egui::Visuals::dark(),
@@ -411,7 +773,7 @@ impl DeskworkApp {
         style.spacing.item_spacing = egui::vec2(8.0, 6.0);
         cc.egui_ctx.set_style(style);
 
-        Self {
+        let mut app = Self {
             runtime,
             db,
             settings,
@@ -419,25 +781,56 @@ impl DeskworkApp {
             category_mcp,
             editing_playbook: None,
             skills_context,
+            mcp_servers,
+            editing_mcp_server: None,
+            testing_mcp_server: None,
+            mcp_test_result: None,
             auth_state,
             available_models,
             fetching_models: false,
+            has_api_key,
+            api_key_input: String::new(),
+            fetching_api_models: false,
+            endpoint_kind,
+            endpoint_url_input,
+            openai_key_input: String::new(),
+            fetching_endpoint_models: false,
             messages: Vec::new(),
             api_history: vec![],
+            conversation_id: None,
+            persisted_messages: 0,
+            checkpoints,
+            checkpointed_messages: HashSet::new(),
+            shell_session: None,
+            processes: ProcessManager::new(),
+            mcp_pool: McpPool::new(),
+            mcp_prompts_requested: false,
             input: String::new(),
             is_generating: false,
             current_blocks: Vec::new(),
             streaming_block_kind: StreamingBlockKind::None,
             event_rx: None,
             generation_handle: None,
+            cancel_token: None,
+            pending_approvals: Vec::new(),
+            turn_usage: TokenUsage::default(),
+            session_usage: TokenUsage::default(),
+            day_usage,
             show_settings: false,
             settings_tab: Default::default(),
             show_command_bar: true,
+            show_history: false,
+            show_processes: false,
+            conversations: Vec::new(),
+            renaming_conversation: None,
             working_dir,
             scroll_to_bottom: false,
             status_message: None,
             auth_result_rx: None,
             models_result_rx: None,
+            api_models_result_rx: None,
+            endpoint_models_result_rx: None,
+            mcp_test_rx: None,
             folder_result_rx: None,
             tool_statuses: std::collections::HashMap::new(),
             tool_status_rx: None,
@@ -446,12 +839,40 @@ impl DeskworkApp {
             tool_uninstall_result_rx: Vec::new(),
             pending_attachments: Vec::new(),
             pending_documents: Vec::new(),
+        };
+
+        // Load the API key's models if a key (e.g. from the environment) has none yet
+        let registry = app.model_registry();
+        let has_api_models = app
+            .available_models
+            .iter()
+            .any(|m| registry.resolve(m).model_type == ModelType::Anthropic);
+        if app.has_api_key && !has_api_models {
+            app.fetch_api_models();
         }
+
+        app
     }
 
-    /// Check if authenticated.
-    pub fn is_authenticated(&self) -> bool {
-        self.auth_state == AuthState::Authenticated
+    /// Check if the provider `model_type` has credentials: a Claude Code
+    /// sign-in or an Anthropic API key. Endpoint models need none.
+    pub fn has_credentials(&self, model_type: ModelType) -> bool {
+        match model_type {
+            ModelType::Anthropic => self.has_api_key,
+            ModelType::ClaudeCode => self.auth_state == AuthState::Authenticated,
+            ModelType::OpenAiCompatible | ModelType::Local => true,
+        }
+    }
+
+    /// Status hint for when the provider `model_type` has no credentials.
+    pub fn sign_in_hint(model_type: ModelType) -> &'static str {
+        match model_type {
+            ModelType::Anthropic => "Add an Anthropic API key in Settings first",
+            ModelType::ClaudeCode => "Please sign in first",
+            ModelType::OpenAiCompatible | ModelType::Local => {
+                "Load the endpoint's models in Settings first"
+            }
+        }
     }
 
     /// Start the OAuth authentication flow.
@@ -512,7 +933,7 @@ impl DeskworkApp {
 
     /// Fetch available models from the API.
     pub fn fetch_models(&mut self) {
-        if self.fetching_models || !self.is_authenticated() {
+        if self.fetching_models || self.auth_state != AuthState::Authenticated {
             return;
         }
 
@@ -580,8 +1001,10 @@ impl DeskworkApp {
             match rx.try_recv() {
                 Ok(Ok(models)) => {
                     info!("Fetched {} models", models.len());
-                    self.available_models = models.clone();
-                    self.settings.set_available_models(models);
+                    let registry = self.model_registry();
+                    self.settings
+                        .set_provider_models(&registry, ModelType::ClaudeCode, models);
+                    self.available_models = self.settings.available_models.clone();
                     self.fetching_models = false;
                     self.save_settings();
                 }
@@ -610,16 +1033,258 @@ impl DeskworkApp {
             error!("Failed to sign out: {}", e);
         }
         self.auth_state = AuthState::NotAuthenticated;
-        self.available_models.clear();
-        self.settings.available_models.clear();
+        let registry = self.model_registry();
+        self.settings
+            .set_provider_models(&registry, ModelType::ClaudeCode, Vec::new());
+        self.available_models = self.settings.available_models.clone();
         self.save_settings();
         self.set_status("Signed out");
     }
 
-    /// Get access token, refreshing if needed.
-    fn get_access_token(&self) -> Option<String> {
-        let auth = ClaudeCodeAuth::new(&self.db);
-        auth.get_access_token().ok()
+    /// Store the API key typed in the settings panel and load its models.
+    pub fn save_api_key(&mut self) {
+        let auth = AnthropicAuth::new(&self.db);
+        match auth.save_api_key(&self.api_key_input) {
+            Ok(()) => {
+                info!("Saved Anthropic API key");
+                self.api_key_input.clear();
+                self.has_api_key = true;
+                self.set_status("API key saved");
+                self.fetch_api_models();
+            }
+            Err(e) => {
+                error!("Failed to save API key: {}", e);
+                self.set_status(&format!("Failed to save API key: {}", e));
+            }
+        }
+    }
+
+    /// Delete the stored API key and, unless the environment still provides
+    /// one, its models.
+    pub fn remove_api_key(&mut self) {
+        let auth = AnthropicAuth::new(&self.db);
+        if let Err(e) = auth.remove_api_key() {
+            error!("Failed to remove API key: {}", e);
+        }
+        self.has_api_key = auth.has_api_key();
+        if !self.has_api_key {
+            let registry = self.model_registry();
+            self.settings
+                .set_provider_models(&registry, ModelType::Anthropic, Vec::new());
+            self.available_models = self.settings.available_models.clone();
+            self.save_settings();
+        }
+        self.set_status("API key removed");
+    }
+
+    /// Fetch the models available to the Anthropic API key.
+    pub fn fetch_api_models(&mut self) {
+        if self.fetching_api_models {
+            return;
+        }
+        let api_key = match AnthropicAuth::new(&self.db).api_key() {
+            Ok(key) => key,
+            Err(_) => return,
+        };
+
+        info!("Fetching models for the API key");
+        self.fetching_api_models = true;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.api_models_result_rx = Some(rx);
+
+        let db_path = self.db.path().to_path_buf();
+
+        self.runtime.spawn(async move {
+            match deskwork_core::fetch_anthropic_models(&api_key).await {
+                Ok(models) => {
+                    let filtered = deskwork_core::filter_latest_models(models);
+
+                    let db = match Database::open_at(db_path) {
+                        Ok(db) => db,
+                        Err(e) => {
+                            let _ = tx.send(Err(format!("Failed to open database: {}", e)));
+                            return;
+                        }
+                    };
+
+                    if let Err(e) = deskwork_core::save_anthropic_models_to_db(&db, &filtered) {
+                        warn!("Failed to save models to database: {}", e);
+                    }
+
+                    let prefixed: Vec<String> = filtered
+                        .iter()
+                        .map(|m| deskwork_core::anthropic_model_name(m))
+                        .collect();
+                    let _ = tx.send(Ok(prefixed));
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(e.to_string()));
+                }
+            }
+        });
+    }
+
+    /// Check for API key model fetch completion.
+    fn check_api_models_completion(&mut self) {
+        if let Some(mut rx) = self.api_models_result_rx.take() {
+            match rx.try_recv() {
+                Ok(Ok(models)) => {
+                    info!("Fetched {} API key models", models.len());
+                    let registry = self.model_registry();
+                    self.settings
+                        .set_provider_models(&registry, ModelType::Anthropic, models);
+                    self.available_models = self.settings.available_models.clone();
+                    self.fetching_api_models = false;
+                    self.save_settings();
+                }
+                Ok(Err(e)) => {
+                    error!("Failed to fetch API key models: {}", e);
+                    self.fetching_api_models = false;
+                    self.set_status(&format!("Failed to fetch models: {}", e));
+                }
+                Err(tokio::sync::oneshot::error::TryRecvError::Empty) => {
+                    // Still waiting
+                    self.api_models_result_rx = Some(rx);
+                }
+                Err(tokio::sync::oneshot::error::TryRecvError::Closed) => {
+                    warn!("API models channel closed unexpectedly");
+                    self.fetching_api_models = false;
+                }
+            }
+        }
+    }
+
+    /// Store the OpenAI API key typed in the settings panel.
+    pub fn save_openai_key(&mut self) {
+        let api_key = self.openai_key_input.trim();
+        if api_key.is_empty() {
+            return;
+        }
+        match self.db.save_api_key(deskwork_core::OPENAI_API_KEY, api_key) {
+            Ok(()) => {
+                info!("Saved OpenAI API key");
+                self.openai_key_input.clear();
+                self.set_status("API key saved");
+            }
+            Err(e) => {
+                error!("Failed to save OpenAI API key: {}", e);
+                self.set_status(&format!("Failed to save API key: {}", e));
+            }
+        }
+    }
+
+    /// Delete the stored OpenAI API key.
+    pub fn remove_openai_key(&mut self) {
+        if let Err(e) = self.db.delete_api_key(deskwork_core::OPENAI_API_KEY) {
+            error!("Failed to remove OpenAI API key: {}", e);
+        }
+        self.set_status("API key removed");
+    }
+
+    /// Fetch the models served at the endpoint set up in the settings panel
+    /// and register them with its base URL.
+    pub fn fetch_endpoint_models(&mut self) {
+        if self.fetching_endpoint_models {
+            return;
+        }
+        let base_url = self
+            .endpoint_url_input
+            .trim()
+            .trim_end_matches('/')
+            .to_string();
+        if base_url.is_empty() {
+            return;
+        }
+        let model_type = self.endpoint_kind;
+        let api_key = deskwork_core::openai_api_key(&self.db);
+
+        info!("Fetching models from {}", base_url);
+        self.fetching_endpoint_models = true;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.endpoint_models_result_rx = Some(rx);
+
+        let db_path = self.db.path().to_path_buf();
+
+        self.runtime.spawn(async move {
+            let result = match deskwork_core::fetch_openai_models(&base_url, &api_key).await {
+                Ok(models) => match Database::open_at(db_path) {
+                    Ok(db) => deskwork_core::save_endpoint_models_to_db(
+                        &db, model_type, &base_url, &models,
+                    )
+                    .map_err(|e| format!("Failed to save models: {}", e)),
+                    Err(e) => Err(format!("Failed to open database: {}", e)),
+                },
+                Err(e) => Err(e),
+            };
+            let _ = tx.send((model_type, result));
+        });
+    }
+
+    /// Check for endpoint model fetch completion.
+    fn check_endpoint_models_completion(&mut self) {
+        if let Some(mut rx) = self.endpoint_models_result_rx.take() {
+            match rx.try_recv() {
+                Ok((model_type, Ok(models))) => {
+                    info!("Fetched {} {} models", models.len(), model_type);
+                    self.set_status(&format!("Loaded {} models", models.len()));
+                    let registry = self.model_registry();
+                    self.settings
+                        .set_provider_models(&registry, model_type, models);
+                    self.available_models = self.settings.available_models.clone();
+                    self.fetching_endpoint_models = false;
+                    self.save_settings();
+                }
+                Ok((_, Err(e))) => {
+                    error!("Failed to fetch endpoint models: {}", e);
+                    self.fetching_endpoint_models = false;
+                    self.set_status(&format!("Failed to fetch models: {}", e));
+                }
+                Err(tokio::sync::oneshot::error::TryRecvError::Empty) => {
+                    // Still waiting
+                    self.endpoint_models_result_rx = Some(rx);
+                }
+                Err(tokio::sync::oneshot::error::TryRecvError::Closed) => {
+                    warn!("Endpoint models channel closed unexpectedly");
+                    self.fetching_endpoint_models = false;
+                }
+            }
+        }
+    }
+
+    /// Registry entry of the selected model, or what is assumed for it.
+    pub fn selected_model_config(&self) -> ModelConfig {
+        ModelRegistry::resolve_from_db(&self.db, &self.settings.model)
+    }
+
+    /// The models stored in the database.
+    fn model_registry(&self) -> ModelRegistry {
+        ModelRegistry::load_from_db(&self.db).unwrap_or_else(|e| {
+            error!("Failed to load models: {}", e);
+            ModelRegistry::default()
+        })
+    }
+
+    /// Store capability flags edited in the settings panel.
+    pub fn save_model_config(&mut self, config: &ModelConfig) {
+        if let Err(e) = ModelRegistry::add_model_to_db_with_source(&self.db, config, "endpoint") {
+            error!("Failed to save model {}: {}", config.name, e);
+            self.set_status(&format!("Failed to save model: {}", e));
+        }
+    }
+
+    /// Get the credential of the provider `model_type`: the OAuth access
+    /// token (refreshing if needed), the Anthropic API key, or the (possibly
+    /// empty) OpenAI API key.
+    fn get_access_token(&self, model_type: ModelType) -> Option<String> {
+        match model_type {
+            ModelType::Anthropic => AnthropicAuth::new(&self.db).api_key().ok(),
+            ModelType::ClaudeCode => ClaudeCodeAuth::new(&self.db).get_access_token().ok(),
+            ModelType::OpenAiCompatible | ModelType::Local => {
+                Some(deskwork_core::openai_api_key(&self.db))
+            }
+        }
     }
 
     /// Send the current input as a message.
@@ -635,25 +1300,40 @@ impl DeskworkApp {
             return;
         }
 
-        if !self.is_authenticated() {
-            self.set_status("Please sign in first");
+        // The registry entry decides the credential and the backend
+        let model_config = self.selected_model_config();
+        let model_type = model_config.model_type;
+        if !self.has_credentials(model_type) {
+            self.set_status(Self::sign_in_hint(model_type));
             return;
         }
 
         // Get access token
-        let access_token = match self.get_access_token() {
+        let access_token = match self.get_access_token(model_type) {
             Some(token) => token,
             None => {
-                self.set_status("Not authenticated. Please sign in.");
-                self.auth_state = AuthState::NotAuthenticated;
+                if model_type == ModelType::Anthropic {
+                    self.has_api_key = false;
+                } else {
+                    self.auth_state = AuthState::NotAuthenticated;
+                }
+                self.set_status(Self::sign_in_hint(model_type));
                 return;
             }
         };
 
-        // Resolve slash commands into full command prompts.
+        if raw_input == "/compact" {
+            self.input.clear();
+            self.start_compaction(access_token, model_config);
+            return;
+        }
+
+        // Resolve slash commands into full command prompts. MCP prompts are
+        // fetched from their server when the run starts.
         let mut agent_input = raw_input.clone();
+        let mut mcp_prompt = None;
         if raw_input.starts_with('/') {
-            let user_inputs = parse_inline_command_inputs(&raw_input);
+            let user_inputs = skill_commands::parse_inline_command_inputs(&raw_input);
             if let Some(parsed) = skill_commands::parse_slash_command(&raw_input) {
                 match skill_commands::get_command_handler(&self.category_registry, &parsed.slash_command)
                 {
@@ -665,11 +1345,21 @@ impl DeskworkApp {
                         );
                     }
                     None => {
-                        self.set_status(&format!(
-                            "No enabled command handler found for {}",
-                            parsed.slash_command
-                        ));
-                        return;
+                        let prompts = self.mcp_pool.prompts();
+                        let Some(prompt) = find_mcp_prompt(&prompts, &parsed.slash_command) else {
+                            self.set_status(&format!(
+                                "No enabled command handler found for {}",
+                                parsed.slash_command
+                            ));
+                            return;
+                        };
+                        match prompt.invocation(parsed.raw_args.as_deref()) {
+                            Ok(invocation) => mcp_prompt = Some(invocation),
+                            Err(e) => {
+                                self.set_status(&e);
+                                return;
+                            }
+                        }
                     }
                 }
             }
@@ -684,6 +1374,16 @@ impl DeskworkApp {
         self.messages.push(Message::user(raw_input.clone()));
         self.input.clear();
 
+        // Store the user message now so the turn's file checkpoints can be
+        // tagged with its ID
+        self.persist_conversation();
+        let checkpointer = self.checkpointer_for_turn();
+        let shell_session = self.shell_session_for_turn();
+        let processes = self
+            .conversation_id
+            .as_deref()
+            .map(|id| self.processes.conversation(id));
+
         // Reset streaming state
         self.current_blocks.clear();
         self.streaming_block_kind = StreamingBlockKind::None;
@@ -741,19 +1441,40 @@ impl DeskworkApp {
         // Spawn the agent
         let settings = self.settings.clone();
         let model_name = settings.model.clone();
-        let plugin_mcp_configs = self.category_mcp.configs.clone();
+        let plugin_mcp_configs = self.mcp_configs();
+        let mcp_pool = self.mcp_pool.clone();
         let api_history = self.api_history.clone();
+        let context_length = context_length_for_model(&self.db, &model_name);
+        let cancel = CancellationToken::new();
+        self.cancel_token = Some(cancel.clone());
         let handle = self.runtime.spawn(async move {
+            let agent_input = match mcp_prompt {
+                Some(invocation) => match mcp_pool.get_prompt(&invocation).await {
+                    Ok(text) => text,
+                    Err(e) => {
+                        let _ = tx.send(ExecutorEvent::Error(e));
+                        return;
+                    }
+                },
+                None => agent_input,
+            };
             let agent_handle = run_agent(RunAgentArgs {
                 access_token,
                 model_name,
+                model_config: Some(model_config),
                 settings,
                 system_prompt,
                 user_input: agent_input,
                 images,
                 documents,
                 message_history: api_history,
+                context_length,
                 plugin_mcp_configs,
+                mcp_pool: Some(mcp_pool),
+                checkpointer,
+                shell_session,
+                processes,
+                cancel,
                 event_sender: tx,
             });
 
@@ -768,15 +1489,62 @@ impl DeskworkApp {
         self.generation_handle = Some(handle);
     }
 
+    /// Summarize the conversation so far to free up context (`/compact`).
+    ///
+    /// Runs like a generation: events arrive through `process_events`.
+    fn start_compaction(&mut self, access_token: String, model_config: ModelConfig) {
+        if self.api_history.is_empty() {
+            self.set_status("Nothing to compact yet");
+            return;
+        }
+
+        info!("Compacting conversation");
+        self.set_status("Compacting conversation...");
+        self.current_blocks.clear();
+        self.streaming_block_kind = StreamingBlockKind::None;
+        self.is_generating = true;
+
+        let (tx, rx) = event_channel();
+        self.event_rx = Some(rx);
+
+        let args = CompactArgs {
+            access_token,
+            model_name: self.settings.model.clone(),
+            model_config: Some(model_config),
+            message_history: self.api_history.clone(),
+            event_sender: tx,
+        };
+        let handle = self.runtime.spawn(async move {
+            let _ = compact_conversation(args).await;
+        });
+        self.generation_handle = Some(handle);
+    }
+
     /// Stop the current generation.
+    ///
+    /// An agent run is cancelled first and ends with a `Cancelled` event once
+    /// its tools have stopped. Compaction, or a run already being cancelled,
+    /// is aborted right away.
     pub fn stop_generation(&mut self) {
+        if let Some(cancel) = self.cancel_token.take() {
+            info!("Cancelling generation");
+            cancel.cancel();
+            self.pending_approvals.clear();
+            self.set_status("Stopping...");
+            return;
+        }
+
         info!("Stopping generation");
 
         if let Some(handle) = self.generation_handle.take() {
             handle.abort();
         }
+        self.pending_approvals.clear();
 
         self.finalize_response();
+        self.persist_conversation();
+        self.record_turn_usage();
+        self.refresh_checkpointed_messages();
         self.is_generating = false;
         self.event_rx = None;
     }
@@ -842,35 +1610,91 @@ impl DeskworkApp {
                         debug!(name, "Tool call complete");
                     }
 
-                    ExecutorEvent::ToolResult {
+                    ExecutorEvent::ApprovalRequested {
                         id: _,
+                        tool_name,
+                        arguments,
+                        suggested_rule,
+                        responder,
+                    } => {
+                        debug!(tool_name, "Approval requested");
+                        let arguments = serde_json::to_string_pretty(&arguments)
+                            .unwrap_or_else(|_| arguments.to_string());
+                        self.pending_approvals.push(PendingApproval {
+                            tool_name,
+                            arguments,
+                            suggested_rule,
+                            feedback: String::new(),
+                            responder,
+                        });
+                        ctx.request_repaint();
+                    }
+
+                    ExecutorEvent::ToolOutputDelta { id, delta } => {
+                        if let Some(tc) = tool_call_mut(&mut self.current_blocks, &id) {
+                            tc.live_output.push_str(&delta);
+                        }
+                        ctx.request_repaint();
+                    }
+
+                    ExecutorEvent::ToolResult {
+                        id,
                         name,
                         result,
                         success,
                     } => {
                         debug!(name, success, "Tool result");
-                        // Find the last ToolUse block and set its result
-                        if let Some(ContentBlock::ToolUse(ref mut tc)) = self
-                            .current_blocks
-                            .iter_mut()
-                            .rev()
-                            .find(|b| matches!(b, ContentBlock::ToolUse(_)))
-                        {
+                        if let Some(tc) = tool_call_mut(&mut self.current_blocks, &id) {
                             tc.result = Some(result);
                             tc.success = success;
+                            tc.live_output.clear();
                         }
                         ctx.request_repaint();
                     }
 
+                    ExecutorEvent::Usage(usage) => {
+                        self.turn_usage.add(&usage);
+                    }
+
+                    ExecutorEvent::Compacted {
+                        summarized_requests,
+                        tokens_before,
+                        tokens_after,
+                        message_history,
+                    } => {
+                        info!(
+                            summarized_requests,
+                            tokens_before, tokens_after, "History compacted"
+                        );
+                        self.api_history = message_history;
+                        // `self.event_rx` is still borrowed, so no `set_status`
+                        let message = format!(
+                            "Compacted {} earlier messages (~{} → ~{} tokens)",
+                            summarized_requests,
+                            format_token_count(tokens_before as u64),
+                            format_token_count(tokens_after as u64),
+                        );
+                        self.status_message = Some((message, chrono::Utc::now()));
+                        ctx.request_repaint();
+                    }
+
                     ExecutorEvent::Done {
-                        input_tokens,
-                        output_tokens,
+                        usage,
                         message_history,
                     } => {
-                        info!(input_tokens, output_tokens, "Generation complete");
+                        info!(
+                            input_tokens = usage.total_input(),
+                            output_tokens = usage.output_tokens,
+                            "Generation complete"
+                        );
                         self.api_history = message_history;
                         self.finalize_response();
+                        self.persist_conversation();
+                        self.record_turn_usage();
+                        self.refresh_checkpointed_messages();
+                        self.pending_approvals.clear();
                         self.is_generating = false;
+                        self.cancel_token = None;
                         self.event_rx = None;
                         ctx.request_repaint();
                         break;
@@ -880,16 +1704,27 @@ impl DeskworkApp {
                         error!(error = %msg, "Agent error");
                         self.set_status(&format!("Error: {}", msg));
                         self.finalize_response();
+                        self.persist_conversation();
+                        self.record_turn_usage();
+                        self.refresh_checkpointed_messages();
+                        self.pending_approvals.clear();
                         self.is_generating = false;
+                        self.cancel_token = None;
                         self.event_rx = None;
                         ctx.request_repaint();
                         break;
                     }
 
-                    ExecutorEvent::Cancelled => {
+                    ExecutorEvent::Cancelled { message_history } => {
                         info!("Generation cancelled");
+                        self.api_history = message_history;
                         self.finalize_response();
+                        self.persist_conversation();
+                        self.record_turn_usage();
+                        self.refresh_checkpointed_messages();
+                        self.pending_approvals.clear();
                         self.is_generating = false;
+                        self.cancel_token = None;
                         self.event_rx = None;
                         ctx.request_repaint();
                         break;
@@ -899,6 +1734,60 @@ impl DeskworkApp {
         }
     }
 
+    /// Answer the oldest pending approval request.
+    ///
+    /// "Approve always" also persists the suggested rule so future runs don't ask.
+    pub fn answer_approval(&mut self, decision: ApprovalDecision) {
+        if self.pending_approvals.is_empty() {
+            return;
+        }
+        let approval = self.pending_approvals.remove(0);
+
+        if decision == ApprovalDecision::ApproveAlways
+            && !self
+                .settings
+                .permission_rules
+                .iter()
+                .any(|r| r.pattern == approval.suggested_rule)
+        {
+            self.settings.permission_rules.push(PermissionRule::new(
+                approval.suggested_rule.clone(),
+                PermissionAction::Allow,
+            ));
+            self.save_settings();
+        }
+
+        if !approval.responder.respond(decision) {
+            warn!(tool = %approval.tool_name, "Approval answered after the run ended");
+        }
+    }
+
+    /// Record the usage of the run that just ended in the ledger and add it to
+    /// the session and day totals.
+    fn record_turn_usage(&mut self) {
+        let usage = std::mem::take(&mut self.turn_usage);
+        if usage.is_empty() {
+            return;
+        }
+
+        self.session_usage.add(&usage);
+        let day = deskwork_core::usage_day_today();
+        if let Err(e) = self.db.record_usage(
+            self.conversation_id.as_deref(),
+            &self.settings.model,
+            &day,
+            &usage,
+        ) {
+            error!("Failed to record token usage: {}", e);
+        }
+        self.day_usage = self.db.usage_for_day(&day).unwrap_or_else(|e| {
+            warn!("Failed to load today's token usage: {}", e);
+            let mut day_usage = self.day_usage;
+            day_usage.add(&usage);
+            day_usage
+        });
+    }
+
     /// Finalize the current response into a message.
     fn finalize_response(&mut self) {
         if self.current_blocks.is_empty() {
@@ -940,6 +1829,7 @@ impl DeskworkApp {
     pub fn reload_categories(&mut self) {
         self.category_registry = SkillCategoryRegistry::load(&self.settings.plugins_enabled);
         self.category_mcp = build_mcp_map(&self.category_registry.enabled_categories());
+        self.sync_mcp_pool();
         self.set_status("Skill categories reloaded");
     }
 
@@ -962,8 +1852,10 @@ impl DeskworkApp {
             self.settings.plugins_enabled.retain(|id| id != category_id);
         }
 
-        // Rebuild MCP map after category change
+        // Rebuild MCP map after category change, shutting down the
+        // connectors of a disabled category
         self.category_mcp = build_mcp_map(&self.category_registry.enabled_categories());
+        self.sync_mcp_pool();
         self.save_settings();
     }
 
@@ -1024,12 +1916,422 @@ impl DeskworkApp {
         self.editing_playbook = None;
     }
 
-    /// Clear the chat history.
+    // =========================================================================
+    // MCP Servers
+    // =========================================================================
+
+    /// Reload the user's MCP servers from the database and `mcp.json`.
+    pub fn refresh_mcp_servers(&mut self) {
+        self.mcp_servers = load_user_mcp_servers(&self.db);
+        self.sync_mcp_pool();
+    }
+
+    /// MCP connectors for a run: the enabled categories' and the user's own.
+    fn mcp_configs(&self) -> HashMap<String, McpServerEntry> {
+        let mut configs = self.category_mcp.configs.clone();
+        configs.extend(user_mcp_configs(&self.db));
+        configs
+    }
+
+    /// Shut down pooled connections to servers that are no longer
+    /// configured, or whose config changed.
+    fn sync_mcp_pool(&mut self) {
+        // Closing a transport may need the runtime
+        let _guard = self.runtime.enter();
+        self.mcp_pool.sync(&self.mcp_configs());
+        self.mcp_prompts_requested = false;
+    }
+
+    /// Connect the MCP servers in the background so their prompts can be
+    /// suggested as slash commands. Once per change of the servers.
+    pub fn load_mcp_prompts(&mut self) {
+        if self.mcp_prompts_requested {
+            return;
+        }
+        self.mcp_prompts_requested = true;
+
+        let pool = self.mcp_pool.clone();
+        let configs = self.mcp_configs();
+        self.runtime.spawn(async move {
+            pool.manager(&configs).await;
+        });
+    }
+
+    /// Drop a server's connection and backoff; the next run reconnects it.
+    pub fn reconnect_mcp_server(&mut self, name: &str) {
+        let _guard = self.runtime.enter();
+        self.mcp_pool.reconnect(name);
+        self.set_status(&format!(
+            "MCP server `{}` will reconnect on the next message",
+            name
+        ));
+    }
+
+    /// Enable or disable a server stored in the database.
+    pub fn set_mcp_server_enabled(&mut self, name: &str, enabled: bool) {
+        if let Err(e) = self.db.set_mcp_server_enabled(name, enabled) {
+            error!("Failed to update MCP server {}: {}", name, e);
+            self.set_status(&format!("Failed to update MCP server: {}", e));
+        }
+        self.refresh_mcp_servers();
+    }
+
+    /// Delete a server stored in the database.
+    pub fn delete_mcp_server(&mut self, name: &str) {
+        if let Err(e) = self.db.delete_mcp_server(name) {
+            error!("Failed to delete MCP server {}: {}", name, e);
+            self.set_status(&format!("Failed to delete MCP server: {}", e));
+        }
+        self.refresh_mcp_servers();
+    }
+
+    /// Open the MCP server editor, for a stored server or a new one.
+    pub fn open_mcp_server_editor(&mut self, name: Option<&str>) {
+        let server = name.and_then(|name| self.mcp_servers.iter().find(|s| s.name == name));
+        self.editing_mcp_server = Some(match server {
+            Some(server) => McpServerEditorState::from_server(server),
+            None => McpServerEditorState::new_server(),
+        });
+    }
+
+    /// Save the server in the editor and close it, unless the form is invalid.
+    pub fn save_mcp_server_editor(&mut self) {
+        let Some(editor) = self.editing_mcp_server.as_mut() else {
+            return;
+        };
+        let name = editor.name.trim().to_string();
+        if name.is_empty() {
+            editor.error = Some("Enter a name".to_string());
+            return;
+        }
+        let entry = match editor.to_entry() {
+            Ok(entry) => entry,
+            Err(e) => {
+                editor.error = Some(e);
+                return;
+            }
+        };
+
+        if let Err(e) = self.db.save_mcp_server(&name, &entry, editor.enabled) {
+            editor.error = Some(format!("Failed to save: {}", e));
+            return;
+        }
+        // A rename replaces the old entry
+        if let Some(original) = editor.original_name.take().filter(|o| *o != name) {
+            if let Err(e) = self.db.delete_mcp_server(&original) {
+                warn!("Failed to remove renamed MCP server {}: {}", original, e);
+            }
+        }
+
+        self.editing_mcp_server = None;
+        self.refresh_mcp_servers();
+        self.set_status(&format!("MCP server `{}` saved", name));
+    }
+
+    /// Close the MCP server editor without saving.
+    pub fn close_mcp_server_editor(&mut self) {
+        self.editing_mcp_server = None;
+    }
+
+    /// Import the servers of Claude Desktop's config into the database.
+    pub fn import_claude_desktop_servers(&mut self) {
+        let Some(path) = claude_desktop_config_path() else {
+            self.set_status("Could not determine Claude Desktop's config location");
+            return;
+        };
+        match import_mcp_config(&self.db, &path) {
+            Ok(count) => self.set_status(&format!("Imported {} MCP servers", count)),
+            Err(e) => self.set_status(&format!("Import failed: {}", e)),
+        }
+        self.refresh_mcp_servers();
+    }
+
+    /// Connect to a server in the background and list its tools.
+    pub fn start_mcp_test(&mut self, name: &str, entry: McpServerEntry) {
+        if self.mcp_test_rx.is_some() {
+            return;
+        }
+        info!("Testing MCP server {}", name);
+        self.testing_mcp_server = Some(name.to_string());
+        self.mcp_test_result = None;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.mcp_test_rx = Some(rx);
+
+        self.runtime.spawn(async move {
+            let _ = tx.send(test_mcp_server(&entry).await);
+        });
+    }
+
+    /// Check for MCP server test completion.
+    fn check_mcp_test_completion(&mut self) {
+        if let Some(mut rx) = self.mcp_test_rx.take() {
+            match rx.try_recv() {
+                Ok(result) => {
+                    let name = self.testing_mcp_server.take().unwrap_or_default();
+                    self.mcp_test_result = Some((name, result));
+                }
+                Err(tokio::sync::oneshot::error::TryRecvError::Empty) => {
+                    // Still waiting
+                    self.mcp_test_rx = Some(rx);
+                }
+                Err(tokio::sync::oneshot::error::TryRecvError::Closed) => {
+                    warn!("MCP test channel closed unexpectedly");
+                    self.testing_mcp_server = None;
+                }
+            }
+        }
+    }
+
+    /// Clear the chat history and start a new conversation.
+    ///
+    /// The previous conversation stays in the database and can be resumed
+    /// from the history window.
     pub fn clear_chat(&mut self) {
         self.messages.clear();
         self.api_history.clear();
         self.current_blocks.clear();
         self.streaming_block_kind = StreamingBlockKind::None;
+        self.conversation_id = None;
+        self.persisted_messages = 0;
+        self.checkpointed_messages.clear();
+        self.shell_session = None;
+    }
+
+    // =========================================================================
+    // Conversation History
+    // =========================================================================
+
+    /// Write any new messages and the current API history to the database.
+    ///
+    /// Creates the backing conversation on first use, titled after the first
+    /// user message.
+    fn persist_conversation(&mut self) {
+        if self.messages.is_empty() {
+            return;
+        }
+
+        let conversation_id = match &self.conversation_id {
+            Some(id) => id.clone(),
+            None => {
+                let title = self
+                    .messages
+                    .iter()
+                    .find(|m| m.role == MessageRole::User)
+                    .map(|m| conversation_title(&m.content));
+                match self.db.create_conversation(title.as_deref()) {
+                    Ok(id) => {
+                        self.conversation_id = Some(id.clone());
+                        id
+                    }
+                    Err(e) => {
+                        error!("Failed to create conversation: {}", e);
+                        return;
+                    }
+                }
+            }
+        };
+
+        for message in &mut self.messages[self.persisted_messages..] {
+            let (role, blocks) = match message.role {
+                MessageRole::User => ("user", None),
+                MessageRole::Assistant => ("assistant", serde_json::to_string(&message.blocks).ok()),
+            };
+            match self.db.append_message(
+                &conversation_id,
+                role,
+                &message.plain_text(),
+                blocks.as_deref(),
+            ) {
+                Ok(id) => message.id = Some(id),
+                Err(e) => {
+                    error!("Failed to save message: {}", e);
+                    return;
+                }
+            }
+            self.persisted_messages += 1;
+        }
+
+        if let Err(e) = self
+            .db
+            .save_conversation_history(&conversation_id, &self.api_history)
+        {
+            error!("Failed to save conversation history: {}", e);
+        }
+    }
+
+    /// Refresh the cached conversation list and show the history window.
+    pub fn open_history(&mut self) {
+        self.refresh_conversations();
+        self.show_history = true;
+    }
+
+    /// Reload the conversation list from the database.
+    pub fn refresh_conversations(&mut self) {
+        match self.db.list_conversations() {
+            Ok(list) => self.conversations = list,
+            Err(e) => {
+                error!("Failed to list conversations: {}", e);
+                self.set_status("Failed to load conversation history");
+            }
+        }
+    }
+
+    /// Resume a stored conversation, replacing the current chat.
+    pub fn resume_conversation(&mut self, conversation_id: &str) {
+        if self.is_generating {
+            self.set_status("Please wait for the current response to complete");
+            return;
+        }
+
+        match self.db.load_conversation(conversation_id) {
+            Ok(Some(stored)) => {
+                self.clear_chat();
+                self.messages = stored.messages.iter().map(Message::from_stored).collect();
+                self.api_history = stored.history;
+                self.persisted_messages = self.messages.len();
+                self.conversation_id = Some(stored.summary.id);
+                self.refresh_checkpointed_messages();
+                self.scroll_to_bottom = true;
+                self.show_history = false;
+                let title = stored
+                    .summary
+                    .title
+                    .unwrap_or_else(|| "Untitled".to_string());
+                self.set_status(&format!("Resumed: {}", title));
+            }
+            Ok(None) => {
+                self.set_status("Conversation not found");
+                self.refresh_conversations();
+            }
+            Err(e) => {
+                error!("Failed to load conversation: {}", e);
+                self.set_status(&format!("Failed to load conversation: {}", e));
+            }
+        }
+    }
+
+    /// Rename a stored conversation.
+    pub fn rename_conversation(&mut self, conversation_id: &str, title: &str) {
+        let title = title.trim();
+        if title.is_empty() {
+            return;
+        }
+        if let Err(e) = self.db.rename_conversation(conversation_id, title) {
+            error!("Failed to rename conversation: {}", e);
+            self.set_status("Failed to rename conversation");
+        }
+        self.refresh_conversations();
+    }
+
+    /// Delete a stored conversation. Clears the chat if it is the current one.
+    pub fn delete_conversation(&mut self, conversation_id: &str) {
+        if self.conversation_id.as_deref() == Some(conversation_id) {
+            if self.is_generating {
+                self.set_status("Please wait for the current response to complete");
+                return;
+            }
+            self.clear_chat();
+        }
+        self.processes.kill_conversation(conversation_id);
+        if let Err(e) = self.db.delete_conversation(conversation_id) {
+            error!("Failed to delete conversation: {}", e);
+            self.set_status("Failed to delete conversation");
+        } else if let Some(store) = &self.checkpoints {
+            if let Err(e) = store.delete_conversation(conversation_id) {
+                warn!("Failed to delete checkpoints: {}", e);
+            }
+        }
+        self.refresh_conversations();
+    }
+
+    // =========================================================================
+    // Checkpoints
+    // =========================================================================
+
+    /// Checkpointer for the turn started by the last user message.
+    ///
+    /// `None` if the conversation or message couldn't be stored.
+    fn checkpointer_for_turn(&self) -> Option<Checkpointer> {
+        let store = self.checkpoints.as_ref()?;
+        let conversation_id = self.conversation_id.as_deref()?;
+        let message_id = self.messages.last().and_then(|m| m.id);
+        Some(store.conversation(conversation_id).checkpointer(message_id))
+    }
+
+    /// The chat's persistent shell, started on first use. `None` if
+    /// persistent shells are disabled.
+    fn shell_session_for_turn(&mut self) -> Option<ShellSession> {
+        if !self.settings.persistent_shell {
+            self.shell_session = None;
+            return None;
+        }
+        Some(self.shell_session.get_or_insert_with(ShellSession::new).clone())
+    }
+
+    /// Stop a background process without blocking the UI.
+    pub fn stop_process(&self, id: u32) {
+        let processes = self.processes.clone();
+        self.runtime.spawn(async move {
+            if let Err(e) = processes.stop(id).await {
+                warn!("Failed to stop background process: {}", e);
+            }
+        });
+    }
+
+    /// Reload which user messages of the current chat have checkpoints.
+    fn refresh_checkpointed_messages(&mut self) {
+        self.checkpointed_messages.clear();
+        let (Some(store), Some(conversation_id)) = (&self.checkpoints, &self.conversation_id)
+        else {
+            return;
+        };
+        match store.conversation(conversation_id).message_ids() {
+            Ok(ids) => self.checkpointed_messages = ids,
+            Err(e) => warn!("Failed to load checkpoints: {}", e),
+        }
+    }
+
+    /// Undo the file changes made in the turn started by user message
+    /// `message_id`.
+    pub fn revert_turn(&mut self, message_id: i64) {
+        self.revert_checkpoints(|checkpoints| checkpoints.revert_turn(message_id));
+    }
+
+    /// Undo all file changes made since user message `message_id`, restoring
+    /// the files to their state before it was sent.
+    pub fn revert_to_before(&mut self, message_id: i64) {
+        self.revert_checkpoints(|checkpoints| checkpoints.revert_since(message_id));
+    }
+
+    fn revert_checkpoints(
+        &mut self,
+        revert: impl FnOnce(&ConversationCheckpoints) -> Result<RevertSummary, CheckpointError>,
+    ) {
+        if self.is_generating {
+            self.set_status("Please wait for the current response to complete");
+            return;
+        }
+        let (Some(store), Some(conversation_id)) = (&self.checkpoints, &self.conversation_id)
+        else {
+            return;
+        };
+
+        match revert(&store.conversation(conversation_id)) {
+            Ok(summary) => {
+                let files = summary.file_count();
+                self.set_status(&format!(
+                    "Reverted {} file{}",
+                    files,
+                    if files == 1 { "" } else { "s" }
+                ));
+            }
+            Err(e) => {
+                error!("Failed to revert checkpoint: {}", e);
+                self.set_status(&format!("Failed to revert: {}", e));
+            }
+        }
+        self.refresh_checkpointed_messages();
     }
 
     /// Open a folder selection dialog asynchronously.
@@ -1529,6 +2831,9 @@ impl eframe::App for DeskworkApp {
         // Check for async completions
         self.check_auth_completion();
         self.check_models_completion();
+        self.check_api_models_completion();
+        self.check_endpoint_models_completion();
+        self.check_mcp_test_completion();
         self.check_folder_selection();
         self.check_tool_status_completion();
         self.check_tool_installs();
@@ -1573,6 +2878,21 @@ impl eframe::App for DeskworkApp {
             ui::settings::render(self, ctx);
         }
 
+        // Conversation history window if open
+        if self.show_history {
+            ui::history::render(self, ctx);
+        }
+
+        // Background processes window if open
+        if self.show_processes {
+            ui::processes::render(self, ctx);
+        }
+
+        // Tool approval dialog (on top of everything else)
+        if !self.pending_approvals.is_empty() {
+            ui::approval::render(self, ctx);
+        }
+
         // Main chat area (fills remaining space)
         egui::CentralPanel::default().show(ctx, |ui| {
             ui::chat::render(self, ui);
@@ -1582,6 +2902,9 @@ impl eframe::App for DeskworkApp {
         if self.is_generating
             || self.auth_result_rx.is_some()
             || self.models_result_rx.is_some()
+            || self.api_models_result_rx.is_some()
+            || self.endpoint_models_result_rx.is_some()
+            || self.mcp_test_rx.is_some()
             || self.folder_result_rx.is_some()
             || self.tool_status_rx.is_some()
             || !self.tool_install_progress_rx.is_empty()
@@ -0,0 +1,101 @@
+//! Tool-call approval dialog.
+
+use eframe::egui::{self, RichText, Rounding, Vec2};
+
+use deskwork_core::ApprovalDecision;
+
+use crate::app::DeskworkApp;
+use crate::ui::colors;
+
+/// Render the approval dialog for the oldest pending tool call.
+pub fn render(app: &mut DeskworkApp, ctx: &egui::Context) {
+    let queued = app.pending_approvals.len();
+    let Some(approval) = app.pending_approvals.first_mut() else {
+        return;
+    };
+
+    let mut decision = None;
+
+    egui::Window::new("Approve tool call?")
+        .collapsible(false)
+        .resizable(false)
+        .default_width(520.0)
+        .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+        .show(ctx, |ui| {
+            let muted = colors::muted(ui.visuals());
+
+            ui.horizontal(|ui| {
+                ui.label("Claude wants to run");
+                ui.label(RichText::new(&approval.tool_name).monospace().strong());
+            });
+            if queued > 1 {
+                ui.label(
+                    RichText::new(format!("{} more waiting", queued - 1))
+                        .size(11.0)
+                        .color(muted),
+                );
+            }
+
+            egui::Frame::group(ui.style())
+                .fill(colors::tool_bg(ui.visuals()))
+                .show(ui, |ui| {
+                    ui.set_width(ui.available_width());
+                    egui::ScrollArea::vertical()
+                        .max_height(240.0)
+                        .show(ui, |ui| {
+                            ui.label(RichText::new(&approval.arguments).monospace().size(12.0));
+                        });
+                });
+
+            ui.add_space(8.0);
+            ui.add(
+                egui::TextEdit::singleline(&mut approval.feedback)
+                    .desired_width(f32::INFINITY)
+                    .hint_text("Optional feedback for Claude if you deny"),
+            );
+
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if ui
+                    .add(
+                        egui::Button::new(RichText::new("Approve once").strong())
+                            .fill(colors::USER_BG)
+                            .rounding(Rounding::same(8.0)),
+                    )
+                    .clicked()
+                {
+                    decision = Some(ApprovalDecision::ApproveOnce);
+                }
+
+                if ui
+                    .add(egui::Button::new("Always allow").rounding(Rounding::same(8.0)))
+                    .clicked()
+                {
+                    decision = Some(ApprovalDecision::ApproveAlways);
+                }
+
+                if ui
+                    .add(
+                        egui::Button::new(RichText::new("Deny").color(colors::ERROR))
+                            .rounding(Rounding::same(8.0)),
+                    )
+                    .clicked()
+                {
+                    let feedback = approval.feedback.trim().to_string();
+                    decision = Some(ApprovalDecision::Deny {
+                        feedback: (!feedback.is_empty()).then_some(feedback),
+                    });
+                }
+            });
+
+            ui.label(
+                RichText::new(format!("Always allow adds: {}", approval.suggested_rule))
+                    .size(11.0)
+                    .color(muted),
+            );
+        });
+
+    if let Some(decision) = decision {
+        app.answer_approval(decision);
+    }
+}
@@ -2,6 +2,8 @@
 
 use eframe::egui::{self, Color32, RichText, Rounding};
 
+use deskwork_core::ToolOutput;
+
 use crate::app::{ContentBlock, DeskworkApp, Message, MessageRole, ToolCall};
 use crate::ui::colors;
 use crate::ui::markdown::{self, MarkdownRenderState};
@@ -69,9 +71,16 @@ fn render_messages(app: &mut DeskworkApp, ui: &mut egui::Ui) {
         );
     }
 
+    let mut revert = None;
     for (msg_idx, message) in app.messages.iter().enumerate() {
         ui.add_space(8.0);
-        render_message(app, ui, message, max_width, msg_idx);
+        render_message(app, ui, message, max_width, msg_idx, &mut revert);
+    }
+
+    match revert {
+        Some(RevertAction::Turn(message_id)) => app.revert_turn(message_id),
+        Some(RevertAction::Before(message_id)) => app.revert_to_before(message_id),
+        None => {}
     }
 
     if app.is_generating && !app.current_blocks.is_empty() {
@@ -80,15 +89,32 @@ fn render_messages(app: &mut DeskworkApp, ui: &mut egui::Ui) {
     }
 }
 
+/// Checkpoint revert requested from a user message, applied after rendering.
+enum RevertAction {
+    /// Undo the file changes of the turn started by this message.
+    Turn(i64),
+    /// Undo all file changes since this message.
+    Before(i64),
+}
+
 fn render_message(
     app: &DeskworkApp,
     ui: &mut egui::Ui,
     message: &Message,
     max_width: f32,
     msg_idx: usize,
+    revert: &mut Option<RevertAction>,
 ) {
     match message.role {
-        MessageRole::User => render_user_message(ui, message, max_width),
+        MessageRole::User => {
+            render_user_message(ui, message, max_width);
+            let checkpointed = message
+                .id
+                .filter(|id| app.checkpointed_messages.contains(id));
+            if let (Some(message_id), false) = (checkpointed, app.is_generating) {
+                render_revert_buttons(ui, message_id, revert);
+            }
+        }
         MessageRole::Assistant => render_assistant_message(
             ui,
             message,
@@ -121,6 +147,25 @@ fn render_user_message(ui: &mut egui::Ui, message: &Message, max_width: f32) {
     });
 }
 
+fn render_revert_buttons(ui: &mut egui::Ui, message_id: i64, revert: &mut Option<RevertAction>) {
+    ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+        if ui
+            .small_button("Revert to before this")
+            .on_hover_text("Undo all file changes made since this message")
+            .clicked()
+        {
+            *revert = Some(RevertAction::Before(message_id));
+        }
+        if ui
+            .small_button("Revert this turn")
+            .on_hover_text("Undo the file changes made in response to this message")
+            .clicked()
+        {
+            *revert = Some(RevertAction::Turn(message_id));
+        }
+    });
+}
+
 fn render_assistant_message(
     ui: &mut egui::Ui,
     message: &Message,
@@ -475,24 +520,128 @@ fn render_tool_call(ui: &mut egui::Ui, tool_call: &ToolCall, id_source: String)
                         ui.label(RichText::new("Result:").size(11.0).strong());
                         egui::ScrollArea::vertical()
                             .id_salt(format!("{}-result", id_source_clone))
-                            .max_height(150.0)
+                            .max_height(240.0)
                             .show(ui, |ui| {
                                 let color = if tool_call.success {
                                     muted
                                 } else {
                                     colors::ERROR
                                 };
-                                ui.label(RichText::new(result).size(11.0).monospace().color(color));
+                                render_tool_output(ui, result, color);
+                            });
+                    } else if !tool_call.live_output.is_empty() {
+                        ui.add_space(8.0);
+                        ui.label(RichText::new("Output:").size(11.0).strong());
+                        egui::ScrollArea::vertical()
+                            .id_salt(format!("{}-live", id_source_clone))
+                            .max_height(240.0)
+                            .stick_to_bottom(true)
+                            .show(ui, |ui| {
+                                ui.label(
+                                    RichText::new(tool_call.live_output.trim_end())
+                                        .size(11.0)
+                                        .monospace()
+                                        .color(muted),
+                                );
                             });
                     }
                 });
         });
+
+    // Show the latest output line of a running tool under its header
+    if tool_call.result.is_none() {
+        if let Some(line) = tool_call
+            .live_output
+            .lines()
+            .rev()
+            .find(|l| !l.trim().is_empty())
+        {
+            ui.label(
+                RichText::new(line.trim_end())
+                    .size(11.0)
+                    .monospace()
+                    .color(muted),
+            );
+        }
+    }
+}
+
+/// Render a tool result. Shell output is split into its stdout and stderr
+/// sections; JSON is pretty-printed.
+fn render_tool_output(ui: &mut egui::Ui, output: &ToolOutput, color: Color32) {
+    let text = match output {
+        ToolOutput::Text(text) => text,
+        ToolOutput::Json(_) => {
+            ui.label(
+                RichText::new(output.to_display_string())
+                    .size(11.0)
+                    .monospace()
+                    .color(color),
+            );
+            return;
+        }
+    };
+
+    for (label, section) in split_shell_sections(text) {
+        if let Some(label) = label {
+            ui.add_space(4.0);
+            ui.label(RichText::new(label).size(11.0).strong());
+        }
+        if !section.is_empty() {
+            ui.label(RichText::new(section).size(11.0).monospace().color(color));
+        }
+    }
+}
+
+/// Split shell tool output at its `--- stdout ---` / `--- stderr ---`
+/// markers into (label, text) sections. Other output is a single unlabeled
+/// section.
+fn split_shell_sections(text: &str) -> Vec<(Option<&'static str>, &str)> {
+    const MARKERS: [(&str, &str); 2] = [
+        ("\n--- stdout ---\n", "stdout"),
+        ("\n--- stderr ---\n", "stderr"),
+    ];
+
+    let mut sections = Vec::new();
+    let mut label = None;
+    let mut rest = text;
+    loop {
+        let next = MARKERS
+            .iter()
+            .filter_map(|(marker, name)| rest.find(marker).map(|pos| (pos, *marker, *name)))
+            .min_by_key(|(pos, _, _)| *pos);
+        match next {
+            Some((pos, marker, name)) => {
+                sections.push((label, rest[..pos].trim_end()));
+                label = Some(name);
+                rest = &rest[pos + marker.len()..];
+            }
+            None => {
+                sections.push((label, rest.trim_end()));
+                return sections;
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn split_shell_sections_separates_stdout_and_stderr() {
+        let output = "Command failed (exit code: 1)\n\n--- stdout ---\nbuilt\n--- stderr ---\noops";
+        assert_eq!(
+            split_shell_sections(output),
+            vec![
+                (None, "Command failed (exit code: 1)"),
+                (Some("stdout"), "built"),
+                (Some("stderr"), "oops"),
+            ]
+        );
+        assert_eq!(split_shell_sections("3 matches"), vec![(None, "3 matches")]);
+    }
+
     #[test]
     fn split_status_tokens_extracts_leading_badges_and_text() {
         let parts = split_status_tokens("GREEN 🟡 all systems nominal");
@@ -0,0 +1,143 @@
+//! Conversation history window.
+
+use eframe::egui::{self, RichText, Vec2};
+
+use crate::app::DeskworkApp;
+use crate::ui::colors;
+
+/// Action selected in the history window, applied after rendering.
+enum HistoryAction {
+    Resume(String),
+    StartRename(String, String),
+    CommitRename,
+    CancelRename,
+    Delete(String),
+}
+
+/// Render the conversation history window.
+pub fn render(app: &mut DeskworkApp, ctx: &egui::Context) {
+    let mut open = app.show_history;
+    let mut action = None;
+
+    egui::Window::new("Conversations")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(true)
+        .default_width(480.0)
+        .default_height(420.0)
+        .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+        .show(ctx, |ui| {
+            let muted = colors::muted(ui.visuals());
+
+            ui.horizontal(|ui| {
+                if ui.button("New Chat").clicked() {
+                    app.clear_chat();
+                    app.show_history = false;
+                }
+                if ui.button("Refresh").clicked() {
+                    app.refresh_conversations();
+                }
+            });
+            ui.separator();
+
+            if app.conversations.is_empty() {
+                ui.label(
+                    RichText::new("No saved conversations yet.")
+                        .size(11.0)
+                        .color(muted),
+                );
+                return;
+            }
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for convo in &app.conversations {
+                    let is_current = app.conversation_id.as_deref() == Some(convo.id.as_str());
+                    let title = convo.title.as_deref().unwrap_or("Untitled");
+                    let updated = chrono::DateTime::from_timestamp(convo.updated_at, 0)
+                        .map(|t| {
+                            t.with_timezone(&chrono::Local)
+                                .format("%Y-%m-%d %H:%M")
+                                .to_string()
+                        })
+                        .unwrap_or_default();
+
+                    egui::Frame::group(ui.style()).show(ui, |ui| {
+                        ui.set_width(ui.available_width());
+
+                        match &mut app.renaming_conversation {
+                            Some((id, buffer)) if *id == convo.id => {
+                                ui.horizontal(|ui| {
+                                    let response = ui.text_edit_singleline(buffer);
+                                    let submitted = response.lost_focus()
+                                        && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                                    if submitted || ui.button("Save").clicked() {
+                                        action = Some(HistoryAction::CommitRename);
+                                    }
+                                    if ui.button("Cancel").clicked() {
+                                        action = Some(HistoryAction::CancelRename);
+                                    }
+                                });
+                            }
+                            _ => {
+                                let mut text = RichText::new(title).size(14.0);
+                                if is_current {
+                                    text = text.strong();
+                                }
+                                if ui.link(text).clicked() {
+                                    action = Some(HistoryAction::Resume(convo.id.clone()));
+                                }
+                            }
+                        }
+
+                        ui.horizontal(|ui| {
+                            ui.label(
+                                RichText::new(format!(
+                                    "{} · {} messages",
+                                    updated, convo.message_count
+                                ))
+                                .size(11.0)
+                                .color(muted),
+                            );
+                            ui.with_layout(
+                                egui::Layout::right_to_left(egui::Align::Center),
+                                |ui| {
+                                    if ui
+                                        .small_button(RichText::new("Delete").color(colors::ERROR))
+                                        .clicked()
+                                    {
+                                        action = Some(HistoryAction::Delete(convo.id.clone()));
+                                    }
+                                    if ui.small_button("Rename").clicked() {
+                                        action = Some(HistoryAction::StartRename(
+                                            convo.id.clone(),
+                                            title.to_string(),
+                                        ));
+                                    }
+                                },
+                            );
+                        });
+                    });
+                }
+            });
+        });
+
+    if !open {
+        app.show_history = false;
+        app.renaming_conversation = None;
+    }
+
+    match action {
+        Some(HistoryAction::Resume(id)) => app.resume_conversation(&id),
+        Some(HistoryAction::StartRename(id, title)) => {
+            app.renaming_conversation = Some((id, title));
+        }
+        Some(HistoryAction::CommitRename) => {
+            if let Some((id, title)) = app.renaming_conversation.take() {
+                app.rename_conversation(&id, &title);
+            }
+        }
+        Some(HistoryAction::CancelRename) => app.renaming_conversation = None,
+        Some(HistoryAction::Delete(id)) => app.delete_conversation(&id),
+        None => {}
+    }
+}
@@ -2,6 +2,7 @@
 
 use std::collections::BTreeMap;
 
+use deskwork_core::ModelType;
 use eframe::egui::{self, Color32, Key, RichText, Rounding, Vec2};
 
 use crate::app::DeskworkApp;
@@ -14,6 +15,8 @@ pub fn render(app: &mut DeskworkApp, ui: &mut egui::Ui) {
     let muted = colors::muted(ui.visuals());
     let code_bg = colors::code_bg(ui.visuals());
     let border = colors::border(ui.visuals());
+    let model_type = app.selected_model_config().model_type;
+    let authenticated = app.has_credentials(model_type);
 
     // Draw top border line
     let rect = ui.available_rect_before_wrap();
@@ -120,7 +123,7 @@ pub fn render(app: &mut DeskworkApp, ui: &mut egui::Ui) {
                         }
                     } else {
                         // Send button
-                        let can_send = !app.input.trim().is_empty() && app.is_authenticated();
+                        let can_send = !app.input.trim().is_empty() && authenticated;
 
                         let button = egui::Button::new(RichText::new("Send").color(if can_send {
                             Color32::WHITE
@@ -136,8 +139,8 @@ pub fn render(app: &mut DeskworkApp, ui: &mut egui::Ui) {
 
                         if ui
                             .add_sized(Vec2::new(70.0, 32.0), button)
-                            .on_hover_text_at_pointer(if !app.is_authenticated() {
-                                "Sign in first"
+                            .on_hover_text_at_pointer(if !authenticated {
+                                DeskworkApp::sign_in_hint(model_type)
                             } else if app.input.trim().is_empty() {
                                 "Type a message first"
                             } else {
@@ -154,9 +157,11 @@ pub fn render(app: &mut DeskworkApp, ui: &mut egui::Ui) {
 
             // Slash command suggestions
             if app.input.trim_start().starts_with('/') {
+                app.load_mcp_prompts();
                 let prefix = app.input.split_whitespace().next().unwrap_or_default();
                 let suggestions = deskwork_core::skills::commands::command_suggestions_rich(
                     &app.category_registry,
+                    &app.mcp_pool.prompts(),
                     prefix,
                 );
 
@@ -192,8 +197,15 @@ pub fn render(app: &mut DeskworkApp, ui: &mut egui::Ui) {
                                 suggestion.description.chars().take(80).collect::<String>()
                             };
 
-                            let label =
-                                format!("{} — {}", suggestion.slash_command, short_description);
+                            let label = match &suggestion.argument_hint {
+                                Some(hint) => format!(
+                                    "{} {} — {}",
+                                    suggestion.slash_command, hint, short_description
+                                ),
+                                None => {
+                                    format!("{} — {}", suggestion.slash_command, short_description)
+                                }
+                            };
 
                             if ui
                                 .add(
@@ -242,12 +254,22 @@ pub fn render(app: &mut DeskworkApp, ui: &mut egui::Ui) {
                 );
 
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    if !app.is_authenticated() {
+                    if !authenticated {
+                        let uses_api_key = model_type == ModelType::Anthropic;
+                        let link = if uses_api_key {
+                            "Add API key"
+                        } else {
+                            "Sign in"
+                        };
                         if ui
-                            .link(RichText::new("Sign in").size(11.0).color(colors::ERROR))
+                            .link(RichText::new(link).size(11.0).color(colors::ERROR))
                             .clicked()
                         {
-                            app.start_auth();
+                            if uses_api_key {
+                                app.show_settings = true;
+                            } else {
+                                app.start_auth();
+                            }
                         }
                     } else {
                         ui.label(
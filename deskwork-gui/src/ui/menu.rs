@@ -14,15 +14,20 @@ pub fn render(app: &mut DeskworkApp, ui: &mut egui::Ui, ctx: &egui::Context) {
 
         // File menu
         ui.menu_button("File", |ui| {
-            if ui.button("Open Folder...").clicked() {
-                app.open_folder_dialog();
+            if ui.button("New Chat").clicked() {
+                app.clear_chat();
+                ui.close_menu();
+            }
+
+            if ui.button("Conversations...").clicked() {
+                app.open_history();
                 ui.close_menu();
             }
 
             ui.separator();
 
-            if ui.button("Clear Chat").clicked() {
-                app.clear_chat();
+            if ui.button("Open Folder...").clicked() {
+                app.open_folder_dialog();
                 ui.close_menu();
             }
 
@@ -63,6 +68,13 @@ pub fn render(app: &mut DeskworkApp, ui: &mut egui::Ui, ctx: &egui::Context) {
 
                 app.save_settings();
             }
+
+            ui.separator();
+
+            if ui.button("Background Processes...").clicked() {
+                app.show_processes = true;
+                ui.close_menu();
+            }
         });
 
         // Help menu
@@ -82,6 +94,21 @@ pub fn render(app: &mut DeskworkApp, ui: &mut egui::Ui, ctx: &egui::Context) {
 
         // Right-aligned status
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            // Running background processes
+            let running = app.processes.running_count();
+            if running > 0 {
+                let label = if running == 1 {
+                    "1 process running".to_string()
+                } else {
+                    format!("{} processes running", running)
+                };
+                if ui.link(RichText::new(label).size(12.0)).clicked() {
+                    app.show_processes = true;
+                }
+                // Notice when one of them exits
+                ctx.request_repaint_after(std::time::Duration::from_secs(1));
+            }
+
             // Generation indicator
             if app.is_generating {
                 let time = ui.input(|i| i.time);
@@ -100,6 +127,9 @@ pub fn render(app: &mut DeskworkApp, ui: &mut egui::Ui, ctx: &egui::Context) {
 
             // Auth indicator
             match &app.auth_state {
+                AuthState::NotAuthenticated if app.has_api_key => {
+                    ui.label(RichText::new("API key").color(colors::SUCCESS).size(12.0));
+                }
                 AuthState::NotAuthenticated => {
                     if ui
                         .link(RichText::new("Sign in").color(colors::ERROR).size(12.0))
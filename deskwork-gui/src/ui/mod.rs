@@ -1,11 +1,14 @@
 //! UI components for Deskwork.
 
+pub mod approval;
 pub mod attachments;
 pub mod chat;
 pub mod command_bar;
+pub mod history;
 pub mod input;
 pub mod markdown;
 pub mod menu;
+pub mod processes;
 pub mod settings;
 pub mod status;
 
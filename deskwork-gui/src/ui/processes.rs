@@ -0,0 +1,150 @@
+//! Background processes window.
+
+use std::time::Duration;
+
+use eframe::egui::{self, RichText};
+
+use deskwork_core::ProcessStatus;
+
+use crate::app::DeskworkApp;
+use crate::ui::colors;
+
+/// Output lines shown per process.
+const OUTPUT_LINES: usize = 200;
+
+/// Action selected in the processes window, applied after rendering.
+enum ProcessAction {
+    Stop(u32),
+    Remove(u32),
+}
+
+/// Render the background processes window.
+pub fn render(app: &mut DeskworkApp, ctx: &egui::Context) {
+    let mut open = app.show_processes;
+    let mut action = None;
+    let processes = app.processes.list();
+
+    egui::Window::new("Background Processes")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(true)
+        .default_width(560.0)
+        .default_height(420.0)
+        .show(ctx, |ui| {
+            let muted = colors::muted(ui.visuals());
+
+            if processes.is_empty() {
+                ui.label(
+                    RichText::new(
+                        "No background processes. Dev servers and watchers the agent \
+                         starts show up here.",
+                    )
+                    .size(11.0)
+                    .color(muted),
+                );
+                return;
+            }
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                // Newest first
+                for info in processes.iter().rev() {
+                    let is_current =
+                        app.conversation_id.as_deref() == Some(info.conversation_id.as_str());
+                    let status_color = match info.status {
+                        ProcessStatus::Running => colors::SUCCESS,
+                        ProcessStatus::Exited(0) | ProcessStatus::Stopped => muted,
+                        _ => colors::ERROR,
+                    };
+                    let mut details = format!(
+                        "#{} · started {}",
+                        info.id,
+                        info.started_at
+                            .with_timezone(&chrono::Local)
+                            .format("%H:%M:%S")
+                    );
+                    if let Some(pid) = info.pid {
+                        details.push_str(&format!(" · pid {}", pid));
+                    }
+                    if !is_current {
+                        details.push_str(" · other chat");
+                    }
+
+                    egui::Frame::group(ui.style()).show(ui, |ui| {
+                        ui.set_width(ui.available_width());
+                        ui.label(RichText::new(&info.command).monospace().strong());
+
+                        ui.horizontal(|ui| {
+                            ui.label(
+                                RichText::new(info.status.to_string())
+                                    .size(11.0)
+                                    .color(status_color),
+                            );
+                            ui.label(RichText::new(details).size(11.0).color(muted));
+                            ui.with_layout(
+                                egui::Layout::right_to_left(egui::Align::Center),
+                                |ui| {
+                                    if info.status.is_running() {
+                                        if ui
+                                            .small_button(
+                                                RichText::new("Stop").color(colors::ERROR),
+                                            )
+                                            .clicked()
+                                        {
+                                            action = Some(ProcessAction::Stop(info.id));
+                                        }
+                                    } else if ui.small_button("Remove").clicked() {
+                                        action = Some(ProcessAction::Remove(info.id));
+                                    }
+                                },
+                            );
+                        });
+
+                        egui::CollapsingHeader::new(
+                            RichText::new(format!("Output ({} lines)", info.total_lines))
+                                .size(11.0),
+                        )
+                        .id_salt(("process_output", info.id))
+                        .show(ui, |ui| {
+                            match app.processes.read(info.id, None, OUTPUT_LINES) {
+                                Ok(output) if !output.lines.is_empty() => {
+                                    egui::ScrollArea::vertical()
+                                        .id_salt(("process_output_scroll", info.id))
+                                        .max_height(200.0)
+                                        .stick_to_bottom(true)
+                                        .show(ui, |ui| {
+                                            ui.label(
+                                                RichText::new(output.lines.join("\n"))
+                                                    .monospace()
+                                                    .size(11.0),
+                                            );
+                                        });
+                                }
+                                _ => {
+                                    ui.label(
+                                        RichText::new("No output yet.").size(11.0).color(muted),
+                                    );
+                                }
+                            }
+                        });
+                    });
+                }
+            });
+        });
+
+    // Keep status and output current while something is running
+    if processes.iter().any(|info| info.status.is_running()) {
+        ctx.request_repaint_after(Duration::from_millis(500));
+    }
+
+    if !open {
+        app.show_processes = false;
+    }
+
+    match action {
+        Some(ProcessAction::Stop(id)) => app.stop_process(id),
+        Some(ProcessAction::Remove(id)) => {
+            app.processes.remove(id);
+        }
+        None => {}
+    }
+}
@@ -5,6 +5,9 @@ use eframe::egui::{self, RichText, Rounding, Vec2};
 use crate::app::{AuthState, DeskworkApp};
 use crate::ui::colors;
 use deskwork_core::external_tools::get_all_tool_definitions;
+use deskwork_core::{
+    McpServerHealth, McpServerSource, McpServerStatus, ModelType, PermissionAction, PermissionRule,
+};
 
 /// Active tab in the settings dialog.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -14,6 +17,8 @@ pub enum SettingsTab {
     Appearance,
     Skills, // was "Plugins" — now shows skill categories + python tools
     Tools,
+    Mcp,
+    Permissions,
 }
 
 /// Render the settings dialog.
@@ -38,6 +43,8 @@ pub fn render(app: &mut DeskworkApp, ctx: &egui::Context) {
                     (SettingsTab::Appearance, "  Appearance  "),
                     (SettingsTab::Skills, "  Skills  "),
                     (SettingsTab::Tools, "  Tools  "),
+                    (SettingsTab::Mcp, "  MCP  "),
+                    (SettingsTab::Permissions, "  Permissions  "),
                 ] {
                     let selected = app.settings_tab == tab;
                     let response = ui.selectable_label(selected, RichText::new(label).size(14.0));
@@ -59,6 +66,8 @@ pub fn render(app: &mut DeskworkApp, ctx: &egui::Context) {
                     SettingsTab::Appearance => render_appearance_tab(app, ui, ctx, muted),
                     SettingsTab::Skills => render_skills_tab(app, ui, muted),
                     SettingsTab::Tools => render_tools_tab(app, ui, muted),
+                    SettingsTab::Mcp => render_mcp_tab(app, ui, muted),
+                    SettingsTab::Permissions => render_permissions_tab(app, ui, muted),
                 });
 
             // -----------------------------------------------------------------
@@ -111,6 +120,201 @@ pub fn render(app: &mut DeskworkApp, ctx: &egui::Context) {
 
     // Playbook editor overlay (rendered on top of settings)
     render_playbook_editor(app, ctx);
+
+    // MCP server editor overlay
+    render_mcp_server_editor(app, ctx);
+}
+
+fn render_api_key_section(app: &mut DeskworkApp, ui: &mut egui::Ui, muted: egui::Color32) {
+    ui.label(RichText::new("Anthropic API key").strong());
+
+    ui.horizontal(|ui| {
+        ui.label("Status:");
+        if app.has_api_key {
+            ui.label(
+                RichText::new("Configured")
+                    .color(colors::SUCCESS)
+                    .size(14.0),
+            );
+        } else {
+            ui.label(RichText::new("Not configured").color(muted).size(14.0));
+        }
+    });
+
+    ui.add_space(4.0);
+
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut app.api_key_input)
+                .password(true)
+                .hint_text("sk-ant-...")
+                .desired_width(240.0),
+        );
+
+        if ui
+            .add_enabled(
+                !app.api_key_input.trim().is_empty(),
+                egui::Button::new("Save").rounding(Rounding::same(8.0)),
+            )
+            .clicked()
+        {
+            app.save_api_key();
+        }
+
+        if app.has_api_key {
+            if ui
+                .add(egui::Button::new("Refresh Models").rounding(Rounding::same(8.0)))
+                .clicked()
+            {
+                app.fetch_api_models();
+            }
+
+            if app.fetching_api_models {
+                ui.spinner();
+            }
+
+            if ui
+                .add(
+                    egui::Button::new(RichText::new("Remove").color(colors::ERROR))
+                        .rounding(Rounding::same(8.0)),
+                )
+                .clicked()
+            {
+                app.remove_api_key();
+            }
+        }
+    });
+
+    ui.label(
+        RichText::new("Bills usage to your API key; ANTHROPIC_API_KEY is used when none is saved")
+            .size(11.0)
+            .color(muted)
+            .italics(),
+    );
+}
+
+fn render_endpoint_section(app: &mut DeskworkApp, ui: &mut egui::Ui, muted: egui::Color32) {
+    ui.label(RichText::new("OpenAI-compatible endpoint").strong());
+
+    ui.horizontal(|ui| {
+        ui.label("Kind:");
+        for (kind, label) in [
+            (ModelType::Local, "Local (Ollama, llama.cpp)"),
+            (ModelType::OpenAiCompatible, "OpenAI-compatible"),
+        ] {
+            if ui.radio(app.endpoint_kind == kind, label).clicked() {
+                app.endpoint_kind = kind;
+            }
+        }
+    });
+
+    ui.add_space(4.0);
+
+    ui.horizontal(|ui| {
+        ui.label("Base URL:");
+        ui.add(
+            egui::TextEdit::singleline(&mut app.endpoint_url_input)
+                .hint_text(deskwork_core::DEFAULT_LOCAL_BASE_URL)
+                .desired_width(240.0),
+        );
+
+        if ui
+            .add_enabled(
+                !app.endpoint_url_input.trim().is_empty() && !app.fetching_endpoint_models,
+                egui::Button::new("Load Models").rounding(Rounding::same(8.0)),
+            )
+            .clicked()
+        {
+            app.fetch_endpoint_models();
+        }
+
+        if app.fetching_endpoint_models {
+            ui.spinner();
+        }
+    });
+
+    ui.add_space(4.0);
+
+    ui.horizontal(|ui| {
+        ui.label("API key:");
+        ui.add(
+            egui::TextEdit::singleline(&mut app.openai_key_input)
+                .password(true)
+                .hint_text("optional")
+                .desired_width(240.0),
+        );
+
+        if ui
+            .add_enabled(
+                !app.openai_key_input.trim().is_empty(),
+                egui::Button::new("Save").rounding(Rounding::same(8.0)),
+            )
+            .clicked()
+        {
+            app.save_openai_key();
+        }
+
+        if app.db.has_api_key(deskwork_core::OPENAI_API_KEY)
+            && ui
+                .add(
+                    egui::Button::new(RichText::new("Remove").color(colors::ERROR))
+                        .rounding(Rounding::same(8.0)),
+                )
+                .clicked()
+        {
+            app.remove_openai_key();
+        }
+    });
+
+    ui.label(
+        RichText::new(
+            "Requests stay on your machine with a local server; OPENAI_API_KEY is used when no key is saved",
+        )
+        .size(11.0)
+        .color(muted)
+        .italics(),
+    );
+}
+
+/// Capability flags and context window of the selected endpoint model.
+///
+/// Claude models are configured when their models are loaded, so only
+/// endpoint models can be edited here.
+fn render_model_capabilities(app: &mut DeskworkApp, ui: &mut egui::Ui, muted: egui::Color32) {
+    let mut config = app.selected_model_config();
+    if !config.model_type.is_openai_compatible() {
+        return;
+    }
+    let mut changed = false;
+
+    ui.add_space(8.0);
+    ui.horizontal(|ui| {
+        changed |= ui.checkbox(&mut config.supports_tools, "Tools").changed();
+        changed |= ui.checkbox(&mut config.supports_vision, "Images").changed();
+        changed |= ui
+            .checkbox(&mut config.supports_thinking, "Thinking")
+            .changed();
+    });
+    ui.horizontal(|ui| {
+        ui.label("Context window:");
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut config.context_length)
+                    .range(2048..=2_000_000)
+                    .speed(1024.0)
+                    .suffix(" tokens"),
+            )
+            .changed();
+    });
+    ui.label(
+        RichText::new("What the model supports; unsupported parts are left out of requests")
+            .size(11.0)
+            .color(muted),
+    );
+
+    if changed {
+        app.save_model_config(&config);
+    }
 }
 
 fn render_general_tab(app: &mut DeskworkApp, ui: &mut egui::Ui, muted: egui::Color32) {
@@ -228,6 +432,14 @@ fn render_general_tab(app: &mut DeskworkApp, ui: &mut egui::Ui, muted: egui::Col
         }
     }
 
+    ui.add_space(12.0);
+
+    render_api_key_section(app, ui, muted);
+
+    ui.add_space(12.0);
+
+    render_endpoint_section(app, ui, muted);
+
     ui.add_space(16.0);
 
     // Model section
@@ -235,7 +447,7 @@ fn render_general_tab(app: &mut DeskworkApp, ui: &mut egui::Ui, muted: egui::Col
     ui.separator();
 
     ui.horizontal(|ui| {
-        ui.label("Claude Model:");
+        ui.label("Model:");
         ui.add_space(8.0);
 
         let model_label = if app.available_models.is_empty() {
@@ -253,7 +465,7 @@ fn render_general_tab(app: &mut DeskworkApp, ui: &mut egui::Ui, muted: egui::Col
             .show_ui(ui, |ui| {
                 if models.is_empty() {
                     ui.label(
-                        RichText::new("Sign in to load models")
+                        RichText::new("Add an API key, sign in or load an endpoint's models")
                             .color(muted)
                             .italics(),
                     );
@@ -289,6 +501,8 @@ fn render_general_tab(app: &mut DeskworkApp, ui: &mut egui::Ui, muted: egui::Col
         ui.label(RichText::new(model_desc).size(11.0).color(muted).italics());
     }
 
+    render_model_capabilities(app, ui, muted);
+
     ui.add_space(16.0);
 
     // Thinking settings
@@ -320,6 +534,199 @@ fn render_general_tab(app: &mut DeskworkApp, ui: &mut egui::Ui, muted: egui::Col
             .size(11.0)
             .color(muted),
     );
+
+    ui.add_space(16.0);
+
+    // Context settings
+    ui.heading("Context");
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut app.settings.auto_compact, "Compact long conversations");
+        ui.label(
+            RichText::new("(summarize older turns near the context limit)")
+                .size(11.0)
+                .color(muted),
+        );
+    });
+
+    ui.add_space(8.0);
+    ui.add_enabled_ui(app.settings.auto_compact, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Compact at:");
+            ui.add(
+                egui::Slider::new(&mut app.settings.compaction_threshold, 0.5..=0.95)
+                    .step_by(0.05)
+                    .custom_formatter(|value, _| format!("{:.0}%", value * 100.0)),
+            );
+        });
+    });
+    ui.label(
+        RichText::new("Share of the model's context window. Type /compact to compact now.")
+            .size(11.0)
+            .color(muted),
+    );
+
+    ui.add_space(16.0);
+
+    // Shell settings
+    ui.heading("Shell");
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut app.settings.persistent_shell, "Persistent shell sessions");
+        ui.label(
+            RichText::new("(keep cd and environment between commands in a chat)")
+                .size(11.0)
+                .color(muted),
+        );
+    });
+}
+
+fn render_permissions_tab(app: &mut DeskworkApp, ui: &mut egui::Ui, muted: egui::Color32) {
+    ui.heading("Tool Permissions");
+    ui.separator();
+
+    ui.label(
+        RichText::new(
+            "Rules decide whether a tool runs immediately, asks first, or is blocked. \
+             Use a tool name, optionally with an argument pattern, e.g. \
+             run_shell_command(git *) or edit_file(src/*). When several rules match, \
+             Deny beats Ask beats Allow. Without a matching rule, read-only tools run \
+             and everything else asks.",
+        )
+        .size(11.0)
+        .color(muted),
+    );
+
+    ui.add_space(8.0);
+
+    let mut remove_index = None;
+    for (index, rule) in app.settings.permission_rules.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut rule.pattern)
+                    .desired_width(280.0)
+                    .hint_text("tool_name(pattern)"),
+            );
+
+            egui::ComboBox::from_id_salt(("permission_action", index))
+                .selected_text(rule.action.to_string())
+                .show_ui(ui, |ui| {
+                    for action in PermissionAction::all() {
+                        ui.selectable_value(&mut rule.action, *action, action.to_string());
+                    }
+                });
+
+            if ui
+                .add(egui::Button::new(RichText::new("Remove").color(colors::ERROR)).small())
+                .clicked()
+            {
+                remove_index = Some(index);
+            }
+        });
+    }
+
+    if let Some(index) = remove_index {
+        app.settings.permission_rules.remove(index);
+    }
+
+    if app.settings.permission_rules.is_empty() {
+        ui.label(
+            RichText::new("No rules yet. Rules are also added when you choose \"Always allow\".")
+                .size(11.0)
+                .color(muted)
+                .italics(),
+        );
+    }
+
+    ui.add_space(8.0);
+
+    if ui
+        .add(egui::Button::new("Add Rule").rounding(Rounding::same(8.0)))
+        .clicked()
+    {
+        app.settings
+            .permission_rules
+            .push(PermissionRule::new("", PermissionAction::Ask));
+    }
+
+    ui.add_space(16.0);
+
+    ui.heading("File Access");
+    ui.separator();
+
+    ui.label(
+        RichText::new(
+            "File tools can read and modify the working directory. Add folders the \
+             agent may also modify or only read. Paths matching a protected pattern \
+             are never read or modified, wherever they are.",
+        )
+        .size(11.0)
+        .color(muted),
+    );
+
+    ui.add_space(8.0);
+
+    let policy = &mut app.settings.path_policy;
+    ui.checkbox(
+        &mut policy.allow_outside_roots,
+        "Allow access outside these folders",
+    );
+
+    ui.add_space(8.0);
+    ui.label("Writable folders:");
+    render_string_list(ui, &mut policy.writable_roots, "/path/to/folder", "Add Folder", muted);
+
+    ui.add_space(8.0);
+    ui.label("Read-only folders:");
+    render_string_list(ui, &mut policy.read_only_roots, "/path/to/folder", "Add Folder", muted);
+
+    ui.add_space(8.0);
+    ui.label("Protected patterns:");
+    render_string_list(ui, &mut policy.deny_globs, "**/.env", "Add Pattern", muted);
+}
+
+/// Editable list of strings with a Remove button per entry and an Add button.
+fn render_string_list(
+    ui: &mut egui::Ui,
+    items: &mut Vec<String>,
+    hint: &str,
+    add_label: &str,
+    muted: egui::Color32,
+) {
+    let mut remove_index = None;
+    for (index, item) in items.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(item)
+                    .desired_width(280.0)
+                    .hint_text(hint),
+            );
+
+            if ui
+                .add(egui::Button::new(RichText::new("Remove").color(colors::ERROR)).small())
+                .clicked()
+            {
+                remove_index = Some(index);
+            }
+        });
+    }
+
+    if let Some(index) = remove_index {
+        items.remove(index);
+    }
+
+    if items.is_empty() {
+        ui.label(RichText::new("None").size(11.0).color(muted).italics());
+    }
+
+    if ui
+        .add(egui::Button::new(add_label).rounding(Rounding::same(8.0)))
+        .clicked()
+    {
+        items.push(String::new());
+    }
 }
 
 fn render_appearance_tab(
@@ -920,3 +1327,421 @@ pub fn render_playbook_editor(app: &mut DeskworkApp, ctx: &egui::Context) {
         }
     }
 }
+
+fn render_mcp_tab(app: &mut DeskworkApp, ui: &mut egui::Ui, muted: egui::Color32) {
+    ui.heading("MCP Servers");
+    ui.separator();
+
+    ui.label(
+        RichText::new(
+            "Your own MCP servers, available in every chat alongside the skill categories' \
+             connectors. Use ${VAR} in env values and headers to read environment variables.",
+        )
+        .size(11.0)
+        .color(muted),
+    );
+
+    ui.add_space(8.0);
+
+    ui.horizontal(|ui| {
+        if ui
+            .add(egui::Button::new("Add Server").rounding(Rounding::same(8.0)))
+            .clicked()
+        {
+            app.open_mcp_server_editor(None);
+        }
+        if ui
+            .add(egui::Button::new("Import from Claude Desktop").rounding(Rounding::same(8.0)))
+            .clicked()
+        {
+            app.import_claude_desktop_servers();
+        }
+        if ui
+            .add(egui::Button::new("Reload").rounding(Rounding::same(8.0)))
+            .clicked()
+        {
+            app.refresh_mcp_servers();
+        }
+    });
+
+    if let Some(path) = deskwork_core::user_mcp_config_path() {
+        ui.label(
+            RichText::new(format!("Also read from {}", path.display()))
+                .size(10.0)
+                .color(muted),
+        );
+    }
+
+    ui.add_space(8.0);
+
+    if app.mcp_servers.is_empty() {
+        ui.label(
+            RichText::new("No MCP servers configured.")
+                .size(12.0)
+                .color(muted),
+        );
+    }
+
+    let servers = app.mcp_servers.clone();
+    for server in servers {
+        let from_file = server.source == McpServerSource::File;
+        egui::Frame::group(ui.style())
+            .inner_margin(egui::Margin::same(8.0))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    let mut enabled = server.enabled;
+                    let toggle = ui
+                        .add_enabled(!from_file, egui::Checkbox::without_text(&mut enabled))
+                        .on_disabled_hover_text("Set \"disabled\" in mcp.json");
+                    if toggle.changed() {
+                        app.set_mcp_server_enabled(&server.name, enabled);
+                    }
+
+                    ui.vertical(|ui| {
+                        ui.label(RichText::new(&server.name).strong().size(13.0));
+                        let target = match server.entry.r#type.as_str() {
+                            "http" => server.entry.url.clone().unwrap_or_default(),
+                            _ => std::iter::once(server.entry.command.clone().unwrap_or_default())
+                                .chain(server.entry.args.clone().unwrap_or_default())
+                                .collect::<Vec<_>>()
+                                .join(" "),
+                        };
+                        let source = if from_file { " · mcp.json" } else { "" };
+                        ui.label(
+                            RichText::new(format!("{}: {}{}", server.entry.r#type, target, source))
+                                .size(11.0)
+                                .color(muted),
+                        );
+
+                        if app.testing_mcp_server.as_deref() == Some(server.name.as_str()) {
+                            ui.horizontal(|ui| {
+                                ui.spinner();
+                                ui.label(RichText::new("Connecting...").size(11.0).color(muted));
+                            });
+                        } else if let Some((name, result)) = &app.mcp_test_result {
+                            if name == &server.name {
+                                render_mcp_test_result(ui, result);
+                            }
+                        }
+                    });
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if !from_file {
+                            if ui
+                                .add(
+                                    egui::Button::new(
+                                        RichText::new("Delete").size(11.0).color(colors::ERROR),
+                                    )
+                                    .rounding(Rounding::same(6.0)),
+                                )
+                                .clicked()
+                            {
+                                app.delete_mcp_server(&server.name);
+                            }
+                            if ui
+                                .add(
+                                    egui::Button::new(RichText::new("Edit").size(11.0))
+                                        .rounding(Rounding::same(6.0)),
+                                )
+                                .clicked()
+                            {
+                                app.open_mcp_server_editor(Some(&server.name));
+                            }
+                        }
+                        if ui
+                            .add_enabled(
+                                app.testing_mcp_server.is_none(),
+                                egui::Button::new(RichText::new("Test").size(11.0))
+                                    .rounding(Rounding::same(6.0)),
+                            )
+                            .clicked()
+                        {
+                            app.start_mcp_test(&server.name, server.entry.clone());
+                        }
+                    });
+                });
+            });
+        ui.add_space(4.0);
+    }
+
+    render_mcp_connections(app, ui, muted);
+}
+
+/// Health of the pooled connections to every configured server, category
+/// connectors included.
+fn render_mcp_connections(app: &mut DeskworkApp, ui: &mut egui::Ui, muted: egui::Color32) {
+    ui.add_space(12.0);
+    ui.heading("Connections");
+    ui.separator();
+
+    let health = app.mcp_pool.health();
+    if health.is_empty() {
+        ui.label(
+            RichText::new("No MCP servers in use.")
+                .size(12.0)
+                .color(muted),
+        );
+        return;
+    }
+
+    ui.label(
+        RichText::new("Servers connect on the first message that uses them and stay connected.")
+            .size(11.0)
+            .color(muted),
+    );
+    ui.add_space(4.0);
+
+    for server in health {
+        ui.horizontal(|ui| {
+            let (status, color) = mcp_status_label(&server, muted);
+            ui.label(RichText::new("●").size(11.0).color(color));
+            ui.vertical(|ui| {
+                ui.label(RichText::new(&server.name).strong().size(12.0));
+                ui.label(RichText::new(status).size(11.0).color(muted));
+                if let Some(error) = &server.last_error {
+                    ui.label(
+                        RichText::new(format!("Last error: {}", error))
+                            .size(11.0)
+                            .color(colors::ERROR),
+                    );
+                }
+            });
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if server.status != McpServerStatus::NotConnected
+                    && ui
+                        .add(
+                            egui::Button::new(RichText::new("Reconnect").size(11.0))
+                                .rounding(Rounding::same(6.0)),
+                        )
+                        .clicked()
+                {
+                    app.reconnect_mcp_server(&server.name);
+                }
+            });
+        });
+        ui.add_space(4.0);
+    }
+}
+
+fn mcp_status_label(server: &McpServerHealth, muted: egui::Color32) -> (String, egui::Color32) {
+    match server.status {
+        McpServerStatus::NotConnected => ("Not connected yet".to_string(), muted),
+        McpServerStatus::Connecting => ("Connecting...".to_string(), muted),
+        McpServerStatus::Connected => {
+            let mut status = format!("Connected · {} tools", server.tool_count);
+            if server.prompt_count > 0 {
+                status.push_str(&format!(" · {} prompts", server.prompt_count));
+            }
+            (status, colors::SUCCESS)
+        }
+        McpServerStatus::Failed => {
+            let retry = server
+                .retry_at
+                .map(|at| {
+                    at.saturating_duration_since(std::time::Instant::now())
+                        .as_secs()
+                })
+                .filter(|secs| *secs > 0)
+                .map(|secs| format!(", retrying in {}s", secs))
+                .unwrap_or_default();
+            (
+                format!("Failed {} times{}", server.failures, retry),
+                colors::ERROR,
+            )
+        }
+    }
+}
+
+fn render_mcp_test_result(ui: &mut egui::Ui, result: &Result<Vec<String>, String>) {
+    match result {
+        Ok(tools) => {
+            ui.label(
+                RichText::new(format!("Connected · {} tools", tools.len()))
+                    .size(11.0)
+                    .color(colors::SUCCESS),
+            )
+            .on_hover_text(tools.join("\n"));
+        }
+        Err(e) => {
+            ui.label(RichText::new(e).size(11.0).color(colors::ERROR));
+        }
+    }
+}
+
+/// Render the MCP server editor window (add or edit a stored server).
+pub fn render_mcp_server_editor(app: &mut DeskworkApp, ctx: &egui::Context) {
+    if app.editing_mcp_server.is_none() {
+        return;
+    }
+
+    let muted = colors::muted(&ctx.style().visuals);
+    let mut save_clicked = false;
+    let mut cancel_clicked = false;
+    let mut test_entry = None;
+
+    let title = match app
+        .editing_mcp_server
+        .as_ref()
+        .and_then(|e| e.original_name.as_ref())
+    {
+        Some(name) => format!("Edit MCP Server — {name}"),
+        None => "Add MCP Server".to_string(),
+    };
+
+    egui::Window::new(title)
+        .collapsible(false)
+        .resizable(true)
+        .default_width(480.0)
+        .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.spacing_mut().item_spacing = Vec2::new(8.0, 8.0);
+
+            let Some(editor) = app.editing_mcp_server.as_mut() else {
+                return;
+            };
+
+            egui::Grid::new("mcp_server_form")
+                .num_columns(2)
+                .spacing([12.0, 8.0])
+                .show(ui, |ui| {
+                    ui.label("Name:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut editor.name)
+                            .hint_text("github")
+                            .desired_width(300.0),
+                    );
+                    ui.end_row();
+
+                    ui.label("Type:");
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut editor.kind, "stdio".to_string(), "stdio");
+                        ui.radio_value(&mut editor.kind, "http".to_string(), "http");
+                    });
+                    ui.end_row();
+
+                    if editor.kind == "http" {
+                        ui.label("URL:");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut editor.url)
+                                .hint_text("https://example.com/mcp")
+                                .desired_width(300.0),
+                        );
+                        ui.end_row();
+
+                        ui.label("Headers:");
+                        ui.add(
+                            egui::TextEdit::multiline(&mut editor.headers)
+                                .hint_text("Authorization: Bearer ${API_TOKEN}")
+                                .desired_rows(3)
+                                .desired_width(300.0)
+                                .code_editor(),
+                        );
+                        ui.end_row();
+                    } else {
+                        ui.label("Command:");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut editor.command)
+                                .hint_text("npx")
+                                .desired_width(300.0),
+                        );
+                        ui.end_row();
+
+                        ui.label("Arguments:");
+                        ui.add(
+                            egui::TextEdit::multiline(&mut editor.args)
+                                .hint_text("One per line")
+                                .desired_rows(3)
+                                .desired_width(300.0)
+                                .code_editor(),
+                        );
+                        ui.end_row();
+
+                        ui.label("Environment:");
+                        ui.add(
+                            egui::TextEdit::multiline(&mut editor.env)
+                                .hint_text("API_TOKEN=${API_TOKEN}")
+                                .desired_rows(3)
+                                .desired_width(300.0)
+                                .code_editor(),
+                        );
+                        ui.end_row();
+                    }
+
+                    ui.label("");
+                    ui.checkbox(&mut editor.enabled, "Enabled");
+                    ui.end_row();
+                });
+
+            if let Some(error) = &editor.error {
+                ui.label(RichText::new(error).size(11.0).color(colors::ERROR));
+            }
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                if ui
+                    .add_sized(
+                        Vec2::new(80.0, 28.0),
+                        egui::Button::new(RichText::new("Save").strong())
+                            .fill(colors::USER_BG)
+                            .rounding(Rounding::same(8.0)),
+                    )
+                    .clicked()
+                {
+                    save_clicked = true;
+                }
+
+                if ui
+                    .add_sized(
+                        Vec2::new(80.0, 28.0),
+                        egui::Button::new("Cancel").rounding(Rounding::same(8.0)),
+                    )
+                    .clicked()
+                {
+                    cancel_clicked = true;
+                }
+
+                ui.add_space(16.0);
+
+                let testing = app.testing_mcp_server.is_some();
+                if ui
+                    .add_enabled(
+                        !testing,
+                        egui::Button::new("Test Connection").rounding(Rounding::same(8.0)),
+                    )
+                    .clicked()
+                {
+                    match editor.to_entry() {
+                        Ok(entry) => test_entry = Some((editor.name.trim().to_string(), entry)),
+                        Err(e) => editor.error = Some(e),
+                    }
+                }
+                if testing {
+                    ui.spinner();
+                }
+            });
+
+            if let Some((name, result)) = &app.mcp_test_result {
+                if name.as_str() == editor.name.trim() {
+                    render_mcp_test_result(ui, result);
+                }
+            }
+
+            ui.label(
+                RichText::new("Saved servers take effect from the next message")
+                    .size(10.0)
+                    .color(muted),
+            );
+        });
+
+    // Handle actions after the window's mutable borrows are released
+    if let Some((name, entry)) = test_entry {
+        app.start_mcp_test(&name, entry);
+    }
+    if save_clicked {
+        app.save_mcp_server_editor();
+    } else if cancel_clicked {
+        app.close_mcp_server_editor();
+    }
+}
@@ -2,6 +2,8 @@
 
 use eframe::egui::{self, RichText};
 
+use deskwork_core::{format_token_count, TokenUsage};
+
 use crate::app::DeskworkApp;
 use crate::ui::colors;
 
@@ -16,6 +18,25 @@ pub fn render(app: &DeskworkApp, ui: &mut egui::Ui) {
         }
 
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            // Token usage (session and today)
+            if !app.day_usage.is_empty() || !app.session_usage.is_empty() {
+                ui.label(
+                    RichText::new(format!(
+                        "{} tokens this session · {} today",
+                        format_token_count(app.session_usage.total()),
+                        format_token_count(app.day_usage.total()),
+                    ))
+                    .size(11.0)
+                    .color(muted),
+                )
+                .on_hover_text(format!(
+                    "This session\n{}\n\nToday\n{}",
+                    usage_breakdown(&app.session_usage),
+                    usage_breakdown(&app.day_usage),
+                ));
+                ui.separator();
+            }
+
             // Message count
             let msg_count = app.messages.len();
             if msg_count > 0 {
@@ -44,3 +65,16 @@ pub fn render(app: &DeskworkApp, ui: &mut egui::Ui) {
         });
     });
 }
+
+/// Multi-line breakdown of a usage total for the hover text.
+fn usage_breakdown(usage: &TokenUsage) -> String {
+    format!(
+        "Requests: {}\nInput: {}\nOutput: {} (thinking {})\nCache read: {}\nCache write: {}",
+        usage.requests,
+        format_token_count(usage.input_tokens),
+        format_token_count(usage.output_tokens),
+        format_token_count(usage.thinking_tokens),
+        format_token_count(usage.cache_read_tokens),
+        format_token_count(usage.cache_write_tokens),
+    )
+}